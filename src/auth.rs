@@ -0,0 +1,196 @@
+//! Parsing of apt auth.conf and netrc files.
+
+use std::fs;
+use std::path::Path;
+
+use libapt::{Error, Result};
+use log::{debug, error};
+use reqwest::Url;
+
+/// Directory of the apt auth.conf files.
+pub const APT_AUTH_DIR: &str = "/etc/apt/auth.conf.d";
+
+/// Main apt auth.conf file.
+pub const APT_AUTH_FILE: &str = "/etc/apt/auth.conf";
+
+/// Login of a machine entry.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct AuthEntry {
+    // Machine as host[:port][/path], optionally with scheme. None for the default entry.
+    pub machine: Option<String>,
+    // User name.
+    pub login: String,
+    // Password.
+    pub password: Option<String>,
+}
+
+impl AuthEntry {
+    /// Test if the entry applies to the given URL.
+    ///
+    /// Entries without scheme apply to all schemes, entries without port to all ports.
+    /// A path of the entry must be a prefix of the URL path.
+    pub fn matches(&self, url: &Url) -> bool {
+        let machine = match &self.machine {
+            Some(machine) => machine.as_str(),
+            None => return true,
+        };
+
+        let machine = match machine.split_once("://") {
+            Some((scheme, rest)) if scheme == url.scheme() => rest,
+            Some(_) => return false,
+            None => machine,
+        };
+        let (authority, path) = match machine.split_once('/') {
+            Some((authority, path)) => (authority, Some(path.trim_end_matches('/'))),
+            None => (machine, None),
+        };
+        let (host, port) = match authority.rsplit_once(':') {
+            Some((host, port)) => (host, port.parse::<u16>().ok()),
+            None => (authority, None),
+        };
+
+        if url.host_str() != Some(host) {
+            return false;
+        }
+        if port.is_some() && port != url.port_or_known_default() {
+            return false;
+        }
+        match path {
+            Some(path) => {
+                let url_path = url.path().trim_start_matches('/');
+                url_path == path || url_path.starts_with(&format!("{path}/"))
+            }
+            None => true,
+        }
+    }
+
+    /// Length of the machine, used to prefer the most specific entry.
+    fn specificity(&self) -> usize {
+        self.machine.as_ref().map(|machine| machine.len() + 1).unwrap_or_default()
+    }
+}
+
+/// Parse the machine entries of an auth.conf or netrc file.
+///
+/// The tokens may be spread over lines, # starts a comment.
+/// Entries without login are skipped.
+pub fn parse(content: &str) -> Vec<AuthEntry> {
+    let mut entries = Vec::new();
+    let mut current: Option<AuthEntry> = None;
+
+    let mut tokens = content
+        .lines()
+        .map(|line| line.split_once('#').map(|(line, _)| line).unwrap_or(line))
+        .flat_map(|line| line.split_whitespace());
+
+    while let Some(token) = tokens.next() {
+        match token {
+            "machine" | "default" => {
+                entries.extend(current.take().filter(|entry| !entry.login.is_empty()));
+                let machine = if token == "machine" { tokens.next().map(|machine| machine.to_string()) } else { None };
+                current = Some(AuthEntry { machine, ..Default::default() });
+            }
+            "login" => {
+                if let (Some(entry), Some(login)) = (current.as_mut(), tokens.next()) {
+                    entry.login = login.to_string();
+                }
+            }
+            "password" => {
+                if let (Some(entry), Some(password)) = (current.as_mut(), tokens.next()) {
+                    entry.password = Some(password.to_string());
+                }
+            }
+            // Other tokens, e.g. account, don't affect the login.
+            _ => {}
+        }
+    }
+    entries.extend(current.take().filter(|entry| !entry.login.is_empty()));
+
+    entries
+}
+
+/// Read the machine entries of the given file.
+pub fn auth_file_entries(path: &str) -> Result<Vec<AuthEntry>> {
+    match fs::read_to_string(path) {
+        Ok(content) => Ok(parse(&content)),
+        Err(e) => {
+            let message = format!("Reading auth file {path} failed! {e}");
+            error!("{}", message);
+            Err(Error::new(&message, libapt::ErrorType::ApiUsage))
+        }
+    }
+}
+
+/// Read the machine entries apt uses, i.e. of /etc/apt/auth.conf and the files of /etc/apt/auth.conf.d.
+///
+/// Files which are not readable, e.g. because of missing permissions, are skipped.
+pub fn apt_auth_entries() -> Vec<AuthEntry> {
+    let mut paths = vec![Path::new(APT_AUTH_FILE).to_path_buf()];
+    if let Ok(dir) = fs::read_dir(APT_AUTH_DIR) {
+        let mut files: Vec<_> = dir.flatten().map(|entry| entry.path()).filter(|path| path.is_file()).collect();
+        files.sort();
+        paths.extend(files);
+    }
+
+    let mut entries = Vec::new();
+    for path in paths {
+        match fs::read_to_string(&path) {
+            Ok(content) => entries.extend(parse(&content)),
+            Err(e) => debug!("Skipping auth file {}: {e}", path.display()),
+        }
+    }
+    entries
+}
+
+/// Get the most specific entry for the given URL.
+pub fn find<'a>(entries: &'a [AuthEntry], url: &str) -> Option<&'a AuthEntry> {
+    let url = Url::parse(url).ok()?;
+    entries
+        .iter()
+        .filter(|entry| entry.matches(&url))
+        .max_by_key(|entry| entry.specificity())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_entries() {
+        let content = "# apt auth.conf\nmachine example.org/debian login jane password secret\nmachine https://mirror.example.org:8443\n  login john # comment\n  password other\n  account unused\nmachine nologin.example.org password secret\ndefault login anonymous\n";
+        let entries = parse(content);
+        assert_eq!(
+            entries,
+            [
+                AuthEntry { machine: Some("example.org/debian".to_string()), login: "jane".to_string(), password: Some("secret".to_string()) },
+                AuthEntry { machine: Some("https://mirror.example.org:8443".to_string()), login: "john".to_string(), password: Some("other".to_string()) },
+                AuthEntry { machine: None, login: "anonymous".to_string(), password: None },
+            ]
+        );
+    }
+
+    #[test]
+    fn match_entries() {
+        let entry = |machine: &str| AuthEntry { machine: Some(machine.to_string()), login: "jane".to_string(), password: None };
+        let url = Url::parse("https://example.org/debian/dists/stable/Release").unwrap();
+
+        assert!(entry("example.org").matches(&url));
+        assert!(entry("example.org/debian").matches(&url));
+        assert!(entry("example.org:443/debian/").matches(&url));
+        assert!(entry("https://example.org").matches(&url));
+        assert!(!entry("http://example.org").matches(&url));
+        assert!(!entry("example.org:8443").matches(&url));
+        assert!(!entry("example.org/deb").matches(&url));
+        assert!(!entry("mirror.example.org").matches(&url));
+    }
+
+    #[test]
+    fn find_most_specific() {
+        let entries = parse("default login anonymous\nmachine example.org login jane\nmachine example.org/debian login john\n");
+        assert_eq!(find(&entries, "https://example.org/debian/pool/foo.deb").map(|entry| entry.login.as_str()), Some("john"));
+        assert_eq!(find(&entries, "https://example.org/ubuntu/pool/foo.deb").map(|entry| entry.login.as_str()), Some("jane"));
+        assert_eq!(find(&entries, "https://other.org/").map(|entry| entry.login.as_str()), Some("anonymous"));
+        assert_eq!(find(&entries[1..], "https://other.org/"), None);
+        assert_eq!(find(&entries, "not a url"), None);
+    }
+}
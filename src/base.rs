@@ -0,0 +1,103 @@
+//! Read-only base repositories used for the dependency resolution, e.g. the archive a PPA builds on.
+
+use std::collections::HashMap;
+
+use libapt::{Architecture, Distro, Key, Package, Result, Version};
+use log::{info, warn};
+
+use crate::index;
+use crate::release;
+use crate::options::BaseRepo;
+use crate::transport::Transport;
+
+/// Packages and sources of a base repository.
+#[derive(Debug, Default)]
+pub struct BaseIndices {
+    // Name of the base repository, i.e. URL and suite.
+    pub name: String,
+    // Suite of the base repository.
+    pub suite: String,
+    // Available binary packages. (Architecture, (Package, Versions))
+    pub versions: HashMap<String, HashMap<String, Vec<Version>>>,
+    // Provided virtual packages. (Architecture, (Package, Versions))
+    pub provides: HashMap<String, HashMap<String, Vec<Option<Version>>>>,
+    // Available sources. (Source, Versions)
+    pub sources: HashMap<String, Vec<Version>>,
+}
+
+/// Pockets of a suite which publish its security fixes and updates.
+const POCKETS: [&str; 2] = ["security", "updates"];
+
+/// Test if the other suite is the security or updates pocket of the suite, e.g. jammy-security of jammy.
+pub fn is_pocket_of(other: &str, suite: &str) -> bool {
+    POCKETS.iter().any(|pocket| other == format!("{suite}-{pocket}"))
+}
+
+/// Load the binary indices of the given architectures and the source indices of all components.
+///
+/// The base repository is not checked, so its signature is not verified
+/// and unavailable indices are skipped with a warning.
+pub async fn load(transport: &Transport, base: &BaseRepo, default_suite: Option<&str>, architectures: &[Architecture]) -> Result<BaseIndices> {
+    let suite = base.suite.as_deref().or(default_suite).unwrap_or("jammy");
+    let distro = Distro::repo(&base.url, suite, Key::NoSignatureCheck);
+    let release = release::load(transport, &distro).await?;
+
+    let mut indices = BaseIndices {
+        name: format!("{} {suite}", base.url),
+        suite: suite.to_string(),
+        ..Default::default()
+    };
+
+    let mut architectures = architectures.to_vec();
+    if !architectures.contains(&Architecture::Source) {
+        architectures.push(Architecture::Source);
+    }
+
+    for component in &release.components {
+        for architecture in &architectures {
+            let link = match index::package_index_link(transport, &release, component, architecture).await {
+                Ok(link) => link,
+                Err(e) => {
+                    warn!("Base repository {} has no index {component}/{architecture}: {e}", indices.name);
+                    continue;
+                }
+            };
+            let content = match index::download_index(transport, &link).await {
+                Ok(content) => content,
+                Err(e) => {
+                    warn!("Loading base index {} failed: {e}", link.url);
+                    continue;
+                }
+            };
+
+            if architecture == &Architecture::Source {
+                for stanza in index::stanzas(&content) {
+                    let version = index::field(stanza, "Version").and_then(|version| Version::from_str(version).ok());
+                    if let (Some(source), Some(version)) = (index::field(stanza, "Package"), version) {
+                        indices.sources.entry(source.to_string()).or_default().push(version);
+                    }
+                }
+                continue;
+            }
+
+            let versions = indices.versions.entry(architecture.to_string()).or_default();
+            let provides = indices.provides.entry(architecture.to_string()).or_default();
+            for stanza in index::stanzas(&content) {
+                if let Ok(package) = Package::from_stanza(stanza, &release.distro) {
+                    for provided in package.provides {
+                        provides.entry(provided.name).or_default().push(provided.version);
+                    }
+                    versions.entry(package.package).or_default().push(package.version);
+                }
+            }
+        }
+    }
+
+    info!("Loaded {} packages and {} sources of base repository {}.",
+        indices.versions.values().map(|versions| versions.len()).sum::<usize>(),
+        indices.sources.len(),
+        indices.name
+    );
+
+    Ok(indices)
+}
@@ -0,0 +1,81 @@
+//! Baseline files listing known findings, which don't fail the check.
+
+use std::fs;
+
+use libapt::{Architecture, Error, Result};
+use log::error;
+use serde::{Deserialize, Serialize};
+
+use crate::finding::{Finding, FindingKind};
+
+/// Known finding of a baseline file.
+///
+/// The message is kept for reviewing the baseline, but not compared,
+/// because it may contain changing details like dates.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub struct BaselineEntry {
+    // InRelease URL of the repository.
+    pub repo: String,
+    pub kind: FindingKind,
+    // Affected component. None for findings of the release.
+    pub component: Option<String>,
+    // Affected architecture. None for findings of the release or of all architectures.
+    pub architecture: Option<Architecture>,
+    // Affected binary or source package.
+    pub package: Option<String>,
+    // Affected file.
+    pub url: Option<String>,
+    #[serde(default)]
+    pub message: String,
+}
+
+impl BaselineEntry {
+    /// Create the entry for a finding of the given repository.
+    pub fn new(repo: &str, finding: &Finding) -> BaselineEntry {
+        BaselineEntry {
+            repo: repo.to_string(),
+            kind: finding.kind,
+            component: finding.component.clone(),
+            architecture: finding.architecture.clone(),
+            package: finding.package.clone(),
+            url: finding.url.clone(),
+            message: finding.message.clone(),
+        }
+    }
+
+    /// Test if the entry describes the finding of the given repository.
+    pub fn matches(&self, repo: &str, finding: &Finding) -> bool {
+        self.repo == repo
+            && self.kind == finding.kind
+            && self.component == finding.component
+            && self.architecture == finding.architecture
+            && self.package == finding.package
+            && self.url == finding.url
+    }
+}
+
+/// Read the entries of a baseline file.
+pub fn load(path: &str) -> Result<Vec<BaselineEntry>> {
+    let content = match fs::read_to_string(path) {
+        Ok(content) => content,
+        Err(e) => {
+            let message = format!("Reading baseline {path} failed! {e}");
+            error!("{}", message);
+            return Err(Error::new(&message, libapt::ErrorType::ApiUsage));
+        }
+    };
+
+    match serde_json::from_str(&content) {
+        Ok(entries) => Ok(entries),
+        Err(e) => {
+            let message = format!("Parsing baseline {path} failed! {e}");
+            error!("{}", message);
+            Err(Error::new(&message, libapt::ErrorType::ApiUsage))
+        }
+    }
+}
+
+/// Get the baseline entries of the findings of a repository.
+pub fn entries(repo: &str, findings: &[Finding]) -> Vec<BaselineEntry> {
+    findings.iter().map(|finding| BaselineEntry::new(repo, finding)).collect()
+}
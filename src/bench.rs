@@ -0,0 +1,174 @@
+//! Latency and throughput benchmark of apt mirrors.
+
+use libapt::{Link, Package, Release, Result};
+use log::{info, warn};
+use serde::{Deserialize, Serialize};
+
+use crate::index;
+use crate::options::BenchOptions;
+use crate::transport::Transport;
+
+/// Pool files larger than this are not used for the benchmark.
+const MAX_POOL_FILE_SIZE: usize = 16 * 1024 * 1024;
+
+/// A single timed download.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct BenchSample {
+    pub url: String,
+    // Type of the file: release, index or pool.
+    pub kind: String,
+    // Time until the response headers were received.
+    pub latency_ms: f64,
+    // Time until the full file was received.
+    pub duration_ms: f64,
+    pub bytes: u64,
+}
+
+/// Median and 95th percentile of a measurement.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct Percentiles {
+    pub p50: f64,
+    pub p95: f64,
+}
+
+/// Aggregated timings for one type of file.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct BenchStats {
+    pub kind: String,
+    pub count: usize,
+    pub latency_ms: Percentiles,
+    pub throughput_bytes_per_s: Percentiles,
+}
+
+/// BenchReport groups all measurements of a mirror benchmark.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct BenchReport {
+    pub url: String,
+    pub samples: Vec<BenchSample>,
+    pub stats: Vec<BenchStats>,
+    // Failed downloads. (URL, Error)
+    pub failures: Vec<(String, String)>,
+}
+
+/// Get the nearest-rank percentile of the given values.
+fn percentile(values: &[f64], percent: f64) -> f64 {
+    if values.is_empty() {
+        return 0.0;
+    }
+
+    let mut values = values.to_vec();
+    values.sort_by(|a, b| a.total_cmp(b));
+
+    let rank = ((percent / 100.0) * values.len() as f64).ceil() as usize;
+    values[rank.clamp(1, values.len()) - 1]
+}
+
+/// Aggregate the samples of the given kind.
+fn stats(samples: &[BenchSample], kind: &str) -> BenchStats {
+    let samples: Vec<&BenchSample> = samples.iter().filter(|s| kind == "all" || s.kind == kind).collect();
+
+    let latency: Vec<f64> = samples.iter().map(|s| s.latency_ms).collect();
+    let throughput: Vec<f64> = samples
+        .iter()
+        .filter(|s| s.duration_ms > 0.0)
+        .map(|s| s.bytes as f64 / (s.duration_ms / 1000.0))
+        .collect();
+
+    BenchStats {
+        kind: kind.to_string(),
+        count: samples.len(),
+        latency_ms: Percentiles {
+            p50: percentile(&latency, 50.0),
+            p95: percentile(&latency, 95.0),
+        },
+        throughput_bytes_per_s: Percentiles {
+            p50: percentile(&throughput, 50.0),
+            p95: percentile(&throughput, 95.0),
+        },
+    }
+}
+
+/// Select representative pool files from the given package index.
+///
+/// The packages are picked evenly distributed over the index.
+fn select_pool_files(release: &Release, content: &str, count: usize) -> Vec<String> {
+    let links: Vec<Link> = index::stanzas(content)
+        .filter_map(|stanza| Package::from_stanza(stanza, &release.distro).ok())
+        .map(|package| package.link)
+        .filter(|link| link.size <= MAX_POOL_FILE_SIZE)
+        .collect();
+
+    if links.is_empty() || count == 0 {
+        return Vec::new();
+    }
+
+    let step = (links.len() / count).max(1);
+    links.iter().step_by(step).take(count).map(|link| link.url.clone()).collect()
+}
+
+/// Measure latency and throughput for representative files of the release.
+pub async fn run(transport: &Transport, release: &Release, options: &BenchOptions) -> Result<BenchReport> {
+    let mut files: Vec<(String, String)> = vec![("release".to_string(), release.distro.in_release_url()?)];
+
+    let mut index_links: Vec<Link> = Vec::new();
+    'indices: for architecture in &release.architectures {
+        for component in &release.components {
+            if index_links.len() >= options.samples {
+                break 'indices;
+            }
+            match index::package_index_link(transport, release, component, architecture).await {
+                Ok(link) => index_links.push(link),
+                Err(_) => info!("No link for component {component} and architecture {architecture}. Skipping."),
+            }
+        }
+    }
+
+    for link in &index_links {
+        files.push(("index".to_string(), link.url.clone()));
+    }
+
+    if let Some(link) = index_links.first() {
+        match index::download_index(transport, link).await {
+            Ok(content) => {
+                for url in select_pool_files(release, &content, options.samples) {
+                    files.push(("pool".to_string(), url));
+                }
+            }
+            Err(e) => warn!("Loading package index {} failed, no pool files used: {e}", link.url),
+        }
+    }
+
+    let mut samples = Vec::new();
+    let mut failures = Vec::new();
+
+    for round in 1..=options.rounds {
+        info!("Benchmark round {round} of {}...", options.rounds);
+        for (kind, url) in &files {
+            match transport.timed_download(url).await {
+                Ok((latency, duration, bytes)) => samples.push(BenchSample {
+                    url: url.clone(),
+                    kind: kind.clone(),
+                    latency_ms: latency.as_secs_f64() * 1000.0,
+                    duration_ms: duration.as_secs_f64() * 1000.0,
+                    bytes,
+                }),
+                Err(e) => {
+                    warn!("Download of {url} failed: {e}");
+                    failures.push((url.clone(), e.to_string()));
+                }
+            }
+        }
+    }
+
+    let stats = ["release", "index", "pool", "all"]
+        .iter()
+        .map(|kind| stats(&samples, kind))
+        .collect();
+
+    Ok(BenchReport {
+        url: release.distro.url.clone(),
+        samples,
+        stats,
+        failures,
+    })
+}
@@ -0,0 +1,120 @@
+//! Evaluation of the build dependencies of source packages.
+
+use libapt::PackageVersion;
+use log::debug;
+
+/// Test if an architecture specification, e.g. any, linux-any or amd64, matches the architecture.
+pub fn matches_architecture(specification: &str, architecture: &str) -> bool {
+    match specification {
+        "any" | "linux-any" => true,
+        _ => {
+            let specification = specification
+                .strip_prefix("any-")
+                .or_else(|| specification.strip_prefix("linux-"))
+                .unwrap_or(specification);
+            specification == architecture
+        }
+    }
+}
+
+/// Test if a source with the given Architecture field is built on the architecture.
+pub fn builds_on(architectures: &str, architecture: &str) -> bool {
+    architectures
+        .split_whitespace()
+        .any(|specification| matches_architecture(specification, architecture))
+}
+
+/// Test if an architecture restriction, e.g. "amd64 i386" or "!s390x", applies to the architecture.
+fn architecture_applies(restriction: &str, architecture: &str) -> bool {
+    let specifications: Vec<&str> = restriction.split_whitespace().collect();
+    if specifications.iter().all(|specification| specification.starts_with('!')) {
+        !specifications
+            .iter()
+            .any(|specification| matches_architecture(&specification[1..], architecture))
+    } else {
+        specifications
+            .iter()
+            .any(|specification| matches_architecture(specification, architecture))
+    }
+}
+
+/// Test if the build profile restrictions, e.g. "<!nocheck> <stage1>", apply to a default build.
+///
+/// A list applies if it only contains negated profiles, any matching list is sufficient.
+fn profiles_apply(restrictions: &[&str]) -> bool {
+    restrictions.is_empty()
+        || restrictions
+            .iter()
+            .any(|restriction| restriction.split_whitespace().all(|profile| profile.starts_with('!')))
+}
+
+/// Parse a single alternative, if it applies to the architecture.
+fn alternative(alternative: &str, architecture: &str) -> Option<PackageVersion> {
+    // The version constraint may contain < and >, so it is split off before the restrictions are parsed.
+    let (mut rest, version) = match (alternative.find('('), alternative.find(')')) {
+        (Some(start), Some(end)) if start < end => (
+            format!("{} {}", &alternative[..start], &alternative[end + 1..]),
+            Some(&alternative[start..=end]),
+        ),
+        _ => (alternative.to_string(), None),
+    };
+
+    let mut architectures = None;
+    if let (Some(start), Some(end)) = (rest.find('['), rest.find(']')) {
+        if start < end {
+            architectures = Some(rest[start + 1..end].to_string());
+            rest.replace_range(start..=end, "");
+        }
+    }
+
+    let mut profiles = Vec::new();
+    while let (Some(start), Some(end)) = (rest.find('<'), rest.find('>')) {
+        if end < start {
+            break;
+        }
+        profiles.push(rest[start + 1..end].to_string());
+        rest.replace_range(start..=end, "");
+    }
+
+    if architectures.is_some_and(|architectures| !architecture_applies(&architectures, architecture)) {
+        return None;
+    }
+    let profiles: Vec<&str> = profiles.iter().map(|profile| profile.as_str()).collect();
+    if !profiles_apply(&profiles) {
+        return None;
+    }
+
+    // Architecture qualifiers like :any or :native don't affect the availability.
+    let name = rest.split_whitespace().next()?.split(':').next()?;
+    let relation = match version {
+        Some(version) => format!("{name} {version}"),
+        None => name.to_string(),
+    };
+
+    match PackageVersion::from_str(&relation) {
+        Ok(mut versions) if !versions.is_empty() => Some(versions.remove(0)),
+        Ok(_) => None,
+        Err(e) => {
+            debug!("Ignoring invalid build dependency {relation}: {e}");
+            None
+        }
+    }
+}
+
+/// Parse build dependency fields into the groups of alternatives applying to the architecture.
+///
+/// Restrictions are evaluated for a default build, i.e. without build profiles.
+pub fn groups(value: &str, architecture: &str) -> Vec<Vec<PackageVersion>> {
+    value
+        .split(',')
+        .map(|group| group.trim())
+        .filter(|group| !group.is_empty())
+        .map(|group| {
+            group
+                .split('|')
+                .filter_map(|relation| alternative(relation.trim(), architecture))
+                .collect::<Vec<PackageVersion>>()
+        })
+        .filter(|group| !group.is_empty())
+        .collect()
+}
@@ -1,11 +1,194 @@
 //! Implementation of apt repo check.
 
-use std::collections::HashMap;
+use std::borrow::Cow;
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
 
-use libapt::{Architecture, Error, PackageIndex, PackageVersion, Release, Result, SourceIndex, VersionRelation, get_etag};
-use log::{debug, error, info, warn};
+use chrono::Utc;
+use libapt::{Architecture, Distro, Error, Key, Link, LinkHash, Package, PackageIndex, PackageVersion, Release, Result, Source, SourceIndex, Version, VersionRelation};
+use futures::future;
+use rand::rngs::StdRng;
+use rand::seq::SliceRandom;
+use rand::SeedableRng;
+use futures::stream::{self, StreamExt};
+use pgp::SignedPublicKey;
+use log::{debug, error, info, log, warn, Level};
 use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use tokio::sync::Semaphore;
 
+use crate::base::{self, BaseIndices};
+use crate::baseline::{self, BaselineEntry};
+use crate::build_depends;
+use crate::checkpoint;
+use crate::cnf;
+use crate::compare::MirrorLag;
+use crate::contents;
+use crate::cve;
+use crate::deb;
+use crate::dep11;
+use crate::diff;
+use crate::fields;
+use crate::hint;
+use crate::dsc;
+use crate::finding::{Finding, FindingKind, MaintainerSummary, Severity};
+use crate::history::{self, PhasedUpdate};
+use crate::index;
+use crate::options::{CheckOptions, Resolve, Sample};
+use crate::orphans;
+use crate::oval::{self, UnpatchedDefinition};
+use crate::permissions;
+use crate::package_filter::PackageFilter;
+use crate::pdiff::{self, EdCommand, PdiffEntry, PdiffIndex};
+use crate::progress::Progress;
+use crate::release;
+use crate::release_file::ReleaseFile;
+use crate::signature;
+use crate::timing::{Phase, Timing};
+use crate::translation;
+use crate::transport::{self, Transport};
+use crate::util::{format_dependency, format_duration, format_version, pool_directory};
+
+/// Maximum number of file checks running in parallel.
+///
+/// The connections per host are limited by the transport.
+const PARALLEL_FILE_CHECKS: usize = 64;
+
+/// Maximum number of deb files held in memory for the structure validation.
+const PARALLEL_DEB_CHECKS: usize = 4;
+
+/// Maximum size of deb files downloaded for the structure validation. Larger files are only probed.
+const MAX_DEB_STRUCTURE_SIZE: u64 = 256 * 1024 * 1024;
+
+/// Downloaded bytes of a deb file for the control check, which contain the control tarball of usual packages.
+const CONTROL_PREFIX_LENGTH: usize = 4 * 1024 * 1024;
+
+/// Fields compared between the control file of a deb and the index stanza.
+const CONTROL_FIELDS: [&str; 4] = ["Package", "Version", "Architecture", "Depends"];
+
+/// Maximum number of issues listed in a finding message.
+const MAX_LISTED_ISSUES: usize = 5;
+
+/// Packages every Debian based system needs, expected if a component has Essential packages.
+const CORE_PACKAGES: [&str; 5] = ["base-files", "bash", "coreutils", "dpkg", "libc6"];
+
+/// Allowed values of the Multi-Arch field.
+const MULTI_ARCH_VALUES: [&str; 4] = ["no", "same", "foreign", "allowed"];
+
+/// Versions of Multi-Arch: same packages. (Package, (Architecture, Versions))
+type MultiArchVersions = HashMap<String, BTreeMap<String, BTreeSet<String>>>;
+
+/// Referenced file of a package version. (Package, Version, Link)
+type PackageFile = (String, String, Link);
+
+/// Relation fields resolved by the dependency check.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Relation {
+    PreDepends,
+    Depends,
+    Recommends,
+    Suggests,
+}
+
+impl Relation {
+    /// Get the relations resolved at the given level.
+    fn resolved(resolve: Resolve) -> Vec<Relation> {
+        match resolve {
+            Resolve::Depends => vec![Relation::PreDepends, Relation::Depends],
+            Resolve::Recommends => vec![Relation::PreDepends, Relation::Depends, Relation::Recommends],
+            Resolve::Suggests => vec![Relation::PreDepends, Relation::Depends, Relation::Recommends, Relation::Suggests],
+        }
+    }
+
+    /// Test if the relation must be satisfied to install the package.
+    fn is_hard(&self) -> bool {
+        matches!(self, Relation::PreDepends | Relation::Depends)
+    }
+
+    /// Name of the field in the index.
+    fn field(&self) -> &'static str {
+        match self {
+            Relation::PreDepends => "Pre-Depends",
+            Relation::Depends => "Depends",
+            Relation::Recommends => "Recommends",
+            Relation::Suggests => "Suggests",
+        }
+    }
+
+    /// Name of a single relation in messages.
+    fn name(&self) -> &'static str {
+        match self {
+            Relation::PreDepends => "Pre-dependency",
+            Relation::Depends => "Dependency",
+            Relation::Recommends => "Recommended package",
+            Relation::Suggests => "Suggested package",
+        }
+    }
+
+    /// Kind of the finding if the relation is not satisfied.
+    fn missing(&self) -> FindingKind {
+        match self {
+            Relation::PreDepends => FindingKind::MissingPreDependency,
+            Relation::Depends => FindingKind::MissingDependency,
+            Relation::Recommends => FindingKind::MissingRecommends,
+            Relation::Suggests => FindingKind::MissingSuggests,
+        }
+    }
+
+    /// Get the relations of the parsed package.
+    fn of<'a>(&self, package: &'a Package) -> &'a [PackageVersion] {
+        match self {
+            Relation::PreDepends => &package.pre_depends,
+            Relation::Depends => &package.depends,
+            Relation::Recommends => &package.recommends,
+            Relation::Suggests => &package.suggests,
+        }
+    }
+}
+
+/// Breaks and Conflicts, and Replaces of a package.
+type ConflictRelations = (Vec<PackageVersion>, Vec<PackageVersion>);
+
+/// Build dependencies of a source. (Source, Version, Architectures, Build dependencies)
+type SourceBuildDepends = (String, String, String, String);
+
+/// Provided versions of the virtual packages of an architecture. (Package, Versions)
+type ProvidedVersions = HashMap<String, Vec<Option<Version>>>;
+
+/// Binaries listed by a source. (Source, Version, [(Binary, Architectures)])
+type SourceBinaries = (String, String, Vec<(String, String)>);
+
+/// Size, SHA256 hash and structural issues of a checked file, as far as known.
+type FileInfo = (Option<u64>, Option<String>, Vec<String>);
+
+/// Dependency or source which is not available in the component of the package.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+struct Unresolved {
+    // Finding to report if no other component resolves the reference.
+    finding: Finding,
+    // Referenced item, e.g. "Dependency libc6 (>= 2.34) of package hello".
+    reference: String,
+    // Alternatives of a dependency, or the exact version of a source.
+    alternatives: Vec<PackageVersion>,
+}
+
+/// Working state of the cross checks, which is no part of the report, but saved in checkpoints.
+#[derive(Debug, Deserialize, Serialize)]
+pub struct WorkingState<'a> {
+    source_versions: Cow<'a, HashMap<String, HashMap<String, Vec<Version>>>>,
+    multi_arch_same: Cow<'a, HashMap<String, MultiArchVersions>>,
+    build_depends: Cow<'a, HashMap<String, Vec<SourceBuildDepends>>>,
+    priority_packages: Cow<'a, HashMap<String, (String, BTreeSet<String>)>>,
+    source_binaries: Cow<'a, HashMap<String, Vec<SourceBinaries>>>,
+    binary_versions: Cow<'a, HashMap<String, HashMap<String, Vec<Version>>>>,
+    binary_provides: Cow<'a, HashMap<String, ProvidedVersions>>,
+    unresolved_dependencies: Cow<'a, [Unresolved]>,
+    unresolved_sources: Cow<'a, [Unresolved]>,
+    maintainers: Cow<'a, HashMap<String, String>>,
+    pool_hashes: Cow<'a, HashMap<String, String>>,
+}
 
 /// AptCheck groups all metadata and apt repository check results.
 #[derive(Debug, Deserialize, Serialize)]
@@ -16,248 +199,3234 @@ pub struct AptCheck {
     components: Vec<String>,
     // Architectures to check.
     architectures: Vec<Architecture>,
-    // Parsed binary indices.
+    // Parsed binary indices, only kept if requested.
     binary_indices: Vec<PackageIndex>,
-    // Parsed source indices. (Component, Index)
+    // Parsed source indices, only kept if requested. (Component, Index)
     source_indices: HashMap<String, SourceIndex>,
-    // List of found issues. (Component, Architecture, found Issue)
-    issues: Vec<(String, Architecture, Error)>,
-    // (Component, Package, Dependency)
-    missing_packages: Vec<(String, String, PackageVersion)>,
-    // (Component, Package, Source)
-    missing_sources: Vec<(String, String, String)>,
-    // Check existence of referenced files
-    check_files: bool
+    // Available source versions. (Component, (Source, Versions))
+    #[serde(skip)]
+    source_versions: HashMap<String, HashMap<String, Vec<Version>>>,
+    // Versions of Multi-Arch: same packages. (Component, Versions)
+    #[serde(skip)]
+    multi_arch_same: HashMap<String, MultiArchVersions>,
+    // Build dependencies of the sources. (Component, [(Source, Version, Architectures, Build dependencies)])
+    #[serde(skip)]
+    build_depends: HashMap<String, Vec<SourceBuildDepends>>,
+    // Architectures of the required and important packages. (Package, (Priority, Architectures))
+    #[serde(skip)]
+    priority_packages: HashMap<String, (String, BTreeSet<String>)>,
+    // Binaries listed by the sources. (Component, [(Source, Version, [(Binary, Architectures)])])
+    #[serde(skip)]
+    source_binaries: HashMap<String, Vec<SourceBinaries>>,
+    // Available binary packages of all components. (Architecture, (Package, Versions))
+    #[serde(skip)]
+    binary_versions: HashMap<String, HashMap<String, Vec<Version>>>,
+    // Provided virtual packages of all components. (Architecture, (Package, Versions))
+    #[serde(skip)]
+    binary_provides: HashMap<String, ProvidedVersions>,
+    // SHA256 hashes of the pool files of all binary indices. (Filename, Hash)
+    #[serde(skip)]
+    pool_hashes: HashMap<String, String>,
+    // Dependencies not satisfied within their component, resolved by the cross check.
+    #[serde(skip)]
+    unresolved_dependencies: Vec<Unresolved>,
+    // Sources not available in the component of the package, resolved by the cross check.
+    #[serde(skip)]
+    unresolved_sources: Vec<Unresolved>,
+    // List of findings.
+    findings: Vec<Finding>,
+    // Maintainers of the binary and source packages, only kept for the summary. (Package, Maintainer)
+    #[serde(skip)]
+    maintainers: HashMap<String, String>,
+    // Findings per maintainer. (Maintainer, Summary)
+    maintainer_summary: BTreeMap<String, MaintainerSummary>,
+    // (Component, Architecture) pairs which are already checked.
+    completed: Vec<(String, Architecture)>,
+    // The check was stopped before all checks were done.
+    partial: bool,
+    // The check was interrupted by a signal.
+    aborted: bool,
+    // The release level checks are already done.
+    release_checked: bool,
+    // Key which verified the InRelease signature, with its ID.
+    signing_key: Option<String>,
+    // Lag behind the upstream archive, if an upstream is given.
+    mirror_lag: Option<MirrorLag>,
+    // Definitions of the OVAL feed which are not patched, if a feed is given.
+    unpatched_definitions: Vec<UnpatchedDefinition>,
+    // Binary packages with a valid Phased-Update-Percentage.
+    phased_updates: Vec<PhasedUpdate>,
+    // Durations of the check phases.
+    timings: Vec<Timing>,
+    // User-provided check options.
+    options: CheckOptions,
+    // Loaded base repositories, loaded again when resuming.
+    #[serde(skip)]
+    base_indices: Vec<BaseIndices>,
+    // Shared HTTP client.
+    #[serde(skip)]
+    transport: Arc<Transport>,
+    // InRelease file as loaded with the release, downloaded on first use if not given.
+    #[serde(skip)]
+    in_release: Option<String>,
+    // End of the time budget.
+    #[serde(skip)]
+    deadline: Option<Instant>,
+    // Known findings of the baseline, loaded again when resuming.
+    #[serde(skip)]
+    baseline: Vec<BaselineEntry>,
+    // Packages whose dependencies, sources and files are checked, compiled again when resuming.
+    #[serde(skip)]
+    package_filter: PackageFilter,
 }
 
 impl AptCheck {
     /// Initialize the AptCheck structure.
-    pub fn new(release: Release, components: Vec<String>, architectures: Vec<String>, check_files: bool) -> Result<AptCheck> {
-        let components = if components.is_empty() {
+    pub fn new(release: Release, options: CheckOptions, transport: Arc<Transport>) -> Result<AptCheck> {
+        if options.binaries_only && options.sources_only {
+            let message = "Binaries only and sources only exclude each other!";
+            error!("{}", message);
+            return Err(Error::new(message, libapt::ErrorType::ApiUsage));
+        }
+
+        let components = if options.components.is_empty() {
             release.components.clone()
         } else {
-            components
+            options.components.clone()
         };
     
-        let architectures = if architectures.is_empty() {
+        let architectures = if options.architectures.is_empty() {
             release.architectures.clone()
         } else {
             let mut result: Vec<Architecture> = Vec::new();
-            for arch in architectures {
-                result.push(Architecture::from_str(&arch)?);
+            for arch in &options.architectures {
+                result.push(Architecture::from_str(arch)?);
             }
             result
         };
 
+        let components: Vec<String> = components.into_iter().filter(|component| !options.skip_components.contains(component)).collect();
+        let architectures: Vec<Architecture> = architectures
+            .into_iter()
+            .filter(|architecture| !options.skip_architectures.contains(&architecture.to_string()))
+            .collect();
+        if !options.skip_components.is_empty() || !options.skip_architectures.is_empty() {
+            let names: Vec<String> = architectures.iter().map(|architecture| architecture.to_string()).collect();
+            info!("Checking components {} and architectures {} after skipping.", components.join(", "), names.join(", "));
+        }
+
         Ok(AptCheck {
-            components: components,
-            architectures: architectures,
+            components,
+            architectures,
             binary_indices: Vec::new(),
             source_indices: HashMap::new(),
-            issues: Vec::new(),
-            missing_packages: Vec::new(),
-            missing_sources: Vec::new(),
-            release: release,
-            check_files: check_files,
+            source_versions: HashMap::new(),
+            multi_arch_same: HashMap::new(),
+            build_depends: HashMap::new(),
+            priority_packages: HashMap::new(),
+            source_binaries: HashMap::new(),
+            binary_versions: HashMap::new(),
+            binary_provides: HashMap::new(),
+            pool_hashes: HashMap::new(),
+            unresolved_dependencies: Vec::new(),
+            unresolved_sources: Vec::new(),
+            findings: Vec::new(),
+            maintainers: HashMap::new(),
+            maintainer_summary: BTreeMap::new(),
+            release,
+            completed: Vec::new(),
+            partial: false,
+            aborted: false,
+            release_checked: false,
+            signing_key: None,
+            mirror_lag: None,
+            unpatched_definitions: Vec::new(),
+            phased_updates: Vec::new(),
+            timings: Vec::new(),
+            options,
+            base_indices: Vec::new(),
+            transport,
+            in_release: None,
+            deadline: None,
+            baseline: Vec::new(),
+            package_filter: PackageFilter::default(),
         })
     }
 
+    /// Get the findings of the check.
+    pub fn findings(&self) -> &[Finding] {
+        &self.findings
+    }
+
+    /// Get the checked release.
+    pub fn release(&self) -> &Release {
+        &self.release
+    }
+
+    /// Get the lag behind the upstream archive. None if no upstream is given or it is unavailable.
+    pub fn mirror_lag(&self) -> Option<&MirrorLag> {
+        self.mirror_lag.as_ref()
+    }
+
+    /// Get the (Component, Architecture) pairs which are checked.
+    pub fn completed(&self) -> &[(String, Architecture)] {
+        &self.completed
+    }
+
+    /// Get the working state of the cross checks for a checkpoint.
+    pub fn working_state(&self) -> WorkingState<'_> {
+        WorkingState {
+            source_versions: Cow::Borrowed(&self.source_versions),
+            multi_arch_same: Cow::Borrowed(&self.multi_arch_same),
+            build_depends: Cow::Borrowed(&self.build_depends),
+            priority_packages: Cow::Borrowed(&self.priority_packages),
+            source_binaries: Cow::Borrowed(&self.source_binaries),
+            binary_versions: Cow::Borrowed(&self.binary_versions),
+            binary_provides: Cow::Borrowed(&self.binary_provides),
+            unresolved_dependencies: Cow::Borrowed(&self.unresolved_dependencies),
+            unresolved_sources: Cow::Borrowed(&self.unresolved_sources),
+            maintainers: Cow::Borrowed(&self.maintainers),
+            pool_hashes: Cow::Borrowed(&self.pool_hashes),
+        }
+    }
+
+    /// Restore the working state of the cross checks from a checkpoint.
+    pub fn restore_working_state(&mut self, state: WorkingState) {
+        self.source_versions = state.source_versions.into_owned();
+        self.multi_arch_same = state.multi_arch_same.into_owned();
+        self.build_depends = state.build_depends.into_owned();
+        self.priority_packages = state.priority_packages.into_owned();
+        self.source_binaries = state.source_binaries.into_owned();
+        self.binary_versions = state.binary_versions.into_owned();
+        self.binary_provides = state.binary_provides.into_owned();
+        self.unresolved_dependencies = state.unresolved_dependencies.into_owned();
+        self.unresolved_sources = state.unresolved_sources.into_owned();
+        self.maintainers = state.maintainers.into_owned();
+        self.pool_hashes = state.pool_hashes.into_owned();
+    }
+
+    /// Replace the HTTP client, e.g. after loading a checkpoint.
+    pub fn set_transport(&mut self, transport: Arc<Transport>) {
+        self.transport = transport;
+    }
+
+    /// Set the InRelease file the release was loaded from, so it is not downloaded again.
+    pub fn set_in_release(&mut self, content: String) {
+        self.in_release = Some(content);
+    }
+
+    /// Get the InRelease file, downloading it if it was not given.
+    async fn in_release(&mut self) -> Result<String> {
+        if let Some(content) = &self.in_release {
+            return Ok(content.clone());
+        }
+        let url = self.release.distro.in_release_url()?;
+        let content = String::from_utf8_lossy(&self.transport.download(&url).await?).to_string();
+        self.in_release = Some(content.clone());
+        Ok(content)
+    }
+
+    /// Replace the options of a checkpointed check with the current ones.
+    ///
+    /// The components and architectures of the checkpoint are kept, as the completed ones refer to them.
+    pub fn set_options(&mut self, options: CheckOptions) {
+        let scope = |options: &CheckOptions| (options.components.clone(), options.architectures.clone(), options.skip_components.clone(), options.skip_architectures.clone());
+        if scope(&self.options) != scope(&options) {
+            warn!("Components or architectures differ from the checkpoint, continuing with components {} and architectures {}.",
+                self.components.join(", "),
+                self.architectures.iter().map(|architecture| architecture.to_string()).collect::<Vec<String>>().join(", "));
+        }
+        self.options = options;
+    }
+
+    /// Test if a checkpointed check can be continued for the given release.
+    ///
+    /// The release must be the same, else the collected results are outdated.
+    pub fn is_resumable_for(&self, release: &Release) -> bool {
+        self.release.distro.in_release_url() == release.distro.in_release_url()
+            && self.release.date == release.date
+    }
+
+    /// Mark the given component and architecture as checked and write a checkpoint.
+    fn complete(&mut self, component: &str, architecture: &Architecture) {
+        self.completed.push((component.to_string(), architecture.clone()));
+
+        if let Some(state_dir) = &self.options.state_dir {
+            // A failing checkpoint is no reason to abort the check.
+            let _ = checkpoint::save(self, state_dir);
+        }
+    }
+
+    /// Record and log a finding.
+    ///
+    /// Findings listed in the baseline are marked as known and only logged as info.
+    fn add_finding(&mut self, finding: Finding) {
+        let finding = match self.options.severity_overrides.get(&finding.kind) {
+            Some(severity) => finding.with_severity(*severity),
+            None => finding,
+        };
+
+        let known = !self.baseline.is_empty() && {
+            let repo = self.release.distro.in_release_url().unwrap_or_default();
+            self.baseline.iter().any(|entry| entry.matches(&repo, &finding))
+        };
+        let finding = finding.with_known(known);
+        let finding = match finding.hint {
+            Some(_) => finding,
+            None => {
+                let hint = hint::for_finding(&finding, &self.release.distro);
+                finding.with_hint(hint)
+            }
+        };
+
+        let level = match finding.severity {
+            _ if finding.known => Level::Info,
+            Severity::Error => Level::Error,
+            Severity::Warning => Level::Warn,
+            Severity::Info => Level::Info,
+        };
+        let prefix = if finding.known { "Known finding " } else { "" };
+        let architecture = finding.architecture.as_ref().map(|architecture| architecture.to_string());
+        // The kind is logged like in the result.
+        let kind = serde_json::to_value(finding.kind).ok().and_then(|kind| kind.as_str().map(|kind| kind.to_string()));
+        log!(
+            level,
+            kind = kind.as_deref(), severity:% = finding.severity, component = finding.component.as_deref(),
+            arch = architecture.as_deref(), package = finding.package.as_deref(), known = finding.known;
+            "{prefix}{finding}"
+        );
+        self.findings.push(finding);
+    }
+
+    /// Start the progress report of a phase, if requested.
+    fn progress(&self, phase: Phase, component: &str, architecture: &Architecture, unit: &'static str, total: usize) -> Progress {
+        let transport = self.options.progress.then(|| self.transport.clone());
+        Progress::new(phase, component, architecture, unit, total, transport)
+    }
+
+    /// Record the duration of a check phase.
+    pub fn add_timing(&mut self, timing: Timing) {
+        let architecture = timing.architecture.as_ref().map(|architecture| architecture.to_string());
+        debug!(
+            phase:% = timing.phase, component = timing.component.as_deref(), arch = architecture.as_deref(), duration_ms = timing.duration_ms;
+            "Phase {} took {:.1}ms.", timing.phase, timing.duration_ms
+        );
+        self.timings.push(timing);
+    }
+
+    /// Test if the time budget is exhausted.
+    fn is_out_of_time(&self) -> bool {
+        is_expired(self.deadline)
+    }
+
+    /// Mark the check as interrupted.
+    pub fn abort(&mut self) {
+        self.aborted = true;
+        self.partial = true;
+    }
+
+    /// Test if the check was stopped before all checks were done.
+    pub fn is_partial(&self) -> bool {
+        self.partial
+    }
+
+    /// Test if the given component and architecture was already checked.
+    fn is_completed(&self, component: &str, architecture: &Architecture) -> bool {
+        self.completed.iter().any(|(c, a)| c == component && a == architecture)
+    }
+
     /// Execute the apt repository check.
     /// 
-    /// Returns true if no issues were found, false else.
+    /// Returns true if no issues of at least the fail_on severity were found,
+    /// except known findings of the baseline, false else.
     /// In case of major issues the error is provided as result.
     pub async fn check_repo(&mut self) -> Result<bool> {
+        self.partial = false;
+        self.aborted = false;
+        self.deadline = self.options.max_duration.map(|seconds| Instant::now() + Duration::from_secs(seconds));
+
+        // A baseline which gets written is not compared.
+        self.baseline = match &self.options.baseline {
+            Some(path) if !self.options.write_baseline => baseline::load(path)?,
+            _ => Vec::new(),
+        };
+        // Findings of the compared report are known like the findings of the baseline.
+        if let Some(path) = &self.options.compare_with {
+            let repos = diff::load(path)?;
+            let repo = self.release.distro.in_release_url().unwrap_or_default();
+            match diff::findings_of(&repos, &repo, true) {
+                Some(findings) => self.baseline.extend(baseline::entries(&repo, findings)),
+                None => warn!("Repo {repo} is missing in report {path}, all findings are new."),
+            }
+        }
+
+        self.package_filter = PackageFilter::new(&self.options.packages)?;
+        if !self.options.packages.is_empty() {
+            info!("Checking dependencies, sources and files of packages matching {}.", self.options.packages.join(", "));
+        }
+
+        if self.options.check_files && self.options.sample != Sample::All {
+            // The seed is part of the result, so the sample can be reproduced.
+            let seed = *self.options.seed.get_or_insert_with(rand::random);
+            info!("Sampling files using seed {seed}.");
+        }
+
+        if self.options.check_compliance {
+            info!("Checking compliance of InRelease file...");
+            match self.release.check_compliance() {
+                Ok(_) => info!("InRelease complies to Debian policy."),
+                Err(e) => warn!("InRelease does not comply to Debian policy: {e}"),
+            }
+        }
 
-        info!("Checking compliance of InRelease file...");
-        match self.release.check_compliance() {
-            Ok(_) => info!("InRelease complies to Debian policy."),
-            Err(e) => warn!("InRelease does not comply to Debian policy: {e}"),
+        if !self.release_checked {
+            info!("Checking release files...");
+            self.check_release().await?;
+            self.release_checked = true;
         }
     
         // Run check focussing on one component.
         info!("Checking single components...");
         self.check().await?;
 
+        let suite = self.release.distro.name.as_deref().unwrap_or_default();
+        let has_pockets = self.options.base_repos.iter().any(|base| base.suite.as_deref().is_some_and(|other| base::is_pocket_of(other, suite)));
+        let pockets = self.options.check_pockets && has_pockets;
+        if self.base_indices.is_empty() && (self.options.check_dependencies || self.options.check_sources || self.options.check_build_depends || pockets) {
+            self.load_base_repos().await;
+        }
+
         // Run checks requiring more components, e.g. availability of dependencies.
         info!("Checking cross components...");
+        let start = Instant::now();
         self.cross_check()?;
-        
-        // Log results
-        // TODO: better report!
-        info!("Found {} issues.", self.issues.len());
-        for (component, architecture, issue) in &self.issues {
-            error!("Found issue in component {component} for architecture {architecture}: {issue}");
+        self.add_timing(Timing::new(Phase::CrossCheck, start.elapsed()));
+
+        if pockets {
+            self.check_pockets();
+        }
+
+        if self.options.check_orphans {
+            self.check_orphans().await;
+        }
+        if self.options.check_permissions {
+            self.check_permissions();
+        }
+        if self.options.check_cves {
+            self.check_cves().await;
+        }
+        if self.options.oval.is_some() {
+            self.check_oval().await;
+        }
+        if let Some(path) = self.options.history.clone() {
+            self.check_phasing(&path);
         }
 
-        info!("Found {} missing binary dependencies.", self.missing_packages.len());
-        for (component, package, dependency) in &self.missing_packages {
-            warn!("Component {component}: Dependency {:?} of package {} is missing.", dependency, package);
+        if self.options.maintainer_summary {
+            self.summarize_maintainers();
         }
 
-        info!("Found {} missing sources.", self.missing_sources.len());
-        for (component, package, source) in &self.missing_sources {
-            warn!("Component {component}: Source {} of package {} is missing.", source, package);
+        // Log results
+        for severity in [Severity::Error, Severity::Warning, Severity::Info] {
+            let count = self.findings.iter().filter(|f| f.severity == severity && !f.known).count();
+            info!("Found {count} findings with severity {severity}.");
+        }
+        let known = self.findings.iter().filter(|f| f.known).count();
+        if known > 0 {
+            info!("Found {known} known findings of the baseline.");
         }
     
-        // TODO: fix check and consider package metadata issues
-        Ok(self.issues.is_empty() && self.missing_packages.is_empty() && self.missing_sources.is_empty())
+        if self.partial {
+            warn!("Time budget exhausted, the check result is partial.");
+        }
+
+        // TODO: consider package metadata issues
+        Ok(self.findings.iter().all(|f| f.known || f.severity < self.options.fail_on) && !self.partial)
     }
     
-    /// Do checks involving multiple components.
-    fn cross_check(&mut self) -> Result<()> {
-        // TODO: search for missing sources and packages in other components.
+    /// Group the findings of the packages by their maintainers.
+    fn summarize_maintainers(&mut self) {
+        let mut summary: BTreeMap<String, MaintainerSummary> = BTreeMap::new();
+        for finding in &self.findings {
+            let maintainer = finding.package.as_ref().and_then(|package| self.maintainers.get(package));
+            if let (Some(package), Some(maintainer)) = (&finding.package, maintainer) {
+                summary.entry(maintainer.clone()).or_default().add(package, finding.severity);
+            }
+        }
+        self.maintainer_summary = summary;
+    }
+
+    /// Do checks of the release files, not specific to a component.
+    async fn check_release(&mut self) -> Result<()> {
+        if self.options.check_detached_release {
+            let start = Instant::now();
+            self.check_detached_release().await;
+            self.add_timing(Timing::new(Phase::DetachedRelease, start.elapsed()));
+        }
+
+        self.check_valid_until();
+        self.check_release_age();
+        if self.options.check_hash_policy {
+            self.check_release_hashes();
+        }
+        self.check_automatic_flags().await;
+        self.check_mirror_lag().await;
+        if self.options.check_signature {
+            self.check_signing_key().await;
+        }
+
         Ok(())
     }
 
-    // Do checks for a single component.
-    async fn check(&mut self) -> Result<()> {
-        // Check sources for all component.
-        // This also initializes the source package index 
-        // which is required to check the availability of 
-        // source packages.
-        for component in &self.components.clone() {
-            match self.check_source_component(component).await {
-                Ok(_) => {},
-                Err(e) => {
-                    let message = format!("Checking sources of component {component} failed: {e}");
-                    error!("{}", message);
-                    self.issues.push((component.clone(), Architecture::Source, Error::new(&message, libapt::ErrorType::Download)));
+    /// Check that the signing key is not expired, stays valid for the required time,
+    /// uses strong algorithms and can sign.
+    async fn check_signing_key(&mut self) {
+        let key = match signature::load_key(&self.transport, &self.release.distro.key).await {
+            Ok(Some(key)) => key,
+            Ok(None) => {
+                debug!("No key given, skipping key checks.");
+                if !self.options.expected_fingerprints.is_empty() {
+                    let message = "No key given, so the fingerprint of the signing key can't be verified.";
+                    self.add_finding(Finding::for_release(FindingKind::UnexpectedSigningKey, message));
                 }
+                return;
             }
+            Err(e) => {
+                warn!("Loading key failed, skipping key checks: {e}");
+                return;
+            }
+        };
+
+        if !self.options.expected_fingerprints.is_empty() {
+            self.check_fingerprints(&key).await;
         }
 
-        // Check the binary indices for all architectures and components.
-        for component in &self.components.clone() {
-            for architecture in &self.architectures.clone() {
-                if architecture == &Architecture::Source {
-                    continue;
-                }
+        let id = signature::key_id(&key);
+        self.signing_key = match &self.release.distro.key {
+            Key::ArmoredKey(location) | Key::Key(location) => Some(format!("{id} ({})", signature::key_location(location))),
+            Key::NoSignatureCheck => None,
+        };
 
-                match self.check_binary_component(component, architecture).await {
-                    Ok(_) => {},
-                    Err(e) => {
-                        let message = format!("Checking component {component} for architecture {architecture} failed: {e}");
-                        error!("{}", message);
-                        self.issues.push((component.clone(), architecture.clone(), Error::new(&message, libapt::ErrorType::Download)));
-                    }
+        let now = Utc::now();
+        match key.expires_at() {
+            Some(expires_at) => {
+                let remaining = expires_at.signed_duration_since(now);
+                let min_validity = chrono::Duration::seconds(self.options.key_min_validity.unwrap_or_default() as i64);
+                if remaining <= chrono::Duration::zero() {
+                    let message = format!("Signing key {id} expired at {}.", expires_at.to_rfc2822());
+                    self.add_finding(Finding::for_release(FindingKind::KeyExpired, &message));
+                } else if remaining < min_validity {
+                    let message = format!("Signing key {id} expires at {}, in {}.", expires_at.to_rfc2822(), format_duration(remaining.num_seconds()));
+                    self.add_finding(Finding::for_release(FindingKind::KeyExpiring, &message));
+                } else {
+                    info!("Signing key {id} is valid until {}.", expires_at.to_rfc2822());
                 }
             }
+            None => info!("Signing key {id} doesn't expire."),
+        }
+
+        let weaknesses = signature::key_weaknesses(&key);
+        if !weaknesses.is_empty() {
+            let message = format!("Signing key {id} is weak: {}.", weaknesses.join(", "));
+            self.add_finding(Finding::for_release(FindingKind::WeakKey, &message));
+        }
+
+        if !signature::has_signing_key(&key, now) {
+            let message = format!("Signing key {id} has no valid key capable of signing.");
+            self.add_finding(Finding::for_release(FindingKind::MissingSigningKey, &message));
         }
-    
-        Ok(())
     }
-    
-    async fn check_binary_component(&mut self, component: &str, architecture: &Architecture) -> Result<()> {
-        info!("Checking binary index of component {component} for architecture {architecture}...");
-        let index = PackageIndex::new(&self.release, component, architecture).await?;
-
-        for package in index.packages() {
-            debug!("Checking binary package {package}...");
-            // TODO: support for multiple source versions!
-            let package = match index.get(&package, None) {
-                Some(package) => package,
-                None => {
-                    let message = format!("Package {} of component {} and architecture {} is missing.", package, component, architecture);
-                    error!("{}", message);
-                    self.issues.push((component.to_string(), architecture.clone(), Error::new(&message, libapt::ErrorType::Download)));
 
-                    continue;
-                }
-            };
+    /// Check that the release is not expired and stays valid for the required time.
+    fn check_valid_until(&mut self) {
+        let valid_until = match self.release.valid_until {
+            Some(valid_until) => valid_until,
+            None => {
+                debug!("Release has no Valid-Until field.");
+                return;
+            }
+        };
 
-            if self.check_files {
-                debug!("Checking file of binary package {}...", package.package);
-                // Check existence of linked deb file.
-                match get_etag(&package.link.url).await {
-                    Ok(_) => {} // pass!
-                    Err(e) => {
-                        let message = format!("File {} of source {} is broken: {e}", &package.link.url, package.package);
-                        error!("{}", message);
-                        self.issues.push((component.to_string(), Architecture::Source, Error::new(&message, libapt::ErrorType::Download)));
-                    }
-                }
+        let remaining = valid_until.signed_duration_since(Utc::now());
+        let min_validity = chrono::Duration::seconds(self.options.min_validity.unwrap_or_default() as i64);
+
+        let finding = if remaining <= chrono::Duration::zero() {
+            let message = format!("Release expired at {}.", valid_until.to_rfc2822());
+            Finding::for_release(FindingKind::ReleaseExpired, &message)
+        } else if remaining < min_validity {
+            let message = format!(
+                "Release expires at {}, in {}.",
+                valid_until.to_rfc2822(),
+                format_duration(remaining.num_seconds())
+            );
+            Finding::for_release(FindingKind::ReleaseExpiring, &message)
+        } else {
+            info!("Release is valid until {}.", valid_until.to_rfc2822());
+            return;
+        };
+
+        let finding = match self.options.validity_severity {
+            Some(severity) => finding.with_severity(severity),
+            None => finding,
+        };
+        self.add_finding(finding);
+    }
+
+    /// Check that NotAutomatic and ButAutomaticUpgrades are consistent and match the expectations.
+    ///
+    /// apt only recognizes the value yes, and ignores ButAutomaticUpgrades without NotAutomatic.
+    async fn check_automatic_flags(&mut self) {
+        let payload = self.in_release().await.and_then(|content| signature::cleartext_payload(&content));
+        let fields = match payload {
+            Ok(payload) => ReleaseFile::parse(&payload).fields,
+            Err(e) => {
+                warn!("Loading InRelease failed, skipping NotAutomatic check: {e}");
+                return;
             }
+        };
 
-            debug!("Checking dependencies of binary package {}...", package.package);
-            // Check for dependent packages.
-            for dependency in &package.depends {
-                let name = &dependency.name;
-                debug!("Checking dependency {name} of binary package {}...", package.package);
-                let version = Some(dependency.clone());
-                match index.get(name, version) {
-                    Some(_) => {} // OK
-                    None => {
-                        // Missing dependency
-                        self.missing_packages.push((component.to_string(), package.package.clone(), dependency.clone()));
-                    }
-                }
+        let mut findings = Vec::new();
+        let mut flags = Vec::new();
+        for field in ["NotAutomatic", "ButAutomaticUpgrades"] {
+            let value = fields.get(field).map(|value| value.as_str());
+            if let Some(value) = value.filter(|value| *value != "yes" && *value != "no") {
+                let message = format!("{field} has value {value}, but apt only recognizes yes.");
+                findings.push(Finding::for_release(FindingKind::InvalidAutomaticFlags, &message));
             }
+            flags.push(value == Some("yes"));
+        }
+        let (not_automatic, but_automatic_upgrades) = (flags[0], flags[1]);
 
-            debug!("Checking source of binary package {}...", package.package); 
-            // Check for source package.
-            if let Some(source) = &package.source {
-                if let Some(source_index) = self.source_indices.get(component) {
-                    let vd = PackageVersion {
-                        name: source.clone(),
-                        architecture: package.architecture.clone(),
-                        relation: Some(VersionRelation::Exact),
-                        version: Some(package.version.clone()),
-                    };
-                    match source_index.get(source, Some(vd)) {
-                        Some(_) => {}, // Ok.
-                        None => {
-                            // Missing source package
-                            self.missing_sources.push((component.to_string(), package.package.clone(), source.clone()));
-                        }
-                    }
-                } else {
-                    warn!("No source index for component {component} found!");
-                }
-            } else {
-                warn!("No source for package {} of component {component} found!", package.package);
+        if but_automatic_upgrades && !not_automatic {
+            let message = "ButAutomaticUpgrades: yes requires NotAutomatic: yes, apt ignores it.";
+            findings.push(Finding::for_release(FindingKind::InvalidAutomaticFlags, message));
+        }
+        if self.options.expect_not_automatic && !not_automatic {
+            let message = "NotAutomatic: yes is expected, but missing. apt installs the packages of the suite automatically.";
+            findings.push(Finding::for_release(FindingKind::UnexpectedAutomaticFlags, message));
+        }
+        if self.options.expect_but_automatic_upgrades && !but_automatic_upgrades {
+            let message = "ButAutomaticUpgrades: yes is expected, but missing. apt doesn't upgrade installed packages of the suite.";
+            findings.push(Finding::for_release(FindingKind::UnexpectedAutomaticFlags, message));
+        }
+        if findings.is_empty() {
+            debug!("NotAutomatic is {not_automatic}, ButAutomaticUpgrades is {but_automatic_upgrades}.");
+        }
+
+        let in_release_url = self.release.distro.in_release_url().unwrap_or_default();
+        for finding in findings {
+            self.add_finding(finding.with_url(&in_release_url));
+        }
+    }
+
+    /// Add a finding of the hash policy, using the configured severity.
+    fn add_weak_hash_finding(&mut self, finding: Finding) {
+        let finding = match self.options.weak_hash_severity {
+            Some(severity) => finding.with_severity(severity),
+            None => finding,
+        };
+        self.add_finding(finding);
+    }
+
+    /// Check that all files of the Release file have SHA256 or SHA512 hashes, as apt refuses MD5 and SHA1 only.
+    fn check_release_hashes(&mut self) {
+        let mut weak: Vec<&str> = self
+            .release
+            .links
+            .values()
+            .filter(|link| !link.hashes.contains_key(&LinkHash::Sha256) && !link.hashes.contains_key(&LinkHash::Sha512))
+            .map(|link| link.url.as_str())
+            .collect();
+        if weak.is_empty() {
+            return;
+        }
+        weak.sort();
+
+        let message = if weak.len() == self.release.links.len() {
+            "Release file provides no SHA256 or SHA512 hashes, only MD5Sum or SHA1. apt refuses the repository.".to_string()
+        } else {
+            format!("{} files of the Release file have no SHA256 or SHA512 hash, e.g. {}. apt refuses them.", weak.len(), weak[0])
+        };
+        let in_release_url = self.release.distro.in_release_url().unwrap_or_default();
+        self.add_weak_hash_finding(Finding::for_release(FindingKind::WeakHashes, &message).with_url(&in_release_url));
+    }
+
+    /// Check that a binary or source stanza has SHA256 or SHA512 hashes.
+    fn check_stanza_hashes(&mut self, component: &str, architecture: &Architecture, stanza: &str, package: &str, version: &str) {
+        if let Some(issue) = fields::hash_issue(stanza, architecture == &Architecture::Source) {
+            let message = format!("Package {package} {version} has {issue}, which apt refuses.");
+            self.add_weak_hash_finding(Finding::new(FindingKind::WeakHashes, component, architecture, &message).with_package(package).with_version(version));
+        }
+    }
+
+    /// Check that the release is not older than the maximum age.
+    fn check_release_age(&mut self) {
+        let max_age = match self.options.max_age {
+            Some(max_age) => chrono::Duration::seconds(max_age as i64),
+            None => return,
+        };
+
+        let date = match self.release.date {
+            Some(date) => date,
+            None => {
+                warn!("Release has no Date field, skipping age check.");
+                return;
             }
+        };
+
+        let age = Utc::now().signed_duration_since(date);
+        if age > max_age {
+            let message = format!(
+                "Release is outdated: Date {} is {} old, the maximum age is {}.",
+                date.to_rfc2822(),
+                format_duration(age.num_seconds()),
+                format_duration(max_age.num_seconds())
+            );
+            self.add_finding(Finding::for_release(FindingKind::ReleaseOutdated, &message));
+        } else {
+            info!("Release is {} old.", format_duration(age.num_seconds()));
         }
-    
-        Ok(())
     }
-    
-    async fn check_source_component(&mut self, component: &str) -> Result<()> {
-        info!("Checking sources of component {component}...");
 
-        let index = SourceIndex::new(&self.release, component).await?;
+    /// Compare the release with the upstream archive and check that the lag is below the maximum.
+    ///
+    /// The upstream archive is expected to have the same layout, and its InRelease file is verified with the same key.
+    async fn check_mirror_lag(&mut self) {
+        let upstream = match &self.options.upstream {
+            Some(upstream) => Distro { url: upstream.clone(), ..self.release.distro.clone() },
+            None => return,
+        };
 
-        info!("Checking sources packages of component {component}...");
-        for source in index.packages() {
-            debug!("Checking source {source}...");
-            // TODO: support for multiple source versions!
-            let package = match index.get(&source, None) {
-                Some(package) => package,
-                None => {
-                    let message = format!("Source {} of component {} is missing.", source, component);
-                    error!("{}", message);
-                    self.issues.push((component.to_string(), Architecture::Source, Error::new(&message, libapt::ErrorType::Download)));
+        let upstream_release = match release::load(&self.transport, &upstream).await {
+            Ok(release) => release,
+            Err(e) => {
+                warn!("Loading upstream release of {} failed, skipping lag check: {e}", upstream.url);
+                return;
+            }
+        };
 
-                    continue;
-                }
-            };
+        let lag = MirrorLag::new(&self.release, &upstream_release);
+        let indices = lag.differing_indices.len();
+        match lag.lag {
+            Some(seconds) if seconds > 0 => info!("Release lags {} behind {}, {indices} indices differ.", format_duration(seconds), upstream.url),
+            Some(_) => info!("Release is up to date with {}, {indices} indices differ.", upstream.url),
+            None => warn!("Release date is missing, the lag behind {} is unknown. {indices} indices differ.", upstream.url),
+        }
 
-            if self.check_files {
-                debug!("Checking links of source {source}...");
-                for (_key, link) in package.links {
-                    match get_etag(&link.url).await {
-                        Ok(_) => {} // pass!
-                        Err(e) => {
-                            let message = format!("File {} of source {} is broken: {e}", link.url, package.package);
-                            error!("{}", message);
-                            self.issues.push((component.to_string(), Architecture::Source, Error::new(&message, libapt::ErrorType::Download)));
-                        }
-                    }
-                }
+        if let (Some(seconds), Some(max_lag)) = (lag.lag, self.options.max_lag) {
+            if seconds > max_lag as i64 {
+                let message = format!(
+                    "Release lags {} behind {}, the maximum lag is {}. {indices} indices differ.",
+                    format_duration(seconds),
+                    upstream.url,
+                    format_duration(max_lag as i64)
+                );
+                self.add_finding(Finding::for_release(FindingKind::MirrorLag, &message));
             }
         }
+        self.mirror_lag = Some(lag);
+    }
 
-        self.source_indices.insert(component.to_string(), index);
+    /// Check that the InRelease file is signed by a key with an expected fingerprint.
+    ///
+    /// A signature of a subkey is accepted if the fingerprint of the subkey or of its primary key is expected.
+    async fn check_fingerprints(&mut self, key: &SignedPublicKey) {
+        let expected: Vec<String> = self.options.expected_fingerprints.iter().map(|fingerprint| signature::normalize_fingerprint(fingerprint)).collect();
 
-        Ok(())
+        let in_release_url = match self.release.distro.in_release_url() {
+            Ok(url) => url,
+            Err(e) => {
+                warn!("Getting InRelease URL failed, skipping fingerprint check: {e}");
+                return;
+            }
+        };
+
+        let signers = self.in_release().await.and_then(|content| signature::signing_fingerprints(key, &content));
+
+        let message = match signers {
+            Ok(signers) if signers.is_empty() => format!("InRelease {in_release_url} is not signed by the key."),
+            Ok(signers) => {
+                let unexpected: Vec<&str> = signers
+                    .iter()
+                    .filter(|(signer, primary)| !expected.contains(signer) && !expected.contains(primary))
+                    .map(|(signer, _)| signer.as_str())
+                    .collect();
+                if unexpected.is_empty() {
+                    info!("InRelease is signed by an expected key.");
+                    return;
+                }
+                format!("InRelease {in_release_url} is signed by unexpected key {}.", unexpected.join(", "))
+            }
+            Err(e) => format!("Signer of InRelease {in_release_url} can't be determined: {e}"),
+        };
+        self.add_finding(Finding::for_release(FindingKind::UnexpectedSigningKey, &message).with_url(&in_release_url));
     }
-}
\ No newline at end of file
+
+    /// Check that Release and Release.gpg exist, the signature is valid
+    /// and the content matches the InRelease payload.
+    async fn check_detached_release(&mut self) {
+        let in_release_url = match self.release.distro.in_release_url() {
+            Ok(url) => url,
+            Err(e) => {
+                warn!("Getting InRelease URL failed, skipping detached Release check: {e}");
+                return;
+            }
+        };
+        let base = in_release_url.strip_suffix("InRelease").unwrap_or(&in_release_url);
+        let release_url = format!("{base}Release");
+        let signature_url = format!("{base}Release.gpg");
+
+        let release = match self.transport.download(&release_url).await {
+            Ok(release) => release,
+            Err(e) => {
+                let message = format!("Release file {release_url} is not available: {e}");
+                self.add_finding(Finding::for_release(FindingKind::MissingDetachedRelease, &message).with_url(&release_url));
+                return;
+            }
+        };
+
+        let payload = match self.in_release().await.and_then(|content| signature::cleartext_payload(&content)) {
+            Ok(payload) => payload,
+            Err(e) => {
+                let message = format!("Release file {release_url} can't be compared with InRelease {in_release_url}: {e}");
+                self.add_finding(Finding::for_release(FindingKind::ReleaseMismatch, &message).with_url(&release_url));
+                return;
+            }
+        };
+        let release_text = String::from_utf8_lossy(&release);
+        if normalize_lines(&payload) != normalize_lines(&release_text) {
+            let differences = ReleaseFile::parse(&payload).differences(&ReleaseFile::parse(&release_text));
+            if differences.is_empty() {
+                let message = format!("Release file {release_url} doesn't match the InRelease payload.");
+                self.add_finding(Finding::for_release(FindingKind::ReleaseMismatch, &message).with_url(&release_url));
+            }
+            for difference in differences {
+                let message = format!("InRelease and Release file {release_url} drifted: {difference}.");
+                self.add_finding(Finding::for_release(FindingKind::ReleaseMismatch, &message).with_url(&release_url));
+            }
+        }
+
+        let signature = match self.transport.download(&signature_url).await {
+            Ok(signature) => signature,
+            Err(e) => {
+                let message = format!("Signature {signature_url} is not available: {e}");
+                self.add_finding(Finding::for_release(FindingKind::MissingDetachedRelease, &message).with_url(&signature_url));
+                return;
+            }
+        };
+
+        let key = match signature::load_key(&self.transport, &self.release.distro.key).await {
+            Ok(Some(key)) => key,
+            Ok(None) => {
+                info!("No key given, skipping verification of {signature_url}.");
+                return;
+            }
+            Err(e) => {
+                let message = format!("Signature {signature_url} can't be verified, loading the key failed: {e}");
+                self.add_finding(Finding::for_release(FindingKind::InvalidSignature, &message).with_url(&signature_url));
+                return;
+            }
+        };
+
+        match signature::verify_detached(&key, &release, &signature) {
+            Ok(_) => info!("Signature {signature_url} is OK."),
+            Err(e) => {
+                let message = format!("Signature {signature_url} of Release file is invalid: {e}");
+                self.add_finding(Finding::for_release(FindingKind::InvalidSignature, &message).with_url(&signature_url));
+            }
+        }
+    }
+
+    /// Get the local directory of the repository, given as option or by a file:// URL.
+    fn local_root(&self) -> Option<PathBuf> {
+        self.options.local_root.as_ref().map(PathBuf::from).or_else(|| transport::local_path(&self.release.distro.url))
+    }
+
+    /// Check that the files and directories of a local repository are readable, also by other users.
+    fn check_permissions(&mut self) {
+        let root = match self.local_root() {
+            Some(root) => root,
+            None => {
+                warn!("Permission check requires a file:// URL or a local root, skipping.");
+                return;
+            }
+        };
+
+        for (path, kind, issue) in permissions::issues(&root) {
+            let message = format!("{path} {issue}.");
+            self.add_finding(Finding::for_release(kind, &message).with_url(&self.release.distro.url(&path, false)));
+        }
+    }
+
+    /// Check that all files of the pool of a local repository are referenced by an index.
+    ///
+    /// Base repositories with the same URL are other suites sharing the pool, so their indices are included.
+    /// The check is skipped if any index can't be read, as its files would be reported as orphaned.
+    async fn check_orphans(&mut self) {
+        let root = match self.local_root() {
+            Some(root) => root,
+            None => {
+                warn!("Orphan check requires a file:// URL or a local root, skipping.");
+                return;
+            }
+        };
+
+        let mut suites = Vec::new();
+        for base in self.options.base_repos.iter().filter(|base| base.url == self.release.distro.url) {
+            let suite = base.suite.as_deref().or(self.release.distro.name.as_deref()).unwrap_or_default();
+            match release::load(&self.transport, &Distro::repo(&base.url, suite, Key::NoSignatureCheck)).await {
+                Ok(release) => suites.push(release),
+                Err(e) => {
+                    warn!("Loading suite {suite} failed, skipping orphan check: {e}");
+                    return;
+                }
+            }
+        }
+
+        let mut releases = vec![&self.release];
+        releases.extend(suites.iter());
+        let referenced = match orphans::referenced_files(&self.transport, &releases).await {
+            Ok(referenced) => referenced,
+            Err(e) => {
+                warn!("Reading the indices failed, skipping orphan check: {e}");
+                return;
+            }
+        };
+
+        let orphans = match orphans::find(&root, &referenced) {
+            Ok(orphans) => orphans,
+            Err(_) => return,
+        };
+        for orphan in orphans {
+            let message = format!("Pool file {orphan} is not referenced by any index.");
+            self.add_finding(Finding::for_release(FindingKind::OrphanedPoolFile, &message).with_url(&self.release.distro.url(&orphan, false)));
+        }
+    }
+
+    /// Check that no source of the suite is newer than in its security or updates pocket.
+    ///
+    /// The pockets are the base repositories named like the suite with a -security or -updates suffix.
+    /// A newer version in the suite is installed instead of the pocket's version, so the fixes are shadowed.
+    fn check_pockets(&mut self) {
+        let suite = self.release.distro.name.clone().unwrap_or_default();
+        let mut components: Vec<&String> = self.source_versions.keys().collect();
+        components.sort();
+
+        let mut findings = Vec::new();
+        for base in self.base_indices.iter().filter(|base| base::is_pocket_of(&base.suite, &suite)) {
+            info!("Comparing sources with pocket {}...", base.suite);
+            for component in &components {
+                let mut sources: Vec<(&String, &Vec<Version>)> = self.source_versions[*component].iter().collect();
+                sources.sort_by_key(|(source, _)| *source);
+                for (source, versions) in sources {
+                    let (version, pocket_version) = match (versions.iter().max(), base.sources.get(source).and_then(|versions| versions.iter().max())) {
+                        (Some(version), Some(pocket_version)) if version > pocket_version => (format_version(version), format_version(pocket_version)),
+                        _ => continue,
+                    };
+                    let message = format!("Source {source} {version} of {suite} is newer than {pocket_version} of {}, which is shadowed.", base.suite);
+                    findings.push(Finding::new(FindingKind::PocketInversion, component, &Architecture::Source, &message).with_package(source).with_version(&version));
+                }
+            }
+        }
+
+        for finding in findings {
+            self.add_finding(finding);
+        }
+    }
+
+    /// Check the sources against the advisories of the security tracker.
+    ///
+    /// Sources below the version fixing a CVE are reported. CVEs without fix are only counted,
+    /// as every version of the source is affected.
+    async fn check_cves(&mut self) {
+        if self.source_versions.is_empty() {
+            warn!("CVE check requires the sources, skipping.");
+            return;
+        }
+
+        let codename = self.options.cve_release.as_deref().or(self.release.codename.as_deref()).or(self.release.distro.name.as_deref());
+        // The pockets share the advisories of their release, e.g. bookworm-security.
+        let codename = match codename.and_then(|codename| codename.split('-').next()) {
+            Some(codename) if !codename.is_empty() => codename.to_string(),
+            _ => {
+                warn!("Release has no codename, skipping CVE check. Use --cve-release.");
+                return;
+            }
+        };
+
+        let tracker = self.options.cve_tracker.as_deref().unwrap_or(cve::DEBIAN_TRACKER);
+        let transport = match self.external_transport() {
+            Ok(transport) => transport,
+            Err(e) => {
+                warn!("Creating HTTP client for security tracker failed, skipping CVE check: {e}");
+                return;
+            }
+        };
+        let advisories = match cve::load(&transport, tracker).await.and_then(|content| cve::advisories(&content, &codename)) {
+            Ok(advisories) => advisories,
+            Err(e) => {
+                warn!("Loading security tracker data {tracker} failed, skipping CVE check: {e}");
+                return;
+            }
+        };
+
+        let mut components: Vec<String> = self.source_versions.keys().cloned().collect();
+        components.sort();
+        let mut open = 0;
+        for component in components {
+            for exposure in cve::exposures(&advisories, &self.source_versions[&component]) {
+                let advisory = &exposure.advisory;
+                let fixed_version = match &advisory.fixed_version {
+                    Some(fixed_version) => fixed_version,
+                    None => {
+                        open += 1;
+                        continue;
+                    }
+                };
+                let urgency = advisory.urgency.as_deref().map(|urgency| format!(", urgency {urgency}")).unwrap_or_default();
+                let message = format!(
+                    "Source {} {} is affected by {}, fixed in version {fixed_version}{urgency}.",
+                    advisory.source, exposure.version, advisory.cve
+                );
+                let finding = Finding::new(FindingKind::VulnerablePackage, &component, &Architecture::Source, &message)
+                    .with_package(&advisory.source)
+                    .with_version(&exposure.version)
+                    .with_url(&advisory.url);
+                self.add_finding(finding);
+            }
+        }
+        if open > 0 {
+            info!("Sources are affected by {open} open CVEs without fixed version.");
+        }
+    }
+
+    /// Validate the Phased-Update-Percentage of a binary package and remember the phased update.
+    fn add_phased_update(&mut self, component: &str, architecture: &Architecture, package: &Package, value: &str) {
+        let version = format_version(&package.version);
+        match fields::phasing_percentage(value) {
+            Ok(percentage) => self.phased_updates.push(PhasedUpdate {
+                component: component.to_string(),
+                architecture: architecture.to_string(),
+                package: package.package.clone(),
+                version,
+                percentage,
+            }),
+            Err(issue) => {
+                let message = format!("Package {} {version}: {issue}.", package.package);
+                self.add_finding(Finding::new(FindingKind::InvalidPhasing, component, architecture, &message).with_package(&package.package).with_version(&version));
+            }
+        }
+    }
+
+    /// Report the phased updates which stay at their percentage longer than the maximum phasing age, and record the phased updates.
+    ///
+    /// The time a percentage is kept is known from the earlier runs recorded in the history.
+    fn check_phasing(&mut self, path: &str) {
+        let repo = self.release.distro.in_release_url().unwrap_or_default();
+
+        if let Some(max_age) = self.options.max_phasing_age {
+            let now = Utc::now();
+            let mut findings = Vec::new();
+            for update in self.phased_updates.iter().filter(|update| update.percentage < 100) {
+                let since = match history::phasing_since(path, &repo, update) {
+                    Ok(Some(since)) => since,
+                    Ok(None) => continue,
+                    Err(e) => {
+                        warn!("Reading phasing history failed, skipping phasing check: {e}");
+                        return;
+                    }
+                };
+                let age = now.signed_duration_since(since).num_seconds();
+                if age > max_age as i64 {
+                    let message = format!(
+                        "Phased update {} {} is stuck at {}% for {}, since {}.",
+                        update.package,
+                        update.version,
+                        update.percentage,
+                        format_duration(age),
+                        since.to_rfc2822()
+                    );
+                    let architecture = Architecture::from_str(&update.architecture).unwrap_or(Architecture::All);
+                    findings.push(Finding::new(FindingKind::StuckPhasing, &update.component, &architecture, &message).with_package(&update.package).with_version(&update.version));
+                }
+            }
+            for finding in findings {
+                self.add_finding(finding);
+            }
+        }
+
+        if !self.phased_updates.is_empty() {
+            // A failing record is no reason to fail the check.
+            let _ = history::record_phasing(path, &repo, &self.phased_updates);
+        }
+    }
+
+    /// Create a transport for data sources outside the repository, e.g. the security tracker or an OVAL feed.
+    ///
+    /// External sources get no repository credentials.
+    fn external_transport(&self) -> Result<Transport> {
+        Transport::new(&self.options.http.without_auth())
+    }
+
+    /// Evaluate the definitions of the OVAL feed with the highest versions of the binary and source packages.
+    ///
+    /// Binary versions take precedence over source versions of the same name.
+    async fn check_oval(&mut self) {
+        let feed = match &self.options.oval {
+            Some(feed) => feed.clone(),
+            None => return,
+        };
+
+        let mut versions: HashMap<String, Version> = HashMap::new();
+        let sources = self.source_versions.values().flat_map(|sources| sources.iter());
+        let binaries = self.binary_versions.values().flat_map(|packages| packages.iter());
+        for (package, package_versions) in sources.chain(binaries) {
+            if let Some(version) = package_versions.iter().max() {
+                versions.insert(package.clone(), version.clone());
+            }
+        }
+
+        let transport = match self.external_transport() {
+            Ok(transport) => transport,
+            Err(e) => {
+                warn!("Creating HTTP client for OVAL feed failed, skipping OVAL check: {e}");
+                return;
+            }
+        };
+        let unpatched = match oval::load(&transport, &feed).await.and_then(|content| oval::unpatched(&content, &versions)) {
+            Ok(unpatched) => unpatched,
+            Err(e) => {
+                warn!("Loading OVAL feed {feed} failed, skipping OVAL check: {e}");
+                return;
+            }
+        };
+
+        for definition in &unpatched {
+            let packages: Vec<String> = definition
+                .packages
+                .iter()
+                .map(|affected| match &affected.fixed_version {
+                    Some(fixed_version) => format!("{} {} (fixed in {fixed_version})", affected.package, affected.version),
+                    None => format!("{} {}", affected.package, affected.version),
+                })
+                .collect();
+            let message = format!("OVAL definition {} ({}) is unpatched: {}.", definition.id, definition.title, packages.join(", "));
+            let mut finding = Finding::for_release(FindingKind::UnpatchedDefinition, &message);
+            if let Some(affected) = definition.packages.first() {
+                finding = finding.with_package(&affected.package).with_version(&affected.version);
+            }
+            if let Some(url) = &definition.url {
+                finding = finding.with_url(url);
+            }
+            self.add_finding(finding);
+        }
+        self.unpatched_definitions = unpatched;
+    }
+
+    /// Load the indices of the base repositories.
+    ///
+    /// A base repository which can't be loaded is skipped, so its packages are reported as missing.
+    async fn load_base_repos(&mut self) {
+        for base in &self.options.base_repos {
+            info!("Loading base repository {}...", base.url);
+            match base::load(&self.transport, base, self.release.distro.name.as_deref(), &self.architectures).await {
+                Ok(indices) => self.base_indices.push(indices),
+                Err(e) => error!("Loading base repository {} failed: {e}", base.url),
+            }
+        }
+    }
+
+    /// Do checks involving multiple components.
+    fn cross_check(&mut self) -> Result<()> {
+        if self.options.check_multi_arch {
+            self.check_multi_arch_versions();
+        }
+        self.resolve_cross_component();
+
+        // Build dependencies and published binaries require both the sources and the binaries.
+        let complete = !self.options.binaries_only && !self.options.sources_only;
+
+        if self.options.check_build_depends && complete {
+            let start = Instant::now();
+            self.check_build_depends();
+            self.add_timing(Timing::new(Phase::BuildDependencies, start.elapsed()));
+        }
+
+        if self.options.check_priority_coverage {
+            self.check_priority_coverage();
+        }
+
+        if self.options.check_source_binaries && complete {
+            let start = Instant::now();
+            self.check_source_binaries();
+            self.add_timing(Timing::new(Phase::SourceBinaries, start.elapsed()));
+        }
+
+        Ok(())
+    }
+
+    /// Search the dependencies and sources missing in their own component in all other components.
+    ///
+    /// Only references missing in all checked components are reported.
+    fn resolve_cross_component(&mut self) {
+        let no_versions = HashMap::new();
+        let no_provides = HashMap::new();
+        let mut findings = Vec::new();
+
+        for unresolved in std::mem::take(&mut self.unresolved_dependencies) {
+            let architecture = unresolved.finding.architecture.as_ref().map(|a| a.to_string()).unwrap_or_default();
+            let versions = self.binary_versions.get(&architecture).unwrap_or(&no_versions);
+            let provides = self.binary_provides.get(&architecture).unwrap_or(&no_provides);
+            let resolved = if unresolved.alternatives.iter().any(|dependency| is_satisfied(versions, provides, dependency)) {
+                Some("another component".to_string())
+            } else {
+                self.base_indices
+                    .iter()
+                    .find(|base| {
+                        let versions = base.versions.get(&architecture).unwrap_or(&no_versions);
+                        let provides = base.provides.get(&architecture).unwrap_or(&no_provides);
+                        unresolved.alternatives.iter().any(|dependency| is_satisfied(versions, provides, dependency))
+                    })
+                    .map(|base| format!("base repository {}", base.name))
+            };
+            findings.push(self.cross_component_finding(unresolved, resolved.as_deref()));
+        }
+
+        let mut components: Vec<&String> = self.source_versions.keys().collect();
+        components.sort();
+        for unresolved in std::mem::take(&mut self.unresolved_sources) {
+            let resolved = components.iter().find(|component| {
+                unresolved
+                    .alternatives
+                    .iter()
+                    .any(|source| is_satisfied(&self.source_versions[**component], &no_provides, source))
+            });
+            let resolved = match resolved {
+                Some(component) => Some(format!("component {component}")),
+                None => self.base_indices
+                    .iter()
+                    .find(|base| unresolved.alternatives.iter().any(|source| is_satisfied(&base.sources, &no_provides, source)))
+                    .map(|base| format!("base repository {}", base.name)),
+            };
+            findings.push(self.cross_component_finding(unresolved, resolved.as_deref()));
+        }
+
+        for finding in findings.into_iter().flatten() {
+            self.add_finding(finding);
+        }
+    }
+
+    /// Get the finding of a reference after searching all components.
+    ///
+    /// Resolved references are only noted if requested.
+    fn cross_component_finding(&self, unresolved: Unresolved, resolved_by: Option<&str>) -> Option<Finding> {
+        let resolved_by = match resolved_by {
+            Some(resolved_by) => resolved_by,
+            None => return Some(unresolved.finding),
+        };
+
+        if !self.options.note_cross_component {
+            return None;
+        }
+
+        let finding = unresolved.finding;
+        let message = format!("{} is resolved from {resolved_by}.", unresolved.reference);
+        let mut note = match (&finding.component, &finding.architecture) {
+            (Some(component), Some(architecture)) => Finding::new(FindingKind::CrossComponentReference, component, architecture, &message),
+            (Some(component), None) => Finding::for_component(FindingKind::CrossComponentReference, component, &message),
+            _ => Finding::for_release(FindingKind::CrossComponentReference, &message),
+        };
+        note.package = finding.package;
+        note.version = finding.version;
+        Some(note)
+    }
+
+    /// Check that the required and important packages are available on all checked architectures.
+    fn check_priority_coverage(&mut self) {
+        let architectures: Vec<String> = self
+            .architectures
+            .iter()
+            .filter(|architecture| !matches!(architecture, Architecture::Source | Architecture::All))
+            .map(|architecture| architecture.to_string())
+            .collect();
+
+        let mut packages: Vec<&String> = self.priority_packages.keys().collect();
+        packages.sort();
+
+        let mut findings = Vec::new();
+        for package in packages {
+            let (priority, available) = &self.priority_packages[package];
+            let missing: Vec<&str> = architectures
+                .iter()
+                .filter(|architecture| !available.contains(*architecture))
+                .map(|architecture| architecture.as_str())
+                .collect();
+            if !missing.is_empty() {
+                let message = format!("Package {package} of priority {priority} is missing on {}.", missing.join(", "));
+                findings.push(Finding::for_release(FindingKind::MissingPriorityPackage, &message).with_package(package));
+            }
+        }
+
+        for finding in findings {
+            self.add_finding(finding);
+        }
+    }
+
+    /// Check that the binaries listed by the sources are published for the checked architectures.
+    ///
+    /// Binaries of architecture all are expected in any binary index,
+    /// and binaries of a component may be published in another component.
+    fn check_source_binaries(&mut self) {
+        let mut architectures: Vec<&String> = self.binary_versions.keys().collect();
+        architectures.sort();
+
+        let mut components: Vec<&String> = self.source_binaries.keys().collect();
+        components.sort();
+
+        let mut findings = Vec::new();
+        for component in components {
+            for (source, version, binaries) in &self.source_binaries[component] {
+                for (binary, binary_architectures) in binaries {
+                    let published = |architecture: &&String| self.binary_versions[*architecture].contains_key(binary);
+
+                    let missing: Vec<&str> = if binary_architectures.split_whitespace().any(|specification| specification == "all") {
+                        if architectures.iter().any(published) {
+                            Vec::new()
+                        } else {
+                            vec!["any architecture"]
+                        }
+                    } else {
+                        architectures
+                            .iter()
+                            .filter(|architecture| build_depends::builds_on(binary_architectures, architecture))
+                            .filter(|architecture| !published(architecture))
+                            .map(|architecture| architecture.as_str())
+                            .collect()
+                    };
+
+                    if !missing.is_empty() {
+                        let message = format!("Binary {binary} of source {source} {version} is not published for {}.", missing.join(", "));
+                        findings.push(Finding::for_component(FindingKind::MissingBinary, component, &message).with_package(source).with_version(version));
+                    }
+                }
+            }
+        }
+
+        for finding in findings {
+            self.add_finding(finding);
+        }
+    }
+
+    /// Check that the build dependencies of the sources can be satisfied on the checked architectures.
+    ///
+    /// Packages of the base repositories are available for the build.
+    ///
+    /// Sources of architecture all are only resolved for the first architecture.
+    fn check_build_depends(&mut self) {
+        let mut architectures: Vec<&String> = self.binary_versions.keys().collect();
+        architectures.sort();
+        let no_provides = HashMap::new();
+
+        let mut components: Vec<&String> = self.build_depends.keys().collect();
+        components.sort();
+
+        let mut findings = Vec::new();
+        for component in components {
+            for (source, version, source_architectures, build_depends) in &self.build_depends[component] {
+                for (position, architecture) in architectures.iter().enumerate() {
+                    let independent = position == 0 && source_architectures.split_whitespace().any(|specification| specification == "all");
+                    if !independent && !build_depends::builds_on(source_architectures, architecture) {
+                        continue;
+                    }
+
+                    let versions = &self.binary_versions[*architecture];
+                    let provides = self.binary_provides.get(*architecture).unwrap_or(&no_provides);
+                    for alternatives in build_depends::groups(build_depends, architecture) {
+                        let satisfied = |versions: &HashMap<String, Vec<Version>>, provides: &HashMap<String, Vec<Option<Version>>>| {
+                            alternatives.iter().any(|dependency| is_satisfied(versions, provides, dependency))
+                        };
+                        let in_base = || {
+                            self.base_indices.iter().any(|base| {
+                                base.versions.get(*architecture).is_some_and(|versions| satisfied(versions, base.provides.get(*architecture).unwrap_or(&no_provides)))
+                            })
+                        };
+                        if !satisfied(versions, provides) && !in_base() {
+                            let alternatives: Vec<String> = alternatives.iter().map(format_dependency).collect();
+                            let message = format!("Build dependency {} of source {source} is not satisfiable.", alternatives.join(" | "));
+                            let architecture = Architecture::from_str(architecture).unwrap_or(Architecture::Other(architecture.to_string()));
+                            findings.push(
+                                Finding::new(FindingKind::UnsatisfiableBuildDependency, component, &architecture, &message)
+                                    .with_package(source)
+                                    .with_version(version),
+                            );
+                        }
+                    }
+                }
+            }
+        }
+
+        for finding in findings {
+            self.add_finding(finding);
+        }
+    }
+
+    /// Check that Multi-Arch: same packages have the same versions on all architectures.
+    fn check_multi_arch_versions(&mut self) {
+        let mut findings = Vec::new();
+
+        for (component, packages) in &self.multi_arch_same {
+            for (package, architectures) in packages {
+                let mut versions = architectures.values();
+                let first = versions.next();
+                if versions.all(|versions| Some(versions) == first) {
+                    continue;
+                }
+
+                let listed: Vec<String> = architectures
+                    .iter()
+                    .map(|(architecture, versions)| format!("{architecture} {}", versions.iter().cloned().collect::<Vec<String>>().join(", ")))
+                    .collect();
+                let message = format!("Multi-Arch: same package {package} has different versions per architecture: {}.", listed.join("; "));
+                findings.push(Finding::for_component(FindingKind::MultiArchVersionSkew, component, &message).with_package(package));
+            }
+        }
+
+        findings.sort_by(|a, b| (&a.component, &a.package).cmp(&(&b.component, &b.package)));
+        for finding in findings {
+            self.add_finding(finding);
+        }
+    }
+
+    // Do checks for a single component.
+    async fn check(&mut self) -> Result<()> {
+        // Check sources for all component.
+        // This also initializes the source package index 
+        // which is required to check the availability of 
+        // source packages.
+        for component in &self.components.clone() {
+            if self.is_completed(component, &Architecture::Source) {
+                info!("Sources of component {component} are already checked, skipping.");
+                continue;
+            }
+
+            if self.is_out_of_time() {
+                self.partial = true;
+                return Ok(());
+            }
+
+            // Translations belong to the binary packages, so they are checked without the sources, too.
+            if !self.options.binaries_only {
+                if self.options.check_index_hashes {
+                    self.check_index_hashes(component, &Architecture::Source).await;
+                }
+
+                match self.check_source_component(component).await {
+                    Ok(_) => {},
+                    Err(e) => {
+                        let message = format!("Checking sources of component {component} failed: {e}");
+                        self.add_finding(Finding::new(FindingKind::IndexUnavailable, component, &Architecture::Source, &message));
+                    }
+                }
+            }
+
+            if self.options.check_translations {
+                self.check_translations(component).await;
+            }
+
+            if self.options.check_dep11 {
+                self.check_dep11(component).await;
+            }
+
+            if self.partial {
+                return Ok(());
+            }
+
+            self.complete(component, &Architecture::Source);
+        }
+
+        if self.options.sources_only {
+            return Ok(());
+        }
+
+        // Check the binary indices for all architectures and components.
+        for component in &self.components.clone() {
+            for architecture in &self.architectures.clone() {
+                if architecture == &Architecture::Source {
+                    continue;
+                }
+
+                if self.is_completed(component, architecture) {
+                    info!("Component {component} for architecture {architecture} is already checked, skipping.");
+                    continue;
+                }
+
+                if self.is_out_of_time() {
+                    self.partial = true;
+                    return Ok(());
+                }
+
+                if self.options.check_index_hashes {
+                    self.check_index_hashes(component, architecture).await;
+                }
+
+                match self.check_binary_component(component, architecture).await {
+                    Ok(_) => {},
+                    Err(e) => {
+                        let message = format!("Checking component {component} for architecture {architecture} failed: {e}");
+                        self.add_finding(Finding::new(FindingKind::IndexUnavailable, component, architecture, &message));
+                    }
+                }
+
+                if self.options.check_udeb {
+                    self.check_udeb_component(component, architecture).await;
+                }
+
+                if self.partial {
+                    return Ok(());
+                }
+
+                self.complete(component, architecture);
+            }
+        }
+    
+        Ok(())
+    }
+    
+    /// Verify all compression variants of the Packages or Sources index against the Release file.
+    async fn check_index_hashes(&mut self, component: &str, architecture: &Architecture) {
+        let path = if architecture == &Architecture::Source {
+            format!("{component}/source/Sources")
+        } else {
+            format!("{component}/binary-{architecture}/Packages")
+        };
+        self.check_index_variants(component, architecture, &path).await;
+    }
+
+    /// Verify all compression variants of an index against the Release file.
+    async fn check_index_variants(&mut self, component: &str, architecture: &Architecture, path: &str) {
+        let start = Instant::now();
+
+        let base = self.release.distro.url(path, false);
+        let variants = format!("{base}.");
+
+        let mut links: Vec<Link> = self
+            .release
+            .links
+            .iter()
+            .filter(|(url, _)| **url == base || url.strip_prefix(&variants).is_some_and(|extension| !extension.contains('/')))
+            .map(|(_, link)| link.clone())
+            .collect();
+        links.sort_by(|a, b| a.url.cmp(&b.url));
+
+        // SHA256 hashes of the decompressed variants. (URL, Hash)
+        let mut contents: Vec<(String, String)> = Vec::new();
+
+        info!("Verifying {} variants of index {path}...", links.len());
+        for link in links {
+            match self.transport.download(&link.url).await {
+                Ok(data) => {
+                    let mismatches = index::hash_mismatches(&data, &link);
+                    if !mismatches.is_empty() {
+                        let message = format!("Index {} is corrupt: {}.", link.url, mismatches.join("; "));
+                        self.add_finding(Finding::new(FindingKind::IndexHashMismatch, component, architecture, &message).with_url(&link.url));
+                    }
+
+                    let extension = &link.url[base.len()..];
+                    if !index::SUPPORTED_COMPRESSIONS.contains(&extension) {
+                        debug!("Compression of {} is not supported, skipping content comparison.", link.url);
+                    } else {
+                        match index::decompress(data, &link.url) {
+                            Ok(content) => contents.push((link.url.clone(), format!("{:x}", Sha256::digest(&content)))),
+                            Err(e) => {
+                                let message = format!("Index {} can't be decompressed: {e}", link.url);
+                                self.add_finding(Finding::new(FindingKind::InvalidCompression, component, architecture, &message).with_url(&link.url));
+                            }
+                        }
+                    }
+                }
+                Err(e) => {
+                    let message = format!("Index {} listed in Release is not available: {e}", link.url);
+                    self.add_finding(Finding::new(FindingKind::MissingFile, component, architecture, &message).with_url(&link.url));
+                }
+            }
+
+            if self.release.acquire_by_hash {
+                self.check_by_hash(component, architecture, &link).await;
+            }
+        }
+
+        // All compression variants must provide the same content.
+        if let Some((reference_url, reference)) = contents.first() {
+            let differing: Vec<&str> = contents
+                .iter()
+                .filter(|(_, content)| content != reference)
+                .map(|(url, _)| url.as_str())
+                .collect();
+
+            if !differing.is_empty() {
+                let message = format!("Variants of index {path} differ: {} don't match {reference_url}.", differing.join(", "));
+                self.add_finding(Finding::new(FindingKind::IndexVariantMismatch, component, architecture, &message));
+            }
+        }
+
+        self.add_timing(Timing::new(Phase::IndexHashes, start.elapsed()).with_unit(component, architecture));
+    }
+
+    /// Check that the by-hash path of an index serves the content listed in the Release file.
+    async fn check_by_hash(&mut self, component: &str, architecture: &Architecture, link: &Link) {
+        let hash = match link.hashes.get(&LinkHash::Sha256) {
+            Some(hash) => hash.to_lowercase(),
+            None => {
+                debug!("No SHA256 hash for {}, skipping by-hash check.", link.url);
+                return;
+            }
+        };
+
+        let directory = link.url.rsplit_once('/').map(|(directory, _)| directory).unwrap_or(&link.url);
+        let url = format!("{directory}/by-hash/SHA256/{hash}");
+
+        match self.transport.download(&url).await {
+            Ok(data) => {
+                let mismatches = index::hash_mismatches(&data, link);
+                if !mismatches.is_empty() {
+                    let message = format!("By-hash file {url} doesn't match {}: {}.", link.url, mismatches.join("; "));
+                    self.add_finding(Finding::new(FindingKind::ByHashMismatch, component, architecture, &message).with_url(&url));
+                }
+            }
+            Err(e) => {
+                let message = format!("By-hash file {url} of {} is not available: {e}", link.url);
+                self.add_finding(Finding::new(FindingKind::ByHashMissing, component, architecture, &message).with_url(&url));
+            }
+        }
+    }
+
+    /// Verify the Translation indices of the component listed in the Release file and in its i18n/Index file.
+    async fn check_translations(&mut self, component: &str) {
+        info!("Checking translations of component {component}...");
+        let start = Instant::now();
+        // Translations are not specific to an architecture.
+        let architecture = Architecture::All;
+        let prefix = self.release.distro.url(&format!("{component}/i18n/Translation-"), false);
+
+        // Compression variants of each language. (Language, Links)
+        let mut languages: BTreeMap<String, Vec<Link>> = BTreeMap::new();
+        for (url, link) in &self.release.links {
+            let name = match url.strip_prefix(&prefix) {
+                Some(name) => name,
+                None => continue,
+            };
+
+            let language = index::SUPPORTED_COMPRESSIONS
+                .iter()
+                .find_map(|extension| name.strip_suffix(extension))
+                .unwrap_or(name);
+            if language.contains('.') {
+                debug!("Compression of {url} is not supported, skipping.");
+                continue;
+            }
+
+            languages.entry(language.to_string()).or_default().push(link.clone());
+        }
+
+        for (language, links) in languages.iter_mut() {
+            links.sort_by(|a, b| a.url.cmp(&b.url));
+            let mut parsed = false;
+
+            for link in links.iter() {
+                let data = match self.transport.download(&link.url).await {
+                    Ok(data) => data,
+                    Err(e) => {
+                        let message = format!("Translation {} listed in Release is not available: {e}", link.url);
+                        self.add_finding(Finding::new(FindingKind::MissingFile, component, &architecture, &message).with_url(&link.url));
+                        continue;
+                    }
+                };
+
+                let mismatches = index::hash_mismatches(&data, link);
+                if !mismatches.is_empty() {
+                    let message = format!("Translation {} is corrupt: {}.", link.url, mismatches.join("; "));
+                    self.add_finding(Finding::new(FindingKind::IndexHashMismatch, component, &architecture, &message).with_url(&link.url));
+                }
+
+                let content = match index::decompress(data, &link.url) {
+                    Ok(content) => content,
+                    Err(e) => {
+                        let message = format!("Translation {} can't be decompressed: {e}", link.url);
+                        self.add_finding(Finding::new(FindingKind::InvalidCompression, component, &architecture, &message).with_url(&link.url));
+                        continue;
+                    }
+                };
+
+                // The variants provide the same content, so parsing one is enough.
+                if parsed {
+                    continue;
+                }
+                parsed = true;
+
+                let issues = translation::stanza_issues(&String::from_utf8_lossy(&content), language);
+                if !issues.is_empty() {
+                    let listed: Vec<&str> = issues.iter().take(MAX_LISTED_ISSUES).map(|issue| issue.as_str()).collect();
+                    let more = if issues.len() > MAX_LISTED_ISSUES { ", ..." } else { "" };
+                    let message = format!("Translation {} has {} invalid stanzas: {}{more}.", link.url, issues.len(), listed.join(", "));
+                    self.add_finding(Finding::new(FindingKind::InvalidTranslation, component, &architecture, &message).with_url(&link.url));
+                }
+            }
+        }
+
+        self.check_i18n_index(component).await;
+
+        for language in &self.options.required_translations.clone() {
+            if !languages.contains_key(language) {
+                let message = format!("Required translation {prefix}{language} is not listed in Release.");
+                self.add_finding(Finding::new(FindingKind::MissingTranslation, component, &architecture, &message));
+            }
+        }
+
+        self.add_timing(Timing::new(Phase::Translations, start.elapsed()).with_unit(component, &architecture));
+    }
+
+    /// Verify the DEP-11 icon tarballs of the component listed in the Release file.
+    ///
+    /// The tarballs must match their hashes, open, and contain the icons cached by the Components YAML files.
+    async fn check_dep11(&mut self, component: &str) {
+        info!("Checking DEP-11 metadata of component {component}...");
+        let start = Instant::now();
+        // Icons are shared by all architectures.
+        let architecture = Architecture::All;
+        let prefix = self.release.distro.url(&format!("{component}/dep11/"), false);
+
+        let mut tarball_links: Vec<Link> = Vec::new();
+        let mut yaml_paths: BTreeSet<String> = BTreeSet::new();
+        for (url, link) in &self.release.links {
+            let name = match url.strip_prefix(&prefix) {
+                Some(name) => name,
+                None => continue,
+            };
+            if name.starts_with("icons-") && name.ends_with(".tar.gz") {
+                tarball_links.push(link.clone());
+            } else if name.starts_with("Components-") {
+                let path = index::SUPPORTED_COMPRESSIONS.iter().find_map(|extension| name.strip_suffix(extension)).unwrap_or(name);
+                yaml_paths.insert(format!("{component}/dep11/{path}"));
+            }
+        }
+        tarball_links.sort_by(|a, b| a.url.cmp(&b.url));
+
+        // Icons of the tarballs which could be read. (Tarball, Icons)
+        let mut tarballs: HashMap<String, HashSet<String>> = HashMap::new();
+        for link in &tarball_links {
+            let name = link.url.strip_prefix(&prefix).unwrap_or(&link.url).to_string();
+            let data = match self.transport.download(&link.url).await {
+                Ok(data) => data,
+                Err(e) => {
+                    let message = format!("Icon tarball {} listed in Release is not available: {e}", link.url);
+                    self.add_finding(Finding::new(FindingKind::MissingFile, component, &architecture, &message).with_url(&link.url));
+                    continue;
+                }
+            };
+            let mismatches = index::hash_mismatches(&data, link);
+            if !mismatches.is_empty() {
+                let message = format!("Icon tarball {} is corrupt: {}.", link.url, mismatches.join("; "));
+                self.add_finding(Finding::new(FindingKind::IndexHashMismatch, component, &architecture, &message).with_url(&link.url));
+                continue;
+            }
+            let names = index::decompress(data, &link.url).map_err(|e| e.to_string()).and_then(|tarball| deb::tar_names(&tarball));
+            match names {
+                Ok(names) => {
+                    tarballs.insert(name, names.into_iter().collect());
+                }
+                Err(e) => {
+                    let message = format!("Icon tarball {} can't be opened: {e}", link.url);
+                    self.add_finding(Finding::new(FindingKind::InvalidIconTarball, component, &architecture, &message).with_url(&link.url));
+                }
+            }
+        }
+
+        for path in yaml_paths {
+            let link = match self.index_link(&path) {
+                Some(link) => link,
+                None => continue,
+            };
+            let content = match self.load_index(component, &architecture, &link, FindingKind::MissingFile).await {
+                Some(content) => content,
+                None => continue,
+            };
+
+            let icons = dep11::cached_icons(&content);
+            debug!("Components YAML {} caches {} icons.", link.url, icons.len());
+            // Tarballs not listed in Release are reported once per YAML file.
+            let mut unlisted: BTreeSet<String> = BTreeSet::new();
+            for icon in icons {
+                let url = format!("{prefix}{}", icon.tarball);
+                if !tarball_links.iter().any(|link| link.url == url) {
+                    unlisted.insert(icon.tarball);
+                    continue;
+                }
+                // Unreadable tarballs are reported already.
+                let missing = tarballs.get(&icon.tarball).is_some_and(|names| !names.contains(&icon.name));
+                if missing {
+                    let message = format!("Icon {} of component {} is missing in icon tarball {url}.", icon.name, icon.id);
+                    self.add_finding(Finding::new(FindingKind::MissingIcon, component, &architecture, &message).with_package(&icon.package).with_url(&url));
+                }
+            }
+            for tarball in unlisted {
+                let url = format!("{prefix}{tarball}");
+                let message = format!("Components YAML {} references icon tarball {tarball}, which is not listed in Release.", link.url);
+                self.add_finding(Finding::new(FindingKind::MissingIcon, component, &architecture, &message).with_url(&url));
+            }
+        }
+
+        self.add_timing(Timing::new(Phase::Dep11, start.elapsed()).with_unit(component, &architecture));
+    }
+
+    /// Verify the i18n/Index file of the component and the Translation files it lists.
+    ///
+    /// The index is verified against the Release file, the listed files against the hashes of the index.
+    async fn check_i18n_index(&mut self, component: &str) {
+        let architecture = Architecture::All;
+        let base = self.release.distro.url(&format!("{component}/i18n/"), false);
+        let url = format!("{base}Index");
+
+        let link = match self.release.links.get(&url) {
+            Some(link) => link.clone(),
+            None => {
+                if self.transport.probe(&url).await.is_ok() {
+                    let message = format!("Translation index {url} is published, but not listed in Release, so its hashes are not signed.");
+                    self.add_finding(Finding::new(FindingKind::InvalidTranslation, component, &architecture, &message).with_url(&url));
+                }
+                return;
+            }
+        };
+
+        let data = match self.transport.download(&url).await {
+            Ok(data) => data,
+            Err(e) => {
+                let message = format!("Translation index {url} listed in Release is not available: {e}");
+                self.add_finding(Finding::new(FindingKind::MissingFile, component, &architecture, &message).with_url(&url));
+                return;
+            }
+        };
+        let mismatches = index::hash_mismatches(&data, &link);
+        if !mismatches.is_empty() {
+            let message = format!("Translation index {url} is corrupt: {}.", mismatches.join("; "));
+            self.add_finding(Finding::new(FindingKind::IndexHashMismatch, component, &architecture, &message).with_url(&url));
+            return;
+        }
+
+        let links = translation::index_links(&String::from_utf8_lossy(&data), &base);
+        if links.is_empty() {
+            let message = format!("Translation index {url} lists no Translation files.");
+            self.add_finding(Finding::new(FindingKind::InvalidTranslation, component, &architecture, &message).with_url(&url));
+            return;
+        }
+
+        info!("Checking {} Translation files listed by {url}...", links.len());
+        for link in links {
+            let data = match self.transport.download(&link.url).await {
+                Ok(data) => data,
+                Err(e) => {
+                    let message = format!("Translation {} listed in i18n/Index is not available: {e}", link.url);
+                    self.add_finding(Finding::new(FindingKind::MissingFile, component, &architecture, &message).with_url(&link.url));
+                    continue;
+                }
+            };
+            let mismatches = index::listed_mismatches(&data, &link, "i18n/Index");
+            if !mismatches.is_empty() {
+                let message = format!("Translation {} doesn't match i18n/Index: {}.", link.url, mismatches.join("; "));
+                self.add_finding(Finding::new(FindingKind::IndexHashMismatch, component, &architecture, &message).with_url(&link.url));
+            }
+        }
+    }
+
+    async fn check_binary_component(&mut self, component: &str, architecture: &Architecture) -> Result<()> {
+        info!("Checking binary index of component {component} for architecture {architecture}...");
+        let start = Instant::now();
+        let link = index::package_index_link(&self.transport, &self.release, component, architecture).await?;
+        let content = match self.download_component_index(component, architecture, &link).await? {
+            Some(content) => content,
+            None => return Ok(()),
+        };
+
+        // Only the package versions and the dependencies are kept for
+        // the dependency resolution, the full stanzas are dropped.
+        let mut versions: HashMap<String, Vec<Version>> = HashMap::new();
+        // Relations as groups of alternatives. (Package, Version, Relation, Groups)
+        let mut depends: Vec<(String, String, Relation, Vec<Vec<PackageVersion>>)> = Vec::new();
+        // Versions of virtual packages, None for unversioned Provides.
+        let mut provides: HashMap<String, Vec<Option<Version>>> = HashMap::new();
+        // Breaks, Conflicts and Replaces. (Package, Relations)
+        let mut conflicts: HashMap<String, ConflictRelations> = HashMap::new();
+        // Packages which are Essential or of priority required.
+        let mut essential: BTreeSet<String> = BTreeSet::new();
+        // Deb files to compare with the stanza. (Deb file, Stanza fields)
+        let mut controls: Vec<(PackageFile, Vec<Option<String>>)> = Vec::new();
+        // Files to check.
+        let mut files: Vec<PackageFile> = Vec::new();
+        // Listed package versions. (Package, Version, Architecture)
+        let mut listed: HashSet<(String, String, String)> = HashSet::new();
+
+        let mut retained = if self.options.retain_indices {
+            Some(PackageIndex {
+                architecture: architecture.clone(),
+                package_map: HashMap::new(),
+                issues: Vec::new(),
+            })
+        } else {
+            None
+        };
+
+        let progress = self.progress(Phase::Index, component, architecture, "packages", index::stanzas(&content).count());
+        for stanza in index::stanzas(&content) {
+            progress.inc();
+            let package = match Package::from_stanza(stanza, &self.release.distro) {
+                Ok(package) => package,
+                Err(e) => {
+                    let message = format!("Invalid package stanza: {e}");
+                    self.add_finding(Finding::new(FindingKind::InvalidStanza, component, architecture, &message));
+
+                    continue;
+                }
+            };
+
+            debug!("Checking binary package {}...", package.package);
+            let selected = self.package_filter.matches(&package.package);
+
+            if self.options.check_multi_arch {
+                self.check_multi_arch(component, architecture, stanza, &package);
+            }
+
+            if self.options.check_hash_policy {
+                self.check_stanza_hashes(component, architecture, stanza, &package.package, &format_version(&package.version));
+            }
+
+            if let Some(value) = index::field(stanza, "Phased-Update-Percentage") {
+                self.add_phased_update(component, architecture, &package, value);
+            }
+
+            if self.options.check_fields {
+                self.check_stanza_fields(component, architecture, stanza, &package.package, &format_version(&package.version));
+
+                let issues = fields::size_issues(index::field(stanza, "Size"), index::field(stanza, "Installed-Size"));
+                if !issues.is_empty() {
+                    let message = format!("Package {} {}: {}.", package.package, format_version(&package.version), issues.join(", "));
+                    self.add_finding(Finding::new(FindingKind::ImplausibleSize, component, architecture, &message).with_package(&package.package).with_version(&format_version(&package.version)));
+                }
+            }
+
+            if self.options.maintainer_summary {
+                self.maintainers.entry(package.package.clone()).or_insert_with(|| package.maintainer.clone());
+            }
+
+            if self.options.check_priority_coverage && package.architecture != Some(Architecture::All) {
+                if let Some(priority) = index::field(stanza, "Priority").filter(|priority| ["required", "important"].contains(priority)) {
+                    let (_, architectures) = self
+                        .priority_packages
+                        .entry(package.package.clone())
+                        .or_insert_with(|| (priority.to_string(), BTreeSet::new()));
+                    architectures.insert(architecture.to_string());
+                }
+            }
+
+            if self.options.check_duplicates {
+                let package_architecture = package.architecture.as_ref().map(|a| a.to_string()).unwrap_or_default();
+                if !listed.insert((package.package.clone(), format_version(&package.version), package_architecture)) {
+                    let message = format!("Package {} {} is listed twice.", package.package, format_version(&package.version));
+                    self.add_finding(Finding::new(FindingKind::DuplicateStanza, component, architecture, &message).with_package(&package.package).with_version(&format_version(&package.version)));
+                }
+                self.check_pool_hash(component, architecture, stanza, &package);
+            }
+
+            if self.options.check_pool_layout {
+                self.check_package_pool_path(component, architecture, stanza, &package);
+            }
+
+            if self.options.check_files && selected {
+                // Existence of linked deb file is checked after parsing the index.
+                files.push((package.package.clone(), format_version(&package.version), package.link.clone()));
+            }
+
+            if self.options.check_control && selected {
+                let deb = (package.package.clone(), format_version(&package.version), package.link.clone());
+                controls.push((deb, CONTROL_FIELDS.iter().map(|field| index::folded_field(stanza, field)).collect()));
+            }
+
+            if self.options.check_sources && !self.options.binaries_only && selected {
+                self.check_package_source(component, architecture, &package);
+            }
+
+            versions.entry(package.package.clone()).or_default().push(package.version.clone());
+            if self.options.check_essential
+                && (index::field(stanza, "Essential") == Some("yes") || index::field(stanza, "Priority") == Some("required"))
+            {
+                essential.insert(package.package.clone());
+            }
+            if self.options.check_dependencies || self.options.check_conflicts || self.options.check_essential {
+                for relation in Relation::resolved(self.options.resolve) {
+                    // The parsed package lists the alternatives without grouping.
+                    let groups = index::relation_groups(stanza, relation.field()).unwrap_or_else(|_| {
+                        relation.of(&package).iter().map(|dependency| vec![dependency.clone()]).collect()
+                    });
+                    if !groups.is_empty() {
+                        depends.push((package.package.clone(), format_version(&package.version), relation, groups));
+                    }
+                }
+            }
+            if self.options.check_dependencies || self.options.check_build_depends || self.options.check_essential {
+                for provided in &package.provides {
+                    provides.entry(provided.name.clone()).or_default().push(provided.version.clone());
+                }
+            }
+            if self.options.check_conflicts {
+                let relations = conflicts.entry(package.package.clone()).or_default();
+                relations.0.extend(package.breaks.iter().chain(&package.conflicts).cloned());
+                relations.1.extend(package.replaces.iter().cloned());
+            }
+
+            if let Some(index) = &mut retained {
+                index.package_map.entry(package.package.clone()).or_default().push(package);
+            }
+        }
+        progress.finish();
+        self.add_timing(Timing::new(Phase::Index, start.elapsed()).with_unit(component, architecture));
+
+        if self.options.check_dependencies || self.options.check_build_depends || self.options.check_source_binaries || self.options.oval.is_some() {
+            // Dependencies missing in the component and build dependencies are resolved against the packages of all components.
+            let architecture = architecture.to_string();
+            let available = self.binary_versions.entry(architecture.clone()).or_default();
+            for (package, package_versions) in &versions {
+                available.entry(package.clone()).or_default().extend(package_versions.iter().cloned());
+            }
+            let available = self.binary_provides.entry(architecture).or_default();
+            for (package, package_versions) in &provides {
+                available.entry(package.clone()).or_default().extend(package_versions.iter().cloned());
+            }
+        }
+
+        if self.options.check_files {
+            info!("Checking {} files of component {component} for architecture {architecture}...", files.len());
+            self.check_files_exist(component, architecture, files).await;
+        }
+
+        if self.options.check_control {
+            info!("Checking {} control files of component {component} for architecture {architecture}...", controls.len());
+            self.check_controls(component, architecture, controls).await;
+        }
+
+        // Check for dependent packages.
+        if self.options.check_dependencies {
+            let start = Instant::now();
+            for (package, version, relation, dependencies) in depends.iter().filter(|(package, ..)| self.package_filter.matches(package)) {
+                debug!("Checking {} of binary package {package}...", relation.field());
+                for alternatives in dependencies {
+                    if !alternatives.iter().any(|dependency| is_satisfied(&versions, &provides, dependency)) {
+                        let formatted: Vec<String> = alternatives.iter().map(format_dependency).collect();
+                        let reference = format!("{} {} of package {package}", relation.name(), formatted.join(" | "));
+                        let message = format!("{reference} is missing.");
+                        self.unresolved_dependencies.push(Unresolved {
+                            finding: Finding::new(relation.missing(), component, architecture, &message).with_package(package).with_version(version),
+                            reference,
+                            alternatives: alternatives.clone(),
+                        });
+                    }
+                }
+            }
+            self.add_timing(Timing::new(Phase::Dependencies, start.elapsed()).with_unit(component, architecture));
+        }
+
+        if self.options.check_conflicts {
+            let start = Instant::now();
+            self.check_conflicts(component, architecture, &versions, &depends, &conflicts);
+            self.add_timing(Timing::new(Phase::Conflicts, start.elapsed()).with_unit(component, architecture));
+        }
+
+        if self.options.check_essential {
+            self.check_essential_set(component, architecture, &essential, &versions, &provides, &depends);
+        }
+
+        if self.options.check_contents || self.options.check_commands {
+            let packages: HashSet<String> = versions.into_keys().collect();
+            if self.options.check_contents {
+                self.check_contents(component, architecture, &packages).await;
+            }
+            if self.options.check_commands {
+                self.check_commands(component, architecture, &packages).await;
+            }
+        }
+
+        if self.options.check_pdiffs {
+            self.check_pdiffs(component, architecture, &content).await;
+        }
+
+        if let Some(index) = retained {
+            self.binary_indices.push(index);
+        }
+    
+        Ok(())
+    }
+    
+    /// Find the best supported compression variant of an index listed in the Release file.
+    fn index_link(&self, path: &str) -> Option<Link> {
+        let base = self.release.distro.url(path, false);
+        index::SUPPORTED_COMPRESSIONS
+            .iter()
+            .find_map(|extension| self.release.links.get(&format!("{base}{extension}")))
+            .cloned()
+    }
+
+    /// Check the debian-installer index and the referenced udeb files.
+    async fn check_udeb_component(&mut self, component: &str, architecture: &Architecture) {
+        info!("Checking debian-installer index of component {component} for architecture {architecture}...");
+        let path = format!("{component}/debian-installer/binary-{architecture}/Packages");
+        if self.options.check_index_hashes {
+            self.check_index_variants(component, architecture, &path).await;
+        }
+
+        let start = Instant::now();
+        let link = match self.index_link(&path) {
+            Some(link) => link,
+            None => {
+                let message = format!("No debian-installer index {path} listed in Release.");
+                self.add_finding(Finding::new(FindingKind::IndexUnavailable, component, architecture, &message));
+                return;
+            }
+        };
+
+        let content = match self.download_component_index(component, architecture, &link).await {
+            Ok(Some(content)) => content,
+            Ok(None) => return,
+            Err(e) => {
+                let message = format!("Checking debian-installer index {} failed: {e}", link.url);
+                self.add_finding(Finding::new(FindingKind::IndexUnavailable, component, architecture, &message).with_url(&link.url));
+                return;
+            }
+        };
+
+        // Files to check.
+        let mut files: Vec<PackageFile> = Vec::new();
+        for stanza in index::stanzas(&content) {
+            match Package::from_stanza(stanza, &self.release.distro) {
+                Ok(package) => files.push((package.package, format_version(&package.version), package.link)),
+                Err(e) => {
+                    let message = format!("Invalid udeb stanza in {}: {e}", link.url);
+                    self.add_finding(Finding::new(FindingKind::InvalidStanza, component, architecture, &message).with_url(&link.url));
+                }
+            }
+        }
+        self.add_timing(Timing::new(Phase::Udeb, start.elapsed()).with_unit(component, architecture));
+
+        info!("Checking {} udeb files of component {component} for architecture {architecture}...", files.len());
+        self.check_files_exist(component, architecture, files).await;
+    }
+
+    /// Verify the pdiffs of the binary index and optionally apply them.
+    async fn check_pdiffs(&mut self, component: &str, architecture: &Architecture, content: &str) {
+        let path = format!("{component}/binary-{architecture}/Packages.diff/Index");
+        let link = match self.release.links.get(&self.release.distro.url(&path, false)) {
+            Some(link) => link.clone(),
+            None => {
+                debug!("No pdiffs for component {component} and architecture {architecture} listed in Release.");
+                return;
+            }
+        };
+
+        info!("Checking pdiffs of component {component} for architecture {architecture}...");
+        let start = Instant::now();
+
+        let index = match self.load_index(component, architecture, &link, FindingKind::MissingFile).await {
+            Some(index) => PdiffIndex::parse(&index),
+            None => return,
+        };
+
+        let issues = index.issues();
+        if !issues.is_empty() {
+            let message = format!("Pdiff index {} is inconsistent: {}.", link.url, issues.join(", "));
+            self.add_finding(Finding::new(FindingKind::InvalidPdiff, component, architecture, &message).with_url(&link.url));
+        }
+
+        if let Some((hash, size)) = &index.current {
+            if *hash != format!("{:x}", Sha256::digest(content.as_bytes())) || *size != content.len() as u64 {
+                let message = format!("Pdiff index {} is outdated, SHA256-Current doesn't match the binary index.", link.url);
+                self.add_finding(Finding::new(FindingKind::InvalidPdiff, component, architecture, &message).with_url(&link.url));
+            }
+        }
+
+        let directory = link.url.strip_suffix("Index").unwrap_or(&link.url).to_string();
+        // Parsed patches. (Name, Commands)
+        let mut scripts: HashMap<String, Vec<EdCommand>> = HashMap::new();
+        for patch in &index.patches {
+            let download = match index.download(&patch.name) {
+                Some(download) => download,
+                None => continue,
+            };
+
+            let url = format!("{directory}{}", download.name);
+            let data = match self.transport.download(&url).await {
+                Ok(data) => data,
+                Err(e) => {
+                    let message = format!("Pdiff {url} listed in {} is not available: {e}", link.url);
+                    self.add_finding(Finding::new(FindingKind::MissingFile, component, architecture, &message).with_url(&url));
+                    continue;
+                }
+            };
+
+            if data.len() as u64 != download.size || format!("{:x}", Sha256::digest(&data)) != download.hash {
+                let message = format!("Pdiff {url} doesn't match the size or SHA256 hash listed in {}.", link.url);
+                self.add_finding(Finding::new(FindingKind::IndexHashMismatch, component, architecture, &message).with_url(&url));
+                continue;
+            }
+
+            let data = match index::decompress(data, &url) {
+                Ok(data) => data,
+                Err(e) => {
+                    let message = format!("Pdiff {url} can't be decompressed: {e}");
+                    self.add_finding(Finding::new(FindingKind::InvalidCompression, component, architecture, &message).with_url(&url));
+                    continue;
+                }
+            };
+
+            if data.len() as u64 != patch.size || format!("{:x}", Sha256::digest(&data)) != patch.hash {
+                let message = format!("Decompressed pdiff {url} doesn't match the size or SHA256 hash listed in {}.", link.url);
+                self.add_finding(Finding::new(FindingKind::IndexHashMismatch, component, architecture, &message).with_url(&url));
+                continue;
+            }
+
+            match pdiff::parse_script(&String::from_utf8_lossy(&data)) {
+                Ok(commands) => {
+                    scripts.insert(patch.name.clone(), commands);
+                }
+                Err(e) => {
+                    let message = format!("Pdiff {url} is no valid ed script: {e}.");
+                    self.add_finding(Finding::new(FindingKind::InvalidPdiff, component, architecture, &message).with_url(&url));
+                }
+            }
+        }
+
+        if self.options.apply_pdiffs {
+            self.apply_pdiffs(component, architecture, &index, &scripts, content, &link.url).await;
+        }
+
+        self.add_timing(Timing::new(Phase::Pdiffs, start.elapsed()).with_unit(component, architecture));
+    }
+
+    /// Apply the pdiffs to an old version of the binary index and compare the result with the current index.
+    ///
+    /// Old versions are only available by-hash, the newest available one is used.
+    /// The history gives the hashes of the uncompressed versions, so the pdiffs can only be applied
+    /// if the archive publishes the uncompressed index, too. The hashes of old compressed versions are unknown.
+    async fn apply_pdiffs(&mut self, component: &str, architecture: &Architecture, index: &PdiffIndex, scripts: &HashMap<String, Vec<EdCommand>>, content: &str, index_url: &str) {
+        if !self.release.acquire_by_hash {
+            info!("Old versions of the binary index are not available by-hash, skipping application of pdiffs.");
+            return;
+        }
+
+        let directory = index_url.strip_suffix("Packages.diff/Index").unwrap_or(index_url);
+        if !self.release.links.contains_key(&format!("{directory}Packages")) {
+            warn!("Applying pdiffs is unsupported for {directory}Packages, since only compressed variants are published and their old hashes are unknown.");
+            return;
+        }
+
+        for (position, entry) in index.history.iter().enumerate().rev() {
+            let url = format!("{directory}by-hash/SHA256/{}", entry.hash);
+            let base = match self.transport.download(&url).await {
+                Ok(base) if format!("{:x}", Sha256::digest(&base)) == entry.hash => base,
+                _ => continue,
+            };
+
+            // Merged patches apply directly, else the remaining chain is applied.
+            let chain: Vec<&PdiffEntry> = if index.merged {
+                vec![entry]
+            } else {
+                index.history[position..].iter().collect()
+            };
+
+            let mut result = String::from_utf8_lossy(&base).to_string();
+            for patch in chain {
+                let commands = match scripts.get(&patch.name) {
+                    Some(commands) => commands,
+                    None => {
+                        debug!("Pdiff {} is not available, skipping application.", patch.name);
+                        return;
+                    }
+                };
+
+                result = match pdiff::apply(&result, commands) {
+                    Ok(result) => result,
+                    Err(e) => {
+                        let message = format!("Applying pdiff {} to {url} failed: {e}", patch.name);
+                        self.add_finding(Finding::new(FindingKind::InvalidPdiff, component, architecture, &message).with_url(index_url));
+                        return;
+                    }
+                };
+            }
+
+            if result == content {
+                info!("Pdiffs applied to {url} reproduce the binary index.");
+            } else {
+                let message = format!("Pdiffs applied to {url} don't reproduce the binary index.");
+                self.add_finding(Finding::new(FindingKind::InvalidPdiff, component, architecture, &message).with_url(index_url));
+            }
+            return;
+        }
+
+        info!("No old version of the binary index is available by-hash, skipping application of pdiffs.");
+    }
+
+    /// Find the Contents index of the component and architecture.
+    ///
+    /// Contents indices are provided per component, or for the whole suite
+    /// in older or Ubuntu style repos. For suite level indices, the component
+    /// is returned to filter the packages.
+    fn contents_link(&self, component: &str, architecture: &str) -> Option<(Link, Option<String>)> {
+        let candidates = [
+            (format!("{component}/Contents-{architecture}"), None),
+            (format!("Contents-{architecture}"), Some(component.to_string())),
+        ];
+
+        candidates
+            .into_iter()
+            .find_map(|(path, filter)| self.index_link(&path).map(|link| (link, filter)))
+    }
+
+    /// Download a Packages or Sources index, verify its hash and decompress it.
+    ///
+    /// Corrupt compressed data is recorded as finding and None is returned,
+    /// other failures are returned as error.
+    async fn download_component_index(&mut self, component: &str, architecture: &Architecture, link: &Link) -> Result<Option<String>> {
+        let data = index::download_verified(&self.transport, link).await?;
+
+        match index::decompress(data, &link.url) {
+            Ok(content) => Ok(Some(String::from_utf8_lossy(&content).to_string())),
+            Err(e) => {
+                // The variant check may have reported the index already.
+                let reported = self.findings.iter().any(|finding| finding.kind == FindingKind::InvalidCompression && finding.url.as_deref() == Some(link.url.as_str()));
+                if !reported {
+                    let message = format!("Index {} can't be decompressed: {e}", link.url);
+                    self.add_finding(Finding::new(FindingKind::InvalidCompression, component, architecture, &message).with_url(&link.url));
+                }
+                Ok(None)
+            }
+        }
+    }
+
+    /// Download an auxiliary index, verify its hashes and decompress it.
+    ///
+    /// Issues are recorded as findings, using the given kind if the index is not available.
+    async fn load_index(&mut self, component: &str, architecture: &Architecture, link: &Link, unavailable: FindingKind) -> Option<String> {
+        let data = match self.transport.download(&link.url).await {
+            Ok(data) => data,
+            Err(e) => {
+                let message = format!("Index {} is not available: {e}", link.url);
+                self.add_finding(Finding::new(unavailable, component, architecture, &message).with_url(&link.url));
+                return None;
+            }
+        };
+
+        let mismatches = index::hash_mismatches(&data, link);
+        if !mismatches.is_empty() {
+            let message = format!("Index {} is corrupt: {}.", link.url, mismatches.join("; "));
+            self.add_finding(Finding::new(FindingKind::IndexHashMismatch, component, architecture, &message).with_url(&link.url));
+            return None;
+        }
+
+        match index::decompress(data, &link.url) {
+            Ok(content) => Some(String::from_utf8_lossy(&content).to_string()),
+            Err(e) => {
+                let message = format!("Index {} can't be decompressed: {e}", link.url);
+                self.add_finding(Finding::new(FindingKind::InvalidCompression, component, architecture, &message).with_url(&link.url));
+                None
+            }
+        }
+    }
+
+    /// Download a Contents index, verify its hashes and get the listed packages.
+    async fn load_contents(&mut self, component: &str, architecture: &Architecture, link: &Link, filter: Option<&str>) -> Option<HashSet<String>> {
+        let content = self.load_index(component, architecture, link, FindingKind::ContentsUnavailable).await?;
+        Some(contents::packages(&content, filter))
+    }
+
+    /// Cross-reference the Contents index with the packages of the binary index.
+    async fn check_contents(&mut self, component: &str, architecture: &Architecture, packages: &HashSet<String>) {
+        info!("Checking Contents index of component {component} for architecture {architecture}...");
+        let start = Instant::now();
+
+        let (link, filter) = match self.contents_link(component, &architecture.to_string()) {
+            Some(found) => found,
+            None => {
+                let message = format!("No Contents index for architecture {architecture} listed in Release.");
+                self.add_finding(Finding::new(FindingKind::ContentsUnavailable, component, architecture, &message));
+                return;
+            }
+        };
+
+        let mut listed = match self.load_contents(component, architecture, &link, filter.as_deref()).await {
+            Some(listed) => listed,
+            None => return,
+        };
+
+        // Architecture independent packages may be listed in a separate index.
+        if let Some((link, filter)) = self.contents_link(component, "all") {
+            if let Some(all) = self.load_contents(component, architecture, &link, filter.as_deref()).await {
+                listed.extend(all);
+            }
+        }
+
+        let mut missing: Vec<&String> = packages.difference(&listed).collect();
+        missing.sort();
+        for package in missing {
+            let message = format!("Package {package} is missing in Contents index {}.", link.url);
+            self.add_finding(Finding::new(FindingKind::PackageNotInContents, component, architecture, &message).with_package(package).with_url(&link.url));
+        }
+
+        let mut stale: Vec<&String> = listed.difference(packages).collect();
+        stale.sort();
+        for package in stale {
+            let message = format!("Package {package} of Contents index {} is not in the binary index.", link.url);
+            self.add_finding(Finding::new(FindingKind::PackageOnlyInContents, component, architecture, &message).with_package(package).with_url(&link.url));
+        }
+
+        self.add_timing(Timing::new(Phase::Contents, start.elapsed()).with_unit(component, architecture));
+    }
+
+    /// Check the command-not-found metadata against the packages of the binary index.
+    async fn check_commands(&mut self, component: &str, architecture: &Architecture, packages: &HashSet<String>) {
+        info!("Checking command-not-found metadata of component {component} for architecture {architecture}...");
+        let start = Instant::now();
+
+        let link = match self.index_link(&format!("{component}/cnf/Commands-{architecture}")) {
+            Some(link) => link,
+            None => {
+                let message = format!("No command-not-found metadata for architecture {architecture} listed in Release.");
+                self.add_finding(Finding::new(FindingKind::CommandsUnavailable, component, architecture, &message));
+                return;
+            }
+        };
+
+        let content = match self.load_index(component, architecture, &link, FindingKind::CommandsUnavailable).await {
+            Some(content) => content,
+            None => return,
+        };
+
+        let (commands, issues) = cnf::parse(&content);
+        if !issues.is_empty() {
+            let listed: Vec<&str> = issues.iter().take(MAX_LISTED_ISSUES).map(|issue| issue.as_str()).collect();
+            let more = if issues.len() > MAX_LISTED_ISSUES { ", ..." } else { "" };
+            let message = format!("Command-not-found metadata {} has {} invalid stanzas: {}{more}.", link.url, issues.len(), listed.join(", "));
+            self.add_finding(Finding::new(FindingKind::InvalidStanza, component, architecture, &message).with_url(&link.url));
+        }
+
+        for (package, commands) in commands {
+            if !packages.contains(&package) {
+                let message = format!("Commands {} of {} refer to missing package {package}.", commands.join(", "), link.url);
+                self.add_finding(Finding::new(FindingKind::CommandPackageMissing, component, architecture, &message).with_package(&package).with_url(&link.url));
+            }
+        }
+
+        self.add_timing(Timing::new(Phase::Commands, start.elapsed()).with_unit(component, architecture));
+    }
+
+    /// Check that the Essential and required packages form a complete, dependency closed set.
+    ///
+    /// Components without Essential packages, e.g. of add-on repositories, are not checked for core packages.
+    fn check_essential_set(
+        &mut self,
+        component: &str,
+        architecture: &Architecture,
+        essential: &BTreeSet<String>,
+        versions: &HashMap<String, Vec<Version>>,
+        provides: &HashMap<String, Vec<Option<Version>>>,
+        depends: &[(String, String, Relation, Vec<Vec<PackageVersion>>)],
+    ) {
+        if essential.is_empty() {
+            return;
+        }
+
+        let mut findings = Vec::new();
+        for core in CORE_PACKAGES.iter().filter(|core| !versions.contains_key(**core)) {
+            let message = format!("Essential set lacks the core package {core}.");
+            findings.push(Finding::new(FindingKind::IncompleteEssentialSet, component, architecture, &message).with_package(core));
+        }
+
+        // Hard dependencies of all packages. (Package, Groups)
+        let mut hard: HashMap<&str, Vec<&Vec<PackageVersion>>> = HashMap::new();
+        for (package, _, _, groups) in depends.iter().filter(|(_, _, relation, _)| relation.is_hard()) {
+            hard.entry(package.as_str()).or_default().extend(groups);
+        }
+
+        let mut closure: BTreeSet<&str> = BTreeSet::new();
+        let mut missing: BTreeSet<String> = BTreeSet::new();
+        let mut pending: Vec<&str> = essential.iter().map(|package| package.as_str()).collect();
+        while let Some(package) = pending.pop() {
+            if !closure.insert(package) {
+                continue;
+            }
+
+            for group in hard.get(package).into_iter().flatten() {
+                match group.iter().find(|dependency| is_satisfied(versions, provides, dependency)) {
+                    // Virtual packages have no dependencies of their own.
+                    Some(dependency) if versions.contains_key(&dependency.name) => pending.push(dependency.name.as_str()),
+                    Some(_) => {}
+                    None => {
+                        let alternatives: Vec<String> = group.iter().map(format_dependency).collect();
+                        let alternatives = alternatives.join(" | ");
+                        if missing.insert(alternatives.clone()) {
+                            let message = format!("Essential set is not closed: {alternatives}, required by {package}, is missing.");
+                            findings.push(Finding::new(FindingKind::IncompleteEssentialSet, component, architecture, &message).with_package(package));
+                        }
+                    }
+                }
+            }
+        }
+
+        for finding in findings {
+            self.add_finding(finding);
+        }
+    }
+
+    /// Check Breaks, Conflicts and Replaces relations for impossible or unsafe combinations.
+    ///
+    /// Only dependencies without alternatives are followed, since apt can
+    /// choose another alternative to avoid a conflict.
+    fn check_conflicts(
+        &mut self,
+        component: &str,
+        architecture: &Architecture,
+        versions: &HashMap<String, Vec<Version>>,
+        depends: &[(String, String, Relation, Vec<Vec<PackageVersion>>)],
+        conflicts: &HashMap<String, ConflictRelations>,
+    ) {
+        // Dependencies without alternatives. (Package, Dependencies)
+        let mut required: BTreeMap<&str, BTreeSet<&str>> = BTreeMap::new();
+        for (package, _, _, groups) in depends.iter().filter(|(_, _, relation, _)| relation.is_hard()) {
+            let entry = required.entry(package.as_str()).or_default();
+            for group in groups {
+                if let [dependency] = &group[..] {
+                    if versions.contains_key(&dependency.name) {
+                        entry.insert(dependency.name.as_str());
+                    }
+                }
+            }
+        }
+
+        // Test if the package conflicts with an available version of the other package.
+        let no_provides = HashMap::new();
+        let conflicts_with = |package: &str, other: &str| {
+            conflicts.get(package).is_some_and(|(conflicts, _)| {
+                conflicts
+                    .iter()
+                    .any(|conflict| conflict.name == other && is_satisfied(versions, &no_provides, conflict))
+            })
+        };
+
+        let mut findings = Vec::new();
+        for (package, dependencies) in &required {
+            // The package conflicts with one of its transitive dependencies.
+            if conflicts.get(*package).is_some_and(|(conflicts, _)| !conflicts.is_empty()) {
+                let mut closure: BTreeSet<&str> = BTreeSet::new();
+                let mut pending: Vec<&str> = dependencies.iter().copied().collect();
+                while let Some(dependency) = pending.pop() {
+                    if closure.insert(dependency) {
+                        pending.extend(required.get(dependency).into_iter().flatten());
+                    }
+                }
+
+                for dependency in closure {
+                    if dependency != *package && conflicts_with(package, dependency) {
+                        let message = format!("Package {package} conflicts with its dependency {dependency}.");
+                        findings.push(Finding::new(FindingKind::ConflictingDependencies, component, architecture, &message).with_package(package));
+                    }
+                }
+            }
+
+            // Two dependencies of the package conflict with each other.
+            let dependencies: Vec<&str> = dependencies.iter().copied().collect();
+            for (position, first) in dependencies.iter().enumerate() {
+                for second in &dependencies[position + 1..] {
+                    if conflicts_with(first, second) || conflicts_with(second, first) {
+                        let message = format!("Package {package} depends on {first} and {second}, which conflict with each other.");
+                        findings.push(Finding::new(FindingKind::ConflictingDependencies, component, architecture, &message).with_package(package));
+                    }
+                }
+            }
+        }
+
+        // Replaces should be accompanied by Breaks or Conflicts, else files are overwritten silently.
+        let mut packages: Vec<&String> = conflicts.keys().collect();
+        packages.sort();
+        for package in packages {
+            let (breaks, replaces) = &conflicts[package];
+            for replaced in replaces {
+                if replaced.name != *package
+                    && versions.contains_key(&replaced.name)
+                    && !breaks.iter().any(|conflict| conflict.name == replaced.name)
+                {
+                    let message = format!("Package {package} replaces {} without Breaks or Conflicts.", format_dependency(replaced));
+                    findings.push(Finding::new(FindingKind::ReplacesWithoutBreaks, component, architecture, &message).with_package(package));
+                }
+            }
+        }
+
+        for finding in findings {
+            self.add_finding(finding);
+        }
+    }
+
+    /// Check the Multi-Arch field of a binary package.
+    ///
+    /// The versions of Multi-Arch: same packages are recorded, to compare them across architectures.
+    fn check_multi_arch(&mut self, component: &str, architecture: &Architecture, stanza: &str, package: &Package) {
+        let multi_arch = match index::field(stanza, "Multi-Arch") {
+            Some(multi_arch) => multi_arch,
+            None => return,
+        };
+
+        if !MULTI_ARCH_VALUES.contains(&multi_arch) {
+            let message = format!("Package {} has invalid Multi-Arch value {multi_arch}.", package.package);
+            self.add_finding(Finding::new(FindingKind::InvalidMultiArch, component, architecture, &message).with_package(&package.package).with_version(&format_version(&package.version)));
+            return;
+        }
+
+        if multi_arch != "same" {
+            return;
+        }
+
+        if package.architecture == Some(Architecture::All) {
+            let message = format!("Package {} of architecture all can't be Multi-Arch: same.", package.package);
+            self.add_finding(Finding::new(FindingKind::InvalidMultiArch, component, architecture, &message).with_package(&package.package).with_version(&format_version(&package.version)));
+            return;
+        }
+
+        self.multi_arch_same
+            .entry(component.to_string())
+            .or_default()
+            .entry(package.package.clone())
+            .or_default()
+            .entry(architecture.to_string())
+            .or_default()
+            .insert(format_version(&package.version));
+    }
+
+    /// Check that stanzas referencing the same Filename agree on its hash.
+    ///
+    /// Architecture all packages are listed in each binary index with the same Filename.
+    fn check_pool_hash(&mut self, component: &str, architecture: &Architecture, stanza: &str, package: &Package) {
+        let (filename, hash) = match (index::field(stanza, "Filename"), package.link.hashes.get(&LinkHash::Sha256)) {
+            (Some(filename), Some(hash)) => (filename, hash.to_lowercase()),
+            _ => return,
+        };
+
+        match self.pool_hashes.get(filename) {
+            Some(known) if *known != hash => {
+                let message = format!("Filename {filename} of package {} is listed with different SHA256 hashes {known} and {hash}.", package.package);
+                self.add_finding(Finding::new(FindingKind::DuplicateFilename, component, architecture, &message).with_package(&package.package).with_version(&format_version(&package.version)));
+            }
+            Some(_) => {}
+            None => {
+                self.pool_hashes.insert(filename.to_string(), hash);
+            }
+        }
+    }
+
+    /// Check the Section, Priority, Maintainer and Uploaders fields of a binary or source stanza.
+    ///
+    /// Missing Section and Priority fields are reported as info, since they are only recommended.
+    fn check_stanza_fields(&mut self, component: &str, architecture: &Architecture, stanza: &str, package: &str, version: &str) {
+        let checks = [
+            ("Section", FindingKind::InvalidSection, fields::section_issue as fn(&str) -> Option<String>),
+            ("Priority", FindingKind::InvalidPriority, fields::priority_issue),
+        ];
+
+        for (field, kind, issue) in checks {
+            let finding = match index::field(stanza, field) {
+                Some(value) => match issue(value) {
+                    Some(issue) => Finding::new(kind, component, architecture, &format!("Package {package} {version}: {issue}.")),
+                    None => continue,
+                },
+                None => Finding::new(kind, component, architecture, &format!("Package {package} {version} has no {field}.")).with_severity(Severity::Info),
+            };
+            self.add_finding(finding.with_package(package).with_version(version));
+        }
+
+        let mut maintainers: Vec<&str> = match index::field(stanza, "Maintainer") {
+            Some(maintainer) => vec![maintainer],
+            None => {
+                let message = format!("Package {package} {version} has no Maintainer.");
+                self.add_finding(Finding::new(FindingKind::InvalidMaintainer, component, architecture, &message).with_package(package).with_version(version));
+                Vec::new()
+            }
+        };
+        let uploaders = index::folded_field(stanza, "Uploaders").unwrap_or_default();
+        maintainers.extend(fields::uploaders(&uploaders));
+
+        for maintainer in maintainers {
+            if let Some(issue) = fields::maintainer_issue(maintainer) {
+                let message = format!("Package {package} {version}: {issue}.");
+                self.add_finding(Finding::new(FindingKind::InvalidMaintainer, component, architecture, &message).with_package(package).with_version(version));
+            }
+        }
+    }
+
+    /// Check that the Filename of the binary package is in the pool directory of its source.
+    fn check_package_pool_path(&mut self, component: &str, architecture: &Architecture, stanza: &str, package: &Package) {
+        let filename = match index::field(stanza, "Filename") {
+            Some(filename) => filename,
+            None => return,
+        };
+
+        let source = match &package.source {
+            Some(source) => source.split_whitespace().next().unwrap_or(&package.package),
+            None => &package.package,
+        };
+        let directory = pool_directory(component, source);
+
+        let in_directory = filename
+            .strip_prefix(&directory)
+            .and_then(|file| file.strip_prefix('/'))
+            .is_some_and(|file| !file.is_empty() && !file.contains('/'));
+        if !in_directory {
+            let message = format!("Filename {filename} of package {} is not in {directory}/.", package.package);
+            self.add_finding(Finding::new(FindingKind::UnexpectedPoolPath, component, architecture, &message).with_package(&package.package).with_version(&format_version(&package.version)));
+        }
+    }
+
+    /// Check that the Directory of the source package is its pool directory.
+    fn check_source_pool_path(&mut self, component: &str, source: &Source) {
+        let directory = pool_directory(component, &source.package);
+
+        if source.directory.trim_end_matches('/') != directory {
+            let message = format!("Directory {} of source {} is not {directory}/.", source.directory, source.package);
+            self.add_finding(Finding::new(FindingKind::UnexpectedPoolPath, component, &Architecture::Source, &message).with_package(&source.package).with_version(&format_version(&source.version)));
+        }
+    }
+
+    /// Check that the source of the binary package is available.
+    fn check_package_source(&mut self, component: &str, architecture: &Architecture, package: &Package) {
+        debug!("Checking source of binary package {}...", package.package);
+        // Check for source package.
+        if let Some(source) = &package.source {
+            // The source field may contain the source version, e.g. for binNMUs.
+            let (source_name, source_version) = match source.split_once(' ') {
+                Some((name, version)) => {
+                    let version = version.trim().trim_start_matches('(').trim_end_matches(')');
+                    match Version::from_str(version) {
+                        Ok(version) => (name.to_string(), version),
+                        Err(e) => {
+                            warn!("Invalid source version of package {}: {e}", package.package);
+                            (name.to_string(), package.version.clone())
+                        }
+                    }
+                }
+                None => (source.clone(), package.version.clone()),
+            };
+
+            let sources = self.source_versions.get(component);
+            if sources.is_none() {
+                warn!("No source index for component {component} found!");
+            }
+
+            // Sources missing in the component are searched in the other components by the cross check.
+            let available = sources.and_then(|sources| sources.get(&source_name)).map(|versions| versions.contains(&source_version));
+            if available != Some(true) {
+                let reference = format!("Source {source} of package {}", package.package);
+                let message = format!("{reference} is missing.");
+                self.unresolved_sources.push(Unresolved {
+                    finding: Finding::new(FindingKind::MissingSource, component, architecture, &message)
+                        .with_package(&package.package)
+                        .with_version(&format_version(&package.version)),
+                    reference,
+                    alternatives: vec![PackageVersion {
+                        name: source_name,
+                        architecture: None,
+                        relation: Some(VersionRelation::Exact),
+                        version: Some(source_version),
+                    }],
+                });
+            }
+        } else {
+            warn!("No source for package {} of component {component} found!", package.package);
+        }
+    }
+
+    /// Check that the files of the source package match its format.
+    fn check_source_files(&mut self, component: &str, source: &Source) {
+        let files: Vec<&str> = source
+            .links
+            .values()
+            .map(|link| link.url.rsplit('/').next().unwrap_or(&link.url))
+            .collect();
+
+        let issues = dsc::format_issues(&source.format, &files);
+        if !issues.is_empty() {
+            let message = format!(
+                "Source {} {} of format {} has {}.",
+                source.package,
+                format_version(&source.version),
+                source.format.trim(),
+                issues.join(", ")
+            );
+            self.add_finding(Finding::new(FindingKind::InvalidSourceFiles, component, &Architecture::Source, &message).with_package(&source.package).with_version(&format_version(&source.version)));
+        }
+    }
+
+    async fn check_source_component(&mut self, component: &str) -> Result<()> {
+        info!("Checking sources of component {component}...");
+        let start = Instant::now();
+
+        let link = index::package_index_link(&self.transport, &self.release, component, &Architecture::Source).await?;
+        let content = match self.download_component_index(component, &Architecture::Source, &link).await? {
+            Some(content) => content,
+            None => return Ok(()),
+        };
+
+        let mut versions: HashMap<String, Vec<Version>> = HashMap::new();
+        // Files to check.
+        let mut files: Vec<PackageFile> = Vec::new();
+        // Dsc files to compare with the index. (Dsc file, Source files)
+        let mut dsc_files: Vec<(PackageFile, Vec<Link>)> = Vec::new();
+        // Listed source versions. (Source, Version)
+        let mut listed: HashSet<(String, String)> = HashSet::new();
+
+        let mut retained = if self.options.retain_indices {
+            Some(SourceIndex {
+                package_map: HashMap::new(),
+                issues: Vec::new(),
+            })
+        } else {
+            None
+        };
+
+        info!("Checking sources packages of component {component}...");
+        let progress = self.progress(Phase::Index, component, &Architecture::Source, "sources", index::stanzas(&content).count());
+        for stanza in index::stanzas(&content) {
+            progress.inc();
+            let source = match Source::from_stanza(stanza, &self.release.distro) {
+                Ok(source) => source,
+                Err(e) => {
+                    let message = format!("Invalid source stanza: {e}");
+                    self.add_finding(Finding::new(FindingKind::InvalidStanza, component, &Architecture::Source, &message));
+
+                    continue;
+                }
+            };
+
+            debug!("Checking source {}...", source.package);
+            let selected = self.package_filter.matches(&source.package);
+
+            if self.options.check_files && selected {
+                for link in source.links.values() {
+                    files.push((source.package.clone(), format_version(&source.version), link.clone()));
+                }
+                if let Some(dsc) = source.links.values().find(|link| link.url.ends_with(".dsc")) {
+                    let dsc = (source.package.clone(), format_version(&source.version), dsc.clone());
+                    dsc_files.push((dsc, source.links.values().cloned().collect()));
+                }
+            }
+
+            if self.options.check_duplicates && !listed.insert((source.package.clone(), format_version(&source.version))) {
+                let message = format!("Source {} {} is listed twice.", source.package, format_version(&source.version));
+                self.add_finding(Finding::new(FindingKind::DuplicateStanza, component, &Architecture::Source, &message).with_package(&source.package).with_version(&format_version(&source.version)));
+            }
+            if self.options.check_source_format && selected {
+                self.check_source_files(component, &source);
+            }
+
+            if self.options.check_hash_policy {
+                self.check_stanza_hashes(component, &Architecture::Source, stanza, &source.package, &format_version(&source.version));
+            }
+
+            if self.options.check_fields {
+                self.check_stanza_fields(component, &Architecture::Source, stanza, &source.package, &format_version(&source.version));
+            }
+
+            if self.options.maintainer_summary {
+                self.maintainers.entry(source.package.clone()).or_insert_with(|| source.maintainer.clone());
+            }
+
+            if self.options.check_pool_layout {
+                self.check_source_pool_path(component, &source);
+            }
+
+            versions.entry(source.package.clone()).or_default().push(source.version.clone());
+
+            if self.options.check_source_binaries && selected {
+                let binaries = source_binaries(stanza);
+                self.source_binaries
+                    .entry(component.to_string())
+                    .or_default()
+                    .push((source.package.clone(), format_version(&source.version), binaries));
+            }
+
+            if self.options.check_build_depends && selected {
+                let architectures = index::field(stanza, "Architecture").unwrap_or("any").to_string();
+                let build_depends: Vec<String> = ["Build-Depends", "Build-Depends-Arch", "Build-Depends-Indep"]
+                    .iter()
+                    .filter_map(|field| index::folded_field(stanza, field))
+                    .collect();
+                self.build_depends
+                    .entry(component.to_string())
+                    .or_default()
+                    .push((source.package.clone(), format_version(&source.version), architectures, build_depends.join(", ")));
+            }
+
+            if let Some(index) = &mut retained {
+                index.package_map.entry(source.package.clone()).or_default().push(source);
+            }
+        }
+        progress.finish();
+        self.add_timing(Timing::new(Phase::Index, start.elapsed()).with_unit(component, &Architecture::Source));
+
+        if self.options.check_files {
+            info!("Checking {} source files of component {component}...", files.len());
+            self.check_files_exist(component, &Architecture::Source, files).await;
+
+            info!("Checking {} dsc files of component {component}...", dsc_files.len());
+            self.check_dsc_files(component, dsc_files).await;
+        }
+
+        self.source_versions.insert(component.to_string(), versions);
+
+        if let Some(index) = retained {
+            self.source_indices.insert(component.to_string(), index);
+        }
+
+        Ok(())
+    }
+
+    /// Select the files to probe according to the sampling option.
+    ///
+    /// Each component and architecture uses its own generator derived from the seed,
+    /// so the selection doesn't depend on the check order.
+    fn sample_files(&self, component: &str, architecture: &Architecture, mut files: Vec<PackageFile>) -> Vec<PackageFile> {
+        let total = files.len();
+        let count = self.options.sample.count(total);
+        if count == total {
+            return files;
+        }
+
+        let unit = format!("{component}/{architecture}");
+        let seed = self.options.seed.unwrap_or_default() ^ fnv1a(unit.as_bytes());
+        let mut rng = StdRng::seed_from_u64(seed);
+        files.shuffle(&mut rng);
+        files.truncate(count);
+
+        info!("Probing a sample of {count} of {total} files of {unit}.");
+        files
+    }
+
+    /// Check the existence and size of the given files in parallel.
+    ///
+    /// When the time budget is exhausted, no new file checks are started,
+    /// but the running ones are finished.
+    async fn check_files_exist(&mut self, component: &str, architecture: &Architecture, files: Vec<PackageFile>) {
+        let start = Instant::now();
+        let files = self.sample_files(component, architecture, files);
+        let transport = self.transport.clone();
+        let deadline = self.deadline;
+        let total = files.len();
+
+        let verify_hashes = self.options.verify_hashes;
+        let check_structure = self.options.check_deb_structure;
+        let progress = self.progress(Phase::Files, component, architecture, "files", total);
+
+        // Probing provides the size, if the server reports it.
+        // Hash verification downloads the file and provides size and SHA256 hash.
+        // Structure validation keeps the downloaded deb file in memory, so only a few large files are validated at once.
+        let deb_checks = Semaphore::new(PARALLEL_DEB_CHECKS);
+        let results: Vec<(PackageFile, Result<FileInfo>)> = stream::iter(files)
+            .take_while(|_| future::ready(!is_expired(deadline)))
+            .map(|file| {
+                let transport = transport.clone();
+                let progress = &progress;
+                let deb_checks = &deb_checks;
+                async move {
+                    let url = &file.2.url;
+                    let validate = check_structure && (url.ends_with(".deb") || url.ends_with(".udeb"));
+                    if validate && file.2.size as u64 > MAX_DEB_STRUCTURE_SIZE {
+                        debug!("Deb file {url} is too large for the structure validation, only probing it.");
+                    }
+
+                    let result = if validate && file.2.size as u64 <= MAX_DEB_STRUCTURE_SIZE {
+                        let _permit = deb_checks.acquire().await;
+                        debug!("Validating structure of {url}...");
+                        transport
+                            .download(url)
+                            .await
+                            .map(|data| (Some(data.len() as u64), Some(format!("{:x}", Sha256::digest(&data))), deb::structure_issues(&data)))
+                    } else if verify_hashes {
+                        transport.sha256(url).await.map(|(size, hash)| (Some(size), Some(hash), Vec::new()))
+                    } else {
+                        transport.probe(url).await.map(|size| (size, None, Vec::new()))
+                    };
+                    progress.inc();
+                    (file, result)
+                }
+            })
+            .buffer_unordered(PARALLEL_FILE_CHECKS)
+            .collect()
+            .await;
+        progress.finish();
+
+        if results.len() < total {
+            warn!("Time budget exhausted, checked only {} of {total} files.", results.len());
+            self.partial = true;
+        }
+        self.add_timing(Timing::new(Phase::Files, start.elapsed()).with_unit(component, architecture));
+
+        for ((package, version, link), result) in results {
+            if let Ok((_, _, issues)) = &result {
+                if !issues.is_empty() {
+                    let message = format!("Deb file {} of {package} is invalid: {}.", link.url, issues.join(", "));
+                    self.add_finding(Finding::new(FindingKind::InvalidDeb, component, architecture, &message).with_package(&package).with_version(&version).with_url(&link.url));
+                }
+            }
+
+            match result {
+                Ok((Some(size), _, _)) if size != link.size as u64 => {
+                    let message = format!("File {} of {package} is corrupt: expected {} bytes, but got {size} bytes.", link.url, link.size);
+                    self.add_finding(Finding::new(FindingKind::SizeMismatch, component, architecture, &message).with_package(&package).with_version(&version).with_url(&link.url));
+                }
+                Ok((_, Some(hash), _)) if link.hashes.get(&LinkHash::Sha256).is_some_and(|expected| expected.to_lowercase() != hash) => {
+                    let message = format!("File {} of {package} is corrupt: SHA256 hash {hash} doesn't match the index.", link.url);
+                    self.add_finding(Finding::new(FindingKind::FileHashMismatch, component, architecture, &message).with_package(&package).with_version(&version).with_url(&link.url));
+                }
+                Ok(_) => {} // pass!
+                Err(e) => {
+                    let message = format!("File {} of {package} is broken: {e}", link.url);
+                    self.add_finding(Finding::new(FindingKind::MissingFile, component, architecture, &message).with_package(&package).with_version(&version).with_url(&link.url));
+                }
+            }
+        }
+    }
+
+    /// Download the beginning of the deb files and compare their control files with the index stanzas.
+    async fn check_controls(&mut self, component: &str, architecture: &Architecture, debs: Vec<(PackageFile, Vec<Option<String>>)>) {
+        let start = Instant::now();
+
+        // Fields of the index stanza, by URL of the deb file.
+        let mut stanza_fields: HashMap<String, Vec<Option<String>>> = HashMap::new();
+        let mut files: Vec<PackageFile> = Vec::new();
+        for (deb, fields) in debs {
+            stanza_fields.insert(deb.2.url.clone(), fields);
+            files.push(deb);
+        }
+
+        let files = self.sample_files(component, architecture, files);
+        let transport = self.transport.clone();
+        let deadline = self.deadline;
+        let total = files.len();
+
+        let results: Vec<(PackageFile, Result<Vec<u8>>)> = stream::iter(files)
+            .take_while(|_| future::ready(!is_expired(deadline)))
+            .map(|file| {
+                let transport = transport.clone();
+                async move {
+                    let result = transport.download_prefix(&file.2.url, CONTROL_PREFIX_LENGTH).await;
+                    (file, result)
+                }
+            })
+            .buffer_unordered(PARALLEL_FILE_CHECKS)
+            .collect()
+            .await;
+
+        if results.len() < total {
+            warn!("Time budget exhausted, checked only {} of {total} control files.", results.len());
+            self.partial = true;
+        }
+        self.add_timing(Timing::new(Phase::Control, start.elapsed()).with_unit(component, architecture));
+
+        for ((package, version, link), result) in results {
+            // Unavailable deb files are reported by the file check.
+            let data = match result {
+                Ok(data) => data,
+                Err(e) => {
+                    debug!("Deb file {} of {package} is not available: {e}", link.url);
+                    continue;
+                }
+            };
+
+            let issues = match deb::control_file(&data) {
+                Ok(control) => {
+                    let expected = stanza_fields.get(&link.url).map(|fields| fields.as_slice()).unwrap_or_default();
+                    CONTROL_FIELDS
+                        .iter()
+                        .zip(expected)
+                        .filter_map(|(field, expected)| {
+                            let actual = index::folded_field(&control, field);
+                            let normalize = |value: &Option<String>| value.as_deref().map(|value| value.split_whitespace().collect::<Vec<&str>>().join(" "));
+                            if normalize(&actual) == normalize(expected) {
+                                None
+                            } else {
+                                let actual = actual.unwrap_or_else(|| "missing".to_string());
+                                let expected = expected.as_deref().unwrap_or("missing");
+                                Some(format!("{field} is {actual} instead of {expected}"))
+                            }
+                        })
+                        .collect()
+                }
+                Err(e) => vec![format!("control file can't be read: {e}")],
+            };
+
+            if !issues.is_empty() {
+                let message = format!("Control file of {} doesn't match the index: {}.", link.url, issues.join(", "));
+                self.add_finding(Finding::new(FindingKind::ControlMismatch, component, architecture, &message).with_package(&package).with_version(&version).with_url(&link.url));
+            }
+        }
+    }
+
+    /// Download the .dsc files and compare their checksum lists with the source files of the index.
+    async fn check_dsc_files(&mut self, component: &str, dsc_files: Vec<(PackageFile, Vec<Link>)>) {
+        let start = Instant::now();
+        let architecture = Architecture::Source;
+
+        // Source files of each dsc file, by URL of the dsc file.
+        let mut source_files: HashMap<String, Vec<Link>> = HashMap::new();
+        let mut files: Vec<PackageFile> = Vec::new();
+        for (dsc, links) in dsc_files {
+            source_files.insert(dsc.2.url.clone(), links);
+            files.push(dsc);
+        }
+
+        let files = self.sample_files(component, &architecture, files);
+        let transport = self.transport.clone();
+        let deadline = self.deadline;
+        let total = files.len();
+
+        let results: Vec<(PackageFile, Result<Vec<u8>>)> = stream::iter(files)
+            .take_while(|_| future::ready(!is_expired(deadline)))
+            .map(|file| {
+                let transport = transport.clone();
+                async move {
+                    let result = transport.download(&file.2.url).await;
+                    (file, result)
+                }
+            })
+            .buffer_unordered(PARALLEL_FILE_CHECKS)
+            .collect()
+            .await;
+
+        if results.len() < total {
+            warn!("Time budget exhausted, checked only {} of {total} dsc files.", results.len());
+            self.partial = true;
+        }
+        self.add_timing(Timing::new(Phase::Dsc, start.elapsed()).with_unit(component, &architecture));
+
+        for ((package, version, link), result) in results {
+            // Unavailable dsc files are reported by the file check.
+            let content = match result {
+                Ok(content) => String::from_utf8_lossy(&content).to_string(),
+                Err(e) => {
+                    debug!("Dsc file {} of {package} is not available: {e}", link.url);
+                    continue;
+                }
+            };
+
+            let issues = match signature::cleartext_payload(&content).ok().as_deref().and_then(dsc::checksums) {
+                Some((hash, listed)) => {
+                    let links = source_files.get(&link.url).map(|links| links.as_slice()).unwrap_or_default();
+                    dsc_issues(&link, links, hash, &listed)
+                }
+                None => vec!["no checksums are listed".to_string()],
+            };
+
+            if !issues.is_empty() {
+                let listed: Vec<&str> = issues.iter().take(MAX_LISTED_ISSUES).map(|issue| issue.as_str()).collect();
+                let more = if issues.len() > MAX_LISTED_ISSUES { ", ..." } else { "" };
+                let message = format!("Dsc file {} of {package} doesn't match the index: {}{more}.", link.url, listed.join(", "));
+                self.add_finding(Finding::new(FindingKind::DscMismatch, component, &architecture, &message).with_package(&package).with_version(&version).with_url(&link.url));
+            }
+        }
+    }
+}
+
+/// FNV-1a hash, which is stable across platforms and Rust versions.
+fn fnv1a(data: &[u8]) -> u64 {
+    data.iter().fold(0xcbf29ce484222325, |hash, byte| (hash ^ *byte as u64).wrapping_mul(0x100000001b3))
+}
+
+/// Normalize line endings and trailing whitespace for comparing file contents.
+fn normalize_lines(content: &str) -> Vec<&str> {
+    let mut lines: Vec<&str> = content.lines().map(|line| line.trim_end()).collect();
+    while lines.last().is_some_and(|line| line.is_empty()) {
+        lines.pop();
+    }
+    lines
+}
+
+/// Get the binaries built by a source stanza, with their architectures.
+///
+/// The Package-List field gives the architectures of each binary, udebs are skipped.
+/// Without Package-List, the Binary field is used with the architectures of the source.
+fn source_binaries(stanza: &str) -> Vec<(String, String)> {
+    let architectures = index::field(stanza, "Architecture").unwrap_or("any");
+    let package_list = index::field_lines(stanza, "Package-List");
+
+    if package_list.is_empty() {
+        return index::folded_field(stanza, "Binary")
+            .unwrap_or_default()
+            .split(',')
+            .map(|binary| binary.trim())
+            .filter(|binary| !binary.is_empty())
+            .map(|binary| (binary.to_string(), architectures.to_string()))
+            .collect();
+    }
+
+    package_list
+        .iter()
+        .filter_map(|line| {
+            let parts: Vec<&str> = line.split_whitespace().collect();
+            match parts[..] {
+                [binary, "deb", ..] => {
+                    let binary_architectures = parts[2..]
+                        .iter()
+                        .find_map(|part| part.strip_prefix("arch="))
+                        .map(|value| value.replace(',', " "))
+                        .unwrap_or_else(|| architectures.to_string());
+                    Some((binary.to_string(), binary_architectures))
+                }
+                _ => None,
+            }
+        })
+        .collect()
+}
+
+/// Compare the checksum list of a dsc file with the source files of the index.
+fn dsc_issues(dsc: &Link, links: &[Link], hash: LinkHash, listed: &dsc::Checksums) -> Vec<String> {
+    let mut issues = Vec::new();
+    let mut names = BTreeSet::new();
+
+    for link in links.iter().filter(|link| link.url != dsc.url) {
+        let name = link.url.rsplit('/').next().unwrap_or(&link.url);
+        names.insert(name);
+        match listed.get(name) {
+            None => issues.push(format!("{name} is not listed")),
+            Some((_, size)) if *size != link.size as u64 => issues.push(format!("{name} has size {size} instead of {}", link.size)),
+            Some((listed_hash, _)) if link.hashes.get(&hash).is_some_and(|expected| expected.to_lowercase() != *listed_hash) => {
+                issues.push(format!("{name} has a different {hash:?} hash"))
+            }
+            Some(_) => {}
+        }
+    }
+
+    for name in listed.keys().filter(|name| !names.contains(name.as_str())) {
+        issues.push(format!("{name} is not in the index"));
+    }
+
+    issues
+}
+
+/// Test if the given deadline has passed.
+fn is_expired(deadline: Option<Instant>) -> bool {
+    deadline.is_some_and(|deadline| Instant::now() >= deadline)
+}
+
+/// Test if a dependency is satisfied by the available versions or the provided virtual packages.
+fn is_satisfied(versions: &HashMap<String, Vec<Version>>, provides: &HashMap<String, Vec<Option<Version>>>, dependency: &PackageVersion) -> bool {
+    let available = versions.get(&dependency.name).into_iter().flatten();
+    // Only versioned Provides satisfy versioned dependencies.
+    let provided = provides.get(&dependency.name).into_iter().flatten().flatten();
+    let mut candidates = available.chain(provided);
+
+    match (&dependency.relation, &dependency.version) {
+        (Some(relation), Some(version)) => candidates.any(|a| relation.matches(a, version)),
+        (None, Some(version)) => candidates.any(|a| a == version),
+        _ => versions.contains_key(&dependency.name) || provides.contains_key(&dependency.name),
+    }
+}
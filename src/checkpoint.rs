@@ -0,0 +1,193 @@
+//! Checkpointing of long-running apt repo checks.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use libapt::{Error, Result};
+use log::{debug, error, info, warn};
+use serde::{Deserialize, Serialize};
+
+use crate::check::{AptCheck, WorkingState};
+
+/// Name of the checkpoint file in the state dir.
+const CHECKPOINT_FILE: &str = "checkpoint.json";
+
+/// Get the path of the checkpoint file for the given state dir.
+fn checkpoint_path(state_dir: &str) -> PathBuf {
+    Path::new(state_dir).join(CHECKPOINT_FILE)
+}
+
+/// Checkpoint to write, the check with the working state of its cross checks.
+#[derive(Serialize)]
+struct Checkpoint<'a> {
+    check: &'a AptCheck,
+    state: WorkingState<'a>,
+}
+
+/// Checkpoint read from the state dir.
+#[derive(Deserialize)]
+struct LoadedCheckpoint {
+    check: AptCheck,
+    state: WorkingState<'static>,
+}
+
+/// Write the current progress of the check to the state dir.
+///
+/// The checkpoint is written to a temporary file first and then moved
+/// in place, so an interrupted write never destroys the last checkpoint.
+pub fn save(check: &AptCheck, state_dir: &str) -> Result<()> {
+    if let Err(e) = fs::create_dir_all(state_dir) {
+        let message = format!("Creating state dir {state_dir} failed! {e}");
+        error!("{}", message);
+        return Err(Error::new(&message, libapt::ErrorType::ApiUsage));
+    }
+
+    let path = checkpoint_path(state_dir);
+    let tmp_path = path.with_extension("json.tmp");
+
+    let checkpoint = Checkpoint { check, state: check.working_state() };
+    let data = match serde_json::to_string(&checkpoint) {
+        Ok(data) => data,
+        Err(e) => {
+            let message = format!("Serializing checkpoint failed! {e}");
+            error!("{}", message);
+            return Err(Error::new(&message, libapt::ErrorType::ApiUsage));
+        }
+    };
+
+    if let Err(e) = fs::write(&tmp_path, data).and_then(|_| fs::rename(&tmp_path, &path)) {
+        let message = format!("Writing checkpoint {} failed! {e}", path.display());
+        error!("{}", message);
+        return Err(Error::new(&message, libapt::ErrorType::ApiUsage));
+    }
+
+    debug!("Checkpoint written to {}.", path.display());
+    Ok(())
+}
+
+/// Load the last checkpoint from the state dir.
+///
+/// Returns None if no checkpoint exists.
+pub fn load(state_dir: &str) -> Result<Option<AptCheck>> {
+    let path = checkpoint_path(state_dir);
+    if !path.exists() {
+        info!("No checkpoint found in {state_dir}.");
+        return Ok(None);
+    }
+
+    let data = match fs::read_to_string(&path) {
+        Ok(data) => data,
+        Err(e) => {
+            let message = format!("Reading checkpoint {} failed! {e}", path.display());
+            error!("{}", message);
+            return Err(Error::new(&message, libapt::ErrorType::ApiUsage));
+        }
+    };
+
+    match serde_json::from_str::<LoadedCheckpoint>(&data) {
+        Ok(LoadedCheckpoint { mut check, state }) => {
+            check.restore_working_state(state);
+            Ok(Some(check))
+        }
+        Err(e) => {
+            let message = format!("Parsing checkpoint {} failed! {e}", path.display());
+            error!("{}", message);
+            Err(Error::new(&message, libapt::ErrorType::ApiUsage))
+        }
+    }
+}
+
+/// Remove the checkpoint after a completed check.
+pub fn remove(state_dir: &str) {
+    let path = checkpoint_path(state_dir);
+    if path.exists() {
+        if let Err(e) = fs::remove_file(&path) {
+            warn!("Removing checkpoint {} failed! {e}", path.display());
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use libapt::{Distro, Key};
+
+    use super::*;
+    use crate::finding::Severity;
+    use crate::options::{CheckOptions, HttpOptions, Profile};
+    use crate::release;
+    use crate::transport::Transport;
+
+    /// Create a check of a release with one component and architecture.
+    fn new_check(options: CheckOptions) -> AptCheck {
+        let distro = Distro::repo("http://archive.example.org/debian", "test", Key::NoSignatureCheck);
+        let release = release::parse(&distro, "Suite: test\nCodename: test\nArchitectures: amd64\nComponents: main\n").unwrap();
+        let transport = Arc::new(Transport::new(&HttpOptions::default()).unwrap());
+        AptCheck::new(release, options, transport).unwrap()
+    }
+
+    /// Get a state dir unique to the test.
+    fn state_dir(name: &str) -> String {
+        let dir = std::env::temp_dir().join(format!("aptcheckr-test-{}-{name}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        dir.to_string_lossy().to_string()
+    }
+
+    #[test]
+    fn load_without_secrets() {
+        let options = CheckOptions {
+            webhooks: vec!["https://hooks.example.org/secret".to_string()],
+            webhook_secret: Some("secret".to_string()),
+            ..CheckOptions::default()
+        };
+        let dir = state_dir("secrets");
+        save(&new_check(options), &dir).unwrap();
+
+        let data = fs::read_to_string(checkpoint_path(&dir)).unwrap();
+        assert!(!data.contains("secret"));
+        assert!(load(&dir).unwrap().is_some());
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn save_and_load() {
+        let mut options = CheckOptions {
+            components: vec!["main".to_string()],
+            fail_on: Severity::Error,
+            max_duration: Some(600),
+            ..CheckOptions::default()
+        };
+        Profile::Deep.apply(&mut options);
+        let mut check = new_check(options);
+        let state: WorkingState = serde_json::from_value(serde_json::json!({
+            "source_versions": {},
+            "multi_arch_same": {"main": {"libfoo1": {"amd64": ["1.0-1"], "arm64": ["1.0-2"]}}},
+            "build_depends": {"main": [["foo", "1.0-1", "any", "debhelper-compat (= 13)"]]},
+            "priority_packages": {"base-files": ["required", ["amd64"]]},
+            "source_binaries": {"main": [["foo", "1.0-1", [["libfoo1", "any"]]]]},
+            "binary_versions": {},
+            "binary_provides": {},
+            "unresolved_dependencies": [],
+            "unresolved_sources": [],
+            "maintainers": {"foo": "Jane Doe <jane@example.org>"},
+            "pool_hashes": {"pool/main/f/foo/libfoo1_1.0-1_amd64.deb": "0123abcd"},
+        }))
+        .unwrap();
+        check.restore_working_state(state);
+
+        let dir = state_dir("save");
+        save(&check, &dir).unwrap();
+        let loaded = load(&dir).unwrap().unwrap();
+        fs::remove_dir_all(&dir).unwrap();
+
+        assert_eq!(serde_json::to_value(&loaded).unwrap(), serde_json::to_value(&check).unwrap());
+        assert_eq!(serde_json::to_value(loaded.working_state()).unwrap(), serde_json::to_value(check.working_state()).unwrap());
+        let report = serde_json::to_value(&loaded).unwrap();
+        assert_eq!(report["options"]["max_duration"], 600);
+        assert_eq!(report["options"]["check_files"], true);
+        assert!(report.get("pool_hashes").is_none());
+        let state = serde_json::to_value(loaded.working_state()).unwrap();
+        assert_eq!(state["pool_hashes"]["pool/main/f/foo/libfoo1_1.0-1_amd64.deb"], "0123abcd");
+    }
+}
@@ -0,0 +1,47 @@
+//! Parsing of command-not-found metadata, i.e. cnf/Commands-<arch> indices.
+
+use std::collections::BTreeMap;
+
+use crate::index;
+
+/// Get the commands of each package listed in a Commands index.
+///
+/// The first stanza is a header naming suite, component and architecture.
+/// Each further stanza must name the package and its commands.
+/// Returns the commands by package and a description of each invalid stanza.
+pub fn parse(content: &str) -> (BTreeMap<String, Vec<String>>, Vec<String>) {
+    let mut commands: BTreeMap<String, Vec<String>> = BTreeMap::new();
+    let mut issues = Vec::new();
+
+    for (number, stanza) in index::stanzas(content).enumerate() {
+        let mut name = None;
+        let mut listed = None;
+        let mut header = false;
+
+        for line in stanza.lines() {
+            match line.split_once(':') {
+                Some(("name", value)) => name = Some(value.trim()).filter(|name| !name.is_empty()),
+                Some(("commands", value)) => listed = Some(value.trim()),
+                Some(("suite", _)) => header = true,
+                _ => {}
+            }
+        }
+
+        if number == 0 && header {
+            continue;
+        }
+
+        match (name, listed) {
+            (Some(name), Some(listed)) => {
+                commands
+                    .entry(name.to_string())
+                    .or_default()
+                    .extend(listed.split(',').map(|command| command.trim().to_string()).filter(|command| !command.is_empty()));
+            }
+            (None, _) => issues.push(format!("stanza {} lacks name", number + 1)),
+            (Some(_), None) => issues.push(format!("stanza {} lacks commands", number + 1)),
+        }
+    }
+
+    (commands, issues)
+}
@@ -0,0 +1,270 @@
+//! Comparison of two mirrors of the same repository.
+//!
+//! The Release files are compared first. Package indices are only downloaded
+//! if their hashes differ, to list the package versions available on one mirror only.
+//! A repository can also be compared with the snapshot.debian.org state at a point in time.
+//! The lag of a mirror behind its upstream archive only compares the Release files.
+
+use std::collections::BTreeSet;
+
+use chrono::{DateTime, NaiveDate, NaiveDateTime};
+use libapt::{Architecture, Error, Link, LinkHash, Release, Result};
+use log::{debug, error, info, warn};
+use serde::{Deserialize, Serialize};
+
+use crate::index;
+use crate::options::CompareOptions;
+use crate::transport::Transport;
+use crate::util::format_duration;
+
+/// Archive of snapshot.debian.org used by default.
+pub const DEBIAN_SNAPSHOT: &str = "https://snapshot.debian.org/archive/debian";
+
+/// Get the URL of the snapshot of an archive at the given time.
+///
+/// The time is a snapshot timestamp like 20240115T120000Z, an RFC 3339 time or a date like 2024-01-15.
+/// The snapshot service redirects to the latest snapshot at or before the time.
+pub fn snapshot_url(archive: &str, time: &str) -> Result<String> {
+    let parsed = NaiveDateTime::parse_from_str(time, "%Y%m%dT%H%M%SZ")
+        .ok()
+        .or_else(|| DateTime::parse_from_rfc3339(time).ok().map(|time| time.naive_utc()))
+        .or_else(|| NaiveDate::parse_from_str(time, "%Y-%m-%d").ok().and_then(|date| date.and_hms_opt(0, 0, 0)));
+
+    match parsed {
+        Some(parsed) => Ok(format!("{}/{}", archive.trim_end_matches('/'), parsed.format("%Y%m%dT%H%M%SZ"))),
+        None => {
+            let message = format!("Invalid snapshot time {time}! Use e.g. 20240115T120000Z or 2024-01-15.");
+            error!("{}", message);
+            Err(Error::new(&message, libapt::ErrorType::ApiUsage))
+        }
+    }
+}
+
+/// Package version available on one mirror only.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Deserialize, Serialize)]
+pub struct MirrorPackage {
+    pub component: String,
+    pub architecture: String,
+    pub package: String,
+    pub version: String,
+}
+
+/// MirrorComparison groups the differences of two mirrors.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct MirrorComparison {
+    // Base URL of the first mirror.
+    pub url_a: String,
+    // Base URL of the second mirror.
+    pub url_b: String,
+    // Release Date of the second mirror minus the Release Date of the first mirror in seconds. None if a date is missing.
+    pub date_skew: Option<i64>,
+    // Files listed by both Release files with different size or hashes. Paths relative to the Release file.
+    pub diverging_files: Vec<String>,
+    // Files listed only by the Release file of the first mirror.
+    pub only_a_files: Vec<String>,
+    // Files listed only by the Release file of the second mirror.
+    pub only_b_files: Vec<String>,
+    // Package versions only in the indices of the first mirror.
+    pub only_a_packages: Vec<MirrorPackage>,
+    // Package versions only in the indices of the second mirror.
+    pub only_b_packages: Vec<MirrorPackage>,
+    // Indices which could not be compared. (Index, Error)
+    pub failures: Vec<(String, String)>,
+}
+
+impl MirrorComparison {
+    /// Test if no differences were found.
+    pub fn is_identical(&self) -> bool {
+        self.date_skew.unwrap_or_default() == 0
+            && self.diverging_files.is_empty()
+            && self.only_a_files.is_empty()
+            && self.only_b_files.is_empty()
+            && self.only_a_packages.is_empty()
+            && self.only_b_packages.is_empty()
+    }
+}
+
+/// Lag of a mirror behind its upstream archive.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct MirrorLag {
+    // Base URL of the upstream archive.
+    pub upstream: String,
+    // Release Date of the upstream minus the Release Date of the mirror in seconds. None if a date is missing.
+    pub lag: Option<i64>,
+    // Files of the upstream Release file which are missing on the mirror or differ. Paths relative to the Release file.
+    pub differing_indices: Vec<String>,
+}
+
+impl MirrorLag {
+    /// Compare the Release file of a mirror with the Release file of its upstream archive.
+    pub fn new(mirror: &Release, upstream: &Release) -> MirrorLag {
+        let links = relative_links(mirror);
+        let differing_indices = relative_links(upstream)
+            .into_iter()
+            .filter(|(path, link)| !links.iter().any(|(other, other_link)| other == path && same_file(link, other_link)))
+            .map(|(path, _)| path)
+            .collect();
+
+        MirrorLag {
+            upstream: upstream.distro.url.clone(),
+            lag: match (mirror.date, upstream.date) {
+                (Some(mirror), Some(upstream)) => Some(upstream.signed_duration_since(mirror).num_seconds()),
+                _ => None,
+            },
+            differing_indices,
+        }
+    }
+}
+
+/// Test if two links describe the same file, using the strongest hash both provide.
+fn same_file(a: &Link, b: &Link) -> bool {
+    if a.size != b.size {
+        return false;
+    }
+
+    for hash in [LinkHash::Sha512, LinkHash::Sha256, LinkHash::Sha1, LinkHash::Md5] {
+        if let (Some(a), Some(b)) = (a.hashes.get(&hash), b.hashes.get(&hash)) {
+            return a.eq_ignore_ascii_case(b);
+        }
+    }
+    true
+}
+
+/// Get the links of the Release file by their path relative to the Release file.
+fn relative_links(release: &Release) -> Vec<(String, &Link)> {
+    let base = release.distro.url("", false);
+    let mut links: Vec<(String, &Link)> = release
+        .links
+        .values()
+        .map(|link| (link.url.strip_prefix(&base).unwrap_or(&link.url).to_string(), link))
+        .collect();
+    links.sort_by(|(a, _), (b, _)| a.cmp(b));
+    links
+}
+
+/// Get the package versions of an index. (Package, Version)
+fn package_versions(content: &str) -> BTreeSet<(String, String)> {
+    index::stanzas(content)
+        .filter_map(|stanza| match (index::field(stanza, "Package"), index::field(stanza, "Version")) {
+            (Some(package), Some(version)) => Some((package.to_string(), version.to_string())),
+            _ => None,
+        })
+        .collect()
+}
+
+/// Download an index of a mirror and get its package versions.
+async fn index_versions(transport: &Transport, release: &Release, link: &Link) -> Result<BTreeSet<(String, String)>> {
+    let content = index::download_index(transport, link).await?;
+    debug!("Loaded index {} of {}.", link.url, release.distro.url);
+    Ok(package_versions(&content))
+}
+
+/// Compare the Release files and the differing package indices of two mirrors.
+pub async fn run(transport: &Transport, a: &Release, b: &Release, options: &CompareOptions) -> Result<MirrorComparison> {
+    let mut comparison = MirrorComparison {
+        url_a: a.distro.url.clone(),
+        url_b: b.distro.url.clone(),
+        date_skew: match (a.date, b.date) {
+            (Some(a), Some(b)) => Some(b.signed_duration_since(a).num_seconds()),
+            _ => None,
+        },
+        diverging_files: Vec::new(),
+        only_a_files: Vec::new(),
+        only_b_files: Vec::new(),
+        only_a_packages: Vec::new(),
+        only_b_packages: Vec::new(),
+        failures: Vec::new(),
+    };
+
+    match comparison.date_skew {
+        Some(0) => info!("Release dates are equal."),
+        Some(skew) if skew > 0 => info!("Release of {} is {} newer.", b.distro.url, format_duration(skew)),
+        Some(skew) => info!("Release of {} is {} newer.", a.distro.url, format_duration(-skew)),
+        None => warn!("Release date is missing, the skew is unknown."),
+    }
+
+    let links_a = relative_links(a);
+    let links_b = relative_links(b);
+    for (path, link) in &links_a {
+        match links_b.iter().find(|(other, _)| other == path) {
+            Some((_, other)) if !same_file(link, other) => comparison.diverging_files.push(path.clone()),
+            Some(_) => {}
+            None => comparison.only_a_files.push(path.clone()),
+        }
+    }
+    for (path, _) in &links_b {
+        if !links_a.iter().any(|(other, _)| other == path) {
+            comparison.only_b_files.push(path.clone());
+        }
+    }
+    info!(
+        "Found {} diverging files, {} files only on {} and {} files only on {}.",
+        comparison.diverging_files.len(),
+        comparison.only_a_files.len(),
+        a.distro.url,
+        comparison.only_b_files.len(),
+        b.distro.url
+    );
+
+    let components: Vec<&String> = a
+        .components
+        .iter()
+        .filter(|component| b.components.contains(component))
+        .filter(|component| options.components.is_empty() || options.components.contains(component))
+        .collect();
+    let mut architectures: Vec<Architecture> = a
+        .architectures
+        .iter()
+        .filter(|architecture| b.architectures.contains(architecture))
+        .cloned()
+        .collect();
+    architectures.push(Architecture::Source);
+    architectures.retain(|architecture| options.architectures.is_empty() || options.architectures.contains(&architecture.to_string()));
+
+    for component in components {
+        for architecture in &architectures {
+            let name = format!("{component} ({architecture})");
+            let (link_a, link_b) = match (
+                index::package_index_link(transport, a, component, architecture).await,
+                index::package_index_link(transport, b, component, architecture).await,
+            ) {
+                (Ok(link_a), Ok(link_b)) => (link_a, link_b),
+                (Err(e), _) | (_, Err(e)) => {
+                    debug!("Skipping index {name}: {e}");
+                    continue;
+                }
+            };
+            if same_file(&link_a, &link_b) {
+                continue;
+            }
+
+            info!("Comparing packages of index {name}...");
+            let (versions_a, versions_b) = match (index_versions(transport, a, &link_a).await, index_versions(transport, b, &link_b).await) {
+                (Ok(versions_a), Ok(versions_b)) => (versions_a, versions_b),
+                (Err(e), _) | (_, Err(e)) => {
+                    warn!("Comparing index {name} failed: {e}");
+                    comparison.failures.push((name, e.to_string()));
+                    continue;
+                }
+            };
+
+            let package = |(package, version): &(String, String)| MirrorPackage {
+                component: component.clone(),
+                architecture: architecture.to_string(),
+                package: package.clone(),
+                version: version.clone(),
+            };
+            comparison.only_a_packages.extend(versions_a.difference(&versions_b).map(package));
+            comparison.only_b_packages.extend(versions_b.difference(&versions_a).map(package));
+        }
+    }
+    info!(
+        "Found {} package versions only on {} and {} only on {}.",
+        comparison.only_a_packages.len(),
+        a.distro.url,
+        comparison.only_b_packages.len(),
+        b.distro.url
+    );
+
+    Ok(comparison)
+}
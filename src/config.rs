@@ -0,0 +1,313 @@
+//! Config file of aptcheckr.
+
+use std::collections::HashMap;
+use std::fs;
+
+use libapt::{Distro, Error, Key, Result};
+use log::{error, info};
+use serde::{Deserialize, Serialize};
+
+use crate::finding::{FindingKind, Severity};
+use crate::options::{ChatTarget, CheckOptions, EmailOptions, HostOptions, HttpOptions, Profile, Sample};
+use crate::util::parse_duration;
+
+/// Get the key at the given location. Keys ending with .gpg are binary keys, others are armored.
+fn key_at(location: &str) -> Key {
+    if location.ends_with(".gpg") {
+        Key::key(location)
+    } else {
+        Key::armored_key(location)
+    }
+}
+
+/// Report an invalid config value.
+fn invalid(message: &str) -> Error {
+    error!("{}", message);
+    Error::new(message, libapt::ErrorType::ApiUsage)
+}
+
+/// Config groups the settings read from a TOML config file.
+///
+/// Example:
+///
+/// ```toml
+/// keys = ["/etc/apt/keyrings/old.asc", "https://example.com/new.gpg"]
+/// max_connections = 16
+/// parallel = 4
+/// output = "fleet.json"
+/// fail_on = "warning"
+/// webhooks = ["https://hooks.example.com/aptcheckr"]
+/// webhook_secret = "secret"
+/// report_url = "https://ci.example.com/aptcheckr/fleet.json"
+///
+/// [severity]
+/// release-expiring = "error"
+///
+/// [email]
+/// server = "smtp.example.com"
+/// tls = "starttls"
+/// username = "mirror"
+/// password = "secret"
+/// from = "aptcheckr@example.com"
+/// to = ["mirror-admins@example.com"]
+///
+/// [[chats]]
+/// type = "slack"
+/// webhook = "https://hooks.slack.com/services/T000/B000/XXXX"
+///
+/// [hosts."cdn.example.com"]
+/// max_connections = 32
+/// timeout = 60
+/// retries = 5
+///
+/// [hosts."packages.example.com"]
+/// token = "secret"
+///
+/// [hosts."packagecloud.example.com"]
+/// token = "secret"
+/// token_parameter = "token"
+///
+/// [[repos]]
+/// name = "ubuntu"
+/// url = "http://archive.ubuntu.com/ubuntu"
+/// suite = "jammy"
+/// components = ["main"]
+/// architectures = ["amd64"]
+/// keys = ["/usr/share/keyrings/ubuntu-archive-keyring.gpg"]
+/// profile = "deep"
+/// sample = "5%"
+///
+/// [[repos.chats]]
+/// type = "matrix"
+/// homeserver = "https://matrix.example.com"
+/// room = "!ubuntu-mirror:example.com"
+/// token = "secret"
+/// ```
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+#[serde(deny_unknown_fields)]
+pub struct Config {
+    // Signing keys of the InRelease file. Keys ending with .gpg are binary keys, others are armored.
+    #[serde(default)]
+    pub keys: Vec<String>,
+    // Maximum parallel connections per host, unless set for the host.
+    pub max_connections: Option<usize>,
+    // Number of repositories checked at the same time. Defaults to 1.
+    pub parallel: Option<usize>,
+    // File the result is saved to.
+    pub output: Option<String>,
+    // Severities replacing the defaults of the finding kinds. (Kind, Severity)
+    #[serde(default)]
+    pub severity: HashMap<FindingKind, Severity>,
+    // Lowest severity of the findings which fail the check. Defaults to info.
+    pub fail_on: Option<Severity>,
+    // URLs the summary is posted to, if new findings are found or the status changes.
+    #[serde(default)]
+    pub webhooks: Vec<String>,
+    // Secret of the HMAC-SHA256 signature of the notifications.
+    pub webhook_secret: Option<String>,
+    // SMTP settings of the mail notifications.
+    pub email: Option<EmailOptions>,
+    // Slack channels and Matrix rooms notified about all repositories.
+    #[serde(default)]
+    pub chats: Vec<ChatTarget>,
+    // URL of the full report, linked in the chat messages.
+    pub report_url: Option<String>,
+    // Network settings per host. (Host, Settings)
+    #[serde(default)]
+    pub hosts: HashMap<String, HostOptions>,
+    // Repositories to check.
+    #[serde(default)]
+    pub repos: Vec<RepoConfig>,
+}
+
+/// RepoConfig describes a repository to check and its check settings.
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+#[serde(deny_unknown_fields)]
+pub struct RepoConfig {
+    // Name of the repository in the result. Defaults to URL and suite.
+    pub name: Option<String>,
+    // URL of the repository.
+    pub url: String,
+    // Suite of the repository.
+    pub suite: Option<String>,
+    // Path of a flat repository, used instead of the suite.
+    pub path: Option<String>,
+    // Components to check. All components if empty.
+    #[serde(default)]
+    pub components: Vec<String>,
+    // Architectures to check. All architectures if empty.
+    #[serde(default)]
+    pub architectures: Vec<String>,
+    // Signing keys, any key may verify the signature. Keys ending with .gpg are binary keys, others are armored.
+    #[serde(default)]
+    pub keys: Vec<String>,
+    // Fingerprints of the keys allowed to sign the InRelease file.
+    #[serde(default)]
+    pub expect_fingerprints: Vec<String>,
+    // Expect NotAutomatic: yes, e.g. for backports suites.
+    #[serde(default)]
+    pub expect_not_automatic: bool,
+    // Expect ButAutomaticUpgrades: yes.
+    #[serde(default)]
+    pub expect_but_automatic_upgrades: bool,
+    // Set of checks: quick, standard or deep.
+    pub profile: Option<String>,
+    // Probed random percentage of the files, e.g. 5%.
+    pub sample: Option<String>,
+    // Time budget of the check, e.g. 30m.
+    pub max_duration: Option<String>,
+    // Required remaining validity of the release, e.g. 3d.
+    pub min_validity: Option<String>,
+    // Maximum age of the release Date, e.g. 1d.
+    pub max_age: Option<String>,
+    // Base URL of the upstream archive the repository mirrors.
+    pub upstream: Option<String>,
+    // Maximum lag of the release Date behind the upstream archive, e.g. 6h.
+    pub max_lag: Option<String>,
+    // Local directory of the repository for the orphan check, e.g. a mount of the served directory.
+    pub local_root: Option<String>,
+    // Release of the security tracker data, e.g. bookworm. Defaults to the codename of the release.
+    pub cve_release: Option<String>,
+    // Slack channels and Matrix rooms notified only about this repository.
+    #[serde(default)]
+    pub chats: Vec<ChatTarget>,
+}
+
+impl RepoConfig {
+    /// Get the name of the repository in the result.
+    pub fn label(&self) -> String {
+        match (&self.name, &self.suite, &self.path) {
+            (Some(name), _, _) => name.clone(),
+            (None, Some(suite), _) => format!("{} {suite}", self.url),
+            (None, None, Some(path)) => format!("{} {path}", self.url),
+            (None, None, None) => self.url.clone(),
+        }
+    }
+
+    /// Get the distro of the repository, using the first key.
+    pub fn distro(&self) -> Result<Distro> {
+        if self.suite.is_none() && self.path.is_none() {
+            return Err(invalid(&format!("Repo {} has neither suite nor path!", self.label())));
+        }
+
+        Ok(Distro {
+            url: self.url.clone(),
+            name: self.suite.clone(),
+            path: self.path.clone(),
+            key: self.keys.first().map(|key| key_at(key)).unwrap_or(Key::NoSignatureCheck),
+        })
+    }
+
+    /// Get the check options of the repository, based on the global options.
+    pub fn options(&self, global: &CheckOptions) -> Result<CheckOptions> {
+        let mut options = global.clone();
+        let label = self.label();
+
+        options.components = self.components.clone();
+        options.architectures = self.architectures.clone();
+        options.keys = self.keys.iter().skip(1).map(|key| key_at(key)).collect();
+        options.expected_fingerprints = self.expect_fingerprints.clone();
+        options.expect_not_automatic |= self.expect_not_automatic || self.expect_but_automatic_upgrades;
+        options.expect_but_automatic_upgrades |= self.expect_but_automatic_upgrades;
+        options.chats.extend(self.chats.iter().cloned());
+        options.upstream = self.upstream.clone().or(options.upstream);
+        options.local_root = self.local_root.clone().or(options.local_root);
+        options.cve_release = self.cve_release.clone().or(options.cve_release);
+        // Each repository has its own checkpoint.
+        options.state_dir = global.state_dir.as_ref().map(|dir| format!("{dir}/{}", label.replace(['/', ':', ' '], "_")));
+
+        if let Some(profile) = &self.profile {
+            match Profile::parse(profile) {
+                Some(profile) => profile.apply(&mut options),
+                None => return Err(invalid(&format!("Repo {label} has invalid profile {profile}!"))),
+            }
+        }
+
+        if let Some(sample) = &self.sample {
+            match Sample::parse(sample) {
+                Some(sample) => options.sample = sample,
+                None => return Err(invalid(&format!("Repo {label} has invalid sample {sample}!"))),
+            }
+        }
+
+        let durations = [
+            (&self.max_duration, &mut options.max_duration),
+            (&self.min_validity, &mut options.min_validity),
+            (&self.max_age, &mut options.max_age),
+            (&self.max_lag, &mut options.max_lag),
+        ];
+        for (value, option) in durations {
+            if let Some(value) = value {
+                match parse_duration(value) {
+                    Some(duration) => *option = Some(duration.as_secs()),
+                    None => return Err(invalid(&format!("Repo {label} has invalid duration {value}!"))),
+                }
+            }
+        }
+
+        Ok(options)
+    }
+}
+
+impl Config {
+    /// Read the config from the given TOML file.
+    pub fn load(path: &str) -> Result<Config> {
+        let content = match fs::read_to_string(path) {
+            Ok(content) => content,
+            Err(e) => {
+                let message = format!("Reading config file {path} failed! {e}");
+                error!("{}", message);
+                return Err(Error::new(&message, libapt::ErrorType::ApiUsage));
+            }
+        };
+
+        match toml::from_str(&content) {
+            Ok(config) => {
+                info!("Using config file {path}.");
+                Ok(config)
+            }
+            Err(e) => {
+                let message = format!("Parsing config file {path} failed! {e}");
+                error!("{}", message);
+                Err(Error::new(&message, libapt::ErrorType::ApiUsage))
+            }
+        }
+    }
+
+    /// Get the signing keys of the config.
+    pub fn keys(&self) -> Vec<Key> {
+        self.keys.iter().map(|key| key_at(key)).collect()
+    }
+
+    /// Add the output, the severity overrides, the failure threshold and the notifications of the config to the check options.
+    pub fn apply_check(&self, options: &mut CheckOptions) {
+        if let Some(output) = &self.output {
+            options.output = output.clone();
+        }
+        if let Some(fail_on) = self.fail_on {
+            options.fail_on = fail_on;
+        }
+        options.webhooks.extend(self.webhooks.iter().cloned());
+        if options.webhook_secret.is_none() {
+            options.webhook_secret = self.webhook_secret.clone();
+        }
+        if self.email.is_some() {
+            options.email = self.email.clone();
+        }
+        options.chats.extend(self.chats.iter().cloned());
+        if options.report_url.is_none() {
+            options.report_url = self.report_url.clone();
+        }
+        options.severity_overrides.extend(self.severity.iter().map(|(kind, severity)| (*kind, *severity)));
+    }
+
+    /// Add the host settings of the config to the HTTP settings.
+    pub fn apply_http(&self, http: &mut HttpOptions) {
+        if let Some(max_connections) = self.max_connections {
+            http.max_connections_per_host = max_connections;
+        }
+        for (host, options) in &self.hosts {
+            http.hosts.insert(host.to_string(), options.clone());
+        }
+    }
+}
@@ -0,0 +1,45 @@
+//! Parsing of Contents-<arch> indices.
+
+use std::collections::HashSet;
+
+/// Get the names of all packages listed in a Contents index.
+///
+/// Each line maps a file path to a comma separated list of
+/// [component/]section/package locations. Suite level Contents files
+/// cover all components, and the component is only given for non-main
+/// packages. If a component is given, only packages of this component
+/// are returned, else all packages.
+pub fn packages(content: &str, component: Option<&str>) -> HashSet<String> {
+    let mut packages = HashSet::new();
+
+    for line in content.lines() {
+        let locations = match line.trim_end().rsplit_once(char::is_whitespace) {
+            Some((_, locations)) => locations,
+            None => continue,
+        };
+
+        // Header of old Contents files.
+        if locations == "LOCATION" {
+            continue;
+        }
+
+        for location in locations.split(',') {
+            let parts: Vec<&str> = location.split('/').collect();
+            let package = match parts.last() {
+                Some(package) if !package.is_empty() => package,
+                _ => continue,
+            };
+
+            if let Some(component) = component {
+                let location_component = if parts.len() > 2 { parts[0] } else { "main" };
+                if location_component != component {
+                    continue;
+                }
+            }
+
+            packages.insert(package.to_string());
+        }
+    }
+
+    packages
+}
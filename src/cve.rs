@@ -0,0 +1,193 @@
+//! CVE exposure of the source packages, using the data of the Debian or Ubuntu security tracker.
+//!
+//! The Debian tracker JSON maps the sources to their CVEs and the status per release.
+//! The Ubuntu CVE JSON of the security API lists the CVEs with the status of their packages per release.
+//! Both are reduced to the advisories of one release, which are compared with the source versions of the repository.
+
+use std::collections::HashMap;
+use std::fs;
+
+use libapt::{Error, Result, Version};
+use log::{error, info};
+use serde::Deserialize;
+
+use crate::transport::Transport;
+use crate::util::format_version;
+
+/// Security tracker data of Debian used by default.
+pub const DEBIAN_TRACKER: &str = "https://security-tracker.debian.org/tracker/data/json";
+
+/// Status of a CVE for a release in the Debian tracker.
+#[derive(Debug, Deserialize)]
+struct DebianStatus {
+    status: String,
+    fixed_version: Option<String>,
+    urgency: Option<String>,
+}
+
+/// CVE of a source in the Debian tracker.
+#[derive(Debug, Deserialize)]
+struct DebianCve {
+    #[serde(default)]
+    releases: HashMap<String, DebianStatus>,
+}
+
+/// Debian tracker data. (Source, (CVE, Details))
+type DebianTracker = HashMap<String, HashMap<String, DebianCve>>;
+
+/// Status of a package for a release in the Ubuntu CVE data.
+#[derive(Debug, Deserialize)]
+struct UbuntuStatus {
+    release_codename: String,
+    status: String,
+    // Fixed version for released CVEs.
+    #[serde(default)]
+    description: String,
+}
+
+/// Package affected by a CVE in the Ubuntu CVE data.
+#[derive(Debug, Deserialize)]
+struct UbuntuPackage {
+    name: String,
+    #[serde(default)]
+    statuses: Vec<UbuntuStatus>,
+}
+
+/// CVE of the Ubuntu CVE data.
+#[derive(Debug, Deserialize)]
+struct UbuntuCve {
+    id: String,
+    priority: Option<String>,
+    #[serde(default)]
+    packages: Vec<UbuntuPackage>,
+}
+
+/// Ubuntu CVE data, as served by https://ubuntu.com/security/cves.json.
+#[derive(Debug, Deserialize)]
+struct UbuntuTracker {
+    cves: Vec<UbuntuCve>,
+}
+
+/// CVE affecting a source package of a release.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Advisory {
+    pub cve: String,
+    pub source: String,
+    // Version fixing the CVE. None if the CVE is still open.
+    pub fixed_version: Option<String>,
+    // Urgency or priority assigned by the tracker, e.g. high.
+    pub urgency: Option<String>,
+    // Tracker page of the CVE.
+    pub url: String,
+}
+
+/// Source version of the repository affected by a CVE.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Exposure {
+    pub advisory: Advisory,
+    // Highest version of the source in the repository.
+    pub version: String,
+}
+
+/// Load the raw tracker data.
+///
+/// URLs are downloaded, else the data is read from a local file.
+pub async fn load(transport: &Transport, tracker: &str) -> Result<Vec<u8>> {
+    info!("Loading security tracker data {tracker}...");
+    if tracker.contains("://") {
+        transport.download(tracker).await
+    } else {
+        fs::read(tracker).map_err(|e| Error::from_io_error(e, tracker))
+    }
+}
+
+/// Get the advisories of the Debian tracker data for the release.
+///
+/// CVEs resolved with version 0 don't affect the release, and unimportant ones are not reported.
+fn debian_advisories(tracker: DebianTracker, release: &str) -> Vec<Advisory> {
+    let mut advisories = Vec::new();
+    for (source, cves) in tracker {
+        for (cve, details) in cves {
+            let status = match details.releases.get(release) {
+                Some(status) => status,
+                None => continue,
+            };
+            if status.urgency.as_deref() == Some("unimportant") {
+                continue;
+            }
+            let fixed_version = match (status.status.as_str(), &status.fixed_version) {
+                ("resolved", Some(version)) if version != "0" => Some(version.clone()),
+                ("open", _) => None,
+                _ => continue,
+            };
+            advisories.push(Advisory {
+                url: format!("https://security-tracker.debian.org/tracker/{cve}"),
+                cve,
+                source: source.clone(),
+                fixed_version,
+                urgency: status.urgency.clone(),
+            });
+        }
+    }
+    advisories
+}
+
+/// Get the advisories of the Ubuntu CVE data for the release.
+fn ubuntu_advisories(tracker: UbuntuTracker, release: &str) -> Vec<Advisory> {
+    let mut advisories = Vec::new();
+    for cve in tracker.cves {
+        for package in &cve.packages {
+            for status in package.statuses.iter().filter(|status| status.release_codename == release) {
+                let fixed_version = match status.status.as_str() {
+                    "released" if !status.description.is_empty() => Some(status.description.clone()),
+                    "needed" | "deferred" | "pending" => None,
+                    _ => continue,
+                };
+                advisories.push(Advisory {
+                    cve: cve.id.clone(),
+                    source: package.name.clone(),
+                    fixed_version,
+                    urgency: cve.priority.clone(),
+                    url: format!("https://ubuntu.com/security/{}", cve.id),
+                });
+            }
+        }
+    }
+    advisories
+}
+
+/// Parse the tracker data, either the Debian tracker JSON or the Ubuntu CVE JSON, and get the advisories of the release.
+pub fn advisories(content: &[u8], release: &str) -> Result<Vec<Advisory>> {
+    let advisories = match serde_json::from_slice::<DebianTracker>(content) {
+        Ok(tracker) => debian_advisories(tracker, release),
+        Err(debian_error) => match serde_json::from_slice::<UbuntuTracker>(content) {
+            Ok(tracker) => ubuntu_advisories(tracker, release),
+            Err(ubuntu_error) => {
+                let message = format!("Parsing security tracker data failed! Debian format: {debian_error}, Ubuntu format: {ubuntu_error}");
+                error!("{}", message);
+                return Err(Error::new(&message, libapt::ErrorType::ApiUsage));
+            }
+        },
+    };
+    info!("Found {} advisories for release {release}.", advisories.len());
+    Ok(advisories)
+}
+
+/// Get the sources of the repository affected by the advisories. (Source, Versions)
+///
+/// The highest version of a source is compared, as it is the one apt installs.
+pub fn exposures(advisories: &[Advisory], versions: &HashMap<String, Vec<Version>>) -> Vec<Exposure> {
+    let mut exposures: Vec<Exposure> = advisories
+        .iter()
+        .filter_map(|advisory| {
+            let version = versions.get(&advisory.source)?.iter().max()?;
+            let affected = match &advisory.fixed_version {
+                Some(fixed) => Version::from_str(fixed).is_ok_and(|fixed| *version < fixed),
+                None => true,
+            };
+            affected.then(|| Exposure { advisory: advisory.clone(), version: format_version(version) })
+        })
+        .collect();
+    exposures.sort_by(|a, b| a.advisory.source.cmp(&b.advisory.source).then_with(|| a.advisory.cve.cmp(&b.advisory.cve)));
+    exposures
+}
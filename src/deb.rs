@@ -0,0 +1,209 @@
+//! Parsing of deb archives, i.e. ar archives of a control and a data tarball.
+
+use crate::index;
+
+/// Magic bytes at the start of an ar archive.
+const AR_MAGIC: &[u8] = b"!<arch>\n";
+
+/// Size of an ar member header.
+const AR_HEADER_SIZE: usize = 60;
+
+/// Size of a tar block.
+const TAR_BLOCK_SIZE: usize = 512;
+
+/// Member of an ar archive.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ArMember<'a> {
+    // Name of the member, e.g. control.tar.xz.
+    pub name: String,
+    // Size of the member as given by its header.
+    pub size: usize,
+    // Available data of the member, shorter than size if the archive is truncated.
+    pub data: &'a [u8],
+}
+
+impl ArMember<'_> {
+    /// Test if all data of the member is available.
+    pub fn is_complete(&self) -> bool {
+        self.data.len() == self.size
+    }
+}
+
+/// Parse the members of an ar archive.
+///
+/// The archive may be truncated, e.g. if only the beginning of a deb file was downloaded.
+/// The last member is then returned with the available data.
+pub fn ar_members(data: &[u8]) -> Result<Vec<ArMember<'_>>, String> {
+    if !data.starts_with(AR_MAGIC) {
+        return Err("not an ar archive".to_string());
+    }
+
+    let mut members = Vec::new();
+    let mut position = AR_MAGIC.len();
+    while position + AR_HEADER_SIZE <= data.len() {
+        let header = &data[position..position + AR_HEADER_SIZE];
+        if &header[58..60] != b"`\n" {
+            return Err(format!("invalid ar header at offset {position}"));
+        }
+
+        let name = String::from_utf8_lossy(&header[0..16]).trim_end().trim_end_matches('/').to_string();
+        let size = String::from_utf8_lossy(&header[48..58])
+            .trim()
+            .parse::<usize>()
+            .map_err(|_| format!("invalid size of ar member {name}"))?;
+
+        let start = position + AR_HEADER_SIZE;
+        let end = (start + size).min(data.len());
+        members.push(ArMember { name, size, data: &data[start..end] });
+
+        // Members are aligned to even offsets.
+        position = start + size + size % 2;
+    }
+
+    Ok(members)
+}
+
+/// Parse a tar header block. (Path, Size)
+///
+/// Returns None for the zero blocks ending the archive.
+fn tar_header(header: &[u8]) -> Result<Option<(String, usize)>, String> {
+    if header.iter().all(|byte| *byte == 0) {
+        return Ok(None);
+    }
+
+    let field = |range: std::ops::Range<usize>| {
+        let value = &header[range];
+        let end = value.iter().position(|byte| *byte == 0).unwrap_or(value.len());
+        String::from_utf8_lossy(&value[..end]).to_string()
+    };
+
+    let mut path = field(0..100);
+    let prefix = field(345..500);
+    if &header[257..262] == b"ustar" && !prefix.is_empty() {
+        path = format!("{prefix}/{path}");
+    }
+
+    let size = usize::from_str_radix(field(124..136).trim(), 8).map_err(|_| format!("invalid size of tar entry {path}"))?;
+    Ok(Some((path, size)))
+}
+
+/// Get a file of a tar archive. Leading "./" of the names is ignored.
+pub fn tar_file<'a>(data: &'a [u8], name: &str) -> Result<Option<&'a [u8]>, String> {
+    let mut position = 0;
+    while position + TAR_BLOCK_SIZE <= data.len() {
+        let (path, size) = match tar_header(&data[position..position + TAR_BLOCK_SIZE])? {
+            Some(entry) => entry,
+            None => break,
+        };
+
+        let start = position + TAR_BLOCK_SIZE;
+        if path.trim_start_matches("./") == name {
+            return match data.get(start..start + size) {
+                Some(content) => Ok(Some(content)),
+                None => Err(format!("tar entry {path} is truncated")),
+            };
+        }
+
+        position = start + size.div_ceil(TAR_BLOCK_SIZE) * TAR_BLOCK_SIZE;
+    }
+
+    Ok(None)
+}
+
+/// Get the names of all entries of a tar archive, without leading "./".
+///
+/// Fails if an entry is truncated, i.e. the archive is incomplete.
+pub fn tar_names(data: &[u8]) -> Result<Vec<String>, String> {
+    let mut names = Vec::new();
+    let mut position = 0;
+    while position + TAR_BLOCK_SIZE <= data.len() {
+        let (path, size) = match tar_header(&data[position..position + TAR_BLOCK_SIZE])? {
+            Some(entry) => entry,
+            None => return Ok(names),
+        };
+
+        let start = position + TAR_BLOCK_SIZE;
+        if start + size > data.len() {
+            return Err(format!("tar entry {path} is truncated"));
+        }
+        names.push(path.trim_start_matches("./").to_string());
+
+        position = start + size.div_ceil(TAR_BLOCK_SIZE) * TAR_BLOCK_SIZE;
+    }
+
+    if position < data.len() {
+        return Err("tar archive ends with a partial block".to_string());
+    }
+    Ok(names)
+}
+
+/// Extract the control file of a deb archive.
+///
+/// Only the members up to the control tarball need to be available.
+pub fn control_file(data: &[u8]) -> Result<String, String> {
+    let members = ar_members(data)?;
+    let member = members
+        .iter()
+        .find(|member| member.name.starts_with("control.tar"))
+        .ok_or_else(|| "control tarball is missing".to_string())?;
+
+    if !member.is_complete() {
+        return Err(format!("{} is truncated", member.name));
+    }
+    if member.name.ends_with(".zst") {
+        return Err(format!("compression of {} is not supported", member.name));
+    }
+
+    let tarball = index::decompress(member.data.to_vec(), &member.name).map_err(|e| format!("{} is corrupt: {e}", member.name))?;
+    match tar_file(&tarball, "control")? {
+        Some(control) => Ok(String::from_utf8_lossy(control).to_string()),
+        None => Err(format!("{} has no control file", member.name)),
+    }
+}
+
+/// Describe the structural issues of a complete deb archive.
+///
+/// The archive must contain the members debian-binary, control.tar.* and data.tar.* in this order,
+/// and both tarballs must decompress. Members starting with an underscore are allowed in between.
+pub fn structure_issues(data: &[u8]) -> Vec<String> {
+    let members = match ar_members(data) {
+        Ok(members) => members,
+        Err(e) => return vec![e],
+    };
+
+    let mut issues = Vec::new();
+
+    let length = members.iter().fold(AR_MAGIC.len(), |length, member| length + AR_HEADER_SIZE + member.size + member.size % 2);
+    if let Some(member) = members.iter().find(|member| !member.is_complete()) {
+        issues.push(format!("member {} is truncated", member.name));
+    } else if data.len() > length {
+        issues.push(format!("{} bytes of trailing data", data.len() - length));
+    }
+
+    let names: Vec<&str> = members.iter().map(|member| member.name.as_str()).filter(|name| !name.starts_with('_')).collect();
+    let layout = matches!(names[..], ["debian-binary", control, data] if control.starts_with("control.tar") && data.starts_with("data.tar"));
+    if !layout {
+        issues.push(format!("members are {} instead of debian-binary, control.tar.*, data.tar.*", names.join(", ")));
+    }
+
+    if let Some(member) = members.iter().find(|member| member.name == "debian-binary") {
+        if !member.data.starts_with(b"2.") {
+            issues.push(format!("debian-binary has unsupported format {}", String::from_utf8_lossy(member.data).trim()));
+        }
+    }
+
+    for member in members.iter().filter(|member| member.name.starts_with("control.tar") || member.name.starts_with("data.tar")) {
+        if !member.is_complete() {
+            continue;
+        }
+        if member.name.ends_with(".zst") {
+            // Not supported, the tarball is not validated.
+            continue;
+        }
+        if let Err(e) = index::decompress(member.data.to_vec(), &member.name) {
+            issues.push(format!("{} is corrupt: {e}", member.name));
+        }
+    }
+
+    issues
+}
@@ -0,0 +1,142 @@
+//! Parsing of DEP-11 AppStream metadata, i.e. the Components YAML and the icon tarballs.
+//!
+//! The Components-<arch>.yml files are YAML streams with one document per software component.
+//! Icons cached by the archive are listed with their size, and shipped in the icon tarball
+//! of this size, e.g. icons-64x64.tar.gz or icons-128x128@2.tar.gz for HiDPI icons.
+//! Only the few keys needed are read, so no full YAML parser is required.
+
+/// Icon of a component, which must be contained in an icon tarball.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CachedIcon {
+    // Package shipping the component.
+    pub package: String,
+    // ID of the component, e.g. org.gnome.Calculator.desktop.
+    pub id: String,
+    // File name of the icon in the tarball.
+    pub name: String,
+    // Name of the icon tarball, e.g. icons-64x64.tar.gz.
+    pub tarball: String,
+}
+
+/// Get the name of the icon tarball for the icon size.
+fn tarball_name(width: &str, height: &str, scale: &str) -> String {
+    if scale.is_empty() || scale == "1" {
+        format!("icons-{width}x{height}.tar.gz")
+    } else {
+        format!("icons-{width}x{height}@{scale}.tar.gz")
+    }
+}
+
+/// Split a YAML line into its indentation and the trimmed content.
+fn indentation(line: &str) -> (usize, &str) {
+    let content = line.trim_start();
+    (line.len() - content.len(), content.trim_end())
+}
+
+/// Get the cached icons of a component document.
+///
+/// The cached key is either a list of icons with name, width, height and scale,
+/// or a plain file name of a 64x64 icon in DEP-11 versions before 0.8.
+fn document_icons(document: &[&str]) -> Vec<CachedIcon> {
+    let value = |key: &str| {
+        document
+            .iter()
+            .find_map(|line| line.strip_prefix(key)?.strip_prefix(':'))
+            .map(|value| value.trim().trim_matches('\'').trim_matches('"').to_string())
+            .unwrap_or_default()
+    };
+    let package = value("Package");
+    let id = value("ID");
+
+    let mut icons = Vec::new();
+    let icon_start = match document.iter().position(|line| line.trim_end() == "Icon:") {
+        Some(start) => start + 1,
+        None => return icons,
+    };
+
+    // Attributes of the current list entry. (Name, Width, Height, Scale)
+    let mut entry: Option<[String; 4]> = None;
+    let mut cached_indentation: Option<usize> = None;
+    let push = |entry: &mut Option<[String; 4]>, icons: &mut Vec<CachedIcon>| {
+        if let Some([name, width, height, scale]) = entry.take() {
+            if !name.is_empty() {
+                icons.push(CachedIcon { package: package.clone(), id: id.clone(), name, tarball: tarball_name(&width, &height, &scale) });
+            }
+        }
+    };
+
+    for line in &document[icon_start..] {
+        let (indent, content) = indentation(line);
+        if content.is_empty() || content.starts_with('#') {
+            continue;
+        }
+        // The Icon mapping ends with the next top level key.
+        if indent == 0 {
+            break;
+        }
+
+        let cached = match cached_indentation {
+            Some(cached) => cached,
+            None => {
+                if let Some(name) = content.strip_prefix("cached:") {
+                    let name = name.trim();
+                    if name.is_empty() {
+                        cached_indentation = Some(indent);
+                    } else {
+                        // DEP-11 before 0.8.
+                        icons.push(CachedIcon { package: package.clone(), id: id.clone(), name: name.to_string(), tarball: tarball_name("64", "64", "") });
+                    }
+                }
+                continue;
+            }
+        };
+
+        // Another key of the Icon mapping ends the list. List entries may have the indentation of the key.
+        let item = content.strip_prefix("- ");
+        if indent < cached || (indent == cached && item.is_none()) {
+            push(&mut entry, &mut icons);
+            cached_indentation = None;
+            continue;
+        }
+
+        let attribute = match item {
+            Some(attribute) => {
+                push(&mut entry, &mut icons);
+                entry = Some(Default::default());
+                attribute
+            }
+            None => content,
+        };
+        if let (Some(values), Some((key, value))) = (entry.as_mut(), attribute.split_once(':')) {
+            let index = match key.trim() {
+                "name" => 0,
+                "width" => 1,
+                "height" => 2,
+                "scale" => 3,
+                _ => continue,
+            };
+            values[index] = value.trim().trim_matches('\'').trim_matches('"').to_string();
+        }
+    }
+    push(&mut entry, &mut icons);
+
+    icons
+}
+
+/// Get the cached icons of all components of a Components YAML file.
+///
+/// The header document, which gives the origin and the media base URL, has no icons.
+pub fn cached_icons(content: &str) -> Vec<CachedIcon> {
+    let mut icons = Vec::new();
+    let mut document: Vec<&str> = Vec::new();
+    for line in content.lines() {
+        if line.trim_end() == "---" {
+            icons.extend(document_icons(&document));
+            document.clear();
+        } else {
+            document.push(line);
+        }
+    }
+    icons.extend(document_icons(&document));
+    icons
+}
@@ -0,0 +1,159 @@
+//! Comparison of two reports, separating new, fixed and persisting findings.
+//!
+//! Reports of single checks, config or suite checks and sources.list checks can be compared.
+//! Findings are compared like the findings of the watch mode, ignoring the message.
+
+use std::fs;
+
+use libapt::{Distro, Error, Result};
+use log::{error, warn};
+use serde::{Deserialize, Serialize};
+
+use crate::finding::Finding;
+use crate::CheckStatus;
+
+/// Repository of a config or suite check report.
+#[derive(Deserialize)]
+struct BatchRepo {
+    name: String,
+    status: Option<CheckStatus>,
+    findings: Vec<Finding>,
+}
+
+/// Entry of a sources.list check report.
+#[derive(Deserialize)]
+struct SourceEntry {
+    entry: String,
+    status: Option<CheckStatus>,
+    findings: Vec<Finding>,
+}
+
+/// Release of a single check report, only the distro is needed.
+#[derive(Deserialize)]
+struct SingleRelease {
+    distro: Distro,
+}
+
+/// Any report saved by aptcheckr.
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum Report {
+    Batch { repos: Vec<BatchRepo> },
+    SourcesList(Vec<SourceEntry>),
+    Single { release: SingleRelease, findings: Vec<Finding> },
+}
+
+/// Findings of a repository of a report. (Name, Findings)
+pub type ReportRepo = (String, Vec<Finding>);
+
+/// Read and parse a report.
+fn read(path: &str) -> Result<Report> {
+    let content = match fs::read_to_string(path) {
+        Ok(content) => content,
+        Err(e) => {
+            let message = format!("Reading report {path} failed! {e}");
+            error!("{}", message);
+            return Err(Error::new(&message, libapt::ErrorType::ApiUsage));
+        }
+    };
+
+    serde_json::from_str(&content).map_err(|e| {
+        let message = format!("Parsing report {path} failed! {e}");
+        error!("{}", message);
+        Error::new(&message, libapt::ErrorType::ApiUsage)
+    })
+}
+
+/// Read the findings per repository of a report.
+///
+/// Single checks are named by the InRelease URL, config and suite checks by the repository name,
+/// and sources.list checks by the entry.
+pub fn load(path: &str) -> Result<Vec<ReportRepo>> {
+    Ok(match read(path)? {
+        Report::Batch { repos } => repos.into_iter().map(|repo| (repo.name, repo.findings)).collect(),
+        Report::SourcesList(entries) => entries.into_iter().map(|entry| (entry.entry, entry.findings)).collect(),
+        Report::Single { release, findings } => vec![(release.distro.in_release_url().unwrap_or_default(), findings)],
+    })
+}
+
+/// Read the status per repository of a report.
+///
+/// Single check reports don't contain the status, so they give no status.
+pub fn statuses(path: &str) -> Result<Vec<(String, CheckStatus)>> {
+    Ok(match read(path)? {
+        Report::Batch { repos } => repos.into_iter().filter_map(|repo| Some((repo.name, repo.status?))).collect(),
+        Report::SourcesList(entries) => entries.into_iter().filter_map(|entry| Some((entry.entry, entry.status?))).collect(),
+        Report::Single { .. } => Vec::new(),
+    })
+}
+
+/// Get the findings of the repository with the given name.
+///
+/// With single, a report with one repository matches regardless of the name, e.g. after moving to another mirror.
+pub fn findings_of<'a>(repos: &'a [ReportRepo], name: &str, single: bool) -> Option<&'a [Finding]> {
+    match repos {
+        [(_, findings)] if single => Some(findings),
+        _ => repos.iter().find(|(repo, _)| repo == name).map(|(_, findings)| findings.as_slice()),
+    }
+}
+
+/// Finding of a repository in the diff.
+#[derive(Debug, Clone, Serialize)]
+pub struct DiffFinding {
+    // Name of the repository.
+    pub repo: String,
+    pub finding: Finding,
+}
+
+/// Differences of the findings of two reports.
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct ReportDiff {
+    // Findings of the new report which are not in the old report.
+    pub new: Vec<DiffFinding>,
+    // Findings of the old report which are not in the new report.
+    pub fixed: Vec<DiffFinding>,
+    // Findings of both reports, as found by the new report.
+    pub persisting: Vec<DiffFinding>,
+}
+
+impl ReportDiff {
+    /// Compare the findings of the repositories of two reports.
+    ///
+    /// Repositories only in the new report have new findings only, and vice versa.
+    /// Reports with one repository each are compared regardless of the name.
+    pub fn new(old: &[ReportRepo], new: &[ReportRepo]) -> ReportDiff {
+        let mut diff = ReportDiff::default();
+        let single = old.len() == 1 && new.len() == 1;
+
+        for (name, findings) in new {
+            let previous = findings_of(old, name, single).unwrap_or_default();
+            for finding in findings {
+                let entry = DiffFinding { repo: name.clone(), finding: finding.clone() };
+                if previous.iter().any(|other| other.is_same(finding)) {
+                    diff.persisting.push(entry);
+                } else {
+                    diff.new.push(entry);
+                }
+            }
+        }
+
+        for (name, findings) in old {
+            let current = findings_of(new, name, single);
+            if current.is_none() {
+                warn!("Repo {name} is missing in the new report, all its findings are fixed.");
+            }
+            for finding in findings {
+                if !current.unwrap_or_default().iter().any(|other| other.is_same(finding)) {
+                    diff.fixed.push(DiffFinding { repo: name.clone(), finding: finding.clone() });
+                }
+            }
+        }
+
+        diff
+    }
+
+    /// Number of new findings which are not known from the baseline, i.e. regressions.
+    pub fn regressions(&self) -> usize {
+        self.new.iter().filter(|entry| !entry.finding.known).count()
+    }
+}
@@ -0,0 +1,154 @@
+//! Parsing of .dsc files and checks of the files of source packages.
+
+use std::collections::BTreeMap;
+
+use libapt::LinkHash;
+
+/// Checksum sections of a .dsc file, strongest first.
+const CHECKSUM_SECTIONS: [(&str, LinkHash); 3] = [
+    ("Checksums-Sha256", LinkHash::Sha256),
+    ("Checksums-Sha1", LinkHash::Sha1),
+    ("Files", LinkHash::Md5),
+];
+
+/// Files listed in a checksum section. (File, (Hash, Size))
+pub type Checksums = BTreeMap<String, (String, u64)>;
+
+/// Get the strongest checksum list of a .dsc file.
+///
+/// Returns the hash algorithm and the listed files.
+pub fn checksums(content: &str) -> Option<(LinkHash, Checksums)> {
+    let mut sections: BTreeMap<&str, Checksums> = BTreeMap::new();
+    let mut section: Option<&str> = None;
+
+    for line in content.lines() {
+        if line.starts_with(' ') || line.starts_with('\t') {
+            if let Some(section) = section {
+                let parts: Vec<&str> = line.split_whitespace().collect();
+                if let [hash, size, file] = parts[..] {
+                    let size = size.parse::<u64>().unwrap_or_default();
+                    sections
+                        .entry(section)
+                        .or_default()
+                        .insert(file.to_string(), (hash.to_lowercase(), size));
+                }
+            }
+            continue;
+        }
+
+        section = line
+            .split_once(':')
+            .and_then(|(key, _)| CHECKSUM_SECTIONS.iter().find(|(name, _)| *name == key.trim()))
+            .map(|(name, _)| *name);
+    }
+
+    CHECKSUM_SECTIONS
+        .into_iter()
+        .find_map(|(name, hash)| sections.remove(name).map(|files| (hash, files)))
+}
+
+/// Describe the issues of the file set of a source package with the given format.
+///
+/// Non-native sources need an upstream tarball and a Debian tarball or diff,
+/// while native sources consist of a single tarball. Signatures and additional
+/// upstream tarballs are ignored. Formats other than 1.0, 3.0 (quilt) and
+/// 3.0 (native) aren't checked.
+pub fn format_issues(format: &str, files: &[&str]) -> Vec<String> {
+    let count = |pattern: &str| {
+        files
+            .iter()
+            .filter(|file| !file.ends_with(".asc") && !file.ends_with(".dsc"))
+            .filter(|file| file.contains(pattern))
+            .count()
+    };
+
+    let orig = count(".orig.tar.");
+    let debian = count(".debian.tar.");
+    let diff = count(".diff.gz");
+    let tarballs = count(".tar.") - orig - debian - count(".orig-");
+
+    let mut issues = Vec::new();
+    let mut expect = |name: &str, found: usize, expected: usize| {
+        if found != expected {
+            issues.push(format!("{found} {name} instead of {expected}"));
+        }
+    };
+
+    match format.trim() {
+        "3.0 (quilt)" => {
+            expect("orig tarballs", orig, 1);
+            expect("debian tarballs", debian, 1);
+            expect("diffs", diff, 0);
+            expect("native tarballs", tarballs, 0);
+        }
+        "3.0 (native)" => {
+            expect("native tarballs", tarballs, 1);
+            expect("orig tarballs", orig, 0);
+            expect("debian tarballs", debian, 0);
+            expect("diffs", diff, 0);
+        }
+        "1.0" if orig > 0 || diff > 0 => {
+            expect("orig tarballs", orig, 1);
+            expect("diffs", diff, 1);
+            expect("debian tarballs", debian, 0);
+            expect("native tarballs", tarballs, 0);
+        }
+        "1.0" => {
+            expect("native tarballs", tarballs, 1);
+            expect("debian tarballs", debian, 0);
+        }
+        _ => {}
+    }
+
+    issues
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn strongest_checksums() {
+        let content = "Format: 3.0 (quilt)\nSource: foo\nFiles:\n 0123 10 foo_1.0.orig.tar.gz\nChecksums-Sha256:\n ABCD 10 foo_1.0.orig.tar.gz\n EF01 5 foo_1.0-1.debian.tar.xz\nChecksums-Sha1:\n 4567 10 foo_1.0.orig.tar.gz\n";
+        let (hash, files) = checksums(content).unwrap();
+        assert_eq!(hash, LinkHash::Sha256);
+        assert_eq!(files.len(), 2);
+        assert_eq!(files["foo_1.0.orig.tar.gz"], ("abcd".to_string(), 10));
+        assert_eq!(files["foo_1.0-1.debian.tar.xz"], ("ef01".to_string(), 5));
+    }
+
+    #[test]
+    fn md5_checksums() {
+        let (hash, files) = checksums("Source: foo\nFiles:\n 0123 10 foo_1.0.tar.gz\n").unwrap();
+        assert_eq!(hash, LinkHash::Md5);
+        assert_eq!(files.keys().collect::<Vec<_>>(), ["foo_1.0.tar.gz"]);
+        assert!(checksums("Source: foo\n").is_none());
+    }
+
+    #[test]
+    fn quilt_format() {
+        let files = ["foo_1.0-1.dsc", "foo_1.0.orig.tar.gz", "foo_1.0.orig.tar.gz.asc", "foo_1.0.orig-doc.tar.gz", "foo_1.0-1.debian.tar.xz"];
+        assert!(format_issues("3.0 (quilt)", &files).is_empty());
+        assert_eq!(
+            format_issues("3.0 (quilt)", &["foo_1.0-1.dsc", "foo_1.0-1.debian.tar.xz"]),
+            ["0 orig tarballs instead of 1"]
+        );
+    }
+
+    #[test]
+    fn native_format() {
+        assert!(format_issues("3.0 (native)", &["foo_1.0.dsc", "foo_1.0.tar.xz"]).is_empty());
+        assert_eq!(
+            format_issues("3.0 (native)", &["foo_1.0.dsc", "foo_1.0.orig.tar.gz", "foo_1.0-1.debian.tar.xz"]),
+            ["0 native tarballs instead of 1", "1 orig tarballs instead of 0", "1 debian tarballs instead of 0"]
+        );
+    }
+
+    #[test]
+    fn v1_format() {
+        assert!(format_issues("1.0", &["foo_1.0.dsc", "foo_1.0.tar.gz"]).is_empty());
+        assert!(format_issues("1.0", &["foo_1.0-1.dsc", "foo_1.0.orig.tar.gz", "foo_1.0-1.diff.gz"]).is_empty());
+        assert_eq!(format_issues("1.0", &["foo_1.0-1.dsc", "foo_1.0.orig.tar.gz"]), ["0 diffs instead of 1"]);
+        assert!(format_issues("2.0", &[]).is_empty());
+    }
+}
@@ -0,0 +1,259 @@
+//! Validation of the metadata fields of index stanzas.
+
+use crate::index;
+
+/// Sections of the archive, as listed by the Debian policy.
+const SECTIONS: [&str; 59] = [
+    "admin", "cli-mono", "comm", "database", "debian-installer", "debug", "devel", "doc", "editors",
+    "education", "electronics", "embedded", "fonts", "games", "gnome", "gnu-r", "gnustep", "graphics",
+    "hamradio", "haskell", "httpd", "interpreters", "introspection", "java", "javascript", "kde",
+    "kernel", "libdevel", "libs", "lisp", "localization", "mail", "math", "metapackages", "misc", "net",
+    "news", "ocaml", "oldlibs", "otherosfs", "perl", "php", "python", "ruby", "rust", "science",
+    "shells", "sound", "tasks", "tex", "text", "translations", "utils", "vcs", "video", "web", "x11",
+    "xfce", "zope",
+];
+
+/// Values of the Priority field. The priority extra is deprecated, but still valid.
+const PRIORITIES: [&str; 5] = ["required", "important", "standard", "optional", "extra"];
+
+/// Describe the issue of a Section value, if any.
+///
+/// The section may be prefixed by the archive area, e.g. contrib/net.
+pub fn section_issue(section: &str) -> Option<String> {
+    let name = match section.rsplit_once('/') {
+        Some((area, name)) if !area.is_empty() => name,
+        Some(_) => return Some(format!("section {section} has an empty area")),
+        None => section,
+    };
+
+    if SECTIONS.contains(&name) {
+        None
+    } else {
+        Some(format!("section {section} is unknown"))
+    }
+}
+
+/// Describe the issue of a Priority value, if any.
+pub fn priority_issue(priority: &str) -> Option<String> {
+    if PRIORITIES.contains(&priority) {
+        None
+    } else {
+        Some(format!("priority {priority} is invalid"))
+    }
+}
+
+/// Bytes of a deb file which are not part of the installed files, e.g. the control archive.
+const DEB_OVERHEAD: u64 = 4096;
+
+/// Describe the issues of the Size and Installed-Size fields of a binary stanza.
+///
+/// The Size is given in bytes and the Installed-Size in KiB. A missing
+/// Installed-Size is not an issue, since it is only recommended.
+pub fn size_issues(size: Option<&str>, installed_size: Option<&str>) -> Vec<String> {
+    let mut issues = Vec::new();
+
+    let size = match size.map(|size| size.parse::<u64>()) {
+        Some(Ok(0)) => {
+            issues.push("Size is 0".to_string());
+            None
+        }
+        Some(Ok(size)) => Some(size),
+        Some(Err(_)) => {
+            issues.push("Size is not a number".to_string());
+            None
+        }
+        None => {
+            issues.push("Size is missing".to_string());
+            None
+        }
+    };
+
+    let installed_size = match installed_size.map(|size| size.parse::<u64>()) {
+        Some(Ok(0)) => {
+            issues.push("Installed-Size is 0".to_string());
+            None
+        }
+        Some(Ok(installed_size)) => Some(installed_size),
+        Some(Err(_)) => {
+            issues.push("Installed-Size is not a number".to_string());
+            None
+        }
+        None => None,
+    };
+
+    if let (Some(size), Some(installed_size)) = (size, installed_size) {
+        if installed_size * 1024 + DEB_OVERHEAD < size {
+            issues.push(format!("Installed-Size of {installed_size} KiB is smaller than the Size of {size} bytes"));
+        }
+    }
+
+    issues
+}
+
+/// Names used as placeholders instead of a real maintainer.
+const PLACEHOLDER_NAMES: [&str; 7] = ["unknown", "nobody", "root", "maintainer", "todo", "your name", "firstname lastname"];
+
+/// Mail domains used as placeholders instead of a real address.
+const PLACEHOLDER_DOMAINS: [&str; 5] = ["localhost", "localdomain", "example.com", "example.org", "example.net"];
+
+/// Describe the issue of a maintainer, e.g. of the Maintainer field, if any.
+///
+/// A maintainer must be given as "Name <address>", as specified by RFC 5322.
+pub fn maintainer_issue(maintainer: &str) -> Option<String> {
+    let maintainer = maintainer.trim();
+    if maintainer.is_empty() {
+        return Some("maintainer is empty".to_string());
+    }
+
+    let (name, address) = match maintainer.strip_suffix('>').and_then(|rest| rest.rsplit_once('<')) {
+        Some((name, address)) => (name.trim().trim_matches('"').trim(), address.trim()),
+        None => return Some(format!("maintainer {maintainer} is not of the form Name <address>")),
+    };
+
+    let domain = match address.split_once('@') {
+        Some((local, domain)) if !local.is_empty() && !domain.is_empty() && !address.contains(char::is_whitespace) => domain,
+        _ => return Some(format!("maintainer {maintainer} has an invalid address")),
+    };
+
+    if name.is_empty() {
+        return Some(format!("maintainer {maintainer} has no name"));
+    }
+
+    let domain = domain.to_lowercase();
+    let placeholder_domain = PLACEHOLDER_DOMAINS
+        .iter()
+        .any(|placeholder| domain == *placeholder || domain.ends_with(&format!(".{placeholder}")));
+    if placeholder_domain || PLACEHOLDER_NAMES.contains(&name.to_lowercase().as_str()) {
+        return Some(format!("maintainer {maintainer} is a placeholder"));
+    }
+
+    None
+}
+
+/// Split an Uploaders field into the single maintainers.
+pub fn uploaders(value: &str) -> Vec<&str> {
+    value
+        .split_inclusive('>')
+        .map(|uploader| uploader.trim().trim_start_matches(',').trim())
+        .filter(|uploader| !uploader.is_empty())
+        .collect()
+}
+
+/// Parse a Phased-Update-Percentage value, an integer from 0 to 100, or describe its issue.
+pub fn phasing_percentage(value: &str) -> Result<u8, String> {
+    match value.parse::<u8>() {
+        Ok(percentage) if percentage <= 100 => Ok(percentage),
+        _ => Err(format!("Phased-Update-Percentage {value} is not an integer from 0 to 100")),
+    }
+}
+
+/// Describe the issue of the hash fields of a binary or source stanza, if it has no SHA256 or SHA512 hashes.
+///
+/// apt refuses files which are only verified by MD5 or SHA1.
+pub fn hash_issue(stanza: &str, is_source: bool) -> Option<String> {
+    let (strong, weak): (&[&str], &[&str]) = if is_source {
+        (&["Checksums-Sha256", "Checksums-Sha512"], &["Files", "Checksums-Sha1"])
+    } else {
+        (&["SHA256", "SHA512"], &["MD5sum", "SHA1"])
+    };
+
+    if strong.iter().any(|field| index::field(stanza, field).is_some()) {
+        return None;
+    }
+    let present: Vec<&str> = weak.iter().filter(|field| index::field(stanza, field).is_some()).copied().collect();
+    if present.is_empty() {
+        Some("no hashes".to_string())
+    } else {
+        Some(format!("only {} hashes", present.join(" and ")))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sections() {
+        assert_eq!(section_issue("net"), None);
+        assert_eq!(section_issue("contrib/net"), None);
+        assert_eq!(section_issue("non-free-firmware/kernel"), None);
+        assert_eq!(section_issue("/net").as_deref(), Some("section /net has an empty area"));
+        assert_eq!(section_issue("networking").as_deref(), Some("section networking is unknown"));
+        assert_eq!(section_issue("contrib/networking").as_deref(), Some("section contrib/networking is unknown"));
+    }
+
+    #[test]
+    fn priorities() {
+        assert_eq!(priority_issue("optional"), None);
+        assert_eq!(priority_issue("extra"), None);
+        assert_eq!(priority_issue("Optional").as_deref(), Some("priority Optional is invalid"));
+    }
+
+    #[test]
+    fn sizes() {
+        assert!(size_issues(Some("12345"), Some("20")).is_empty());
+        assert!(size_issues(Some("12345"), None).is_empty());
+        assert_eq!(size_issues(None, Some("20")), ["Size is missing"]);
+        assert_eq!(size_issues(Some("0"), Some("0")), ["Size is 0", "Installed-Size is 0"]);
+        assert_eq!(size_issues(Some("big"), Some("-1")), ["Size is not a number", "Installed-Size is not a number"]);
+        assert_eq!(
+            size_issues(Some("1000000"), Some("100")),
+            ["Installed-Size of 100 KiB is smaller than the Size of 1000000 bytes"]
+        );
+        // The control archive may exceed tiny installed sizes.
+        assert!(size_issues(Some("5120"), Some("1")).is_empty());
+    }
+
+    #[test]
+    fn maintainers() {
+        assert_eq!(maintainer_issue("Jane Doe <jane@debian.org>"), None);
+        assert_eq!(maintainer_issue("\"Doe, Jane\" <jane@debian.org>"), None);
+        assert_eq!(maintainer_issue(" ").as_deref(), Some("maintainer is empty"));
+        assert_eq!(
+            maintainer_issue("jane@debian.org").as_deref(),
+            Some("maintainer jane@debian.org is not of the form Name <address>")
+        );
+        assert_eq!(
+            maintainer_issue("Jane Doe <jane>").as_deref(),
+            Some("maintainer Jane Doe <jane> has an invalid address")
+        );
+        assert_eq!(
+            maintainer_issue("<jane@debian.org>").as_deref(),
+            Some("maintainer <jane@debian.org> has no name")
+        );
+        assert_eq!(
+            maintainer_issue("Jane Doe <jane@mail.example.com>").as_deref(),
+            Some("maintainer Jane Doe <jane@mail.example.com> is a placeholder")
+        );
+        assert_eq!(
+            maintainer_issue("root <root@build.debian.org>").as_deref(),
+            Some("maintainer root <root@build.debian.org> is a placeholder")
+        );
+    }
+
+    #[test]
+    fn uploader_list() {
+        assert_eq!(
+            uploaders("Jane Doe <jane@debian.org>, \"Roe, John\" <john@debian.org>,"),
+            ["Jane Doe <jane@debian.org>", "\"Roe, John\" <john@debian.org>"]
+        );
+        assert!(uploaders(" ").is_empty());
+    }
+
+    #[test]
+    fn phasing() {
+        assert_eq!(phasing_percentage("0"), Ok(0));
+        assert_eq!(phasing_percentage("100"), Ok(100));
+        assert!(phasing_percentage("101").is_err());
+        assert!(phasing_percentage("50%").is_err());
+    }
+
+    #[test]
+    fn hashes() {
+        assert_eq!(hash_issue("Package: foo\nMD5sum: 00\nSHA256: 11\n", false), None);
+        assert_eq!(hash_issue("Package: foo\nMD5sum: 00\nSHA1: 11\n", false).as_deref(), Some("only MD5sum and SHA1 hashes"));
+        assert_eq!(hash_issue("Package: foo\n", false).as_deref(), Some("no hashes"));
+        assert_eq!(hash_issue("Package: foo\nChecksums-Sha512:\n 00 1 foo.dsc\n", true), None);
+        assert_eq!(hash_issue("Package: foo\nFiles:\n 00 1 foo.dsc\n", true).as_deref(), Some("only Files hashes"));
+    }
+}
@@ -0,0 +1,456 @@
+//! Findings of an apt repo check.
+
+use std::collections::BTreeSet;
+use std::fmt;
+
+use libapt::Architecture;
+use serde::{Deserialize, Serialize};
+
+/// Severity of a finding.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Severity {
+    Info,
+    Warning,
+    Error,
+}
+
+impl Severity {
+    /// Parse the severity from its CLI representation.
+    pub fn parse(severity: &str) -> Option<Severity> {
+        match severity.to_lowercase().as_str() {
+            "info" => Some(Severity::Info),
+            "warning" => Some(Severity::Warning),
+            "error" => Some(Severity::Error),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for Severity {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            Severity::Info => "info",
+            Severity::Warning => "warning",
+            Severity::Error => "error",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+/// Classes of findings.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Deserialize, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum FindingKind {
+    // An index could not be downloaded or processed.
+    IndexUnavailable,
+    // Release or Release.gpg is not available.
+    MissingDetachedRelease,
+    // The Release file doesn't match the InRelease payload.
+    ReleaseMismatch,
+    // A signature doesn't verify with the configured key.
+    InvalidSignature,
+    // The Valid-Until date of the release has passed.
+    ReleaseExpired,
+    // The release expires within the required validity.
+    ReleaseExpiring,
+    // The release is older than the maximum age.
+    ReleaseOutdated,
+    // The signing key is expired.
+    KeyExpired,
+    // The signing key expires within the required validity.
+    KeyExpiring,
+    // The signing key uses weak algorithms.
+    WeakKey,
+    // Neither the primary key nor a valid subkey of the signing key can sign.
+    MissingSigningKey,
+    // The InRelease file is signed by a key without expected fingerprint.
+    UnexpectedSigningKey,
+    // A stanza of an index could not be parsed.
+    InvalidStanza,
+    // An index doesn't match the size or hashes listed in the Release file.
+    IndexHashMismatch,
+    // An index can't be decompressed.
+    InvalidCompression,
+    // The compression variants of an index provide different content.
+    IndexVariantMismatch,
+    // The pdiffs of an index are inconsistent or don't apply.
+    InvalidPdiff,
+    // The by-hash path of an index is not available.
+    ByHashMissing,
+    // The by-hash path of an index serves other content than listed in the Release file.
+    ByHashMismatch,
+    // A referenced file does not exist.
+    MissingFile,
+    // The size of a referenced file doesn't match the index.
+    SizeMismatch,
+    // The hash of a referenced file doesn't match the index.
+    FileHashMismatch,
+    // The checksum list of a .dsc file doesn't match the source files of the index.
+    DscMismatch,
+    // The control file of a deb file doesn't match the index stanza.
+    ControlMismatch,
+    // A deb file has an invalid ar layout or corrupt tarballs.
+    InvalidDeb,
+    // The files of a source package don't match its format.
+    InvalidSourceFiles,
+    // A Filename or Directory field doesn't follow the pool layout.
+    UnexpectedPoolPath,
+    // The Section field is missing or unknown.
+    InvalidSection,
+    // The Priority field is missing or invalid.
+    InvalidPriority,
+    // A Maintainer or Uploaders entry is missing, invalid or a placeholder.
+    InvalidMaintainer,
+    // The Size or Installed-Size of a binary package is missing, zero or implausible.
+    ImplausibleSize,
+    // A package version is listed twice in an index.
+    DuplicateStanza,
+    // Stanzas reference the same Filename with different hashes.
+    DuplicateFilename,
+    // The Contents index is not available.
+    ContentsUnavailable,
+    // A binary package is not listed in the Contents index.
+    PackageNotInContents,
+    // A package of the Contents index is not in the binary index.
+    PackageOnlyInContents,
+    // The command-not-found metadata is not available.
+    CommandsUnavailable,
+    // A command of the command-not-found metadata refers to a package not in the binary index.
+    CommandPackageMissing,
+    // A required Translation index is not listed in the Release file.
+    MissingTranslation,
+    // A Translation index can't be parsed.
+    InvalidTranslation,
+    // A DEP-11 icon tarball can't be decompressed or read.
+    InvalidIconTarball,
+    // An icon cached by the DEP-11 Components YAML is missing in its icon tarball.
+    MissingIcon,
+    // The Multi-Arch field of a binary package is invalid.
+    InvalidMultiArch,
+    // A Multi-Arch: same package has different versions across architectures.
+    MultiArchVersionSkew,
+    // A package can't be installed, because its dependencies conflict.
+    ConflictingDependencies,
+    // A package replaces another one without Breaks or Conflicts.
+    ReplacesWithoutBreaks,
+    // A pre-dependency of a binary package is not available.
+    MissingPreDependency,
+    // A dependency of a binary package is not available.
+    MissingDependency,
+    // A recommended package is not available.
+    MissingRecommends,
+    // A suggested package is not available.
+    MissingSuggests,
+    // A build dependency of a source package can't be satisfied.
+    UnsatisfiableBuildDependency,
+    // The source of a binary package is not available.
+    MissingSource,
+    // A binary listed by a source is not published.
+    MissingBinary,
+    // A required or important package is not available on all architectures.
+    MissingPriorityPackage,
+    // The Essential and required packages are incomplete or miss dependencies.
+    IncompleteEssentialSet,
+    // A dependency or source is only available in another component.
+    CrossComponentReference,
+    // The mirror lags behind its upstream archive more than allowed.
+    MirrorLag,
+    // A pool file is not referenced by any index of the checked suites.
+    OrphanedPoolFile,
+    // A file or directory of a local repository can't be read.
+    UnreadableFile,
+    // A file or directory of a local repository is not readable by other users, e.g. the web server.
+    RestrictivePermissions,
+    // A source version is below the version fixing a CVE according to the security tracker.
+    VulnerablePackage,
+    // A source is newer than in the security or updates pocket of the suite, so the pocket's fixes are shadowed.
+    PocketInversion,
+    // A vulnerability or patch definition of the OVAL feed is not patched in the repository.
+    UnpatchedDefinition,
+    // The Release file or a stanza only provides MD5 or SHA1 hashes, so apt refuses the files.
+    WeakHashes,
+    // NotAutomatic or ButAutomaticUpgrades have invalid values, or ButAutomaticUpgrades is set without NotAutomatic.
+    InvalidAutomaticFlags,
+    // NotAutomatic or ButAutomaticUpgrades don't match the expectation.
+    UnexpectedAutomaticFlags,
+    // The Phased-Update-Percentage of a binary package is not an integer from 0 to 100.
+    InvalidPhasing,
+    // A phased update stays at its Phased-Update-Percentage longer than allowed.
+    StuckPhasing,
+}
+
+impl FindingKind {
+    /// Test if the kind reports a missing or corrupt file, which can be fetched again.
+    pub fn is_broken_file(&self) -> bool {
+        matches!(
+            self,
+            FindingKind::MissingFile
+                | FindingKind::SizeMismatch
+                | FindingKind::FileHashMismatch
+                | FindingKind::InvalidDeb
+                | FindingKind::IndexHashMismatch
+                | FindingKind::InvalidCompression
+                | FindingKind::ByHashMissing
+                | FindingKind::ByHashMismatch
+                | FindingKind::UnreadableFile
+        )
+    }
+
+    /// Default severity of the finding class.
+    pub fn severity(&self) -> Severity {
+        match self {
+            FindingKind::IndexUnavailable => Severity::Error,
+            FindingKind::MissingDetachedRelease => Severity::Warning,
+            FindingKind::ReleaseMismatch => Severity::Error,
+            FindingKind::InvalidSignature => Severity::Error,
+            FindingKind::ReleaseExpired => Severity::Error,
+            FindingKind::ReleaseExpiring => Severity::Warning,
+            FindingKind::ReleaseOutdated => Severity::Warning,
+            FindingKind::KeyExpired => Severity::Error,
+            FindingKind::KeyExpiring => Severity::Warning,
+            FindingKind::WeakKey => Severity::Warning,
+            FindingKind::MissingSigningKey => Severity::Error,
+            FindingKind::UnexpectedSigningKey => Severity::Error,
+            FindingKind::InvalidStanza => Severity::Error,
+            FindingKind::IndexHashMismatch => Severity::Error,
+            FindingKind::InvalidCompression => Severity::Error,
+            FindingKind::IndexVariantMismatch => Severity::Error,
+            FindingKind::InvalidPdiff => Severity::Error,
+            FindingKind::ByHashMissing => Severity::Error,
+            FindingKind::ByHashMismatch => Severity::Error,
+            FindingKind::MissingFile => Severity::Error,
+            FindingKind::SizeMismatch => Severity::Error,
+            FindingKind::FileHashMismatch => Severity::Error,
+            FindingKind::DscMismatch => Severity::Error,
+            FindingKind::ControlMismatch => Severity::Error,
+            FindingKind::InvalidDeb => Severity::Error,
+            FindingKind::InvalidSourceFiles => Severity::Error,
+            FindingKind::UnexpectedPoolPath => Severity::Warning,
+            FindingKind::InvalidSection => Severity::Warning,
+            FindingKind::InvalidPriority => Severity::Warning,
+            FindingKind::InvalidMaintainer => Severity::Warning,
+            FindingKind::ImplausibleSize => Severity::Warning,
+            FindingKind::DuplicateStanza => Severity::Error,
+            FindingKind::DuplicateFilename => Severity::Error,
+            FindingKind::ContentsUnavailable => Severity::Warning,
+            FindingKind::PackageNotInContents => Severity::Warning,
+            FindingKind::PackageOnlyInContents => Severity::Warning,
+            FindingKind::CommandsUnavailable => Severity::Warning,
+            FindingKind::CommandPackageMissing => Severity::Warning,
+            FindingKind::MissingTranslation => Severity::Error,
+            FindingKind::InvalidTranslation => Severity::Error,
+            FindingKind::InvalidIconTarball => Severity::Error,
+            FindingKind::MissingIcon => Severity::Warning,
+            FindingKind::InvalidMultiArch => Severity::Error,
+            FindingKind::MultiArchVersionSkew => Severity::Error,
+            FindingKind::ConflictingDependencies => Severity::Error,
+            FindingKind::ReplacesWithoutBreaks => Severity::Info,
+            FindingKind::MissingPreDependency => Severity::Error,
+            FindingKind::MissingDependency => Severity::Error,
+            FindingKind::MissingRecommends => Severity::Warning,
+            FindingKind::MissingSuggests => Severity::Info,
+            FindingKind::UnsatisfiableBuildDependency => Severity::Warning,
+            FindingKind::MissingSource => Severity::Warning,
+            FindingKind::MissingBinary => Severity::Warning,
+            FindingKind::MissingPriorityPackage => Severity::Error,
+            FindingKind::IncompleteEssentialSet => Severity::Error,
+            FindingKind::CrossComponentReference => Severity::Info,
+            FindingKind::MirrorLag => Severity::Warning,
+            FindingKind::OrphanedPoolFile => Severity::Warning,
+            FindingKind::UnreadableFile => Severity::Error,
+            FindingKind::RestrictivePermissions => Severity::Warning,
+            FindingKind::VulnerablePackage => Severity::Warning,
+            FindingKind::PocketInversion => Severity::Error,
+            FindingKind::UnpatchedDefinition => Severity::Warning,
+            FindingKind::WeakHashes => Severity::Error,
+            FindingKind::InvalidAutomaticFlags => Severity::Error,
+            FindingKind::UnexpectedAutomaticFlags => Severity::Error,
+            FindingKind::InvalidPhasing => Severity::Warning,
+            FindingKind::StuckPhasing => Severity::Warning,
+        }
+    }
+}
+
+/// A Finding is a single issue found during the check.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub struct Finding {
+    pub kind: FindingKind,
+    pub severity: Severity,
+    // Affected component. None for findings of the release.
+    pub component: Option<String>,
+    // Affected architecture. None for findings of the release or of all architectures.
+    pub architecture: Option<Architecture>,
+    // Affected binary or source package.
+    pub package: Option<String>,
+    // Affected version of the package.
+    pub version: Option<String>,
+    // Affected file.
+    pub url: Option<String>,
+    pub message: String,
+    // The finding is listed in the baseline, so it doesn't fail the check.
+    #[serde(default)]
+    pub known: bool,
+    // Actionable repair hint, e.g. the file to upload again. None if the kind has no known fix.
+    #[serde(default)]
+    pub hint: Option<String>,
+}
+
+impl Finding {
+    /// Create a new finding using the default severity of the kind.
+    pub fn new(kind: FindingKind, component: &str, architecture: &Architecture, message: &str) -> Finding {
+        Finding {
+            kind,
+            severity: kind.severity(),
+            component: Some(component.to_string()),
+            architecture: Some(architecture.clone()),
+            package: None,
+            version: None,
+            url: None,
+            message: message.to_string(),
+            known: false,
+            hint: None,
+        }
+    }
+
+    /// Create a new finding of the release, not specific to a component.
+    pub fn for_release(kind: FindingKind, message: &str) -> Finding {
+        Finding {
+            kind,
+            severity: kind.severity(),
+            component: None,
+            architecture: None,
+            package: None,
+            version: None,
+            url: None,
+            message: message.to_string(),
+            known: false,
+            hint: None,
+        }
+    }
+
+    /// Create a new finding of a component, not specific to an architecture.
+    pub fn for_component(kind: FindingKind, component: &str, message: &str) -> Finding {
+        Finding {
+            kind,
+            severity: kind.severity(),
+            component: Some(component.to_string()),
+            architecture: None,
+            package: None,
+            version: None,
+            url: None,
+            message: message.to_string(),
+            known: false,
+            hint: None,
+        }
+    }
+
+    /// Set the affected package.
+    pub fn with_package(mut self, package: &str) -> Finding {
+        self.package = Some(package.to_string());
+        self
+    }
+
+    /// Set the affected version of the package.
+    pub fn with_version(mut self, version: &str) -> Finding {
+        self.version = Some(version.to_string());
+        self
+    }
+
+    /// Override the default severity.
+    pub fn with_severity(mut self, severity: Severity) -> Finding {
+        self.severity = severity;
+        self
+    }
+
+    /// Mark the finding as known from the baseline.
+    pub fn with_known(mut self, known: bool) -> Finding {
+        self.known = known;
+        self
+    }
+
+    /// Set the affected file.
+    pub fn with_url(mut self, url: &str) -> Finding {
+        self.url = Some(url.to_string());
+        self
+    }
+
+    /// Set the repair hint.
+    pub fn with_hint(mut self, hint: Option<String>) -> Finding {
+        self.hint = hint;
+        self
+    }
+
+    /// Test if the other finding reports the same issue, ignoring the message and version.
+    pub fn is_same(&self, other: &Finding) -> bool {
+        self.kind == other.kind
+            && self.component == other.component
+            && self.architecture == other.architecture
+            && self.package == other.package
+            && self.url == other.url
+    }
+}
+
+impl fmt::Display for Finding {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match (&self.component, &self.architecture) {
+            (Some(component), Some(architecture)) => {
+                write!(f, "[{}] Component {} ({}): {}", self.severity, component, architecture, self.message)?
+            }
+            (Some(component), None) => write!(f, "[{}] Component {}: {}", self.severity, component, self.message)?,
+            _ => write!(f, "[{}] Release: {}", self.severity, self.message)?,
+        }
+        match &self.hint {
+            Some(hint) => write!(f, " Hint: {hint}"),
+            None => Ok(()),
+        }
+    }
+}
+
+/// Summary of the findings of the packages of a maintainer.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize, Serialize)]
+pub struct MaintainerSummary {
+    // Affected packages of the maintainer.
+    pub packages: BTreeSet<String>,
+    // Number of findings with severity error.
+    pub errors: usize,
+    // Number of findings with severity warning.
+    pub warnings: usize,
+    // Number of findings with severity info.
+    pub infos: usize,
+}
+
+impl MaintainerSummary {
+    /// Count the finding of a package of the maintainer.
+    pub fn add(&mut self, package: &str, severity: Severity) {
+        self.packages.insert(package.to_string());
+        match severity {
+            Severity::Error => self.errors += 1,
+            Severity::Warning => self.warnings += 1,
+            Severity::Info => self.infos += 1,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn dependency_severities() {
+        assert_eq!(FindingKind::MissingPreDependency.severity(), Severity::Error);
+        assert_eq!(FindingKind::MissingDependency.severity(), Severity::Error);
+        assert_eq!(FindingKind::MissingRecommends.severity(), Severity::Warning);
+        assert_eq!(FindingKind::MissingSuggests.severity(), Severity::Info);
+        assert_eq!(FindingKind::ReplacesWithoutBreaks.severity(), Severity::Info);
+    }
+
+    #[test]
+    fn broken_files_are_errors() {
+        for kind in [FindingKind::MissingFile, FindingKind::SizeMismatch, FindingKind::FileHashMismatch, FindingKind::ByHashMismatch] {
+            assert!(kind.is_broken_file());
+            assert_eq!(kind.severity(), Severity::Error);
+        }
+        assert!(!FindingKind::MissingRecommends.is_broken_file());
+    }
+}
@@ -0,0 +1,88 @@
+//! Repair hints of the findings.
+//!
+//! The hints name the file to upload again or to regenerate, and a command of the common
+//! repository tools which fixes the issue, e.g. reprepro or apt-ftparchive.
+
+use libapt::Distro;
+
+use crate::finding::{Finding, FindingKind};
+
+/// Get the path of the affected file relative to the repository root, e.g. pool/main/h/hello/hello_1.0-1_amd64.deb.
+fn relative_path(finding: &Finding, distro: &Distro) -> Option<String> {
+    let url = finding.url.as_deref()?;
+    let root = format!("{}/", distro.url.trim_end_matches('/'));
+    Some(url.strip_prefix(&root).unwrap_or(url).to_string())
+}
+
+/// Get the actionable hint for the finding, if its kind has a known fix.
+pub fn for_finding(finding: &Finding, distro: &Distro) -> Option<String> {
+    let suite = distro.name.as_deref().unwrap_or("<suite>");
+    let path = relative_path(finding, distro);
+    let file = path.as_deref().unwrap_or("the file");
+    let package = finding.package.as_deref().unwrap_or("the package");
+
+    let hint = match finding.kind {
+        FindingKind::MissingFile | FindingKind::SizeMismatch | FindingKind::FileHashMismatch | FindingKind::InvalidDeb | FindingKind::ControlMismatch => {
+            format!("Upload {file} again from the build or the source archive, e.g. with rsync.")
+        }
+        FindingKind::DscMismatch | FindingKind::InvalidSourceFiles => {
+            format!("Include the source package {package} again, e.g. `reprepro includedsc {suite} <dsc>`.")
+        }
+        FindingKind::IndexUnavailable
+        | FindingKind::IndexHashMismatch
+        | FindingKind::InvalidCompression
+        | FindingKind::IndexVariantMismatch
+        | FindingKind::InvalidStanza
+        | FindingKind::ByHashMissing
+        | FindingKind::ByHashMismatch
+        | FindingKind::InvalidPdiff
+        | FindingKind::InvalidTranslation => {
+            format!("Regenerate {} and the Release file, e.g. `reprepro export {suite}` or `apt-ftparchive packages` and `apt-ftparchive release`.", path.as_deref().unwrap_or("the index"))
+        }
+        FindingKind::InvalidIconTarball | FindingKind::MissingIcon => {
+            format!("Regenerate the DEP-11 metadata and icons, e.g. with appstream-generator, and publish them with the Release file, e.g. `reprepro export {suite}`.")
+        }
+        FindingKind::MissingTranslation => format!("Publish the Translation index, e.g. `apt-ftparchive release` after adding it, or `reprepro export {suite}`."),
+        FindingKind::ReleaseMismatch | FindingKind::MissingDetachedRelease | FindingKind::InvalidSignature => {
+            format!("Sign the release again, e.g. `reprepro export {suite}` or `gpg --clearsign` and `gpg --detach-sign` of the Release file.")
+        }
+        FindingKind::ReleaseExpired | FindingKind::ReleaseExpiring | FindingKind::ReleaseOutdated => {
+            format!("Publish the release again to refresh Date and Valid-Until, e.g. `reprepro export {suite}`.")
+        }
+        FindingKind::KeyExpired | FindingKind::KeyExpiring => "Extend the expiry with `gpg --quick-set-expire <fingerprint> <time>` and publish the updated key.".to_string(),
+        FindingKind::WeakKey | FindingKind::MissingSigningKey => "Create a new Ed25519 or RSA 4096 signing key and sign the release with it.".to_string(),
+        FindingKind::UnexpectedSigningKey => "Sign the release with the expected key, or update the expected fingerprints.".to_string(),
+        FindingKind::UnexpectedPoolPath => format!("Include {package} again, so the repository tool places it in the pool directory of its source."),
+        FindingKind::DuplicateStanza | FindingKind::DuplicateFilename => format!("Remove the duplicate of {package}, e.g. `reprepro remove {suite} {package}`, and include it once."),
+        FindingKind::MissingPreDependency
+        | FindingKind::MissingDependency
+        | FindingKind::MissingRecommends
+        | FindingKind::MissingSuggests
+        | FindingKind::UnsatisfiableBuildDependency => {
+            "Upload the missing package, or add the repository providing it as base repository.".to_string()
+        }
+        FindingKind::MissingSource => format!("Include the source of {package}, e.g. `reprepro includedsc {suite} <dsc>`."),
+        FindingKind::MissingBinary => format!("Build and include the missing binaries of {package}, e.g. `reprepro includedeb {suite} <deb>`."),
+        FindingKind::MultiArchVersionSkew => format!("Include the same version of {package} for all architectures."),
+        FindingKind::ContentsUnavailable | FindingKind::PackageNotInContents | FindingKind::PackageOnlyInContents => {
+            format!("Regenerate the Contents indices, e.g. `apt-ftparchive contents` or `reprepro export {suite}` with Contents enabled.")
+        }
+        FindingKind::MirrorLag => "Run the sync job of the mirror again, e.g. ftpsync or rsync, and check its logs.".to_string(),
+        FindingKind::OrphanedPoolFile => format!("Remove {file}, e.g. `reprepro deleteunreferenced`."),
+        FindingKind::UnreadableFile | FindingKind::RestrictivePermissions => format!("Make {file} readable, e.g. `chmod o+r` for files and `chmod o+rx` for directories."),
+        FindingKind::VulnerablePackage | FindingKind::UnpatchedDefinition => {
+            format!("Include the fixed version of {package} from the security archive, e.g. `reprepro includedsc {suite} <dsc>`, or add the security suite to the sources.")
+        }
+        FindingKind::PocketInversion => {
+            format!("Copy the newer version of {package} to the pocket, or remove it from {suite} so the pocket's version is installed.")
+        }
+        FindingKind::WeakHashes => format!("Regenerate the indices and the Release file with SHA256 hashes, e.g. with a current reprepro or apt-ftparchive, and `reprepro export {suite}`."),
+        FindingKind::InvalidAutomaticFlags | FindingKind::UnexpectedAutomaticFlags => {
+            "Set NotAutomatic and ButAutomaticUpgrades in the conf/distributions of reprepro, or with `apt-ftparchive -o APT::FTPArchive::Release::NotAutomatic=yes release`.".to_string()
+        }
+        FindingKind::InvalidPhasing => format!("Set the Phased-Update-Percentage of {package} to an integer from 0 to 100, or remove the field to release the update to all systems."),
+        FindingKind::StuckPhasing => format!("Check the error reports of {package}, then raise its Phased-Update-Percentage or remove the update from the pocket."),
+        _ => return None,
+    };
+    Some(hint)
+}
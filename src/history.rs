@@ -0,0 +1,200 @@
+//! History of the check runs in an SQLite database.
+//!
+//! Every run is recorded with the results of its repositories and their findings,
+//! so questions like "when did package X first go missing" can be answered with SQL, e.g.
+//!
+//! ```sql
+//! SELECT MIN(runs.timestamp) FROM findings
+//! JOIN results ON results.id = findings.result_id JOIN runs ON runs.id = results.run_id
+//! WHERE findings.package = 'hello' AND findings.kind = 'missing-dependency';
+//! ```
+//!
+//! The Phased-Update-Percentage of the binary packages is recorded per repository, too,
+//! so phased updates which are stuck at a percentage can be detected.
+
+use std::collections::HashMap;
+
+use chrono::{DateTime, Utc};
+use libapt::{Error, Result};
+use log::{error, info};
+use rusqlite::{params, Connection};
+use serde::{Deserialize, Serialize};
+
+use crate::{CheckStatus, RepoResult};
+
+/// Version of the schema, stored as user_version of the database.
+const SCHEMA_VERSION: i64 = 2;
+
+/// Tables and indices of the history.
+const SCHEMA: &str = "
+CREATE TABLE IF NOT EXISTS runs (
+    id INTEGER PRIMARY KEY,
+    timestamp TEXT NOT NULL
+);
+CREATE TABLE IF NOT EXISTS results (
+    id INTEGER PRIMARY KEY,
+    run_id INTEGER NOT NULL REFERENCES runs(id),
+    repo TEXT NOT NULL,
+    status TEXT NOT NULL,
+    error TEXT
+);
+CREATE TABLE IF NOT EXISTS findings (
+    result_id INTEGER NOT NULL REFERENCES results(id),
+    kind TEXT NOT NULL,
+    severity TEXT NOT NULL,
+    component TEXT,
+    architecture TEXT,
+    package TEXT,
+    version TEXT,
+    url TEXT,
+    message TEXT NOT NULL,
+    known INTEGER NOT NULL
+);
+CREATE TABLE IF NOT EXISTS phasing (
+    repo TEXT NOT NULL,
+    timestamp TEXT NOT NULL,
+    architecture TEXT NOT NULL,
+    package TEXT NOT NULL,
+    version TEXT NOT NULL,
+    percentage INTEGER NOT NULL
+);
+CREATE INDEX IF NOT EXISTS results_repo ON results(repo);
+CREATE INDEX IF NOT EXISTS findings_result ON findings(result_id);
+CREATE INDEX IF NOT EXISTS findings_package ON findings(package, kind);
+CREATE INDEX IF NOT EXISTS phasing_package ON phasing(repo, package, version);
+";
+
+/// Phased update of a binary package.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub struct PhasedUpdate {
+    pub component: String,
+    pub architecture: String,
+    pub package: String,
+    pub version: String,
+    // Value of the Phased-Update-Percentage field.
+    pub percentage: u8,
+}
+
+/// Report a failure of the history database.
+fn history_error(path: &str, e: rusqlite::Error) -> Error {
+    let message = format!("Accessing history {path} failed! {e}");
+    error!("{}", message);
+    Error::new(&message, libapt::ErrorType::ApiUsage)
+}
+
+/// Get the name of a value in the result, e.g. missing-dependency for a finding kind.
+fn code<T: Serialize>(value: &T) -> String {
+    match serde_json::to_value(value) {
+        Ok(serde_json::Value::String(code)) => code,
+        _ => String::new(),
+    }
+}
+
+/// Open the database, creating the tables if needed.
+fn open(path: &str) -> rusqlite::Result<Connection> {
+    let connection = Connection::open(path)?;
+    connection.execute_batch(SCHEMA)?;
+    connection.pragma_update(None, "user_version", SCHEMA_VERSION)?;
+    Ok(connection)
+}
+
+/// Record a run with the results of its repositories.
+///
+/// The run is written in one transaction, so an interrupted write leaves no partial run.
+pub fn record(path: &str, repos: &[RepoResult]) -> Result<()> {
+    let mut connection = open(path).map_err(|e| history_error(path, e))?;
+    let transaction = connection.transaction().map_err(|e| history_error(path, e))?;
+
+    let write = |transaction: &rusqlite::Transaction| -> rusqlite::Result<()> {
+        transaction.execute("INSERT INTO runs (timestamp) VALUES (?1)", params![Utc::now().to_rfc3339()])?;
+        let run_id = transaction.last_insert_rowid();
+
+        let mut insert_result = transaction.prepare("INSERT INTO results (run_id, repo, status, error) VALUES (?1, ?2, ?3, ?4)")?;
+        let mut insert_finding = transaction.prepare(
+            "INSERT INTO findings (result_id, kind, severity, component, architecture, package, version, url, message, known)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10)",
+        )?;
+        for repo in repos {
+            insert_result.execute(params![run_id, repo.name, code(&repo.status), repo.error])?;
+            let result_id = transaction.last_insert_rowid();
+
+            for finding in &repo.findings {
+                insert_finding.execute(params![
+                    result_id,
+                    code(&finding.kind),
+                    code(&finding.severity),
+                    finding.component,
+                    finding.architecture.as_ref().map(|architecture| architecture.to_string()),
+                    finding.package,
+                    finding.version,
+                    finding.url,
+                    finding.message,
+                    finding.known,
+                ])?;
+            }
+        }
+        Ok(())
+    };
+    write(&transaction).map_err(|e| history_error(path, e))?;
+    transaction.commit().map_err(|e| history_error(path, e))?;
+
+    info!("Recorded run of {} repos in history {path}.", repos.len());
+    Ok(())
+}
+
+/// Get the status of every repository in its latest recorded run.
+pub fn last_statuses(path: &str) -> Result<HashMap<String, CheckStatus>> {
+    let connection = open(path).map_err(|e| history_error(path, e))?;
+    let read = || -> rusqlite::Result<Vec<(String, String)>> {
+        let mut select = connection.prepare("SELECT repo, status FROM results WHERE id IN (SELECT MAX(id) FROM results GROUP BY repo)")?;
+        let rows = select.query_map([], |row| Ok((row.get(0)?, row.get(1)?)))?;
+        rows.collect()
+    };
+    let rows = read().map_err(|e| history_error(path, e))?;
+
+    Ok(rows
+        .into_iter()
+        .filter_map(|(repo, status)| Some((repo, serde_json::from_value(serde_json::Value::String(status)).ok()?)))
+        .collect())
+}
+
+/// Record the phased updates of a repository.
+pub fn record_phasing(path: &str, repo: &str, updates: &[PhasedUpdate]) -> Result<()> {
+    let mut connection = open(path).map_err(|e| history_error(path, e))?;
+    let transaction = connection.transaction().map_err(|e| history_error(path, e))?;
+
+    let write = |transaction: &rusqlite::Transaction| -> rusqlite::Result<()> {
+        let timestamp = Utc::now().to_rfc3339();
+        let mut insert = transaction.prepare(
+            "INSERT INTO phasing (repo, timestamp, architecture, package, version, percentage) VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+        )?;
+        for update in updates {
+            insert.execute(params![repo, timestamp, update.architecture, update.package, update.version, update.percentage])?;
+        }
+        Ok(())
+    };
+    write(&transaction).map_err(|e| history_error(path, e))?;
+    transaction.commit().map_err(|e| history_error(path, e))?;
+
+    info!("Recorded {} phased updates of {repo} in history {path}.", updates.len());
+    Ok(())
+}
+
+/// Get the time since which the phased update has its current percentage, according to the earlier runs.
+///
+/// None if the update wasn't recorded with this percentage before.
+pub fn phasing_since(path: &str, repo: &str, update: &PhasedUpdate) -> Result<Option<DateTime<Utc>>> {
+    let connection = open(path).map_err(|e| history_error(path, e))?;
+    let since: Option<String> = connection
+        .query_row(
+            "SELECT MIN(timestamp) FROM phasing
+             WHERE repo = ?1 AND architecture = ?2 AND package = ?3 AND version = ?4 AND percentage = ?5
+             AND timestamp > (SELECT COALESCE(MAX(timestamp), '') FROM phasing
+                 WHERE repo = ?1 AND architecture = ?2 AND package = ?3 AND version = ?4 AND percentage != ?5)",
+            params![repo, update.architecture, update.package, update.version, update.percentage],
+            |row| row.get(0),
+        )
+        .map_err(|e| history_error(path, e))?;
+
+    Ok(since.and_then(|since| DateTime::parse_from_rfc3339(&since).ok()).map(|since| since.with_timezone(&Utc)))
+}
@@ -0,0 +1,266 @@
+//! Download and stanza-wise processing of Packages and Sources indices.
+
+use std::io::Read;
+
+use bzip2::read::MultiBzDecoder;
+use flate2::read::MultiGzDecoder;
+use libapt::{Architecture, Error, Link, LinkHash, PackageVersion, Release, Result};
+use log::debug;
+use md5::Md5;
+use sha1::Sha1;
+use sha2::{Digest, Sha256, Sha512};
+
+use crate::transport::Transport;
+
+/// Verify the strongest available hash of the downloaded index.
+fn verify_hash(data: &[u8], link: &Link) -> Result<()> {
+    let (name, expected, actual) = if let Some(hash) = link.hashes.get(&LinkHash::Sha512) {
+        ("SHA512", hash, format!("{:x}", Sha512::digest(data)))
+    } else if let Some(hash) = link.hashes.get(&LinkHash::Sha256) {
+        ("SHA256", hash, format!("{:x}", Sha256::digest(data)))
+    } else {
+        return Err(Error::new(
+            &format!("No SHA256 or SHA512 hash for URL {} provided!", link.url),
+            libapt::ErrorType::Download,
+        ));
+    };
+
+    if expected.to_lowercase() != actual {
+        return Err(Error::new(
+            &format!("{name} hash verification of URL {} failed!", link.url),
+            libapt::ErrorType::Download,
+        ));
+    }
+
+    debug!("Verified {name} hash of URL {}.", link.url);
+    Ok(())
+}
+
+/// Compare the data with the size and all hashes given in the Release file.
+///
+/// Returns a description of each mismatch.
+pub fn hash_mismatches(data: &[u8], link: &Link) -> Vec<String> {
+    listed_mismatches(data, link, "Release")
+}
+
+/// Compare the data with the size and all hashes listed by the named file, e.g. i18n/Index.
+pub fn listed_mismatches(data: &[u8], link: &Link, listed_by: &str) -> Vec<String> {
+    let mut mismatches = Vec::new();
+
+    if data.len() != link.size {
+        mismatches.push(format!("size is {} bytes, but {listed_by} lists {} bytes", data.len(), link.size));
+    }
+
+    for hash in [LinkHash::Md5, LinkHash::Sha1, LinkHash::Sha256, LinkHash::Sha512] {
+        let expected = match link.hashes.get(&hash) {
+            Some(expected) => expected.to_lowercase(),
+            None => continue,
+        };
+
+        let (name, actual) = match hash {
+            LinkHash::Md5 => ("MD5Sum", format!("{:x}", Md5::digest(data))),
+            LinkHash::Sha1 => ("SHA1", format!("{:x}", Sha1::digest(data))),
+            LinkHash::Sha256 => ("SHA256", format!("{:x}", Sha256::digest(data))),
+            LinkHash::Sha512 => ("SHA512", format!("{:x}", Sha512::digest(data))),
+        };
+
+        if expected != actual {
+            mismatches.push(format!("{name} is {actual}, but {listed_by} lists {expected}"));
+        }
+    }
+
+    mismatches
+}
+
+/// Compression extensions of index files supported for decompression, best first.
+pub const SUPPORTED_COMPRESSIONS: [&str; 4] = [".xz", ".gz", ".bz2", ""];
+
+/// Decompress the index data, guessing the compression from the URL extension.
+///
+/// The complete data is decompressed, including concatenated streams,
+/// so truncated or corrupt data at the end is detected.
+pub fn decompress(data: Vec<u8>, url: &str) -> Result<Vec<u8>> {
+    let data = if url.ends_with(".xz") {
+        lzma::decompress(&data).map_err(|e| Error::from_lzma(e, url))?
+    } else if url.ends_with(".gz") {
+        let mut content = Vec::new();
+        MultiGzDecoder::new(&data[..])
+            .read_to_end(&mut content)
+            .map_err(|e| Error::from_io_error(e, url))?;
+        content
+    } else if url.ends_with(".bz2") {
+        let mut content = Vec::new();
+        MultiBzDecoder::new(&data[..])
+            .read_to_end(&mut content)
+            .map_err(|e| Error::from_io_error(e, url))?;
+        content
+    } else {
+        data
+    };
+
+    Ok(data)
+}
+
+/// Download an index file and verify its hash, without decompressing it.
+pub async fn download_verified(transport: &Transport, link: &Link) -> Result<Vec<u8>> {
+    let data = transport.download(&link.url).await?;
+    verify_hash(&data, link)?;
+    Ok(data)
+}
+
+/// Download an index file, verify its hash and decompress it.
+pub async fn download_index(transport: &Transport, link: &Link) -> Result<String> {
+    let data = download_verified(transport, link).await?;
+    let data = decompress(data, &link.url)?;
+    Ok(String::from_utf8_lossy(&data).to_string())
+}
+
+/// Get the link of the package index of the component and architecture.
+///
+/// Like the lookup of libapt, the first available index of xz, gz and
+/// uncompressed is used, but the availability is probed using the transport.
+pub async fn package_index_link(transport: &Transport, release: &Release, component: &str, architecture: &Architecture) -> Result<Link> {
+    let path = if architecture == &Architecture::Source {
+        format!("{component}/source/Sources")
+    } else {
+        format!("{component}/binary-{architecture}/Packages")
+    };
+    let url = release.distro.url(&path, false);
+
+    for extension in [".xz", ".gz", ""] {
+        let link = match release.links.get(&format!("{url}{extension}")) {
+            Some(link) => link,
+            None => continue,
+        };
+        match transport.probe(&link.url).await {
+            Ok(_) => return Ok(link.clone()),
+            Err(e) => debug!("Index {} is not available, trying next compression: {e}", link.url),
+        }
+    }
+
+    Err(Error::new(
+        &format!("No matching package index found for component {component} and architecture {architecture}!"),
+        libapt::ErrorType::ApiUsage,
+    ))
+}
+
+/// Iterate over the stanzas of an index.
+///
+/// The stanzas are parsed one by one by the caller, so only the
+/// plain text of the index is kept in memory.
+pub fn stanzas(content: &str) -> impl Iterator<Item = &str> {
+    content
+        .split("\n\n")
+        .map(|stanza| stanza.trim())
+        .filter(|stanza| !stanza.is_empty())
+}
+
+/// Get the value of a single line field of a stanza.
+pub fn field<'a>(stanza: &'a str, name: &str) -> Option<&'a str> {
+    stanza
+        .lines()
+        .filter_map(|line| line.split_once(':'))
+        .find(|(key, _)| key.eq_ignore_ascii_case(name))
+        .map(|(_, value)| value.trim())
+}
+
+/// Get the value of a folded field of a stanza, joining the continuation lines.
+pub fn folded_field(stanza: &str, name: &str) -> Option<String> {
+    let mut lines = stanza.lines().skip_while(|line| {
+        !line
+            .split_once(':')
+            .is_some_and(|(key, _)| key.eq_ignore_ascii_case(name))
+    });
+
+    let (_, first) = lines.next()?.split_once(':')?;
+    let mut value = first.trim().to_string();
+    for line in lines.take_while(|line| line.starts_with(' ') || line.starts_with('\t')) {
+        value.push(' ');
+        value.push_str(line.trim());
+    }
+
+    Some(value.trim().to_string())
+}
+
+/// Get the continuation lines of a multiline field, e.g. Package-List.
+pub fn field_lines<'a>(stanza: &'a str, name: &str) -> Vec<&'a str> {
+    stanza
+        .lines()
+        .skip_while(|line| {
+            line.starts_with(' ')
+                || line.starts_with('\t')
+                || !line.split_once(':').is_some_and(|(key, _)| key.eq_ignore_ascii_case(name))
+        })
+        .skip(1)
+        .take_while(|line| line.starts_with(' ') || line.starts_with('\t'))
+        .map(|line| line.trim())
+        .filter(|line| !line.is_empty())
+        .collect()
+}
+
+/// Parse a relation field of a stanza, e.g. Depends, into groups of alternatives.
+///
+/// A group is satisfied if any of its alternatives is available.
+pub fn relation_groups(stanza: &str, name: &str) -> Result<Vec<Vec<PackageVersion>>> {
+    let value = match folded_field(stanza, name) {
+        Some(value) => value,
+        None => return Ok(Vec::new()),
+    };
+
+    value
+        .split(',')
+        .map(|group| group.trim())
+        .filter(|group| !group.is_empty())
+        .map(PackageVersion::from_str)
+        .collect()
+}
+
+/// Get the files of a binary stanza. (Path, Size)
+pub fn package_files(stanza: &str) -> Vec<(String, u64)> {
+    match field(stanza, "Filename") {
+        Some(filename) => vec![(filename.to_string(), field(stanza, "Size").and_then(|size| size.parse().ok()).unwrap_or_default())],
+        None => Vec::new(),
+    }
+}
+
+/// Get the files of a source stanza from its strongest checksum list. (Path, Size)
+pub fn source_files(stanza: &str) -> Vec<(String, u64)> {
+    let directory = field(stanza, "Directory").unwrap_or_default();
+    let lines = ["Checksums-Sha512", "Checksums-Sha256", "Checksums-Sha1", "Files"]
+        .iter()
+        .map(|name| field_lines(stanza, name))
+        .find(|lines| !lines.is_empty())
+        .unwrap_or_default();
+
+    lines
+        .iter()
+        .filter_map(|line| match line.split_whitespace().collect::<Vec<&str>>()[..] {
+            [_, size, name] => Some((format!("{directory}/{name}"), size.parse().unwrap_or_default())),
+            _ => None,
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use libapt::VersionRelation;
+
+    use super::*;
+
+    #[test]
+    fn relation_group_list() {
+        let stanza = "Package: foo\nDepends: libc6 (>= 2.36), default-mta | mail-transport-agent,\n python3\nRecommends: bar\n";
+        let groups = relation_groups(stanza, "Depends").unwrap();
+        let names: Vec<Vec<&str>> = groups.iter().map(|group| group.iter().map(|dependency| dependency.name.as_str()).collect()).collect();
+        assert_eq!(names, [vec!["libc6"], vec!["default-mta", "mail-transport-agent"], vec!["python3"]]);
+        assert_eq!(groups[0][0].relation, Some(VersionRelation::Larger));
+        assert!(groups[0][0].version.is_some());
+        assert_eq!(groups[1][0].version, None);
+    }
+
+    #[test]
+    fn missing_relation_field() {
+        assert!(relation_groups("Package: foo\n", "Depends").unwrap().is_empty());
+        assert!(relation_groups("Package: foo\nDepends:\n", "Depends").unwrap().is_empty());
+    }
+}
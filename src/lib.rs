@@ -1,21 +1,135 @@
 use libapt::{Distro, Key, Release, Result, Error};
-use log::{debug, info, error};
+use log::{debug, info, error, warn, Record};
+use log::kv::{Key as KvKey, Value, VisitSource};
 use env_logger::Env;
-use serde_json;
+use futures::stream::{self, StreamExt};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeSet, HashMap};
 use std::fs::File;
 use std::io::prelude::*;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, RwLock};
+use std::time::{Duration, Instant};
 
+mod auth;
+mod base;
+mod baseline;
+mod bench;
+mod build_depends;
 mod check;
+mod checkpoint;
+mod cnf;
+mod compare;
+mod config;
+mod contents;
+mod cve;
+mod deb;
+mod dep11;
+mod diff;
+mod fields;
+mod dsc;
+mod finding;
+mod hint;
+mod history;
+mod index;
+mod metrics;
+mod notify;
+mod options;
+mod orphans;
+mod oval;
+mod package_filter;
+mod pdiff;
+mod permissions;
+mod progress;
+mod release;
+mod release_file;
+mod s3;
+mod server;
+mod signature;
+mod smtp;
+mod sources_list;
+mod stats;
+mod timing;
+mod translation;
+mod transport;
+mod util;
+mod watch;
 
 use crate::check::AptCheck;
+use crate::metrics::RepoMetrics;
+use crate::notify::{Notification, RepoSummary};
+pub use crate::auth::{apt_auth_entries, auth_file_entries, AuthEntry};
+pub use crate::baseline::BaselineEntry;
+pub use crate::bench::{BenchReport, BenchSample, BenchStats, Percentiles};
+pub use crate::compare::{snapshot_url, MirrorComparison, MirrorLag, MirrorPackage, DEBIAN_SNAPSHOT};
+use crate::transport::Transport;
+pub use crate::config::{Config, RepoConfig};
+pub use crate::diff::{DiffFinding, ReportDiff};
+use crate::diff::ReportRepo;
+pub use crate::finding::{Finding, FindingKind, MaintainerSummary, Severity};
+pub use crate::signature::keyring_keys;
+pub use crate::oval::{AffectedPackage, UnpatchedDefinition};
+pub use crate::options::{BaseRepo, BenchOptions, ChatTarget, CheckId, CheckOptions, CompareOptions, EmailOptions, Http2Mode, HostOptions, HttpOptions, ProbeMethod, Profile, Resolve, Sample, SmtpTls, StatsOptions, WatchOptions};
+pub use crate::stats::{IndexStats, RepoStats};
+pub use crate::timing::{Phase, Timing};
+pub use crate::util::{format_bytes, parse_duration};
+use crate::util::format_duration;
+pub use crate::watch::WatchedRepo;
+use crate::watch::RepoState;
+use crate::server::Latest;
+
+/// Collects the key-values of a log record as JSON fields.
+struct JsonFields(serde_json::Map<String, serde_json::Value>);
+
+impl<'kvs> VisitSource<'kvs> for JsonFields {
+    fn visit_pair(&mut self, key: KvKey<'kvs>, value: Value<'kvs>) -> std::result::Result<(), log::kv::Error> {
+        let value = if let Some(value) = value.to_bool() {
+            serde_json::Value::from(value)
+        } else if let Some(value) = value.to_u64() {
+            serde_json::Value::from(value)
+        } else if let Some(value) = value.to_i64() {
+            serde_json::Value::from(value)
+        } else if let Some(value) = value.to_f64() {
+            serde_json::Value::from(value)
+        } else if value.to_string() == "None" {
+            serde_json::Value::Null
+        } else {
+            serde_json::Value::from(value.to_string())
+        };
+        self.0.insert(key.to_string(), value);
+        Ok(())
+    }
+}
+
+/// Write a log record as a single line JSON object.
+///
+/// The key-values of the record, e.g. component, arch and package of findings, become fields of the object.
+fn format_json(buf: &mut env_logger::fmt::Formatter, record: &Record) -> std::io::Result<()> {
+    let mut fields = JsonFields(serde_json::Map::new());
+    fields.0.insert("timestamp".to_string(), chrono::Utc::now().to_rfc3339().into());
+    fields.0.insert("level".to_string(), record.level().as_str().to_lowercase().into());
+    fields.0.insert("target".to_string(), record.target().into());
+    fields.0.insert("message".to_string(), record.args().to_string().into());
+    let _ = record.key_values().visit(&mut fields);
+
+    writeln!(buf, "{}", serde_json::Value::Object(fields.0))
+}
 
 /// Setup env_logger.
+///
+/// With APTCHECKR_LOG_FORMAT=json, one JSON object is written per log event.
 fn init_logging() {
     let env = Env::default()
         .filter_or("APTCHECKR_LOG_LEVEL", "info")
         .write_style_or("APTCHECKR_LOG_STYLE", "always");
 
-    env_logger::init_from_env(env);
+    let mut builder = env_logger::Builder::from_env(env);
+    if std::env::var("APTCHECKR_LOG_FORMAT").is_ok_and(|format| format.eq_ignore_ascii_case("json")) {
+        builder.format(format_json);
+    }
+
+    // Batch runs call the lib entry points repeatedly.
+    let _ = builder.try_init();
 }
 
 /// Log user-provided distro information.
@@ -29,15 +143,15 @@ fn log_distro(distro: &Distro) {
     };
 
     let key = match &distro.key {
-        Key::ArmoredKey(url) => format!("Armored key from {url}"),
-        Key::Key(url) => format!("Binary key from {url}"),
+        Key::ArmoredKey(url) => format!("Armored key from {}", signature::key_location(url)),
+        Key::Key(url) => format!("Binary key from {}", signature::key_location(url)),
         Key::NoSignatureCheck => "No key. InRelease signature will not get verified!".to_string(),
     };
 
     info!("Distro-Info:\nURL: {}\n{}\nKey: {}", distro.url, name, key);
 }
 
-fn save_as_json(check: AptCheck, filename: &str) ->Result<()> {
+fn save_as_json<T: Serialize>(data: &T, filename: &str) ->Result<()> {
     let mut file = match File::create(filename) {
         Ok(file) => file,
         Err(e) => {
@@ -48,7 +162,7 @@ fn save_as_json(check: AptCheck, filename: &str) ->Result<()> {
         }
     }; 
 
-    let data = match serde_json::to_string_pretty(&check) {
+    let data = match serde_json::to_string_pretty(data) {
         Ok(data) => data,
         Err(e) => {
             let message = format!("Json serializing failed! {e}");
@@ -58,7 +172,7 @@ fn save_as_json(check: AptCheck, filename: &str) ->Result<()> {
         }
     };
 
-    match file.write(&data.as_bytes()) {
+    match file.write(data.as_bytes()) {
         Ok(_) => {},
         Err(e) => {
             let message = format!("Writing json failed! {e}");
@@ -71,20 +185,802 @@ fn save_as_json(check: AptCheck, filename: &str) ->Result<()> {
     Ok(())
 }
 
+/// Overall result of a repo check.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+pub enum CheckStatus {
+    // No issues were found.
+    Passed,
+    // Issues were found, or not all checks were done.
+    Failed,
+    // The check was interrupted by a signal.
+    Aborted,
+}
+
+/// Result of the check of a sources.list entry.
+#[derive(Debug, Clone, Serialize)]
+pub struct SourceResult {
+    // Line number of the entry, starting with 1.
+    pub line: usize,
+    // Text of the entry.
+    pub entry: String,
+    // Overall result of the check.
+    pub status: CheckStatus,
+    // Error which stopped the check, e.g. an unavailable InRelease file.
+    pub error: Option<String>,
+    // Findings of the check.
+    pub findings: Vec<Finding>,
+}
+
+/// Result of the check of a repository.
+#[derive(Debug, Clone, Serialize)]
+pub struct RepoResult {
+    // Name of the repository.
+    pub name: String,
+    // Overall result of the check.
+    pub status: CheckStatus,
+    // Error which stopped the check, e.g. an unavailable InRelease file.
+    pub error: Option<String>,
+    // Highest severity of the findings, except known findings, error if the check stopped with an error.
+    pub worst_severity: Option<Severity>,
+    // Findings of the check.
+    pub findings: Vec<Finding>,
+}
+
+impl RepoResult {
+    /// Create the result of a repository check.
+    fn new(name: String, status: CheckStatus, error: Option<String>, findings: Vec<Finding>) -> RepoResult {
+        let worst_severity = match error {
+            Some(_) => Some(Severity::Error),
+            None => findings.iter().filter(|finding| !finding.known).map(|finding| finding.severity).max(),
+        };
+        RepoResult { name, status, error, worst_severity, findings }
+    }
+}
+
+/// Aggregated report of a batch of repository checks.
+#[derive(Debug, Clone, Serialize)]
+pub struct BatchReport {
+    // Overall result: aborted if any check was aborted, passed if all checks passed.
+    pub status: CheckStatus,
+    // Highest severity over all repositories.
+    pub worst_severity: Option<Severity>,
+    // Number of passed repositories.
+    pub passed: usize,
+    // Number of failed or aborted repositories.
+    pub failed: usize,
+    // Results per repository.
+    pub repos: Vec<RepoResult>,
+}
+
+impl BatchReport {
+    /// Aggregate the results of the repositories.
+    pub fn new(repos: Vec<RepoResult>) -> BatchReport {
+        let status = if repos.iter().any(|repo| repo.status == CheckStatus::Aborted) {
+            CheckStatus::Aborted
+        } else if repos.iter().all(|repo| repo.status == CheckStatus::Passed) {
+            CheckStatus::Passed
+        } else {
+            CheckStatus::Failed
+        };
+        let passed = repos.iter().filter(|repo| repo.status == CheckStatus::Passed).count();
+
+        BatchReport {
+            status,
+            worst_severity: repos.iter().filter_map(|repo| repo.worst_severity).max(),
+            passed,
+            failed: repos.len() - passed,
+            repos,
+        }
+    }
+
+    /// Get the findings per repository for a comparison.
+    fn findings(&self) -> Vec<ReportRepo> {
+        self.repos.iter().map(|repo| (repo.name.clone(), repo.findings.clone())).collect()
+    }
+
+    /// Summarize the repositories for a notification, with the status of the previous run.
+    fn summaries(&self, previous: &HashMap<String, CheckStatus>) -> Vec<RepoSummary> {
+        self.repos
+            .iter()
+            .map(|repo| RepoSummary::new(&repo.name, repo.status, repo.error.clone(), &repo.findings, previous_of(previous, &repo.name)))
+            .collect()
+    }
+}
+
+/// Wait for SIGINT or SIGTERM.
+async fn shutdown_signal() {
+    #[cfg(unix)]
+    {
+        use tokio::signal::unix::{signal, SignalKind};
+
+        match signal(SignalKind::terminate()) {
+            Ok(mut terminate) => {
+                tokio::select! {
+                    _ = tokio::signal::ctrl_c() => {},
+                    _ = terminate.recv() => {},
+                }
+            }
+            Err(e) => {
+                warn!("Handling of SIGTERM failed: {e}");
+                let _ = tokio::signal::ctrl_c().await;
+            }
+        }
+    }
+
+    #[cfg(not(unix))]
+    {
+        let _ = tokio::signal::ctrl_c().await;
+    }
+}
+
+/// Load the release, verifying the InRelease signature with the key of the distro or one of the further keys.
+///
+/// The first key which verifies the signature is used for the release.
+/// If the signature check is disabled, the release is loaded without key.
+/// The InRelease file is returned with the release. (Release, InRelease)
+async fn load_release(transport: &Transport, distro: &Distro, options: &CheckOptions) -> Result<(Release, String)> {
+    if !options.check_signature {
+        warn!("Signature check is disabled, the InRelease signature will not get verified!");
+        let unsigned = Distro { key: Key::NoSignatureCheck, ..distro.clone() };
+        return release::load_with_content(transport, &unsigned).await;
+    }
+
+    let keys = &options.keys;
+    let mut result = release::load_with_content(transport, distro).await;
+
+    for key in keys.iter().filter(|key| **key != distro.key) {
+        match &result {
+            Ok(_) => break,
+            Err(e) => warn!("Loading release failed, trying next key: {e}"),
+        }
+
+        let candidate = Distro { key: key.clone(), ..distro.clone() };
+        result = release::load_with_content(transport, &candidate).await;
+    }
+
+    result
+}
+
 /// Lib entry point for apt repo checking.
-pub async fn check_repo(distro: &Distro, components: Vec<String>, architectures: Vec<String>, check_files: bool) -> Result<bool> {
+///
+/// On SIGINT or SIGTERM the running checks are cancelled
+/// and the findings collected so far are saved.
+/// The result is named by the InRelease URL.
+pub async fn check_repo(distro: &Distro, options: CheckOptions) -> Result<RepoResult> {
+    init_logging();
+
+    let previous = load_comparison(&options)?;
+    let previous_statuses = load_previous_statuses(&options)?;
+    let output = options.output.clone();
+    let repo = distro.in_release_url().unwrap_or_default();
+    let start = Instant::now();
+    let (status, check) = match run_check(distro, options.clone()).await {
+        Ok(result) => result,
+        Err(e) => {
+            // The error of the check is returned, an error of the metrics is only logged.
+            let _ = write_metrics(&options, &[RepoMetrics::new(&repo, CheckStatus::Failed, None, start.elapsed())]).await;
+            let _ = write_history(&options, &[RepoResult::new(repo.clone(), CheckStatus::Failed, Some(e.to_string()), Vec::new())]);
+            let summary = RepoSummary::new(&repo, CheckStatus::Failed, Some(e.to_string()), &[], previous_of(&previous_statuses, &repo));
+            notify(&options, vec![(summary, options.chats.clone())]).await;
+            return Err(e);
+        }
+    };
+    save_as_json(&check, &output)?;
+    write_baseline(&options, &baseline_entries(distro, check.findings()))?;
+    write_metrics(&options, &[RepoMetrics::new(&repo, status, Some(&check), start.elapsed())]).await?;
+    let result = RepoResult::new(repo, status, None, check.findings().to_vec());
+    write_history(&options, std::slice::from_ref(&result))?;
+    write_missing_files(&options, std::slice::from_ref(&result))?;
+    log_comparison(&options, previous, &[(result.name.clone(), result.findings.clone())]);
+    let summary = RepoSummary::new(&result.name, status, None, &result.findings, previous_of(&previous_statuses, &result.name));
+    notify(&options, vec![(summary, options.chats.clone())]).await;
+
+    Ok(result)
+}
+
+/// Lib entry point for checking all entries of a one-line sources.list file or a deb822 .sources file.
+///
+/// The entries are checked one after another, and the results are saved per entry.
+/// A failing entry doesn't stop the checks of the following entries, but SIGINT or SIGTERM does.
+pub async fn check_sources_list(path: &str, options: CheckOptions) -> Result<Vec<SourceResult>> {
+    init_logging();
+
+    let previous = load_comparison(&options)?;
+    let previous_statuses = load_previous_statuses(&options)?;
+
+    let content = match std::fs::read_to_string(path) {
+        Ok(content) => content,
+        Err(e) => {
+            let message = format!("Reading sources list {path} failed! {e}");
+            error!("{}", message);
+            return Err(Error::new(&message, libapt::ErrorType::ApiUsage));
+        }
+    };
+
+    let entries = if sources_list::is_deb822(&content) {
+        sources_list::parse_deb822(&content)
+    } else {
+        sources_list::parse(&content)
+    };
+
+    let entries = match entries {
+        Ok(entries) => entries,
+        Err(e) => {
+            let message = format!("Parsing sources list {path} failed! {e}");
+            error!("{}", message);
+            return Err(Error::new(&message, libapt::ErrorType::ApiUsage));
+        }
+    };
+
+    let mut results = Vec::new();
+    let mut known = Vec::new();
+    let mut metrics = Vec::new();
+    for (index, entry) in entries.into_iter().enumerate() {
+        info!("Checking entry in line {}: {}", entry.line, entry.text);
+
+        let mut entry_options = options.clone();
+        entry_options.components = entry.components.clone();
+        if !entry.architectures.is_empty() {
+            entry_options.architectures = entry.architectures.clone();
+        }
+
+        let mut keys = entry.keys();
+        if let Some(key) = &entry.inline_key {
+            // The embedded key is loaded from memory.
+            keys.insert(0, Key::armored_key(key));
+        }
+        let key = keys.first().cloned().unwrap_or(Key::NoSignatureCheck);
+        entry_options.keys.extend(keys.into_iter().skip(1));
+        entry_options.expected_fingerprints.extend(entry.fingerprints());
+        // Each entry has its own checkpoint.
+        entry_options.state_dir = options.state_dir.as_ref().map(|dir| format!("{dir}/entry-{}", index + 1));
+
+        let distro = entry.distro(key);
+        let (status, error, findings, entry_metrics) = run_entry(&distro, entry_options).await;
+        known.extend(baseline_entries(&distro, &findings));
+        metrics.push(entry_metrics);
+        let result = SourceResult {
+            line: entry.line,
+            entry: entry.text,
+            status,
+            error,
+            findings,
+        };
+
+        let aborted = result.status == CheckStatus::Aborted;
+        results.push(result);
+        if aborted {
+            break;
+        }
+    }
+
+    save_as_json(&results, &options.output)?;
+    write_baseline(&options, &known)?;
+    write_metrics(&options, &metrics).await?;
+    let history: Vec<RepoResult> = results
+        .iter()
+        .map(|result| RepoResult::new(result.entry.clone(), result.status, result.error.clone(), result.findings.clone()))
+        .collect();
+    write_history(&options, &history)?;
+    write_missing_files(&options, &history)?;
+    let repos: Vec<ReportRepo> = results.iter().map(|result| (result.entry.clone(), result.findings.clone())).collect();
+    log_comparison(&options, previous, &repos);
+    let summaries = results
+        .iter()
+        .map(|result| {
+            let summary = RepoSummary::new(&result.entry, result.status, result.error.clone(), &result.findings, previous_of(&previous_statuses, &result.entry));
+            (summary, options.chats.clone())
+        })
+        .collect();
+    notify(&options, summaries).await;
+
+    Ok(results)
+}
+
+/// Result of a repository of a config file. (Result, Baseline entries, Metrics, Chats)
+type ConfigRepoResult = (RepoResult, Vec<BaselineEntry>, RepoMetrics, Vec<ChatTarget>);
+
+/// Lib entry point for checking all repositories of a config file.
+///
+/// Up to `parallel` repositories of the config are checked at the same time,
+/// and the results are saved as one aggregated report with a section per repository.
+/// A failing repository doesn't stop the checks of the following repositories, but SIGINT or SIGTERM does.
+pub async fn check_config(config: &Config, options: CheckOptions) -> Result<BatchReport> {
+    init_logging();
+
+    let previous = load_comparison(&options)?;
+    let previous_statuses = load_previous_statuses(&options)?;
+    let parallel = config.parallel.unwrap_or(1).max(1);
+    let aborted = AtomicBool::new(false);
+
+    let results: Vec<Option<ConfigRepoResult>> = stream::iter(&config.repos)
+        .map(|repo| {
+            let options = &options;
+            let aborted = &aborted;
+            async move {
+                // Repositories which were not started before an abort are skipped.
+                if aborted.load(Ordering::SeqCst) {
+                    return None;
+                }
+
+                let name = repo.label();
+                info!("Checking repo {name}...");
+
+                let (status, error, findings, known, metrics, chats) = match (repo.distro(), repo.options(options)) {
+                    (Ok(distro), Ok(repo_options)) => {
+                        let chats = repo_options.chats.clone();
+                        let (status, error, findings, metrics) = run_entry(&distro, repo_options).await;
+                        let known = baseline_entries(&distro, &findings);
+                        (status, error, findings, known, metrics, chats)
+                    }
+                    (Err(e), _) | (_, Err(e)) => {
+                        let metrics = RepoMetrics::new(&name, CheckStatus::Failed, None, Duration::ZERO);
+                        (CheckStatus::Failed, Some(e.to_string()), Vec::new(), Vec::new(), metrics, options.chats.clone())
+                    }
+                };
+
+                if status == CheckStatus::Aborted {
+                    aborted.store(true, Ordering::SeqCst);
+                }
+                Some((RepoResult::new(name, status, error, findings), known, metrics, chats))
+            }
+        })
+        .buffered(parallel)
+        .collect()
+        .await;
+
+    let mut repos = Vec::new();
+    let mut known = Vec::new();
+    let mut metrics = Vec::new();
+    let mut chats = Vec::new();
+    for (repo, repo_known, repo_metrics, repo_chats) in results.into_iter().flatten() {
+        repos.push(repo);
+        known.extend(repo_known);
+        metrics.push(repo_metrics);
+        chats.push(repo_chats);
+    }
+    let report = BatchReport::new(repos);
+    save_as_json(&report, &options.output)?;
+    write_baseline(&options, &known)?;
+    write_metrics(&options, &metrics).await?;
+    write_history(&options, &report.repos)?;
+    write_missing_files(&options, &report.repos)?;
+    log_comparison(&options, previous, &report.findings());
+    notify(&options, report.summaries(&previous_statuses).into_iter().zip(chats).collect()).await;
+
+    Ok(report)
+}
+
+/// Lib entry point for checking several suites of one archive, e.g. jammy, jammy-updates and jammy-security.
+///
+/// Each suite is checked with the other suites as base repositories,
+/// so dependencies and sources can be resolved across the suites.
+/// The results are saved per suite.
+pub async fn check_suites(distro: &Distro, suites: &[String], options: CheckOptions) -> Result<BatchReport> {
+    init_logging();
+
+    let previous = load_comparison(&options)?;
+    let previous_statuses = load_previous_statuses(&options)?;
+    let mut results = Vec::new();
+    let mut known = Vec::new();
+    let mut metrics = Vec::new();
+    for suite in suites {
+        info!("Checking suite {suite}...");
+
+        let suite_distro = Distro { name: Some(suite.clone()), path: None, ..distro.clone() };
+        let mut suite_options = options.clone();
+        suite_options.base_repos.extend(suites.iter().filter(|other| *other != suite).map(|other| BaseRepo {
+            url: distro.url.clone(),
+            suite: Some(other.clone()),
+        }));
+        // Each suite has its own checkpoint.
+        suite_options.state_dir = options.state_dir.as_ref().map(|dir| format!("{dir}/{suite}"));
+
+        let (status, error, findings, suite_metrics) = run_entry(&suite_distro, suite_options).await;
+        known.extend(baseline_entries(&suite_distro, &findings));
+        metrics.push(suite_metrics);
+        let aborted = status == CheckStatus::Aborted;
+        results.push(RepoResult::new(suite.clone(), status, error, findings));
+        if aborted {
+            break;
+        }
+    }
+
+    let report = BatchReport::new(results);
+    save_as_json(&report, &options.output)?;
+    write_baseline(&options, &known)?;
+    write_metrics(&options, &metrics).await?;
+    write_history(&options, &report.repos)?;
+    write_missing_files(&options, &report.repos)?;
+    log_comparison(&options, previous, &report.findings());
+    let summaries = report.summaries(&previous_statuses).into_iter().map(|summary| (summary, options.chats.clone())).collect();
+    notify(&options, summaries).await;
+
+    Ok(report)
+}
+
+/// Lib entry point for watching repositories.
+///
+/// The repositories are checked again every interval until SIGINT or SIGTERM.
+/// A repository is only checked again if the ETag or Last-Modified of its InRelease file changed,
+/// so time based findings like an outdated release are not reported for unchanged files.
+/// The report is only saved if the status of a repository changed, the metrics after every round.
+/// If requested, the latest results are served by an HTTP server.
+/// Returns the report of the last results.
+pub async fn watch(repos: Vec<WatchedRepo>, options: CheckOptions, watch_options: WatchOptions) -> Result<BatchReport> {
+    init_logging();
+
+    let interval = watch_options.interval;
+    let transport = Transport::new(&options.http.for_repos(repos.iter().map(|repo| repo.distro.url.as_str())))?;
+    let mut states: Vec<RepoState> = repos.into_iter().map(RepoState::new).collect();
+    let mut aborted = false;
+
+    let latest = Arc::new(RwLock::new(Latest::default()));
+    // The server task ends with the runtime.
+    if let Some(address) = watch_options.listen {
+        let listener = server::bind(address).await?;
+        tokio::spawn(server::serve(listener, latest.clone()));
+    }
+
+    info!("Watching {} repos every {}...", states.len(), format_duration(interval.as_secs() as i64));
+    loop {
+        let mut changed = false;
+        let mut summaries = Vec::new();
+        let mut checked = Vec::new();
+        for state in states.iter_mut() {
+            let url = state.repo.distro.in_release_url()?;
+            let validator = match transport.validator(&url).await {
+                Ok(validator) => validator,
+                Err(e) => {
+                    debug!("Getting validator of {url} failed: {e}");
+                    None
+                }
+            };
+            if state.is_unchanged(&validator) {
+                debug!("InRelease of repo {} is unchanged, skipping check.", state.repo.name);
+                continue;
+            }
+
+            info!("Checking repo {}...", state.repo.name);
+            let (status, error, findings, metrics) = run_entry(&state.repo.distro, state.repo.options.clone()).await;
+            let known = baseline_entries(&state.repo.distro, &findings);
+            let result = RepoResult::new(state.repo.name.clone(), status, error, findings);
+            let previous = state.result.as_ref().map(|previous| (previous.status, previous.findings.as_slice()));
+            let summary = RepoSummary::new(&result.name, status, result.error.clone(), &result.findings, previous);
+            summaries.push((summary, state.repo.options.chats.clone()));
+            checked.push(result.clone());
+            changed |= state.update(validator, result, metrics, known);
+
+            if status == CheckStatus::Aborted {
+                aborted = true;
+                break;
+            }
+        }
+
+        let report = BatchReport::new(states.iter().filter_map(|state| state.result.clone()).collect());
+        if changed {
+            save_as_json(&report, &options.output)?;
+            let known: Vec<BaselineEntry> = states.iter().flat_map(|state| state.known.clone()).collect();
+            write_baseline(&options, &known)?;
+            write_missing_files(&options, &report.repos)?;
+        }
+        let metrics: Vec<RepoMetrics> = states.iter().filter_map(|state| state.metrics.clone()).collect();
+        write_metrics(&options, &metrics).await?;
+        // Only the checked repositories are recorded, unchanged repositories have no new results.
+        if !checked.is_empty() {
+            write_history(&options, &checked)?;
+        }
+        if watch_options.listen.is_some() {
+            // The results are replaced completely, so a panic of a reader doesn't leave them inconsistent.
+            let mut latest = latest.write().unwrap_or_else(|e| e.into_inner());
+            latest.report = serde_json::to_string_pretty(&report).ok();
+            latest.metrics = metrics::render(&metrics);
+        }
+        notify(&options, summaries).await;
+
+        if aborted {
+            return Ok(report);
+        }
+
+        tokio::select! {
+            _ = tokio::time::sleep(interval) => {},
+            _ = shutdown_signal() => {
+                info!("Stopped watching.");
+                return Ok(report);
+            }
+        }
+    }
+}
+
+/// Get the baseline entries of the findings of the distro.
+fn baseline_entries(distro: &Distro, findings: &[Finding]) -> Vec<BaselineEntry> {
+    baseline::entries(&distro.in_release_url().unwrap_or_default(), findings)
+}
+
+/// Write the findings to the baseline file, if requested.
+fn write_baseline(options: &CheckOptions, entries: &[BaselineEntry]) -> Result<()> {
+    if let (Some(path), true) = (&options.baseline, options.write_baseline) {
+        save_as_json(&entries, path)?;
+        info!("Wrote {} findings to baseline {path}.", entries.len());
+    }
+    Ok(())
+}
+
+/// Load the report to compare with, if requested.
+///
+/// The report is loaded before the check, so it may be the file the result is saved to.
+fn load_comparison(options: &CheckOptions) -> Result<Option<Vec<ReportRepo>>> {
+    options.compare_with.as_deref().map(diff::load).transpose()
+}
+
+/// Load the status of the repositories in the previous run, so status changes are notified like in watch mode.
+///
+/// The history has precedence over the report to compare with. Both are read before the results of this run are written.
+fn load_previous_statuses(options: &CheckOptions) -> Result<HashMap<String, CheckStatus>> {
+    let mut statuses = HashMap::new();
+    if let Some(path) = &options.compare_with {
+        statuses.extend(diff::statuses(path)?);
+    }
+    if let Some(path) = &options.history {
+        statuses.extend(history::last_statuses(path)?);
+    }
+    Ok(statuses)
+}
+
+/// Get the previous result of a repository for its summary.
+///
+/// The findings of the previous one-shot run are already marked as known by --compare-with, so only the status is given.
+fn previous_of<'a>(statuses: &HashMap<String, CheckStatus>, name: &str) -> Option<(CheckStatus, &'a [Finding])> {
+    statuses.get(name).map(|status| (*status, &[][..]))
+}
+
+/// Log the new and fixed findings compared with the loaded report.
+fn log_comparison(options: &CheckOptions, previous: Option<Vec<ReportRepo>>, repos: &[ReportRepo]) {
+    let (Some(path), Some(previous)) = (&options.compare_with, previous) else {
+        return;
+    };
+
+    let diff = ReportDiff::new(&previous, repos);
+    for entry in &diff.new {
+        info!("New finding of {}: {}", entry.repo, entry.finding);
+    }
+    for entry in &diff.fixed {
+        info!("Fixed finding of {}: {}", entry.repo, entry.finding);
+    }
+    info!("Compared with {path}: {} new, {} fixed and {} persisting findings.", diff.new.len(), diff.fixed.len(), diff.persisting.len());
+}
+
+/// Lib entry point for comparing two reports.
+///
+/// The reports may be results of any check, but both should be of the same kind,
+/// since the repositories are matched by name.
+pub fn diff_reports(old: &str, new: &str) -> Result<ReportDiff> {
+    init_logging();
+
+    let diff = ReportDiff::new(&diff::load(old)?, &diff::load(new)?);
+    info!("Found {} new, {} fixed and {} persisting findings.", diff.new.len(), diff.fixed.len(), diff.persisting.len());
+    Ok(diff)
+}
+
+/// Write the URLs or the paths of the missing and corrupt files, if requested.
+///
+/// Paths are relative to the repository root, i.e. they start with pool/ or dists/. Files outside are written as URL.
+fn write_missing_files(options: &CheckOptions, repos: &[RepoResult]) -> Result<()> {
+    let Some(path) = &options.missing_files_out else {
+        return Ok(());
+    };
+
+    let files: BTreeSet<&str> = repos
+        .iter()
+        .flat_map(|repo| &repo.findings)
+        .filter(|finding| finding.kind.is_broken_file())
+        .filter_map(|finding| finding.url.as_deref())
+        .map(|url| match url.find("/pool/").or_else(|| url.find("/dists/")) {
+            Some(start) if options.missing_files_relative => &url[start + 1..],
+            _ => url,
+        })
+        .collect();
+
+    let content: String = files.iter().map(|file| format!("{file}\n")).collect();
+    if let Err(e) = std::fs::write(path, content) {
+        let message = format!("Writing missing files {path} failed! {e}");
+        error!("{}", message);
+        return Err(Error::new(&message, libapt::ErrorType::ApiUsage));
+    }
+
+    info!("Wrote {} missing or corrupt files to {path}.", files.len());
+    Ok(())
+}
+
+/// Record the run in the history database, if requested.
+fn write_history(options: &CheckOptions, repos: &[RepoResult]) -> Result<()> {
+    match &options.history {
+        Some(path) => history::record(path, repos),
+        None => Ok(()),
+    }
+}
+
+/// Write the metrics to the metrics file and push them to the Pushgateway, if requested.
+async fn write_metrics(options: &CheckOptions, repos: &[RepoMetrics]) -> Result<()> {
+    if options.metrics_file.is_none() && options.pushgateway.is_none() {
+        return Ok(());
+    }
+
+    let content = metrics::render(repos);
+    if let Some(path) = &options.metrics_file {
+        metrics::write_file(path, &content)?;
+    }
+    if let Some(gateway) = &options.pushgateway {
+        let transport = Transport::new(&options.http.without_auth())?;
+        metrics::push(&transport, gateway, &content).await?;
+    }
+    Ok(())
+}
+
+/// Notify the webhooks, the mail recipients and the chats about the checked repositories, if requested.
+///
+/// The chats of a repository, i.e. the global chats and its own, are only notified about this repository. (Summary, Chats)
+async fn notify(options: &CheckOptions, repos: Vec<(RepoSummary, Vec<ChatTarget>)>) {
+    if options.webhooks.is_empty() && options.email.is_none() && repos.iter().all(|(_, chats)| chats.is_empty()) {
+        return;
+    }
+
+    let transport = match Transport::new(&options.http.without_auth()) {
+        Ok(transport) => transport,
+        Err(e) => {
+            error!("Sending notification failed! {e}");
+            return;
+        }
+    };
+
+    let mut chats: Vec<(ChatTarget, Vec<RepoSummary>)> = Vec::new();
+    for (summary, repo_chats) in &repos {
+        for chat in repo_chats {
+            match chats.iter_mut().find(|(target, _)| target == chat) {
+                Some((_, summaries)) => summaries.push(summary.clone()),
+                None => chats.push((chat.clone(), vec![summary.clone()])),
+            }
+        }
+    }
+    let chats: Vec<(ChatTarget, Notification)> = chats.into_iter().map(|(chat, summaries)| (chat, Notification::new(summaries))).collect();
+    notify::send_chats(&transport, &chats, options.report_url.as_deref()).await;
+
+    let summaries = repos.into_iter().map(|(summary, _)| summary).collect();
+    notify::send(&transport, options, &Notification::new(summaries)).await;
+}
+
+/// Check a repo of a batch, turning an error into a failed result.
+async fn run_entry(distro: &Distro, options: CheckOptions) -> (CheckStatus, Option<String>, Vec<Finding>, RepoMetrics) {
+    let repo = distro.in_release_url().unwrap_or_default();
+    let start = Instant::now();
+    match run_check(distro, options).await {
+        Ok((status, check)) => (status, None, check.findings().to_vec(), RepoMetrics::new(&repo, status, Some(&check), start.elapsed())),
+        Err(e) => {
+            error!("Checking repo {} failed: {e}", distro.url);
+            (CheckStatus::Failed, Some(e.to_string()), Vec::new(), RepoMetrics::new(&repo, CheckStatus::Failed, None, start.elapsed()))
+        }
+    }
+}
+
+/// Check a repo, continuing from a checkpoint if requested.
+async fn run_check(distro: &Distro, options: CheckOptions) -> Result<(CheckStatus, AptCheck)> {
+    log_distro(distro);
+
+    let mut options = options;
+    options.http = options.http.for_repos([distro.url.as_str()]);
+    let transport = Arc::new(Transport::new(&options.http)?);
+
+    debug!("Parsing InRelease file...");
+    let start = Instant::now();
+    let (release, in_release) = load_release(&transport, distro, &options).await?;
+    let release_duration = start.elapsed();
+
+    let state_dir = options.state_dir.clone();
+
+    let checkpoint = match (&state_dir, options.resume) {
+        (Some(state_dir), true) => checkpoint::load(state_dir)?,
+        (None, true) => {
+            warn!("Resume requested, but no state dir given. Starting a new check.");
+            None
+        }
+        _ => None,
+    };
+
+    let mut check = match checkpoint {
+        Some(mut check) if check.is_resumable_for(&release) => {
+            info!("Resuming check from last checkpoint.");
+            check.set_transport(transport);
+            check.set_options(options);
+            check
+        }
+        Some(_) => {
+            warn!("Checkpoint belongs to a different release. Starting a new check.");
+            AptCheck::new(release, options, transport)?
+        }
+        None => {
+            debug!("Checking indices for components {:?} and architectures {:?}...", options.components, options.architectures);
+            AptCheck::new(release, options, transport)?
+        }
+    };
+
+    check.set_in_release(in_release);
+    check.add_timing(Timing::new(Phase::Release, release_duration));
+    let result = tokio::select! {
+        result = check.check_repo() => Some(result?),
+        _ = shutdown_signal() => None,
+    };
+
+    let status = match result {
+        Some(true) => CheckStatus::Passed,
+        Some(false) => CheckStatus::Failed,
+        None => {
+            warn!("Check was aborted, saving partial result.");
+            check.abort();
+            CheckStatus::Aborted
+        }
+    };
+
+    // A partial check keeps the checkpoint, so it can be resumed.
+    if let Some(state_dir) = &state_dir {
+        if !check.is_partial() {
+            checkpoint::remove(state_dir);
+        }
+    }
+
+    Ok((status, check))
+}
+
+/// Lib entry point for mirror benchmarks.
+pub async fn bench_repo(distro: &Distro, options: BenchOptions) -> Result<BenchReport> {
     init_logging();
     log_distro(distro);
 
+    let transport = Transport::new(&options.http.for_repos([distro.url.as_str()]))?;
+
     debug!("Parsing InRelease file...");
-    let release = Release::from_distro(distro).await?;
+    let release = release::load(&transport, distro).await?;
 
-    debug!("Checking indices for components {:?} and architectures {:?}...", components, architectures);
-    let mut check = AptCheck::new(release, components, architectures, check_files)?;
+    let report = bench::run(&transport, &release, &options).await?;
 
-    let result = check.check_repo().await?;
+    save_as_json(&report, "bench.json")?;
 
-    save_as_json(check, "result.json")?;
+    Ok(report)
+}
 
-    Ok(result)
+/// Lib entry point for comparing two mirrors of a repository.
+///
+/// The first distro is compared with the second distro, which should be the same suite on another mirror.
+pub async fn compare_mirrors(a: &Distro, b: &Distro, options: CompareOptions) -> Result<MirrorComparison> {
+    init_logging();
+    log_distro(a);
+    log_distro(b);
+
+    let transport = Transport::new(&options.http.for_repos([a.url.as_str(), b.url.as_str()]))?;
+
+    debug!("Parsing InRelease files...");
+    let release_a = release::load(&transport, a).await?;
+    let release_b = release::load(&transport, b).await?;
+
+    let comparison = compare::run(&transport, &release_a, &release_b, &options).await?;
+
+    save_as_json(&comparison, "compare.json")?;
+
+    Ok(comparison)
+}
+
+/// Lib entry point for the statistics of a repository.
+pub async fn repo_stats(distro: &Distro, options: StatsOptions) -> Result<RepoStats> {
+    init_logging();
+    log_distro(distro);
+
+    let transport = Transport::new(&options.http.for_repos([distro.url.as_str()]))?;
+
+    debug!("Parsing InRelease file...");
+    let release = release::load(&transport, distro).await?;
+
+    let stats = stats::run(&transport, &release, &options).await?;
+
+    save_as_json(&stats, "stats.json")?;
+
+    Ok(stats)
 }
@@ -1,58 +1,278 @@
+use std::env;
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::net::SocketAddr;
 use std::process::exit;
 
-use clap::{arg, command};
+use clap::{arg, command, Arg, ArgMatches, Command};
 use libapt::{Distro, Key};
-use aptcheckr::check_repo;
+use aptcheckr::{apt_auth_entries, auth_file_entries, bench_repo, BaseRepo, BatchReport, check_config, check_repo, check_sources_list, check_suites, compare_mirrors, diff_reports, format_bytes, keyring_keys, parse_duration, repo_stats, snapshot_url, watch, BenchOptions, CheckId, CheckOptions, CheckStatus, CompareOptions, Config, DEBIAN_SNAPSHOT, Finding, FindingKind, Http2Mode, HttpOptions, ProbeMethod, Profile, Resolve, Sample, Severity, StatsOptions, WatchOptions, WatchedRepo};
 
-/// App entry point - processing of CLI parameters.
-#[tokio::main]
-async fn main() {
-    let matches = command!()
-        .arg(arg!([url] "URL of the APT repository. Defaults to Ubuntu apt repo.").required(false))
-        .arg(arg!(-d --distro <DISTRO> "Name of the distribution. Defaults to jammy.").required(false))
-        .arg(arg!(-p --path <PATH> "Path for flat repos. Use './' for root folder.").required(false))
-        .arg(arg!(-k --key <KEY> "Signing key of the InRelease file.").required(false))
-        .arg(arg!(-r --rawkey "Key is a binary key, i.e. not armored.").required(false))
-        .arg(arg!(-c --component <COMPONENT> ... "Component to check.").required(false))
-        .arg(arg!(-a --arch <ARCHITECTURE> ... "Architecture to check.").required(false))
-        .arg(arg!(-f --files "Check existence of referenced files.").required(false))
-        .get_matches();
+/// CLI parameters describing the repository location.
+fn repo_args() -> Vec<Arg> {
+    vec![
+        arg!([url] "URL of the APT repository, file:///path or a directory for a local repository, or s3://bucket/prefix for an S3 bucket. Defaults to Ubuntu apt repo.").required(false),
+        arg!(-d --distro <DISTRO>... "Name of the distribution. Defaults to jammy. Repeat to check several suites, which resolve dependencies from each other.").required(false),
+        arg!(-p --path <PATH> "Path for flat repos. Use './' for root folder.").required(false),
+        arg!(-k --key <KEY>... "Signing key of the InRelease file. Can be given multiple times, any key may verify the signature.").required(false),
+        arg!(-r --rawkey "Key is a binary key, i.e. not armored.").required(false),
+        arg!(--"keyring-dir" <PATH> "Directory of signing keys, e.g. /etc/apt/trusted.gpg.d. Loads all .gpg and .asc files.").required(false),
+    ]
+}
+
+/// CLI parameters for the HTTP client.
+fn http_args() -> Vec<Arg> {
+    vec![
+        arg!(--"max-connections" <COUNT> "Maximum parallel connections per host. Defaults to 8.").required(false).value_parser(clap::value_parser!(usize)),
+        arg!(--http2 <MODE> "Usage of HTTP/2: auto, on or off. Defaults to auto.").required(false).value_parser(["auto", "on", "off"]),
+        arg!(--"keep-alive" <SECONDS> "Keep-alive timeout of idle connections.").required(false).value_parser(clap::value_parser!(u64)),
+        arg!(--"probe-method" <METHOD> "Request for file checks: head, range or get. Defaults to head.").required(false).value_parser(["head", "range", "get"]),
+        arg!(--timeout <SECONDS> "Timeout of a single request. Defaults to no timeout.").required(false).value_parser(clap::value_parser!(u64)),
+        arg!(--retries <COUNT> "Retries of failed requests. Defaults to 0.").required(false).value_parser(clap::value_parser!(u32)),
+        arg!(--"http-user" <USER> "User for HTTP basic authentication of the requests to the checked repository. Defaults to env var APTCHECKR_HTTP_USER.").required(false),
+        arg!(--"http-password" <PASSWORD> "Password for HTTP basic authentication. Defaults to env var APTCHECKR_HTTP_PASSWORD.").required(false),
+        arg!(--"auth-token" <TOKEN> "Token for bearer authentication of the requests to the checked repository, e.g. for hosted repo services. Defaults to env var APTCHECKR_AUTH_TOKEN.").required(false),
+        arg!(--"auth-token-parameter" <NAME> "Send the token as query parameter NAME instead of the Authorization header, e.g. token.").required(false),
+        arg!(--proxy <URL> "Proxy for all requests, e.g. http://proxy:3128. Defaults to the http_proxy and https_proxy env vars, no_proxy is honored.").required(false),
+        arg!(--"ca-cert" <FILE> "PEM file of additional trusted CA certificates, e.g. of an internal CA.").required(false),
+        arg!(--"insecure-skip-verify" "Don't verify TLS server certificates. Only for testing, the results can't be trusted.").required(false),
+        arg!(--"tls-cert" <FILE> "PEM file of the client TLS certificate for repos requiring mutual TLS. Requires --tls-key.").required(false).requires("tls-key"),
+        arg!(--"tls-key" <FILE> "PEM file of the PKCS#8 key of the client TLS certificate. Requires --tls-cert.").required(false).requires("tls-cert"),
+        arg!(--"auth-file" <FILE> ... "apt auth.conf or netrc file with machine logins. Defaults to the apt files /etc/apt/auth.conf and /etc/apt/auth.conf.d/*.").required(false),
+        arg!(--config <FILE> "TOML config file, e.g. with repositories, per-host network settings or signing keys.").required(false),
+    ]
+}
 
+/// CLI parameters selecting and configuring the checks.
+fn check_args() -> Vec<Arg> {
+    vec![
+        arg!(-c --component <COMPONENT> ... "Component to check.").required(false),
+        arg!(-a --arch <ARCHITECTURE> ... "Architecture to check.").required(false),
+        arg!(--"skip-component" <COMPONENT> ... "Component not to check, e.g. main/debug. Repeatable.").required(false),
+        arg!(--"skip-arch" <ARCHITECTURE> ... "Architecture not to check, e.g. riscv64. Repeatable.").required(false),
+        arg!(--"binaries-only" "Check only the binary indices and skip the sources, e.g. for repos without sources.").required(false).conflicts_with("sources-only"),
+        arg!(--"sources-only" "Check only the source indices and skip the binary indices.").required(false),
+        arg!(-f --files "Check existence of referenced files and the checksum lists of .dsc files.").required(false),
+        arg!(--resolve <LEVEL> "Weakest resolved relation: depends, recommends or suggests. Defaults to depends.").required(false).value_parser(["depends", "recommends", "suggests"]),
+        arg!(--"base-repo" <URL> ... "Repository used read-only to resolve dependencies and sources, as url[,suite]. Repeatable.").required(false),
+        arg!(--"note-cross-component" "Report dependencies and sources resolved from other components or base repositories as info findings.").required(false),
+        arg!(--"check-build-depends" "Resolve the build dependencies of the sources against the binary indices.").required(false),
+        arg!(--"check-conflicts" "Analyze Breaks, Conflicts and Replaces of binary packages.").required(false),
+        arg!(--"check-control" "Compare the control files of the deb files with the index stanzas. Uses the file sampling.").required(false),
+        arg!(--"check-deb-structure" "Download the deb files up to 256 MiB and validate their ar layout and tarballs. Implies --files.").required(false),
+        arg!(--"check-essential" "Check that the Essential and required packages form a complete, dependency closed set.").required(false),
+        arg!(--"check-source-binaries" "Check that the binaries listed by the sources are published for the checked architectures.").required(false),
+        arg!(--"check-fields" "Validate the Section, Priority, Maintainer, Uploaders, Size and Installed-Size fields of the stanzas.").required(false),
+        arg!(--"check-priority-coverage" "Check that required and important packages are available on all architectures.").required(false),
+        arg!(--"maintainer-summary" "Summarize the findings per maintainer in the result.").required(false),
+        arg!(--"check-pool-layout" "Check that Filename and Directory fields follow the pool/<component>/<prefix>/<source>/ layout.").required(false),
+        arg!(--"check-orphans" "Walk the pool of a local repository and report files not referenced by any index. Suites given as base repositories of the same URL are included.").required(false),
+        arg!(--"check-permissions" "Check that all files and directories of a local repository are readable, also by other users.").required(false),
+        arg!(--local "Validate a local repository before uploading it: verify the hashes of all files, and report unreadable files, restrictive permissions and orphaned pool files.").required(false),
+        arg!(--"local-root" <DIR> "Local directory of the repository for --check-orphans, e.g. a mount of the served directory. Defaults to the path of file:// URLs.").required(false),
+        arg!(--"check-cves" "Report sources below the fixed version of a CVE, using the security tracker data. Requires the sources.").required(false),
+        arg!(--"cve-tracker" <URL> "URL or file of the security tracker data, the Debian tracker JSON or the Ubuntu CVE JSON. Defaults to the Debian tracker. Implies --check-cves.").required(false),
+        arg!(--"cve-release" <CODENAME> "Release of the security tracker data, e.g. bookworm. Defaults to the codename of the release without -security or -updates.").required(false),
+        arg!(--oval <URL> "URL or file of an OVAL feed of the release, e.g. oval-definitions-bookworm.xml.bz2. The unpatched definitions are reported.").required(false),
+        arg!(--"check-pdiffs" "Verify the pdiffs of the binary indices.").required(false),
+        arg!(--"apply-pdiffs" "Apply the pdiffs to old index versions served by-hash. Only supported if the uncompressed index is published. Implies --check-pdiffs.").required(false),
+        arg!(--"check-udeb" "Check the debian-installer indices and the referenced udeb files.").required(false),
+        arg!(--"check-contents" "Cross-reference the Contents indices with the binary indices.").required(false),
+        arg!(--"check-commands" "Cross-reference the command-not-found metadata with the binary indices.").required(false),
+        arg!(--"check-translations" "Verify the Translation indices and i18n/Index files of the components.").required(false),
+        arg!(--"require-translation" <LANGUAGE> ... "Language which must have a Translation index, e.g. en. Implies --check-translations.").required(false),
+        arg!(--"check-dep11" "Verify the DEP-11 icon tarballs against the Release file and the Components YAML.").required(false),
+        arg!(--"verify-hashes" "Download referenced files and verify their SHA256 hashes. Implies --files.").required(false),
+        arg!(--package <PATTERN> ... "Check dependencies, sources and files only of matching packages. Glob like libfoo* or /regex/. Repeatable.").required(false),
+        arg!(--"enable-check" <CHECK> ... "Enable a check, e.g. signature, index-hashes, dependencies, sources, files or compliance. Repeatable.").required(false).value_parser(CheckId::NAMES),
+        arg!(--"disable-check" <CHECK> ... "Disable a check. Takes the same checks as --enable-check and is applied after it. Repeatable.").required(false).value_parser(CheckId::NAMES),
+        arg!(--profile <PROFILE> "Set of checks: quick, standard or deep. Defaults to standard.").required(false).value_parser(["quick", "standard", "deep"]),
+        arg!(--"state-dir" <DIR> "Directory for checkpoints, which are only written if a dir is given or --resume is used. Defaults to .aptcheckr.").required(false),
+        arg!(--resume "Continue an interrupted check from the last checkpoint.").required(false),
+        arg!(--"no-progress" "Don't report the progress of long-running phases.").required(false),
+        arg!(--"retain-indices" "Keep the parsed indices and add them to the result.").required(false),
+        arg!(--"max-duration" <DURATION> "Time budget of the check, e.g. 30m. The result is partial if exceeded.").required(false),
+        arg!(--"min-validity" <DURATION> "Required remaining validity of the release, e.g. 3d.").required(false),
+        arg!(--"max-age" <DURATION> "Maximum age of the release Date, e.g. 1d.").required(false),
+        arg!(--upstream <URL> "Base URL of the upstream archive the repository mirrors. The lag behind it is reported.").required(false),
+        arg!(--"max-lag" <DURATION> "Maximum lag of the release Date behind the upstream archive, e.g. 6h. Requires --upstream.").required(false).requires("upstream"),
+        arg!(--"expect-fingerprint" <FPR>... "Fingerprint of a key allowed to sign the InRelease file. Can be given multiple times.").required(false),
+        arg!(--"expect-not-automatic" "Expect NotAutomatic: yes in the Release file, e.g. for backports suites.").required(false),
+        arg!(--"expect-but-automatic-upgrades" "Expect ButAutomaticUpgrades: yes in the Release file. Implies --expect-not-automatic.").required(false),
+        arg!(--"key-min-validity" <DURATION> "Required remaining validity of the signing key, e.g. 30d.").required(false),
+        arg!(--"validity-severity" <SEVERITY> "Severity of Valid-Until findings: info, warning or error.").required(false).value_parser(["info", "warning", "error"]),
+        arg!(--"weak-hash-severity" <SEVERITY> "Severity of the findings of Release files and stanzas without SHA256 hashes: info, warning or error.").required(false).value_parser(["info", "warning", "error"]),
+        arg!(--sample <PERCENT> "Probe only a random percentage of the files, e.g. 5%.").required(false).conflicts_with("sample-count"),
+        arg!(--"sample-count" <COUNT> "Probe only a random number of files per component and architecture.").required(false).value_parser(clap::value_parser!(usize)),
+        arg!(--seed <SEED> "Seed of the file sampling. Defaults to a random seed.").required(false).value_parser(clap::value_parser!(u64)),
+        arg!(--"fail-on" <SEVERITY> "Lowest severity of the findings which fail the check: info, warning or error. Defaults to warning, so informational findings are only reported.").required(false).value_parser(["info", "warning", "error"]),
+        arg!(--baseline <FILE> "JSON file of known findings. Known findings are reported, but don't fail the check.").required(false),
+        arg!(--"write-baseline" "Write all findings of this run to the baseline file. Requires --baseline.").required(false).requires("baseline"),
+        arg!(--"compare-with" <REPORT> "Report of an earlier run. Its findings don't fail the check, and new and fixed findings are logged.").required(false),
+        arg!(--"metrics-file" <FILE> "Write Prometheus metrics to the file, e.g. for the textfile collector of the node exporter.").required(false),
+        arg!(--pushgateway <URL> "Push Prometheus metrics to the Pushgateway at the URL.").required(false),
+        arg!(--"missing-files-out" <FILE> "Write the URL of every missing or corrupt file to the file, one per line, e.g. for a follow-up rsync job.").required(false),
+        arg!(--"missing-files-relative" "Write paths relative to the repository root, e.g. pool/main/h/hello/hello_1.0-1_amd64.deb, instead of URLs.").required(false).requires("missing-files-out"),
+        arg!(--history <FILE> "Record the run and its findings in the SQLite database, e.g. history.sqlite.").required(false),
+        arg!(--"max-phasing-age" <DURATION> "Maximum time a phased update may stay at its Phased-Update-Percentage, e.g. 3d. Requires --history.").required(false).requires("history"),
+        arg!(--webhook <URL> ... "Post a JSON summary to the URL if new findings are found or the status changes. Repeatable.").required(false),
+        arg!(--"webhook-secret" <SECRET> "Sign the webhook requests with HMAC-SHA256. Defaults to env var APTCHECKR_WEBHOOK_SECRET.").required(false),
+        arg!(--"webhook-retries" <COUNT> "Retries of failed webhook requests. Defaults to 3.").required(false).value_parser(clap::value_parser!(u32)),
+        arg!(--"report-url" <URL> "URL of the full report, linked in the chat notifications.").required(false),
+        arg!(--format <FORMAT> "Format of the summary on stdout: text, nagios for a plugin status line with perfdata and exit codes, or gha for GitHub Actions annotations and a step summary. Defaults to text.").required(false).value_parser(["text", "nagios", "gha"]),
+    ]
+}
+
+/// Get the repository location from the CLI parameters.
+fn distro_from_matches(matches: &ArgMatches) -> Distro {
     let url = match matches.get_one::<String>("url"){
         Some(name) => name.to_string(),
         None => "http://archive.ubuntu.com/ubuntu".to_string(),
     };
-
-    let distro = match matches.get_one::<String>("distro"){
-        Some(name) => Some(name.to_string()),
-        None => None,
+    // A directory is read directly, like a file:// URL.
+    let url = match fs::canonicalize(&url) {
+        Ok(path) if !url.contains("://") && path.is_dir() => format!("file://{}", path.display()),
+        _ => url,
     };
 
-    let path = match matches.get_one::<String>("path"){
-        Some(name) => Some(name.to_string()),
-        None => None,
-    };
+    let distro = matches.get_one::<String>("distro").map(|name| name.to_string());
 
-    let key = match matches.get_one::<String>("key"){
-        Some(name) => Some(name.to_string()),
-        None => None,
-    };
+    let path = matches.get_one::<String>("path").map(|name| name.to_string());
 
-    let distro = if distro == None && path == None {
+    let distro = if distro.is_none() && path.is_none() {
         Some("jammy".to_string())
     } else {
         distro
     };
 
-    let key = match key {
-        Some(key) => if matches.get_flag("raw-key") {
-            Key::key(&key)
-        } else {
-            Key::armored_key(&key)
-        },
-        None => Key::NoSignatureCheck,
+    let key = keys_from_matches(matches).into_iter().next().unwrap_or(Key::NoSignatureCheck);
+
+    Distro {
+        url,
+        name: distro,
+        path,
+        key,
+    }
+}
+
+/// Get the signing keys from the CLI parameters, followed by the keys of the config file and the keyring directory.
+fn keys_from_matches(matches: &ArgMatches) -> Vec<Key> {
+    let mut keys: Vec<Key> = match matches.get_many::<String>("key") {
+        Some(keys) => keys
+            .map(|key| if matches.get_flag("rawkey") { Key::key(key) } else { Key::armored_key(key) })
+            .collect(),
+        None => Vec::new(),
+    };
+
+    if let Some(config) = config_from_matches(matches) {
+        keys.extend(config.keys());
+    }
+
+    if let Some(path) = matches.get_one::<String>("keyring-dir") {
+        match keyring_keys(path) {
+            Ok(keyring) if keyring.is_empty() => {
+                println!("Keyring directory {path} contains no keys!");
+                exit(2);
+            }
+            Ok(keyring) => keys.extend(keyring),
+            Err(e) => {
+                println!("Loading keyring directory failed with error: {e}!");
+                exit(2);
+            }
+        }
+    }
+    keys
+}
+
+/// Format of the summary printed on stdout.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum OutputFormat {
+    Text,
+    // Status line and exit code of the Nagios plugin API, also used by Icinga.
+    Nagios,
+    // Text summary with GitHub Actions annotations of the new findings and a step summary.
+    Gha,
+}
+
+/// Get the format of the summary from the CLI parameters.
+fn format_from_matches(matches: &ArgMatches) -> OutputFormat {
+    match matches.get_one::<String>("format").map(|format| format.as_str()) {
+        Some("nagios") => OutputFormat::Nagios,
+        Some("gha") => OutputFormat::Gha,
+        _ => OutputFormat::Text,
+    }
+}
+
+/// Load the config file given by the CLI parameters.
+fn config_from_matches(matches: &ArgMatches) -> Option<Config> {
+    let path = matches.get_one::<String>("config")?;
+    match Config::load(path) {
+        Ok(config) => Some(config),
+        Err(e) => {
+            println!("Loading config failed with error: {e}!");
+            exit(2);
+        }
+    }
+}
+
+/// Get the HTTP client settings from the CLI parameters.
+fn http_from_matches(matches: &ArgMatches) -> HttpOptions {
+    let mut http = HttpOptions::default();
+    if let Some(max_connections) = matches.get_one::<usize>("max-connections") {
+        http.max_connections_per_host = *max_connections;
+    }
+    if let Some(mode) = matches.get_one::<String>("http2") {
+        http.http2 = Http2Mode::parse(mode).unwrap_or_default();
+    }
+    http.keep_alive = matches.get_one::<u64>("keep-alive").copied();
+    if let Some(method) = matches.get_one::<String>("probe-method") {
+        http.probe_method = ProbeMethod::parse(method).unwrap_or_default();
+    }
+    http.timeout = matches.get_one::<u64>("timeout").copied();
+    if let Some(retries) = matches.get_one::<u32>("retries") {
+        http.retries = *retries;
+    }
+    http.username = matches.get_one::<String>("http-user").cloned().or_else(|| env::var("APTCHECKR_HTTP_USER").ok());
+    http.password = matches.get_one::<String>("http-password").cloned().or_else(|| env::var("APTCHECKR_HTTP_PASSWORD").ok());
+    http.proxy = matches.get_one::<String>("proxy").cloned();
+    http.ca_cert = matches.get_one::<String>("ca-cert").cloned();
+    http.insecure = matches.get_flag("insecure-skip-verify");
+    http.tls_cert = matches.get_one::<String>("tls-cert").cloned();
+    http.tls_key = matches.get_one::<String>("tls-key").cloned();
+    http.token = matches.get_one::<String>("auth-token").cloned().or_else(|| env::var("APTCHECKR_AUTH_TOKEN").ok());
+    http.token_parameter = matches.get_one::<String>("auth-token-parameter").cloned();
+    http.auth_entries = match matches.get_many::<String>("auth-file") {
+        Some(paths) => paths
+            .flat_map(|path| match auth_file_entries(path) {
+                Ok(entries) => entries,
+                Err(e) => {
+                    println!("Loading auth file failed with error: {e}!");
+                    exit(2);
+                }
+            })
+            .collect(),
+        None => apt_auth_entries(),
     };
+    if let Some(config) = config_from_matches(matches) {
+        config.apply_http(&mut http);
+    }
+    http
+}
+
+/// Get a duration parameter in seconds.
+fn duration_from_matches(matches: &ArgMatches, name: &str) -> Option<u64> {
+    let duration = matches.get_one::<String>(name)?;
+    match parse_duration(duration) {
+        Some(duration) => Some(duration.as_secs()),
+        None => {
+            println!("Invalid duration {duration}!");
+            exit(2);
+        }
+    }
+}
 
+/// Get the check options from the CLI parameters.
+fn options_from_matches(matches: &ArgMatches) -> CheckOptions {
     let components: Vec<String> = match matches.get_many("component") {
         Some(comps) => {
             comps.map(|c: &String| c.to_string()).collect()
@@ -67,28 +287,831 @@ async fn main() {
         None => Vec::new(),
     };
 
-    let d = Distro {
-        url: url,
-        name: distro,
-        path: path,
-        key: key,
+    // Checkpoints are only written if requested, by a state dir or by resuming.
+    let state_dir = match matches.get_one::<String>("state-dir") {
+        Some(dir) => Some(dir.to_string()),
+        None if matches.get_flag("resume") => Some(".aptcheckr".to_string()),
+        None => None,
     };
 
-    let check_files = matches.get_flag("files");
+    let max_duration = duration_from_matches(matches, "max-duration");
+    let min_validity = duration_from_matches(matches, "min-validity");
+    let key_min_validity = duration_from_matches(matches, "key-min-validity");
+    let max_age = duration_from_matches(matches, "max-age");
+    let max_lag = duration_from_matches(matches, "max-lag");
+    let max_phasing_age = duration_from_matches(matches, "max-phasing-age");
+
+    let sample = if let Some(percent) = matches.get_one::<String>("sample") {
+        match Sample::parse(percent) {
+            Some(sample) => sample,
+            None => {
+                println!("Invalid sample percentage {percent}!");
+                exit(2);
+            }
+        }
+    } else if let Some(count) = matches.get_one::<usize>("sample-count") {
+        Sample::Count(*count)
+    } else {
+        Sample::All
+    };
+
+    let mut options = CheckOptions {
+        components,
+        architectures,
+        skip_components: matches.get_many::<String>("skip-component").map(|components| components.cloned().collect()).unwrap_or_default(),
+        skip_architectures: matches.get_many::<String>("skip-arch").map(|architectures| architectures.cloned().collect()).unwrap_or_default(),
+        binaries_only: matches.get_flag("binaries-only"),
+        sources_only: matches.get_flag("sources-only"),
+        state_dir,
+        resume: matches.get_flag("resume"),
+        retain_indices: matches.get_flag("retain-indices"),
+        progress: !matches.get_flag("no-progress"),
+        http: http_from_matches(matches),
+        expected_fingerprints: matches.get_many::<String>("expect-fingerprint").map(|fingerprints| fingerprints.cloned().collect()).unwrap_or_default(),
+        max_duration,
+        sample,
+        seed: matches.get_one::<u64>("seed").copied(),
+        min_validity,
+        key_min_validity,
+        validity_severity: matches.get_one::<String>("validity-severity").and_then(|severity| Severity::parse(severity)),
+        weak_hash_severity: matches.get_one::<String>("weak-hash-severity").and_then(|severity| Severity::parse(severity)),
+        max_age,
+        expect_not_automatic: matches.get_flag("expect-not-automatic") || matches.get_flag("expect-but-automatic-upgrades"),
+        expect_but_automatic_upgrades: matches.get_flag("expect-but-automatic-upgrades"),
+        upstream: matches.get_one::<String>("upstream").cloned(),
+        local_root: matches.get_one::<String>("local-root").cloned(),
+        cve_tracker: matches.get_one::<String>("cve-tracker").cloned(),
+        cve_release: matches.get_one::<String>("cve-release").cloned(),
+        oval: matches.get_one::<String>("oval").cloned(),
+        max_lag,
+        max_phasing_age,
+        resolve: matches.get_one::<String>("resolve").and_then(|resolve| Resolve::parse(resolve)).unwrap_or_default(),
+        note_cross_component: matches.get_flag("note-cross-component"),
+        maintainer_summary: matches.get_flag("maintainer-summary"),
+        base_repos: matches.get_many::<String>("base-repo").map(|bases| bases.map(|base| BaseRepo::parse(base)).collect()).unwrap_or_default(),
+        packages: matches.get_many::<String>("package").map(|packages| packages.cloned().collect()).unwrap_or_default(),
+        baseline: matches.get_one::<String>("baseline").cloned(),
+        write_baseline: matches.get_flag("write-baseline"),
+        compare_with: matches.get_one::<String>("compare-with").cloned(),
+        metrics_file: matches.get_one::<String>("metrics-file").cloned(),
+        pushgateway: matches.get_one::<String>("pushgateway").cloned(),
+        history: matches.get_one::<String>("history").cloned(),
+        missing_files_out: matches.get_one::<String>("missing-files-out").cloned(),
+        missing_files_relative: matches.get_flag("missing-files-relative"),
+        webhooks: matches.get_many::<String>("webhook").map(|webhooks| webhooks.cloned().collect()).unwrap_or_default(),
+        webhook_secret: matches.get_one::<String>("webhook-secret").cloned().or_else(|| env::var("APTCHECKR_WEBHOOK_SECRET").ok()),
+        report_url: matches.get_one::<String>("report-url").cloned(),
+        ..Default::default()
+    };
+
+    if let Some(profile) = matches.get_one::<String>("profile") {
+        Profile::parse(profile).unwrap_or_default().apply(&mut options);
+    }
+    // Explicitly requested checks extend the profile.
+    if matches.get_flag("files") {
+        options.check_files = true;
+    }
+    if matches.get_flag("check-build-depends") {
+        options.check_build_depends = true;
+    }
+    if matches.get_flag("check-conflicts") {
+        options.check_conflicts = true;
+    }
+    if matches.get_flag("check-control") {
+        options.check_control = true;
+    }
+    if matches.get_flag("check-deb-structure") {
+        options.check_files = true;
+        options.check_deb_structure = true;
+    }
+    if matches.get_flag("check-essential") {
+        options.check_essential = true;
+    }
+    if matches.get_flag("check-source-binaries") {
+        options.check_source_binaries = true;
+    }
+    if matches.get_flag("check-fields") {
+        options.check_fields = true;
+    }
+    if matches.get_flag("check-priority-coverage") {
+        options.check_priority_coverage = true;
+    }
+    if matches.get_flag("check-pool-layout") {
+        options.check_pool_layout = true;
+    }
+    if matches.get_flag("check-orphans") {
+        options.check_orphans = true;
+    }
+    if matches.get_flag("check-permissions") {
+        options.check_permissions = true;
+    }
+    if matches.get_flag("check-cves") || options.cve_tracker.is_some() {
+        options.check_cves = true;
+    }
+    if matches.get_flag("local") {
+        options.check_files = true;
+        options.verify_hashes = true;
+        options.check_orphans = true;
+        options.check_permissions = true;
+    }
+    if matches.get_flag("check-pdiffs") {
+        options.check_pdiffs = true;
+    }
+    if matches.get_flag("apply-pdiffs") {
+        options.check_pdiffs = true;
+        options.apply_pdiffs = true;
+    }
+    if matches.get_flag("check-udeb") {
+        options.check_udeb = true;
+    }
+    if matches.get_flag("check-contents") {
+        options.check_contents = true;
+    }
+    if matches.get_flag("check-commands") {
+        options.check_commands = true;
+    }
+    if matches.get_flag("check-translations") {
+        options.check_translations = true;
+    }
+    if let Some(languages) = matches.get_many::<String>("require-translation") {
+        options.check_translations = true;
+        options.required_translations = languages.cloned().collect();
+    }
+    if matches.get_flag("check-dep11") {
+        options.check_dep11 = true;
+    }
+    if matches.get_flag("verify-hashes") {
+        options.check_files = true;
+        options.verify_hashes = true;
+    }
+    // Selected checks override the profile and the check flags.
+    for (name, enabled) in [("enable-check", true), ("disable-check", false)] {
+        for check in matches.get_many::<String>(name).into_iter().flatten().filter_map(|check| CheckId::parse(check)) {
+            check.set(&mut options, enabled);
+        }
+    }
+
+    if let Some(config) = config_from_matches(matches) {
+        config.apply_check(&mut options);
+    }
+    if let Some(retries) = matches.get_one::<u32>("webhook-retries") {
+        options.webhook_retries = *retries;
+    }
+    // The CLI threshold takes precedence over the config.
+    if let Some(fail_on) = matches.get_one::<String>("fail-on").and_then(|severity| Severity::parse(severity)) {
+        options.fail_on = fail_on;
+    }
+
+    options
+}
+
+/// Run the repository check.
+///
+/// Without URL, the repositories of the config file are checked, if any.
+async fn run_check(matches: &ArgMatches) {
+    if matches.get_one::<String>("url").is_none() {
+        if let Some(mut config) = config_from_matches(matches).filter(|config| !config.repos.is_empty()) {
+            if let Some(parallel) = matches.get_one::<usize>("parallel") {
+                config.parallel = Some(*parallel);
+            }
+            run_config(&config, options_from_matches(matches), format_from_matches(matches)).await;
+        }
+    }
+
+    let d = distro_from_matches(matches);
+    let mut options = options_from_matches(matches);
+    options.keys = keys_from_matches(matches).into_iter().skip(1).collect();
+    let format = format_from_matches(matches);
+
+    let suites: Vec<String> = matches.get_many::<String>("distro").map(|suites| suites.cloned().collect()).unwrap_or_default();
+    if suites.len() > 1 {
+        run_suites(&d, &suites, options, format).await;
+    }
+
+    match check_repo(&d, options).await {
+        Ok(result) if format == OutputFormat::Nagios => exit_nagios(&[(result.name, result.status, result.error, result.findings)]),
+        Ok(result) => {
+            let status = result.status;
+            if format == OutputFormat::Gha {
+                report_gha(&[(result.name, result.status, result.error, result.findings)]);
+            }
+
+            match status {
+                CheckStatus::Passed => {
+                    println!("Repo is OK.");
+                    exit(0);
+                }
+                CheckStatus::Failed => {
+                    println!("Issues were found during check, see logs.");
+                    exit(1);
+                }
+                CheckStatus::Aborted => {
+                    println!("Check was aborted, the result is partial.");
+                    exit(130);
+                }
+            }
+        }
+        // An unavailable or invalid repository is critical, not unknown.
+        Err(e) if format == OutputFormat::Nagios => {
+            let name = d.in_release_url().unwrap_or_default();
+            exit_nagios(&[(name, CheckStatus::Failed, Some(e.to_string()), Vec::new())])
+        }
+        Err(e) => exit_with_error(format, &format!("Repo check failed with error: {e}!")),
+    }
+}
+
+/// Print the error which stopped a check and exit, with UNKNOWN for the Nagios format.
+fn exit_with_error(format: OutputFormat, message: &str) -> ! {
+    match format {
+        OutputFormat::Text => {
+            println!("{message}");
+            exit(2);
+        }
+        OutputFormat::Gha => {
+            println!("::error title=aptcheckr::{}", escape_workflow_data(message));
+            exit(2);
+        }
+        OutputFormat::Nagios => {
+            println!("APTCHECKR UNKNOWN - {message}");
+            exit(3);
+        }
+    }
+}
+
+/// Run the checks of all entries of a sources.list file.
+async fn run_sources_list(matches: &ArgMatches) {
+    let path = matches.get_one::<String>("sources").expect("Sources list is required.");
+    let options = options_from_matches(matches);
+    let format = format_from_matches(matches);
+
+    match check_sources_list(path, options).await {
+        Ok(results) => {
+            let summary: Vec<BatchSummary> = results
+                .into_iter()
+                .map(|result| (format!("Line {}: {}", result.line, result.entry), result.status, result.error, result.findings))
+                .collect();
+            exit_with_summary(&summary, format);
+        }
+        Err(e) => exit_with_error(format, &format!("Sources list check failed with error: {e}!")),
+    }
+}
+
+/// Run the checks of all repositories of the config file.
+async fn run_config(config: &Config, options: CheckOptions, format: OutputFormat) -> ! {
+    match check_config(config, options).await {
+        Ok(report) => exit_with_report(report, format),
+        Err(e) => exit_with_error(format, &format!("Config check failed with error: {e}!")),
+    }
+}
+
+/// Run the checks of several suites of one archive.
+async fn run_suites(distro: &Distro, suites: &[String], options: CheckOptions, format: OutputFormat) -> ! {
+    match check_suites(distro, suites, options).await {
+        Ok(report) => exit_with_report(report, format),
+        Err(e) => exit_with_error(format, &format!("Suite check failed with error: {e}!")),
+    }
+}
+
+/// Print the aggregated report of a batch check and exit with the overall status.
+fn exit_with_report(report: BatchReport, format: OutputFormat) -> ! {
+    if format != OutputFormat::Nagios {
+        println!("{} of {} repos passed.", report.passed, report.passed + report.failed);
+        if let Some(severity) = report.worst_severity {
+            println!("Worst severity: {severity}");
+        }
+    }
+
+    let summary: Vec<BatchSummary> = report
+        .repos
+        .into_iter()
+        .map(|result| (result.name, result.status, result.error, result.findings))
+        .collect();
+    exit_with_summary(&summary, format);
+}
 
-    match check_repo(&d, components, architectures, check_files).await {
-        Ok(success) => {
-            if success {
-                println!("Repo is OK.");
+/// Number of findings which are not known from the baseline.
+fn new_findings(findings: &[Finding]) -> usize {
+    findings.iter().filter(|finding| !finding.known).count()
+}
+
+/// Summary of a repo of a batch check. (Name, Status, Error, Findings)
+type BatchSummary = (String, CheckStatus, Option<String>, Vec<Finding>);
+
+/// Line of a repo in the summary, e.g. "ubuntu jammy - 3 findings".
+fn summary_line((name, status, error, findings): &BatchSummary) -> String {
+    let status = match (status, error) {
+        (_, Some(error)) => format!("failed: {error}"),
+        (CheckStatus::Passed, None) => "OK".to_string(),
+        (CheckStatus::Failed, None) => format!("{} findings", new_findings(findings)),
+        (CheckStatus::Aborted, None) => "aborted".to_string(),
+    };
+    format!("{name} - {status}")
+}
+
+/// Print the status line of the Nagios plugin API with perfdata, followed by the failed repos, and exit with its code.
+///
+/// The state is OK if all repos passed, CRITICAL if a check stopped with an error or found new errors,
+/// WARNING for other failures, and UNKNOWN if the check was aborted.
+fn exit_nagios(summary: &[BatchSummary]) -> ! {
+    let findings: Vec<&Finding> = summary.iter().flat_map(|(_, _, _, findings)| findings).collect();
+    let issues = findings.iter().filter(|finding| !finding.known).count();
+    let missing_deps = findings
+        .iter()
+        .filter(|finding| !finding.known && matches!(finding.kind, FindingKind::MissingDependency | FindingKind::MissingPreDependency))
+        .count();
+    let failed: Vec<&BatchSummary> = summary.iter().filter(|(_, status, _, _)| *status != CheckStatus::Passed).collect();
+
+    let critical = |(_, _, error, findings): &&BatchSummary| {
+        error.is_some() || findings.iter().any(|finding| !finding.known && finding.severity == Severity::Error)
+    };
+    let (state, code) = if failed.iter().any(|(_, status, _, _)| *status == CheckStatus::Aborted) {
+        ("UNKNOWN", 3)
+    } else if failed.is_empty() {
+        ("OK", 0)
+    } else if failed.iter().any(critical) {
+        ("CRITICAL", 2)
+    } else {
+        ("WARNING", 1)
+    };
+
+    let text = match failed.len() {
+        0 => format!("{} of {} repos passed, {issues} new findings", summary.len(), summary.len()),
+        _ if code == 3 => "check was aborted, the result is partial".to_string(),
+        count => format!("{count} of {} repos failed, {issues} new findings", summary.len()),
+    };
+    println!(
+        "APTCHECKR {state} - {text} | issues={issues};; missing_deps={missing_deps};; known_issues={};; failed_repos={};;",
+        findings.len() - issues,
+        failed.len()
+    );
+    // Further lines are the long output of the plugin.
+    for repo in failed {
+        println!("{}", summary_line(repo));
+    }
+    exit(code);
+}
+
+/// Maximum number of new findings per repo listed in the step summary.
+const MAX_STEP_SUMMARY_FINDINGS: usize = 100;
+
+/// Escape the message of a workflow command.
+fn escape_workflow_data(text: &str) -> String {
+    text.replace('%', "%25").replace('\r', "%0D").replace('\n', "%0A")
+}
+
+/// Escape a property of a workflow command, e.g. the title.
+fn escape_workflow_property(text: &str) -> String {
+    escape_workflow_data(text).replace(':', "%3A").replace(',', "%2C")
+}
+
+/// Get the step summary in Markdown: a table of the repos and the new findings of each failed repo.
+fn step_summary(summary: &[BatchSummary]) -> String {
+    let mut lines = vec![
+        "## aptcheckr".to_string(),
+        String::new(),
+        "| Repo | Status | New findings | Known findings |".to_string(),
+        "| --- | --- | --- | --- |".to_string(),
+    ];
+    for (name, status, _, findings) in summary {
+        let new = new_findings(findings);
+        lines.push(format!("| {} | {status:?} | {new} | {} |", name.replace('|', "\\|"), findings.len() - new));
+    }
+
+    for (name, _, error, findings) in summary {
+        let new: Vec<&Finding> = findings.iter().filter(|finding| !finding.known).collect();
+        if error.is_none() && new.is_empty() {
+            continue;
+        }
+
+        lines.push(String::new());
+        lines.push(format!("### {name}"));
+        lines.push(String::new());
+        if let Some(error) = error {
+            lines.push(format!("Error: {error}"));
+            lines.push(String::new());
+        }
+        lines.extend(new.iter().take(MAX_STEP_SUMMARY_FINDINGS).map(|finding| format!("- {finding}")));
+        if new.len() > MAX_STEP_SUMMARY_FINDINGS {
+            lines.push(format!("- ... and {} more, see the report.", new.len() - MAX_STEP_SUMMARY_FINDINGS));
+        }
+    }
+
+    lines.push(String::new());
+    lines.join("\n")
+}
+
+/// Print a GitHub Actions annotation per error and new finding, and append the step summary to $GITHUB_STEP_SUMMARY.
+///
+/// Findings with severity info become notices. Without $GITHUB_STEP_SUMMARY, e.g. outside of GitHub Actions, no step summary is written.
+fn report_gha(summary: &[BatchSummary]) {
+    for (name, _, error, findings) in summary {
+        let title = escape_workflow_property(&format!("aptcheckr {name}"));
+        if let Some(error) = error {
+            println!("::error title={title}::{}", escape_workflow_data(error));
+        }
+        for finding in findings.iter().filter(|finding| !finding.known) {
+            let command = match finding.severity {
+                Severity::Error => "error",
+                Severity::Warning => "warning",
+                Severity::Info => "notice",
+            };
+            println!("::{command} title={title}::{}", escape_workflow_data(&finding.to_string()));
+        }
+    }
+
+    let path = match env::var("GITHUB_STEP_SUMMARY") {
+        Ok(path) if !path.is_empty() => path,
+        _ => return,
+    };
+    let result = OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(&path)
+        .and_then(|mut file| file.write_all(step_summary(summary).as_bytes()));
+    if let Err(e) = result {
+        println!("::warning title=aptcheckr::{}", escape_workflow_data(&format!("Writing step summary {path} failed! {e}")));
+    }
+}
+
+/// Print the summary of a batch check and exit with the overall status.
+fn exit_with_summary(summary: &[BatchSummary], format: OutputFormat) -> ! {
+    match format {
+        OutputFormat::Nagios => exit_nagios(summary),
+        OutputFormat::Gha => report_gha(summary),
+        OutputFormat::Text => {}
+    }
+
+    for repo in summary {
+        println!("{}", summary_line(repo));
+    }
+
+    if summary.iter().any(|(_, status, _, _)| *status == CheckStatus::Aborted) {
+        println!("Check was aborted, the result is partial.");
+        exit(130);
+    } else if summary.iter().all(|(_, status, _, _)| *status == CheckStatus::Passed) {
+        println!("All repos are OK.");
+        exit(0);
+    } else {
+        println!("Issues were found during check, see logs.");
+        exit(1);
+    }
+}
+
+/// Get the repositories to watch: the repositories of the config file, or the repository of the CLI parameters.
+fn watched_from_matches(matches: &ArgMatches) -> Vec<WatchedRepo> {
+    let options = options_from_matches(matches);
+
+    if matches.get_one::<String>("url").is_none() {
+        if let Some(config) = config_from_matches(matches).filter(|config| !config.repos.is_empty()) {
+            return config
+                .repos
+                .iter()
+                .map(|repo| match (repo.distro(), repo.options(&options)) {
+                    (Ok(distro), Ok(options)) => WatchedRepo { name: repo.label(), distro, options },
+                    (Err(e), _) | (_, Err(e)) => {
+                        println!("Invalid repo {} in config: {e}!", repo.label());
+                        exit(2);
+                    }
+                })
+                .collect();
+        }
+    }
+
+    let distro = distro_from_matches(matches);
+    let mut options = options;
+    options.keys = keys_from_matches(matches).into_iter().skip(1).collect();
+    let name = match (&distro.name, &distro.path) {
+        (Some(name), _) => format!("{} {name}", distro.url),
+        (None, Some(path)) => format!("{} {path}", distro.url),
+        (None, None) => distro.url.clone(),
+    };
+
+    vec![WatchedRepo { name, distro, options }]
+}
+
+/// Watch the repositories, checking them again on a schedule.
+async fn run_watch(matches: &ArgMatches) {
+    let mut watch_options = WatchOptions {
+        listen: matches.get_one::<SocketAddr>("listen").copied(),
+        ..Default::default()
+    };
+    if let Some(interval) = matches.get_one::<String>("interval") {
+        match parse_duration(interval) {
+            Some(interval) if !interval.is_zero() => watch_options.interval = interval,
+            _ => {
+                println!("Invalid interval {interval}!");
+                exit(2);
+            }
+        }
+    }
+
+    let repos = watched_from_matches(matches);
+    let format = format_from_matches(matches);
+    match watch(repos, options_from_matches(matches), watch_options).await {
+        Ok(report) => exit_with_report(report, format),
+        Err(e) => exit_with_error(format, &format!("Watching failed with error: {e}!")),
+    }
+}
+
+/// Compare two reports, listing the new, fixed and persisting findings.
+///
+/// Exits with 1 if new findings are found, which are not known from the baseline.
+fn run_diff(matches: &ArgMatches) {
+    let old = matches.get_one::<String>("old").expect("Old report is required.");
+    let new = matches.get_one::<String>("new").expect("New report is required.");
+
+    let diff = match diff_reports(old, new) {
+        Ok(diff) => diff,
+        Err(e) => {
+            println!("Comparing reports failed with error: {e}!");
+            exit(2);
+        }
+    };
+
+    for (title, prefix, entries) in [("New", "+", &diff.new), ("Fixed", "-", &diff.fixed), ("Persisting", " ", &diff.persisting)] {
+        if !entries.is_empty() {
+            println!("{title} findings:");
+        }
+        for entry in entries {
+            println!("{prefix} {}: {}", entry.repo, entry.finding);
+        }
+    }
+    println!("{} new, {} fixed and {} persisting findings.", diff.new.len(), diff.fixed.len(), diff.persisting.len());
+
+    if diff.regressions() > 0 {
+        exit(1);
+    }
+    exit(0);
+}
+
+/// Compare two mirrors of a repository, or a repository and a snapshot.
+///
+/// Exits with 1 if the mirrors differ.
+async fn run_compare(matches: &ArgMatches) {
+    let a = distro_from_matches(matches);
+    let mirror = match (matches.get_one::<String>("mirror"), matches.get_one::<String>("snapshot")) {
+        (Some(mirror), _) => mirror.clone(),
+        (None, Some(time)) => {
+            let archive = matches.get_one::<String>("snapshot-archive").map(|archive| archive.as_str()).unwrap_or(DEBIAN_SNAPSHOT);
+            match snapshot_url(archive, time) {
+                Ok(url) => url,
+                Err(e) => {
+                    println!("{e}");
+                    exit(2);
+                }
+            }
+        }
+        (None, None) => unreachable!("Either mirror or snapshot is required."),
+    };
+    let b = Distro { url: mirror, ..a.clone() };
+
+    let options = CompareOptions {
+        components: matches.get_many::<String>("component").map(|components| components.cloned().collect()).unwrap_or_default(),
+        architectures: matches.get_many::<String>("arch").map(|architectures| architectures.cloned().collect()).unwrap_or_default(),
+        http: http_from_matches(matches),
+    };
+
+    match compare_mirrors(&a, &b, options).await {
+        Ok(comparison) => {
+            match comparison.date_skew {
+                Some(skew) => println!("Release date skew: {skew}s"),
+                None => println!("Release date skew: unknown"),
+            }
+            for (title, files) in [
+                ("Diverging files", &comparison.diverging_files),
+                ("Files only on A", &comparison.only_a_files),
+                ("Files only on B", &comparison.only_b_files),
+            ] {
+                if !files.is_empty() {
+                    println!("{title}:");
+                }
+                for file in files {
+                    println!("  {file}");
+                }
+            }
+            for (title, packages) in [("Packages only on A", &comparison.only_a_packages), ("Packages only on B", &comparison.only_b_packages)] {
+                if !packages.is_empty() {
+                    println!("{title}:");
+                }
+                for package in packages {
+                    println!("  {} {} ({} {})", package.package, package.version, package.component, package.architecture);
+                }
+            }
+
+            if comparison.is_identical() {
+                println!("Mirrors are identical.");
                 exit(0);
             } else {
-                println!("Issues were found during check, see logs.");
+                println!("Mirrors differ.");
                 exit(1);
             }
         }
         Err(e) => {
-            println!("Repo check failed with error: {e}!");
+            println!("Mirror comparison failed with error: {e}!");
             exit(2);
         }
     }
 }
+
+/// Run the mirror benchmark.
+async fn run_bench(matches: &ArgMatches) {
+    let d = distro_from_matches(matches);
+
+    let mut options = BenchOptions {
+        http: http_from_matches(matches),
+        ..Default::default()
+    };
+    if let Some(samples) = matches.get_one::<usize>("samples") {
+        options.samples = *samples;
+    }
+    if let Some(rounds) = matches.get_one::<usize>("rounds") {
+        options.rounds = *rounds;
+    }
+
+    match bench_repo(&d, options).await {
+        Ok(report) => {
+            println!("{:<8} {:>6} {:>12} {:>12} {:>14} {:>14}", "Files", "Count", "p50 latency", "p95 latency", "p50 KiB/s", "p95 KiB/s");
+            for stats in &report.stats {
+                println!(
+                    "{:<8} {:>6} {:>10.1}ms {:>10.1}ms {:>14.1} {:>14.1}",
+                    stats.kind,
+                    stats.count,
+                    stats.latency_ms.p50,
+                    stats.latency_ms.p95,
+                    stats.throughput_bytes_per_s.p50 / 1024.0,
+                    stats.throughput_bytes_per_s.p95 / 1024.0,
+                );
+            }
+
+            if report.failures.is_empty() {
+                exit(0);
+            } else {
+                println!("{} downloads failed, see logs.", report.failures.len());
+                exit(1);
+            }
+        }
+        Err(e) => {
+            println!("Mirror benchmark failed with error: {e}!");
+            exit(2);
+        }
+    }
+}
+
+/// Print the statistics of a repository.
+///
+/// Exits with 1 if an index could not be loaded.
+async fn run_stats(matches: &ArgMatches) {
+    let d = distro_from_matches(matches);
+
+    let mut options = StatsOptions {
+        components: matches.get_many::<String>("component").map(|components| components.cloned().collect()).unwrap_or_default(),
+        architectures: matches.get_many::<String>("arch").map(|architectures| architectures.cloned().collect()).unwrap_or_default(),
+        coverage: matches.get_flag("coverage"),
+        http: http_from_matches(matches),
+        ..Default::default()
+    };
+    if let Some(top) = matches.get_one::<usize>("top") {
+        options.top = *top;
+    }
+
+    let top = options.top;
+    match repo_stats(&d, options).await {
+        Ok(stats) => {
+            println!("{:<16} {:<12} {:>10} {:>12}", "Component", "Arch", "Packages", "Size");
+            for index in &stats.indices {
+                println!("{:<16} {:<12} {:>10} {:>12}", index.component, index.architecture, index.packages, format_bytes(index.size));
+            }
+            println!("Pool size: {}", format_bytes(stats.pool_size));
+            println!("Sources: {}", stats.sources);
+            println!("Maintainers: {}", stats.maintainers);
+            if !stats.largest_packages.is_empty() {
+                println!("Largest packages:");
+            }
+            for package in &stats.largest_packages {
+                println!("  {:>12}  {} {} ({} {})", format_bytes(package.size), package.package, package.version, package.component, package.architecture);
+            }
+            for (title, groups) in [("Largest sections", &stats.sections), ("Largest sources", &stats.source_sizes)] {
+                if !groups.is_empty() {
+                    println!("{title}:");
+                }
+                for group in groups.iter().take(top) {
+                    println!("  {:>12}  {} ({} files)", format_bytes(group.size), group.name, group.files);
+                }
+            }
+            if let Some(coverage) = &stats.coverage {
+                // x: built, !: missing, -: not built for the architecture.
+                println!("Architecture coverage:");
+                println!("  {:<30} {}", "Source", coverage.architectures.join(" "));
+                for source in &coverage.sources {
+                    let cells: Vec<String> = coverage
+                        .architectures
+                        .iter()
+                        .map(|architecture| {
+                            let mark = if source.built.contains(architecture) {
+                                "x"
+                            } else if source.missing.contains(architecture) {
+                                "!"
+                            } else {
+                                "-"
+                            };
+                            format!("{mark:<width$}", width = architecture.len())
+                        })
+                        .collect();
+                    println!("{} {:<30} {}", if source.missing.is_empty() { ' ' } else { '!' }, source.source, cells.join(" "));
+                }
+                println!("{} sources have incomplete architecture coverage.", coverage.incomplete().count());
+            }
+
+            if stats.failures.is_empty() {
+                exit(0);
+            } else {
+                println!("{} indices could not be loaded, see logs.", stats.failures.len());
+                exit(1);
+            }
+        }
+        Err(e) => {
+            println!("Repository statistics failed with error: {e}!");
+            exit(2);
+        }
+    }
+}
+
+/// App entry point - processing of CLI parameters.
+#[tokio::main]
+async fn main() {
+    let matches = command!()
+        .args_conflicts_with_subcommands(true)
+        .args(repo_args())
+        .args(check_args())
+        .arg(arg!(--"log-format" <FORMAT> "Format of the log: text or json with one object per event. Defaults to env var APTCHECKR_LOG_FORMAT or text.").required(false).value_parser(["text", "json"]).global(true))
+        .arg(arg!(--parallel <COUNT> "Number of config repos checked at the same time. Defaults to 1.").required(false).value_parser(clap::value_parser!(usize)))
+        .args(http_args())
+        .subcommand(
+            Command::new("bench")
+                .about("Measure latency and throughput of a mirror.")
+                .args(repo_args())
+                .arg(arg!(--samples <COUNT> "Number of index and pool files. Defaults to 10.").required(false).value_parser(clap::value_parser!(usize)))
+                .arg(arg!(--rounds <COUNT> "Downloads per file. Defaults to 3.").required(false).value_parser(clap::value_parser!(usize)))
+                .args(http_args()),
+        )
+        .subcommand(
+            Command::new("watch")
+                .about("Keep running and check the repositories again on a schedule. Reports are only written if a status changes.")
+                .args(repo_args())
+                .arg(arg!(--interval <DURATION> "Time between the checks, e.g. 30m. Defaults to 1h.").required(false))
+                .arg(arg!(--listen <ADDRESS> "Serve /healthz, /metrics and /report.json on the address, e.g. 127.0.0.1:9480.").required(false).value_parser(clap::value_parser!(SocketAddr)))
+                .args(check_args())
+                .args(http_args()),
+        )
+        .subcommand(
+            Command::new("compare")
+                .about("Compare two mirrors: Release date skew, diverging files and package versions available on one mirror only.")
+                .arg(arg!(<url> "URL of the first mirror, A."))
+                .arg(arg!([mirror] "URL of the second mirror, B.").required_unless_present("snapshot"))
+                .arg(arg!(--snapshot <TIME> "Compare with the snapshot at the time as B, e.g. 20240115T120000Z or 2024-01-15.").required(false).conflicts_with("mirror"))
+                .arg(arg!(--"snapshot-archive" <URL> "Snapshot archive. Defaults to https://snapshot.debian.org/archive/debian.").required(false).requires("snapshot"))
+                .args(repo_args().into_iter().filter(|arg| arg.get_id() != "url"))
+                .arg(arg!(-c --component <COMPONENT> ... "Component to compare. Defaults to all components.").required(false))
+                .arg(arg!(-a --arch <ARCHITECTURE> ... "Architecture to compare, e.g. amd64 or source. Defaults to all architectures.").required(false))
+                .args(http_args()),
+        )
+        .subcommand(
+            Command::new("stats")
+                .about("Count the packages per component and architecture, the pool size, the sources and the maintainers.")
+                .args(repo_args())
+                .arg(arg!(-c --component <COMPONENT> ... "Component to count. Defaults to all components.").required(false))
+                .arg(arg!(-a --arch <ARCHITECTURE> ... "Architecture to count, e.g. amd64 or source. Defaults to all architectures.").required(false))
+                .arg(arg!(--coverage "Show the matrix of the sources versus the architectures they have binaries for.").required(false))
+                .arg(arg!(--top <COUNT> "Number of largest packages, sections and sources listed. Defaults to 10.").required(false).value_parser(clap::value_parser!(usize)))
+                .args(http_args()),
+        )
+        .subcommand(
+            Command::new("diff")
+                .about("Compare two reports and list the new, fixed and persisting findings. Fails if new findings are found.")
+                .arg(arg!(<old> "Report of the earlier run."))
+                .arg(arg!(<new> "Report of the later run.")),
+        )
+        .subcommand(
+            Command::new("from-sources-list")
+                .about("Check every deb and deb-src entry of a sources.list file or a deb822 .sources file.")
+                .arg(arg!(<sources> "Path of the sources.list or .sources file."))
+                .args(check_args())
+                .args(http_args()),
+        )
+        .get_matches();
+
+    // The lib sets up the logging from the env vars.
+    if let Some(format) = matches.get_one::<String>("log-format") {
+        env::set_var("APTCHECKR_LOG_FORMAT", format);
+    }
+
+    match matches.subcommand() {
+        Some(("bench", sub_matches)) => run_bench(sub_matches).await,
+        Some(("compare", sub_matches)) => run_compare(sub_matches).await,
+        Some(("diff", sub_matches)) => run_diff(sub_matches),
+        Some(("from-sources-list", sub_matches)) => run_sources_list(sub_matches).await,
+        Some(("stats", sub_matches)) => run_stats(sub_matches).await,
+        Some(("watch", sub_matches)) => run_watch(sub_matches).await,
+        _ => run_check(&matches).await,
+    }
+}
@@ -0,0 +1,214 @@
+//! Prometheus metrics of the checks.
+//!
+//! The metrics use the text exposition format, so they can be read by the
+//! textfile collector of the node exporter or pushed to a Pushgateway.
+
+use std::collections::BTreeMap;
+use std::fs;
+use std::time::Duration;
+
+use chrono::Utc;
+use libapt::{Error, Result};
+use log::{error, info};
+use reqwest::header::CONTENT_TYPE;
+
+use crate::check::AptCheck;
+use crate::finding::FindingKind;
+use crate::transport::Transport;
+use crate::CheckStatus;
+
+/// Job of the metrics pushed to a Pushgateway.
+const PUSH_JOB: &str = "aptcheckr";
+
+/// Metrics of the check of a repository.
+#[derive(Debug, Clone)]
+pub struct RepoMetrics {
+    // InRelease URL of the repository.
+    repo: String,
+    // The check ran, i.e. it didn't stop with an error.
+    up: bool,
+    // The check passed.
+    passed: bool,
+    // New findings per component and architecture. Empty names for findings of the release. ((Component, Architecture), Count)
+    issues: BTreeMap<(String, String), usize>,
+    // Findings known from the baseline.
+    known_issues: usize,
+    // New findings of missing dependencies and pre-dependencies.
+    missing_dependencies: usize,
+    // Age of the release Date in seconds. None if the release has no date.
+    release_age: Option<i64>,
+    // Lag of the release Date behind the upstream archive in seconds. None without upstream or date.
+    mirror_lag: Option<i64>,
+    // Duration of the check.
+    duration: Duration,
+}
+
+impl RepoMetrics {
+    /// Collect the metrics of a check. The check is None if it stopped with an error.
+    ///
+    /// Checked components and architectures without findings count zero issues.
+    pub fn new(repo: &str, status: CheckStatus, check: Option<&AptCheck>, duration: Duration) -> RepoMetrics {
+        let mut metrics = RepoMetrics {
+            repo: repo.to_string(),
+            up: check.is_some(),
+            passed: status == CheckStatus::Passed,
+            issues: BTreeMap::new(),
+            known_issues: 0,
+            missing_dependencies: 0,
+            release_age: None,
+            mirror_lag: None,
+            duration,
+        };
+
+        let check = match check {
+            Some(check) => check,
+            None => return metrics,
+        };
+
+        for (component, architecture) in check.completed() {
+            metrics.issues.insert((component.clone(), architecture.to_string()), 0);
+        }
+
+        for finding in check.findings() {
+            if finding.known {
+                metrics.known_issues += 1;
+                continue;
+            }
+
+            let component = finding.component.clone().unwrap_or_default();
+            let architecture = finding.architecture.as_ref().map(|architecture| architecture.to_string()).unwrap_or_default();
+            *metrics.issues.entry((component, architecture)).or_default() += 1;
+
+            if matches!(finding.kind, FindingKind::MissingDependency | FindingKind::MissingPreDependency) {
+                metrics.missing_dependencies += 1;
+            }
+        }
+
+        metrics.release_age = check.release().date.map(|date| Utc::now().signed_duration_since(date).num_seconds());
+        metrics.mirror_lag = check.mirror_lag().and_then(|lag| lag.lag);
+
+        metrics
+    }
+}
+
+/// Escape a label value of the text format.
+fn escape(value: &str) -> String {
+    value.replace('\\', "\\\\").replace('"', "\\\"").replace('\n', "\\n")
+}
+
+/// Format the labels of a sample, e.g. {repo="...",arch="amd64"}.
+fn labels(labels: &[(&str, &str)]) -> String {
+    let labels: Vec<String> = labels.iter().map(|(name, value)| format!("{name}=\"{}\"", escape(value))).collect();
+    format!("{{{}}}", labels.join(","))
+}
+
+/// Append a gauge with its samples. (Labels, Value)
+fn gauge(out: &mut String, name: &str, help: &str, samples: Vec<(String, f64)>) {
+    if samples.is_empty() {
+        return;
+    }
+
+    out.push_str(&format!("# HELP {name} {help}\n# TYPE {name} gauge\n"));
+    for (labels, value) in samples {
+        out.push_str(&format!("{name}{labels} {value}\n"));
+    }
+}
+
+/// Render the metrics of the checked repositories in the text exposition format.
+pub fn render(repos: &[RepoMetrics]) -> String {
+    let mut out = String::new();
+    let repo_labels = |metrics: &RepoMetrics| labels(&[("repo", &metrics.repo)]);
+    let checked = || repos.iter().filter(|metrics| metrics.up);
+
+    gauge(
+        &mut out,
+        "aptcheckr_up",
+        "Whether the check ran, 0 if it stopped with an error.",
+        repos.iter().map(|metrics| (repo_labels(metrics), metrics.up as u8 as f64)).collect(),
+    );
+    gauge(
+        &mut out,
+        "aptcheckr_check_passed",
+        "Whether the check passed.",
+        repos.iter().map(|metrics| (repo_labels(metrics), metrics.passed as u8 as f64)).collect(),
+    );
+    gauge(
+        &mut out,
+        "aptcheckr_issues_total",
+        "Findings not known from the baseline, per component and architecture.",
+        checked()
+            .flat_map(|metrics| {
+                metrics.issues.iter().map(|((component, architecture), count)| {
+                    (labels(&[("repo", &metrics.repo), ("component", component), ("arch", architecture)]), *count as f64)
+                })
+            })
+            .collect(),
+    );
+    gauge(
+        &mut out,
+        "aptcheckr_known_issues_total",
+        "Findings known from the baseline.",
+        checked().map(|metrics| (repo_labels(metrics), metrics.known_issues as f64)).collect(),
+    );
+    gauge(
+        &mut out,
+        "aptcheckr_missing_dependencies_total",
+        "Dependencies and pre-dependencies not available in the repository.",
+        checked().map(|metrics| (repo_labels(metrics), metrics.missing_dependencies as f64)).collect(),
+    );
+    gauge(
+        &mut out,
+        "aptcheckr_release_age_seconds",
+        "Age of the Date field of the release.",
+        checked()
+            .filter_map(|metrics| metrics.release_age.map(|age| (repo_labels(metrics), age as f64)))
+            .collect(),
+    );
+    gauge(
+        &mut out,
+        "aptcheckr_mirror_lag_seconds",
+        "Lag of the Date field of the release behind the upstream archive.",
+        checked()
+            .filter_map(|metrics| metrics.mirror_lag.map(|lag| (repo_labels(metrics), lag as f64)))
+            .collect(),
+    );
+    gauge(
+        &mut out,
+        "aptcheckr_run_duration_seconds",
+        "Duration of the check.",
+        repos.iter().map(|metrics| (repo_labels(metrics), metrics.duration.as_secs_f64())).collect(),
+    );
+    gauge(
+        &mut out,
+        "aptcheckr_last_run_timestamp_seconds",
+        "Unix time of the end of the check.",
+        vec![(String::new(), Utc::now().timestamp() as f64)],
+    );
+
+    out
+}
+
+/// Write the metrics to a file of the textfile collector.
+///
+/// The file is written to a temporary file first and renamed,
+/// so the collector never reads a partial file.
+pub fn write_file(path: &str, content: &str) -> Result<()> {
+    let temp = format!("{path}.tmp");
+    if let Err(e) = fs::write(&temp, content).and_then(|_| fs::rename(&temp, path)) {
+        let message = format!("Writing metrics {path} failed! {e}");
+        error!("{}", message);
+        return Err(Error::new(&message, libapt::ErrorType::ApiUsage));
+    }
+
+    info!("Wrote metrics to {path}.");
+    Ok(())
+}
+
+/// Push the metrics to a Pushgateway, replacing the metrics of the last push.
+pub async fn push(transport: &Transport, gateway: &str, content: &str) -> Result<()> {
+    let url = format!("{}/metrics/job/{PUSH_JOB}", gateway.trim_end_matches('/'));
+    transport.put(&url, content, &[(CONTENT_TYPE, "text/plain; version=0.0.4".to_string())]).await?;
+
+    info!("Pushed metrics to {url}.");
+    Ok(())
+}
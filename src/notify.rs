@@ -0,0 +1,300 @@
+//! Webhook and mail notifications about check results.
+//!
+//! The summary is posted as JSON. With a secret, the body is signed with HMAC-SHA256,
+//! and the signature is sent as "sha256=<hex>" in the X-Aptcheckr-Signature-256 header.
+//! Mails contain a plain text summary with the most severe findings.
+//! Slack channels and Matrix rooms get a compact summary with a link to the full report.
+
+use std::cmp::Reverse;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::Duration;
+
+use hmac::{Hmac, Mac};
+use log::{debug, error, info, warn};
+use percent_encoding::{utf8_percent_encode, NON_ALPHANUMERIC};
+use reqwest::header::{HeaderName, AUTHORIZATION, CONTENT_TYPE};
+use serde::Serialize;
+use sha2::Sha256;
+
+use crate::finding::{Finding, Severity};
+use crate::options::{ChatTarget, CheckOptions};
+use crate::smtp;
+use crate::transport::Transport;
+use crate::CheckStatus;
+
+/// Header of the HMAC signature of the body.
+const SIGNATURE_HEADER: HeaderName = HeaderName::from_static("x-aptcheckr-signature-256");
+
+/// Delay before the first retry, growing linearly with each attempt.
+const RETRY_DELAY: Duration = Duration::from_secs(2);
+
+/// Maximum number of new findings listed per repository.
+const MAX_EXAMPLES: usize = 10;
+
+/// Maximum number of new findings listed per repository in chat messages.
+const MAX_CHAT_EXAMPLES: usize = 3;
+
+/// Counter making the transaction IDs of Matrix messages unique.
+static MATRIX_TRANSACTION: AtomicU64 = AtomicU64::new(0);
+
+/// Summary of the check of a repository.
+#[derive(Debug, Clone, Serialize)]
+pub struct RepoSummary {
+    // Name of the repository.
+    pub name: String,
+    // Overall result of the check.
+    pub status: CheckStatus,
+    // Result of the previous check, from watch mode, the history or the report to compare with.
+    pub previous_status: Option<CheckStatus>,
+    // Error which stopped the check, e.g. an unavailable InRelease file.
+    pub error: Option<String>,
+    // Highest severity of the new findings.
+    pub worst_severity: Option<Severity>,
+    // Number of findings, including known findings.
+    pub findings: usize,
+    // Number of findings neither known from the baseline nor found by the previous check.
+    pub new_findings: usize,
+    // Most severe new findings.
+    pub examples: Vec<Finding>,
+}
+
+impl RepoSummary {
+    /// Summarize the check of a repository. The previous result is given if known. (Status, Findings)
+    pub fn new(name: &str, status: CheckStatus, error: Option<String>, findings: &[Finding], previous: Option<(CheckStatus, &[Finding])>) -> RepoSummary {
+        let mut new_findings: Vec<&Finding> = findings
+            .iter()
+            .filter(|finding| !finding.known)
+            .filter(|finding| match previous {
+                Some((_, previous)) => !previous.iter().any(|other| other.is_same(finding)),
+                None => true,
+            })
+            .collect();
+        new_findings.sort_by_key(|finding| Reverse(finding.severity));
+
+        RepoSummary {
+            name: name.to_string(),
+            status,
+            previous_status: previous.map(|(status, _)| status),
+            error,
+            worst_severity: new_findings.iter().map(|finding| finding.severity).max(),
+            findings: findings.len(),
+            new_findings: new_findings.len(),
+            examples: new_findings.into_iter().take(MAX_EXAMPLES).cloned().collect(),
+        }
+    }
+
+    /// Test if the repository is worth a notification.
+    ///
+    /// This is the case for new findings, a first check stopped with an error,
+    /// or a change between passed and failed.
+    fn is_notable(&self) -> bool {
+        match (self.status, self.previous_status) {
+            (CheckStatus::Aborted, _) | (_, Some(CheckStatus::Aborted)) => false,
+            (status, Some(previous)) if status != previous => true,
+            (_, None) if self.error.is_some() => true,
+            _ => self.new_findings > 0,
+        }
+    }
+}
+
+/// Notification posted to the webhooks and mailed.
+#[derive(Debug, Clone, Serialize)]
+pub struct Notification {
+    // Overall result: aborted if any check was aborted, passed if all checks passed.
+    pub status: CheckStatus,
+    // Summaries of the checked repositories.
+    pub repos: Vec<RepoSummary>,
+}
+
+impl Notification {
+    /// Create the notification of the checked repositories.
+    pub fn new(repos: Vec<RepoSummary>) -> Notification {
+        let status = if repos.iter().any(|repo| repo.status == CheckStatus::Aborted) {
+            CheckStatus::Aborted
+        } else if repos.iter().all(|repo| repo.status == CheckStatus::Passed) {
+            CheckStatus::Passed
+        } else {
+            CheckStatus::Failed
+        };
+        Notification { status, repos }
+    }
+
+    /// Test if any repository is worth a notification.
+    fn is_notable(&self) -> bool {
+        self.repos.iter().any(|repo| repo.is_notable())
+    }
+
+    /// Get the subject and the plain text body of a mail.
+    fn mail(&self) -> (String, String) {
+        let failed = self.repos.iter().filter(|repo| repo.status != CheckStatus::Passed).count();
+        let subject = match failed {
+            0 => format!("aptcheckr: all {} repos passed", self.repos.len()),
+            _ => format!("aptcheckr: {failed} of {} repos failed", self.repos.len()),
+        };
+
+        let mut body = Vec::new();
+        for repo in &self.repos {
+            match repo.previous_status {
+                Some(previous) if previous != repo.status => body.push(format!("{}: {:?}, was {previous:?}", repo.name, repo.status)),
+                _ => body.push(format!("{}: {:?}", repo.name, repo.status)),
+            }
+            if let Some(error) = &repo.error {
+                body.push(format!("Error: {error}"));
+            }
+            if repo.new_findings > 0 {
+                body.push(format!("{} new of {} findings, most severe:", repo.new_findings, repo.findings));
+                body.extend(repo.examples.iter().map(|finding| format!("  {finding}")));
+            }
+            body.push(String::new());
+        }
+
+        (subject, body.join("\n"))
+    }
+
+    /// Get the compact summary for chats, one line per repository and its most severe findings.
+    ///
+    /// The text is HTML escaped. The markup of the chat is added by the given functions for bold text and links. (Text) (Text, URL)
+    fn chat_lines(&self, report_url: Option<&str>, bold: impl Fn(&str) -> String, link: impl Fn(&str, &str) -> String) -> Vec<String> {
+        let failed = self.repos.iter().filter(|repo| repo.status != CheckStatus::Passed).count();
+        let mut lines = match failed {
+            0 => vec![bold(&format!("aptcheckr: all {} repos passed", self.repos.len()))],
+            _ => vec![bold(&format!("aptcheckr: {failed} of {} repos failed", self.repos.len()))],
+        };
+
+        for repo in &self.repos {
+            let mut line = format!("{}: {:?}", bold(&escape_html(&repo.name)), repo.status);
+            if let Some(previous) = repo.previous_status.filter(|previous| *previous != repo.status) {
+                line.push_str(&format!(", was {previous:?}"));
+            }
+            if repo.new_findings > 0 {
+                line.push_str(&format!(", {} new findings", repo.new_findings));
+            }
+            if let Some(error) = &repo.error {
+                line.push_str(&format!(", {}", escape_html(error)));
+            }
+            lines.push(line);
+            lines.extend(repo.examples.iter().take(MAX_CHAT_EXAMPLES).map(|finding| format!("• {}", escape_html(&finding.to_string()))));
+        }
+
+        if let Some(url) = report_url {
+            lines.push(link("Full report", url));
+        }
+        lines
+    }
+}
+
+/// Escape the characters with a special meaning in HTML and Slack messages.
+fn escape_html(text: &str) -> String {
+    text.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;")
+}
+
+/// Compute the hex encoded HMAC-SHA256 of the body.
+fn sign(secret: &str, body: &str) -> String {
+    let mut mac = Hmac::<Sha256>::new_from_slice(secret.as_bytes()).expect("HMAC accepts keys of any size.");
+    mac.update(body.as_bytes());
+    mac.finalize().into_bytes().iter().map(|byte| format!("{byte:02x}")).collect()
+}
+
+/// Post the notification to the configured webhooks and mail it, if any repository is notable.
+///
+/// Failed webhook requests are retried. Failures are logged, but don't fail the check.
+pub async fn send(transport: &Transport, options: &CheckOptions, notification: &Notification) {
+    if !notification.is_notable() {
+        debug!("No new findings and no status change, skipping notification.");
+        return;
+    }
+
+    let body = match serde_json::to_string(notification) {
+        Ok(body) => body,
+        Err(e) => {
+            error!("Serializing notification failed! {e}");
+            return;
+        }
+    };
+
+    let mut headers = vec![(CONTENT_TYPE, "application/json".to_string())];
+    if let Some(secret) = &options.webhook_secret {
+        headers.push((SIGNATURE_HEADER, format!("sha256={}", sign(secret, &body))));
+    }
+
+    for url in &options.webhooks {
+        let mut attempt = 0;
+        loop {
+            match transport.post(url, &body, &headers).await {
+                Ok(()) => {
+                    info!("Sent notification to {url}.");
+                    break;
+                }
+                Err(e) if attempt < options.webhook_retries => {
+                    attempt += 1;
+                    warn!("Sending notification to {url} failed, retry {attempt} of {}: {e}", options.webhook_retries);
+                    tokio::time::sleep(RETRY_DELAY * attempt).await;
+                }
+                Err(e) => {
+                    error!("Sending notification to {url} failed! {e}");
+                    break;
+                }
+            }
+        }
+    }
+
+    if let Some(email) = &options.email {
+        let (subject, text) = notification.mail();
+        // The SMTP client logs its errors.
+        if smtp::send(email, &subject, &text).await.is_ok() {
+            info!("Sent notification mail to {}.", email.to.join(", "));
+        }
+    }
+}
+
+/// Send the notifications to the chats, if any of their repositories is notable.
+///
+/// Each chat gets the repositories it is configured for. Failures are logged, but don't fail the check.
+pub async fn send_chats(transport: &Transport, chats: &[(ChatTarget, Notification)], report_url: Option<&str>) {
+    for (chat, notification) in chats {
+        if notification.is_notable() {
+            send_chat(transport, chat, notification, report_url).await;
+        }
+    }
+}
+
+/// Send the compact summary to a Slack channel or a Matrix room.
+async fn send_chat(transport: &Transport, chat: &ChatTarget, notification: &Notification, report_url: Option<&str>) {
+    let json = vec![(CONTENT_TYPE, "application/json".to_string())];
+    let result = match chat {
+        ChatTarget::Slack { webhook } => {
+            let lines = notification.chat_lines(report_url, |text| format!("*{text}*"), |text, url| format!("<{url}|{text}>"));
+            let message = serde_json::json!({ "text": lines.join("\n") });
+            transport.post(webhook, &message.to_string(), &json).await.map(|_| "Slack channel".to_string())
+        }
+        ChatTarget::Matrix { homeserver, room, token } => {
+            let (subject, mut text) = notification.mail();
+            if let Some(url) = report_url {
+                text.push_str(&format!("Full report: {url}\n"));
+            }
+            let html = notification.chat_lines(report_url, |text| format!("<b>{text}</b>"), |text, url| format!("<a href=\"{}\">{text}</a>", escape_html(url)));
+            let message = serde_json::json!({
+                "msgtype": "m.text",
+                "body": format!("{subject}\n\n{text}"),
+                "format": "org.matrix.custom.html",
+                "formatted_body": html.join("<br>"),
+            });
+
+            // Matrix deduplicates messages by transaction ID, so it's unique per process and message.
+            let transaction = format!("aptcheckr-{}-{}", chrono::Utc::now().timestamp_millis(), MATRIX_TRANSACTION.fetch_add(1, Ordering::Relaxed));
+            let url = format!(
+                "{}/_matrix/client/v3/rooms/{}/send/m.room.message/{transaction}",
+                homeserver.trim_end_matches('/'),
+                utf8_percent_encode(room, NON_ALPHANUMERIC)
+            );
+            let mut headers = json;
+            headers.push((AUTHORIZATION, format!("Bearer {token}")));
+            transport.put(&url, &message.to_string(), &headers).await.map(|_| format!("Matrix room {room}"))
+        }
+    };
+
+    match result {
+        Ok(chat) => info!("Sent notification to {chat}."),
+        Err(e) => error!("Sending chat notification failed! {e}"),
+    }
+}
@@ -0,0 +1,1053 @@
+//! Options controlling an apt repo check.
+
+use std::collections::HashMap;
+use std::net::SocketAddr;
+use std::time::Duration;
+
+use libapt::Key;
+use reqwest::Url;
+use serde::{Deserialize, Serialize};
+
+use crate::auth::{self, AuthEntry};
+use crate::finding::{FindingKind, Severity};
+
+/// CheckOptions groups all user-provided settings of a repo check.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct CheckOptions {
+    // Components to check. Empty means all components of the release.
+    pub components: Vec<String>,
+    // Architectures to check. Empty means all architectures of the release.
+    pub architectures: Vec<String>,
+    // Components not to check, e.g. main/debug.
+    pub skip_components: Vec<String>,
+    // Architectures not to check, e.g. riscv64.
+    pub skip_architectures: Vec<String>,
+    // Check only the binary indices, e.g. for repos without sources.
+    pub binaries_only: bool,
+    // Check only the source indices.
+    pub sources_only: bool,
+    // Check existence of referenced files.
+    pub check_files: bool,
+    // Download referenced files and verify their hashes. Requires check_files.
+    pub verify_hashes: bool,
+    // Check the InRelease file against the Debian policy.
+    pub check_compliance: bool,
+    // Verify the InRelease signature and check the signing key.
+    pub check_signature: bool,
+    // Verify the compression variants of the indices against the Release file.
+    pub check_index_hashes: bool,
+    // Check that the detached Release and Release.gpg files exist, match InRelease and are validly signed.
+    pub check_detached_release: bool,
+    // Check the dependencies of binary packages.
+    pub check_dependencies: bool,
+    // Check that the sources of binary packages are available.
+    pub check_sources: bool,
+    // Weakest relation resolved by the dependency check.
+    pub resolve: Resolve,
+    // Report dependencies and sources resolved from other components or base repositories as info findings.
+    pub note_cross_component: bool,
+    // Repositories used read-only for the resolution of dependencies and sources.
+    pub base_repos: Vec<BaseRepo>,
+    // Resolve the build dependencies of the sources against the binary indices.
+    pub check_build_depends: bool,
+    // Analyze Breaks, Conflicts and Replaces of binary packages.
+    pub check_conflicts: bool,
+    // Check that the binaries listed by the sources are published.
+    pub check_source_binaries: bool,
+    // Validate the Multi-Arch fields, and that Multi-Arch: same packages have the same versions on all architectures.
+    pub check_multi_arch: bool,
+    // Check that the files of the sources match their format, e.g. orig and debian tarballs of 3.0 (quilt) sources.
+    pub check_source_format: bool,
+    // Check that the Essential and required packages form a complete set.
+    pub check_essential: bool,
+    // Compare the control files of the deb files with the index stanzas.
+    pub check_control: bool,
+    // Download the deb files and validate their ar layout and tarballs.
+    pub check_deb_structure: bool,
+    // Check that Filename and Directory fields follow the pool layout.
+    pub check_pool_layout: bool,
+    // Report packages listed twice in an index, and pool files listed with different hashes.
+    pub check_duplicates: bool,
+    // Walk the pool of a local repository and report files not referenced by any index.
+    pub check_orphans: bool,
+    // Check that all files and directories of a local repository are readable, also by other users.
+    pub check_permissions: bool,
+    // Local directory of the repository for the orphan check, e.g. a mount of the served directory. The path of file:// URLs if None.
+    pub local_root: Option<String>,
+    // Report sources below the fixed version of a CVE, using the security tracker data.
+    pub check_cves: bool,
+    // URL or file of the security tracker data, the Debian tracker JSON or the Ubuntu CVE JSON. The Debian tracker if None.
+    pub cve_tracker: Option<String>,
+    // Release of the tracker data, e.g. bookworm. The codename of the release without pocket suffix if None.
+    pub cve_release: Option<String>,
+    // URL or file of an OVAL feed, e.g. oval-definitions-bookworm.xml.bz2. Its definitions are evaluated against the packages.
+    pub oval: Option<String>,
+    // Check that no source of the suite is newer than in its security or updates pocket given as base repository.
+    pub check_pockets: bool,
+    // Report Release files and stanzas without SHA256 or SHA512 hashes, which apt refuses.
+    pub check_hash_policy: bool,
+    // Severity of the weak hash findings. Default severity of the finding if None.
+    pub weak_hash_severity: Option<Severity>,
+    // Validate the metadata fields of the stanzas, e.g. Section, Priority, Maintainer and Size.
+    pub check_fields: bool,
+    // Check that required and important packages are available on all architectures.
+    pub check_priority_coverage: bool,
+    // Summarize the findings per maintainer in the result.
+    pub maintainer_summary: bool,
+    // Verify the pdiffs of the binary indices.
+    pub check_pdiffs: bool,
+    // Apply the pdiffs to old index versions. Requires check_pdiffs.
+    pub apply_pdiffs: bool,
+    // Check the debian-installer indices and the referenced udeb files.
+    pub check_udeb: bool,
+    // Cross-reference the Contents indices with the binary indices.
+    pub check_contents: bool,
+    // Cross-reference the command-not-found metadata with the binary indices.
+    pub check_commands: bool,
+    // Verify the Translation indices and i18n/Index files of the components.
+    pub check_translations: bool,
+    // Languages which must have a Translation index, e.g. en. Requires check_translations.
+    pub required_translations: Vec<String>,
+    // Verify the DEP-11 icon tarballs against the Release file and the Components YAML.
+    pub check_dep11: bool,
+    // Directory for checkpoints. No checkpoints are written if None.
+    pub state_dir: Option<String>,
+    // Continue from the last checkpoint found in the state dir.
+    pub resume: bool,
+    // Keep the parsed indices and add them to the result.
+    pub retain_indices: bool,
+    // Settings of the HTTP client.
+    pub http: HttpOptions,
+    // Time budget in seconds. No new checks are started when it is exhausted.
+    pub max_duration: Option<u64>,
+    // Files probed by the file checks.
+    pub sample: Sample,
+    // Seed for the file sampling. A random seed is used if None.
+    pub seed: Option<u64>,
+    // Required remaining validity of the release in seconds.
+    pub min_validity: Option<u64>,
+    // Further signing keys. The InRelease signature is valid if any key verifies it.
+    pub keys: Vec<Key>,
+    // Fingerprints of the keys allowed to sign the InRelease file. Any key is allowed if empty.
+    pub expected_fingerprints: Vec<String>,
+    // Required remaining validity of the signing key in seconds.
+    pub key_min_validity: Option<u64>,
+    // Severities replacing the defaults of the finding kinds. (Kind, Severity)
+    pub severity_overrides: HashMap<FindingKind, Severity>,
+    // File the result is saved to.
+    pub output: String,
+    // Severity of Valid-Until findings. Default severity of the finding if None.
+    pub validity_severity: Option<Severity>,
+    // Expect NotAutomatic: yes, e.g. for backports suites.
+    pub expect_not_automatic: bool,
+    // Expect ButAutomaticUpgrades: yes, e.g. for backports suites whose installed packages are upgraded.
+    pub expect_but_automatic_upgrades: bool,
+    // Maximum age of the release in seconds.
+    pub max_age: Option<u64>,
+    // Base URL of the upstream archive the repository mirrors. The lag behind it is reported.
+    pub upstream: Option<String>,
+    // Maximum lag of the Release Date behind the upstream archive in seconds.
+    pub max_lag: Option<u64>,
+    // JSON file of known findings, which don't fail the check.
+    pub baseline: Option<String>,
+    // Write all findings to the baseline file instead of reading it.
+    pub write_baseline: bool,
+    // Report of an earlier run. Its findings are known, so only new findings fail the check.
+    pub compare_with: Option<String>,
+    // Lowest severity of the findings which fail the check.
+    pub fail_on: Severity,
+    // Report the progress of long-running phases, as progress bar on a terminal, else as log lines.
+    pub progress: bool,
+    // Glob patterns or /regular expressions/ of the packages whose dependencies, sources and files are checked. All packages if empty.
+    pub packages: Vec<String>,
+    // File the Prometheus metrics are written to, e.g. for the textfile collector of the node exporter.
+    pub metrics_file: Option<String>,
+    // URL of a Prometheus Pushgateway the metrics are pushed to.
+    pub pushgateway: Option<String>,
+    // SQLite database every run is recorded in.
+    pub history: Option<String>,
+    // Maximum time a phased update may keep its Phased-Update-Percentage, in seconds. Requires history.
+    pub max_phasing_age: Option<u64>,
+    // File the URLs of the missing and corrupt files are written to, one per line.
+    pub missing_files_out: Option<String>,
+    // Write the paths relative to the repository root, e.g. pool/main/h/hello/hello_1.0-1_amd64.deb, instead of URLs.
+    pub missing_files_relative: bool,
+    // URLs the summary is posted to, if new findings are found or the status changes.
+    // The URLs may contain credentials, so they're not saved in the result.
+    #[serde(skip_serializing, default)]
+    pub webhooks: Vec<String>,
+    // Secret of the HMAC-SHA256 signature of the notifications. Unsigned if None. Not saved in the result.
+    #[serde(skip_serializing)]
+    pub webhook_secret: Option<String>,
+    // Retries of failed notifications.
+    pub webhook_retries: u32,
+    // SMTP settings of the mail notifications. No mails if None.
+    pub email: Option<EmailOptions>,
+    // Slack channels and Matrix rooms notified like the webhooks.
+    pub chats: Vec<ChatTarget>,
+    // URL of the full report, linked in the chat messages.
+    pub report_url: Option<String>,
+}
+
+impl Default for CheckOptions {
+    fn default() -> Self {
+        let mut options = CheckOptions {
+            components: Vec::new(),
+            architectures: Vec::new(),
+            skip_components: Vec::new(),
+            skip_architectures: Vec::new(),
+            binaries_only: false,
+            sources_only: false,
+            check_files: false,
+            verify_hashes: false,
+            check_compliance: true,
+            check_signature: true,
+            check_index_hashes: true,
+            check_detached_release: false,
+            check_dependencies: false,
+            check_sources: false,
+            resolve: Resolve::Depends,
+            note_cross_component: false,
+            base_repos: Vec::new(),
+            check_build_depends: false,
+            check_conflicts: false,
+            check_source_binaries: false,
+            check_multi_arch: true,
+            check_source_format: true,
+            check_essential: false,
+            check_control: false,
+            check_deb_structure: false,
+            check_pool_layout: false,
+            check_duplicates: true,
+            check_orphans: false,
+            check_permissions: false,
+            local_root: None,
+            check_cves: false,
+            cve_tracker: None,
+            cve_release: None,
+            oval: None,
+            check_pockets: true,
+            check_hash_policy: true,
+            weak_hash_severity: None,
+            check_fields: false,
+            check_priority_coverage: false,
+            maintainer_summary: false,
+            check_pdiffs: false,
+            apply_pdiffs: false,
+            check_udeb: false,
+            check_contents: false,
+            check_commands: false,
+            check_translations: false,
+            required_translations: Vec::new(),
+            check_dep11: false,
+            state_dir: None,
+            resume: false,
+            retain_indices: false,
+            http: HttpOptions::default(),
+            max_duration: None,
+            sample: Sample::All,
+            seed: None,
+            min_validity: None,
+            keys: Vec::new(),
+            expected_fingerprints: Vec::new(),
+            key_min_validity: None,
+            severity_overrides: HashMap::new(),
+            output: "result.json".to_string(),
+            validity_severity: None,
+            expect_not_automatic: false,
+            expect_but_automatic_upgrades: false,
+            max_age: None,
+            upstream: None,
+            max_lag: None,
+            baseline: None,
+            write_baseline: false,
+            compare_with: None,
+            fail_on: Severity::Warning,
+            progress: false,
+            packages: Vec::new(),
+            metrics_file: None,
+            pushgateway: None,
+            history: None,
+            max_phasing_age: None,
+            missing_files_out: None,
+            missing_files_relative: false,
+            webhooks: Vec::new(),
+            webhook_secret: None,
+            webhook_retries: 3,
+            email: None,
+            chats: Vec::new(),
+            report_url: None,
+        };
+        Profile::Standard.apply(&mut options);
+        options
+    }
+}
+
+/// Predefined sets of checks.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize, Serialize)]
+pub enum Profile {
+    // InRelease and index hashes only.
+    Quick,
+    // Additionally dependencies and sources of binary packages, duplicate stanzas, the Multi-Arch fields, the files of the sources, pocket inversions and the hash policy.
+    #[default]
+    Standard,
+    // Additionally the detached Release files, existence, size and hashes of all referenced files, the control files of the debs, the pdiffs, Contents and Translation indices, the DEP-11 icons, and the stanza fields.
+    Deep,
+}
+
+impl Profile {
+    /// Parse the profile from its CLI representation.
+    pub fn parse(profile: &str) -> Option<Profile> {
+        match profile.to_lowercase().as_str() {
+            "quick" => Some(Profile::Quick),
+            "standard" => Some(Profile::Standard),
+            "deep" => Some(Profile::Deep),
+            _ => None,
+        }
+    }
+
+    /// Enable the checks of the profile and disable all others.
+    pub fn apply(&self, options: &mut CheckOptions) {
+        let standard = *self != Profile::Quick;
+        let deep = *self == Profile::Deep;
+
+        options.check_files = deep;
+        options.verify_hashes = deep;
+        options.check_compliance = true;
+        options.check_signature = true;
+        options.check_index_hashes = true;
+        options.check_detached_release = deep;
+        options.check_dependencies = standard;
+        options.check_sources = standard;
+        options.check_build_depends = false;
+        options.check_conflicts = false;
+        options.check_source_binaries = false;
+        options.check_multi_arch = standard;
+        options.check_source_format = standard;
+        options.check_essential = false;
+        options.check_control = deep;
+        options.check_deb_structure = deep;
+        options.check_pool_layout = false;
+        options.check_duplicates = standard;
+        options.check_orphans = false;
+        options.check_permissions = false;
+        options.check_cves = false;
+        options.check_pockets = standard;
+        options.check_hash_policy = standard;
+        options.check_fields = deep;
+        options.check_priority_coverage = false;
+        options.check_pdiffs = deep;
+        options.apply_pdiffs = false;
+        options.check_udeb = false;
+        options.check_contents = deep;
+        options.check_commands = false;
+        options.check_translations = deep;
+        options.check_dep11 = deep;
+    }
+}
+
+/// Checks which can be enabled or disabled individually.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+pub enum CheckId {
+    Compliance,
+    Signature,
+    DetachedRelease,
+    IndexHashes,
+    Dependencies,
+    Sources,
+    BuildDepends,
+    Conflicts,
+    SourceBinaries,
+    MultiArch,
+    SourceFormat,
+    Essential,
+    Files,
+    Hashes,
+    Control,
+    DebStructure,
+    PoolLayout,
+    Duplicates,
+    Orphans,
+    Permissions,
+    Cves,
+    Pockets,
+    HashPolicy,
+    Fields,
+    PriorityCoverage,
+    Pdiffs,
+    Udeb,
+    Contents,
+    Commands,
+    Translations,
+    Dep11,
+}
+
+impl CheckId {
+    /// CLI representations of all checks.
+    pub const NAMES: [&'static str; 31] = [
+        "compliance",
+        "signature",
+        "detached-release",
+        "index-hashes",
+        "dependencies",
+        "sources",
+        "build-depends",
+        "conflicts",
+        "source-binaries",
+        "multi-arch",
+        "source-format",
+        "essential",
+        "files",
+        "hashes",
+        "control",
+        "deb-structure",
+        "pool-layout",
+        "duplicates",
+        "orphans",
+        "permissions",
+        "cves",
+        "pockets",
+        "hash-policy",
+        "fields",
+        "priority-coverage",
+        "pdiffs",
+        "udeb",
+        "contents",
+        "commands",
+        "translations",
+        "dep11",
+    ];
+
+    /// Parse the check from its CLI representation.
+    pub fn parse(id: &str) -> Option<CheckId> {
+        match id.to_lowercase().as_str() {
+            "compliance" => Some(CheckId::Compliance),
+            "signature" => Some(CheckId::Signature),
+            "detached-release" => Some(CheckId::DetachedRelease),
+            "index-hashes" => Some(CheckId::IndexHashes),
+            "dependencies" => Some(CheckId::Dependencies),
+            "sources" => Some(CheckId::Sources),
+            "build-depends" => Some(CheckId::BuildDepends),
+            "conflicts" => Some(CheckId::Conflicts),
+            "source-binaries" => Some(CheckId::SourceBinaries),
+            "multi-arch" => Some(CheckId::MultiArch),
+            "source-format" => Some(CheckId::SourceFormat),
+            "essential" => Some(CheckId::Essential),
+            "files" => Some(CheckId::Files),
+            "hashes" => Some(CheckId::Hashes),
+            "control" => Some(CheckId::Control),
+            "deb-structure" => Some(CheckId::DebStructure),
+            "pool-layout" => Some(CheckId::PoolLayout),
+            "duplicates" => Some(CheckId::Duplicates),
+            "orphans" => Some(CheckId::Orphans),
+            "permissions" => Some(CheckId::Permissions),
+            "cves" => Some(CheckId::Cves),
+            "pockets" => Some(CheckId::Pockets),
+            "hash-policy" => Some(CheckId::HashPolicy),
+            "fields" => Some(CheckId::Fields),
+            "priority-coverage" => Some(CheckId::PriorityCoverage),
+            "pdiffs" => Some(CheckId::Pdiffs),
+            "udeb" => Some(CheckId::Udeb),
+            "contents" => Some(CheckId::Contents),
+            "commands" => Some(CheckId::Commands),
+            "translations" => Some(CheckId::Translations),
+            "dep11" => Some(CheckId::Dep11),
+            _ => None,
+        }
+    }
+
+    /// Enable or disable the check.
+    ///
+    /// Enabling a check also enables the checks it requires, e.g. hashes enables files.
+    pub fn set(&self, options: &mut CheckOptions, enabled: bool) {
+        match self {
+            CheckId::Compliance => options.check_compliance = enabled,
+            CheckId::Signature => options.check_signature = enabled,
+            CheckId::DetachedRelease => options.check_detached_release = enabled,
+            CheckId::IndexHashes => options.check_index_hashes = enabled,
+            CheckId::Dependencies => options.check_dependencies = enabled,
+            CheckId::Sources => options.check_sources = enabled,
+            CheckId::BuildDepends => options.check_build_depends = enabled,
+            CheckId::Conflicts => options.check_conflicts = enabled,
+            CheckId::SourceBinaries => options.check_source_binaries = enabled,
+            CheckId::MultiArch => options.check_multi_arch = enabled,
+            CheckId::SourceFormat => options.check_source_format = enabled,
+            CheckId::Essential => options.check_essential = enabled,
+            CheckId::Files => options.check_files = enabled,
+            CheckId::Hashes => {
+                options.verify_hashes = enabled;
+                options.check_files |= enabled;
+            }
+            CheckId::Control => options.check_control = enabled,
+            CheckId::DebStructure => {
+                options.check_deb_structure = enabled;
+                options.check_files |= enabled;
+            }
+            CheckId::PoolLayout => options.check_pool_layout = enabled,
+            CheckId::Duplicates => options.check_duplicates = enabled,
+            CheckId::Orphans => options.check_orphans = enabled,
+            CheckId::Permissions => options.check_permissions = enabled,
+            CheckId::Cves => options.check_cves = enabled,
+            CheckId::Pockets => options.check_pockets = enabled,
+            CheckId::HashPolicy => options.check_hash_policy = enabled,
+            CheckId::Fields => options.check_fields = enabled,
+            CheckId::PriorityCoverage => options.check_priority_coverage = enabled,
+            CheckId::Pdiffs => options.check_pdiffs = enabled,
+            CheckId::Udeb => options.check_udeb = enabled,
+            CheckId::Contents => options.check_contents = enabled,
+            CheckId::Commands => options.check_commands = enabled,
+            CheckId::Translations => options.check_translations = enabled,
+            CheckId::Dep11 => options.check_dep11 = enabled,
+        }
+    }
+}
+
+/// Relations resolved by the dependency check.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Deserialize, Serialize)]
+pub enum Resolve {
+    // Pre-Depends and Depends.
+    #[default]
+    Depends,
+    // Additionally Recommends.
+    Recommends,
+    // Additionally Recommends and Suggests.
+    Suggests,
+}
+
+impl Resolve {
+    /// Parse the level from its CLI representation.
+    pub fn parse(resolve: &str) -> Option<Resolve> {
+        match resolve.to_lowercase().as_str() {
+            "depends" => Some(Resolve::Depends),
+            "recommends" => Some(Resolve::Recommends),
+            "suggests" => Some(Resolve::Suggests),
+            _ => None,
+        }
+    }
+}
+
+/// Repository the checked repository builds on, e.g. the archive of a PPA.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub struct BaseRepo {
+    // URL of the repository.
+    pub url: String,
+    // Suite of the repository. Defaults to the suite of the checked repository.
+    pub suite: Option<String>,
+}
+
+impl BaseRepo {
+    /// Parse the base repository from its CLI representation, i.e. url[,suite].
+    pub fn parse(base: &str) -> BaseRepo {
+        match base.split_once(',') {
+            Some((url, suite)) => BaseRepo {
+                url: url.trim().to_string(),
+                suite: Some(suite.trim().to_string()),
+            },
+            None => BaseRepo {
+                url: base.trim().to_string(),
+                suite: None,
+            },
+        }
+    }
+}
+
+/// Selection of the files probed per component and architecture.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Deserialize, Serialize)]
+pub enum Sample {
+    // Probe all files.
+    #[default]
+    All,
+    // Probe the given percentage of the files.
+    Percent(f64),
+    // Probe at most the given number of files.
+    Count(usize),
+}
+
+impl Sample {
+    /// Parse a percentage like "5%" from its CLI representation.
+    pub fn parse(percent: &str) -> Option<Sample> {
+        let percent = percent.trim();
+        let percent = percent.strip_suffix('%').unwrap_or(percent);
+        match percent.trim().parse::<f64>() {
+            Ok(percent) if percent > 0.0 && percent <= 100.0 => Some(Sample::Percent(percent)),
+            _ => None,
+        }
+    }
+
+    /// Number of files to probe out of the given total.
+    pub fn count(&self, total: usize) -> usize {
+        match self {
+            Sample::All => total,
+            Sample::Percent(percent) => ((total as f64 * percent / 100.0).ceil() as usize).min(total),
+            Sample::Count(count) => (*count).min(total),
+        }
+    }
+}
+
+/// Usage of HTTP/2 for downloads.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize, Serialize)]
+pub enum Http2Mode {
+    // Negotiate HTTP/2 using ALPN, fall back to HTTP/1.1.
+    #[default]
+    Auto,
+    // Use HTTP/2 without negotiation.
+    On,
+    // Use only HTTP/1.1.
+    Off,
+}
+
+impl Http2Mode {
+    /// Parse the mode from its CLI representation.
+    pub fn parse(mode: &str) -> Option<Http2Mode> {
+        match mode.to_lowercase().as_str() {
+            "auto" => Some(Http2Mode::Auto),
+            "on" => Some(Http2Mode::On),
+            "off" => Some(Http2Mode::Off),
+            _ => None,
+        }
+    }
+}
+
+/// Request method used to check the existence of files.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize, Serialize)]
+pub enum ProbeMethod {
+    // HEAD request, falling back to a ranged GET for hosts with broken HEAD support.
+    #[default]
+    Head,
+    // GET request for the first byte only.
+    Range,
+    // GET request of the full file.
+    Get,
+}
+
+impl ProbeMethod {
+    /// Parse the method from its CLI representation.
+    pub fn parse(method: &str) -> Option<ProbeMethod> {
+        match method.to_lowercase().as_str() {
+            "head" => Some(ProbeMethod::Head),
+            "range" => Some(ProbeMethod::Range),
+            "get" => Some(ProbeMethod::Get),
+            _ => None,
+        }
+    }
+}
+
+/// HttpOptions groups the settings of the shared HTTP client.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct HttpOptions {
+    // Maximum number of parallel connections per host.
+    pub max_connections_per_host: usize,
+    // Usage of HTTP/2.
+    pub http2: Http2Mode,
+    // Seconds idle connections are kept alive. Client default if None.
+    pub keep_alive: Option<u64>,
+    // Request method for file existence checks.
+    pub probe_method: ProbeMethod,
+    // Timeout of a single request in seconds. No timeout if None.
+    pub timeout: Option<u64>,
+    // Number of retries of failed requests.
+    pub retries: u32,
+    // Settings overriding the defaults for single hosts. (Host, Settings)
+    pub hosts: HashMap<String, HostOptions>,
+    // User for HTTP basic authentication of the checked repositories. Not saved in the result.
+    #[serde(skip_serializing)]
+    pub username: Option<String>,
+    // Password for HTTP basic authentication. Not saved in the result.
+    #[serde(skip_serializing)]
+    pub password: Option<String>,
+    // Proxy for all requests, overriding the http_proxy and https_proxy env vars. Not saved in the result.
+    #[serde(skip_serializing)]
+    pub proxy: Option<String>,
+    // PEM file of additional trusted CA certificates, e.g. of an internal CA.
+    pub ca_cert: Option<String>,
+    // Accept invalid server certificates. Only for testing.
+    #[serde(default)]
+    pub insecure: bool,
+    // PEM file of the client TLS certificate.
+    pub tls_cert: Option<String>,
+    // PEM file of the PKCS#8 key of the client TLS certificate.
+    pub tls_key: Option<String>,
+    // Token for bearer authentication of the checked repositories, if their host has no own token. Not saved in the result.
+    #[serde(skip_serializing)]
+    pub token: Option<String>,
+    // Query parameter the global token is sent in instead of the Authorization header, e.g. token.
+    pub token_parameter: Option<String>,
+    // Logins per machine, e.g. read from apt auth.conf files. Used if no user or token is given.
+    #[serde(skip)]
+    pub auth_entries: Vec<AuthEntry>,
+    // Origins of the checked repositories, i.e. scheme, host and port. The global user and token are only sent to them.
+    #[serde(skip)]
+    pub auth_origins: Vec<String>,
+}
+
+/// Authentication of a request.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Auth {
+    // Basic authentication. (User, Password)
+    Basic(String, Option<String>),
+    // Bearer authentication with the given token.
+    Bearer(String),
+    // Token sent as query parameter. (Parameter, Token)
+    Query(String, String),
+}
+
+impl HttpOptions {
+    /// Maximum number of parallel connections to the given host.
+    pub fn max_connections_for(&self, host: &str) -> usize {
+        self.hosts
+            .get(host)
+            .and_then(|options| options.max_connections)
+            .unwrap_or(self.max_connections_per_host)
+    }
+
+    /// Request timeout in seconds for the given host.
+    pub fn timeout_for(&self, host: &str) -> Option<u64> {
+        self.hosts
+            .get(host)
+            .and_then(|options| options.timeout)
+            .or(self.timeout)
+    }
+
+    /// Authentication of requests to the given URL.
+    ///
+    /// The token of the host takes precedence over the global user and token, which are only used
+    /// for the checked repositories, and the machine logins.
+    pub fn auth_for(&self, url: &str) -> Option<Auth> {
+        let parsed = Url::parse(url).ok();
+        let host = parsed.as_ref().and_then(|url| url.host_str().map(|host| host.to_string())).unwrap_or_default();
+        if let Some(options) = self.hosts.get(&host) {
+            if let Some(token) = &options.token {
+                return Some(token_auth(token, options.token_parameter.as_ref()));
+            }
+        }
+
+        let is_repo = parsed.as_ref().and_then(origin_of).is_some_and(|origin| self.auth_origins.contains(&origin));
+        if is_repo {
+            if let Some(username) = &self.username {
+                return Some(Auth::Basic(username.clone(), self.password.clone()));
+            }
+            if let Some(token) = &self.token {
+                return Some(token_auth(token, self.token_parameter.as_ref()));
+            }
+        }
+        auth::find(&self.auth_entries, url).map(|entry| Auth::Basic(entry.login.clone(), entry.password.clone()))
+    }
+
+    /// Get the options with the global user and token restricted to the repositories of the given URLs.
+    pub fn for_repos<'a>(&self, urls: impl IntoIterator<Item = &'a str>) -> HttpOptions {
+        let mut options = self.clone();
+        options.auth_origins = urls.into_iter().filter_map(|url| origin_of(&Url::parse(url).ok()?)).collect();
+        options
+    }
+
+    /// Get the options without any credentials, for requests to other services, e.g. webhooks or security feeds.
+    pub fn without_auth(&self) -> HttpOptions {
+        let mut options = self.clone();
+        options.username = None;
+        options.password = None;
+        options.token = None;
+        options.auth_entries.clear();
+        options.auth_origins.clear();
+        for host in options.hosts.values_mut() {
+            host.token = None;
+        }
+        options
+    }
+
+    /// Number of retries for the given host.
+    pub fn retries_for(&self, host: &str) -> u32 {
+        self.hosts
+            .get(host)
+            .and_then(|options| options.retries)
+            .unwrap_or(self.retries)
+    }
+}
+
+/// Network settings for a single host.
+///
+/// Settings which are not given fall back to the defaults.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(deny_unknown_fields)]
+pub struct HostOptions {
+    // Maximum number of parallel connections.
+    pub max_connections: Option<usize>,
+    // Timeout of a single request in seconds.
+    pub timeout: Option<u64>,
+    // Number of retries of failed requests.
+    pub retries: Option<u32>,
+    // Token for bearer authentication. Not saved in the result.
+    #[serde(skip_serializing)]
+    pub token: Option<String>,
+    // Query parameter the token is sent in instead of the Authorization header, e.g. token.
+    pub token_parameter: Option<String>,
+}
+
+impl Default for HttpOptions {
+    fn default() -> Self {
+        HttpOptions {
+            max_connections_per_host: 8,
+            http2: Http2Mode::Auto,
+            keep_alive: None,
+            probe_method: ProbeMethod::Head,
+            timeout: None,
+            retries: 0,
+            hosts: HashMap::new(),
+            username: None,
+            password: None,
+            proxy: None,
+            ca_cert: None,
+            insecure: false,
+            tls_cert: None,
+            tls_key: None,
+            token: None,
+            token_parameter: None,
+            auth_entries: Vec::new(),
+            auth_origins: Vec::new(),
+        }
+    }
+}
+
+/// Get the authentication with the token, as query parameter if a parameter is given.
+fn token_auth(token: &str, parameter: Option<&String>) -> Auth {
+    match parameter {
+        Some(parameter) => Auth::Query(parameter.clone(), token.to_string()),
+        None => Auth::Bearer(token.to_string()),
+    }
+}
+
+/// Get the origin of the URL, e.g. https://deb.example.org:443.
+fn origin_of(url: &Url) -> Option<String> {
+    Some(format!("{}://{}:{}", url.scheme(), url.host_str()?, url.port_or_known_default()?))
+}
+
+/// BenchOptions groups the settings of a mirror benchmark.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct BenchOptions {
+    // Number of index and pool files to download.
+    pub samples: usize,
+    // Number of downloads per file.
+    pub rounds: usize,
+    // Settings of the HTTP client.
+    pub http: HttpOptions,
+}
+
+impl Default for BenchOptions {
+    fn default() -> Self {
+        BenchOptions {
+            samples: 10,
+            rounds: 3,
+            http: HttpOptions::default(),
+        }
+    }
+}
+
+/// CompareOptions groups the settings of a mirror comparison.
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+pub struct CompareOptions {
+    // Components to compare. All components of both mirrors if empty.
+    pub components: Vec<String>,
+    // Architectures to compare, including source. All architectures of both mirrors if empty.
+    pub architectures: Vec<String>,
+    // Settings of the HTTP client.
+    pub http: HttpOptions,
+}
+
+/// StatsOptions groups the settings of the repository statistics.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct StatsOptions {
+    // Components to count. All components if empty.
+    pub components: Vec<String>,
+    // Architectures to count, including source. All architectures if empty.
+    pub architectures: Vec<String>,
+    // Number of largest packages listed.
+    pub top: usize,
+    // Compute the matrix of the sources versus the architectures they have binaries for.
+    pub coverage: bool,
+    // Settings of the HTTP client.
+    pub http: HttpOptions,
+}
+
+impl Default for StatsOptions {
+    fn default() -> Self {
+        StatsOptions {
+            components: Vec::new(),
+            architectures: Vec::new(),
+            top: 10,
+            coverage: false,
+            http: HttpOptions::default(),
+        }
+    }
+}
+
+/// Encryption of the SMTP connection.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SmtpTls {
+    // Upgrade the connection using STARTTLS.
+    #[default]
+    Starttls,
+    // Connect using TLS, e.g. on port 465.
+    Tls,
+    // No encryption, e.g. for a local relay.
+    None,
+}
+
+/// EmailOptions groups the SMTP settings of the mail notifications.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(deny_unknown_fields)]
+pub struct EmailOptions {
+    // Host of the SMTP server.
+    pub server: String,
+    // Port of the SMTP server. Defaults to 465 for TLS, 587 for STARTTLS and 25 without encryption.
+    pub port: Option<u16>,
+    // Encryption of the connection.
+    #[serde(default)]
+    pub tls: SmtpTls,
+    // User for the SMTP authentication. No authentication if None.
+    pub username: Option<String>,
+    // Password for the SMTP authentication. Not saved in the result.
+    #[serde(skip_serializing)]
+    pub password: Option<String>,
+    // Sender address.
+    pub from: String,
+    // Recipient addresses.
+    pub to: Vec<String>,
+}
+
+impl EmailOptions {
+    /// Get the port of the SMTP server.
+    pub fn port(&self) -> u16 {
+        match (self.port, self.tls) {
+            (Some(port), _) => port,
+            (None, SmtpTls::Tls) => 465,
+            (None, SmtpTls::Starttls) => 587,
+            (None, SmtpTls::None) => 25,
+        }
+    }
+}
+
+/// Chat notified about the check results.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(tag = "type", rename_all = "lowercase", deny_unknown_fields)]
+pub enum ChatTarget {
+    // Slack incoming webhook. The URL contains the credentials, so it's not saved in the result.
+    Slack {
+        #[serde(skip_serializing, default)]
+        webhook: String,
+    },
+    // Matrix room, e.g. !abc:example.com, messaged with the access token of a bot user. The token is not saved in the result.
+    Matrix {
+        homeserver: String,
+        room: String,
+        #[serde(skip_serializing, default)]
+        token: String,
+    },
+}
+
+/// WatchOptions groups the settings of the watch mode.
+#[derive(Debug, Clone)]
+pub struct WatchOptions {
+    // Time between the checks of a repository.
+    pub interval: Duration,
+    // Address of the HTTP server exposing the latest results. No server if None.
+    pub listen: Option<SocketAddr>,
+}
+
+impl Default for WatchOptions {
+    fn default() -> Self {
+        WatchOptions {
+            interval: Duration::from_secs(60 * 60),
+            listen: None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Get the names of the enabled check flags.
+    fn enabled_checks(options: &CheckOptions) -> Vec<String> {
+        let value = serde_json::to_value(options).unwrap();
+        let mut enabled: Vec<String> = value
+            .as_object()
+            .unwrap()
+            .iter()
+            .filter(|(name, value)| (name.starts_with("check_") || *name == "verify_hashes" || *name == "apply_pdiffs") && value.as_bool() == Some(true))
+            .map(|(name, _)| name.clone())
+            .collect();
+        enabled.sort();
+        enabled
+    }
+
+    /// Get options with all checks enabled.
+    fn all_checks() -> CheckOptions {
+        let mut options = CheckOptions::default();
+        for name in CheckId::NAMES {
+            CheckId::parse(name).unwrap().set(&mut options, true);
+        }
+        options.apply_pdiffs = true;
+        options
+    }
+
+    #[test]
+    fn quick_profile() {
+        let mut options = all_checks();
+        Profile::Quick.apply(&mut options);
+        assert_eq!(enabled_checks(&options), ["check_compliance", "check_index_hashes", "check_signature"]);
+    }
+
+    #[test]
+    fn standard_profile_is_default() {
+        let mut options = all_checks();
+        Profile::Standard.apply(&mut options);
+        assert_eq!(enabled_checks(&options), enabled_checks(&CheckOptions::default()));
+        assert_eq!(
+            enabled_checks(&options),
+            [
+                "check_compliance",
+                "check_dependencies",
+                "check_duplicates",
+                "check_hash_policy",
+                "check_index_hashes",
+                "check_multi_arch",
+                "check_pockets",
+                "check_signature",
+                "check_source_format",
+                "check_sources",
+            ]
+        );
+    }
+
+    #[test]
+    fn deep_profile() {
+        let mut options = CheckOptions::default();
+        Profile::Deep.apply(&mut options);
+        let enabled = enabled_checks(&options);
+        for name in ["check_files", "verify_hashes", "check_deb_structure", "check_pdiffs", "check_dep11", "check_multi_arch"] {
+            assert!(enabled.iter().any(|check| check == name), "{name} is not enabled");
+        }
+        assert!(!options.apply_pdiffs);
+    }
+
+    #[test]
+    fn sample_parse() {
+        assert_eq!(Sample::parse("5%"), Some(Sample::Percent(5.0)));
+        assert_eq!(Sample::parse(" 12.5 "), Some(Sample::Percent(12.5)));
+        assert_eq!(Sample::parse("100%"), Some(Sample::Percent(100.0)));
+        assert_eq!(Sample::parse("0%"), None);
+        assert_eq!(Sample::parse("101%"), None);
+        assert_eq!(Sample::parse("many"), None);
+    }
+
+    #[test]
+    fn sample_count() {
+        assert_eq!(Sample::All.count(7), 7);
+        assert_eq!(Sample::Percent(10.0).count(25), 3);
+        assert_eq!(Sample::Percent(1.0).count(1), 1);
+        assert_eq!(Sample::Percent(100.0).count(25), 25);
+        assert_eq!(Sample::Count(10).count(25), 10);
+        assert_eq!(Sample::Count(10).count(4), 4);
+        assert_eq!(Sample::Percent(50.0).count(0), 0);
+    }
+}
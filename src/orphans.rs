@@ -0,0 +1,81 @@
+//! Detection of pool files not referenced by any index.
+//!
+//! Only local repositories can be walked, i.e. file:// URLs or repositories mounted at a local directory.
+//! The pool is shared by the suites of an archive, so the indices of all given releases are read.
+
+use std::collections::HashSet;
+use std::fs;
+use std::path::Path;
+
+use libapt::{Error, Link, Release, Result};
+use log::{debug, error, info};
+
+use crate::index;
+use crate::transport::Transport;
+
+/// Get the links of the Packages and Sources indices of a release, one per index, preferring compressed variants.
+fn index_links(release: &Release) -> Vec<&Link> {
+    let mut paths: Vec<&str> = release
+        .links
+        .keys()
+        .map(|url| url.trim_end_matches(".xz").trim_end_matches(".gz"))
+        .filter(|url| url.ends_with("/Packages") || url.ends_with("/Sources"))
+        .collect();
+    paths.sort();
+    paths.dedup();
+
+    paths
+        .into_iter()
+        .filter_map(|path| [".xz", ".gz", ""].iter().find_map(|extension| release.links.get(&format!("{path}{extension}"))))
+        .collect()
+}
+
+/// Collect the pool files referenced by the Packages and Sources indices of the releases.
+///
+/// An index which can't be loaded fails, as its files would be reported as orphaned.
+pub async fn referenced_files(transport: &Transport, releases: &[&Release]) -> Result<HashSet<String>> {
+    let mut files = HashSet::new();
+    for release in releases {
+        for link in index_links(release) {
+            let content = index::download_index(transport, link).await?;
+            let is_source = link.url.trim_end_matches(".xz").trim_end_matches(".gz").ends_with("/Sources");
+            for stanza in index::stanzas(&content) {
+                let stanza_files = if is_source { index::source_files(stanza) } else { index::package_files(stanza) };
+                // Flat repositories may prefix the paths with ./
+                files.extend(stanza_files.into_iter().map(|(path, _)| path.trim_start_matches("./").to_string()));
+            }
+            debug!("Read referenced files of index {}.", link.url);
+        }
+    }
+    Ok(files)
+}
+
+/// Collect the files below the directory recursively, as paths relative to the root.
+fn walk(root: &Path, directory: &Path, files: &mut Vec<String>) -> std::io::Result<()> {
+    for entry in fs::read_dir(directory)? {
+        let path = entry?.path();
+        if path.is_dir() {
+            walk(root, &path, files)?;
+        } else if let Ok(relative) = path.strip_prefix(root) {
+            let parts: Vec<String> = relative.components().map(|part| part.as_os_str().to_string_lossy().to_string()).collect();
+            files.push(parts.join("/"));
+        }
+    }
+    Ok(())
+}
+
+/// Get the files of the pool directory below the root which are not referenced. Paths relative to the root.
+pub fn find(root: &Path, referenced: &HashSet<String>) -> Result<Vec<String>> {
+    let pool = root.join("pool");
+    let mut files = Vec::new();
+    if let Err(e) = walk(root, &pool, &mut files) {
+        let message = format!("Reading pool {} failed! {e}", pool.display());
+        error!("{}", message);
+        return Err(Error::new(&message, libapt::ErrorType::ApiUsage));
+    }
+
+    let mut orphans: Vec<String> = files.into_iter().filter(|file| !referenced.contains(file)).collect();
+    orphans.sort();
+    info!("Found {} orphaned files in pool {}.", orphans.len(), pool.display());
+    Ok(orphans)
+}
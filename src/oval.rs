@@ -0,0 +1,408 @@
+//! Evaluation of OVAL definitions against the packages of a repository.
+//!
+//! Debian and Ubuntu publish OVAL feeds per release, e.g. oval-definitions-bookworm.xml.bz2
+//! or com.ubuntu.jammy.cve.oval.xml.bz2. The criteria of the definitions are evaluated with
+//! the highest version of each binary or source package of the repository, as if all packages were installed.
+//! Only dpkginfo tests are evaluated, all other tests like the release checks are assumed to pass.
+
+use std::collections::HashMap;
+use std::fs;
+
+use libapt::{Error, Result, Version};
+use log::{debug, error, info};
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+
+use crate::index;
+use crate::transport::Transport;
+use crate::util::format_version;
+
+/// Maximum depth of nested criteria and extended definitions.
+const MAX_DEPTH: usize = 32;
+
+/// Element of an XML document, with the namespace prefixes removed.
+#[derive(Debug, Default)]
+struct Element {
+    name: String,
+    attributes: HashMap<String, String>,
+    children: Vec<Element>,
+    text: String,
+}
+
+impl Element {
+    /// Get the value of an attribute.
+    fn attribute(&self, name: &str) -> Option<&str> {
+        self.attributes.get(name).map(|value| value.as_str())
+    }
+
+    /// Get the first child element with the name.
+    fn child(&self, name: &str) -> Option<&Element> {
+        self.children.iter().find(|child| child.name == name)
+    }
+
+    /// Get the child elements with the name.
+    fn children_named<'a>(&'a self, name: &'a str) -> impl Iterator<Item = &'a Element> {
+        self.children.iter().filter(move |child| child.name == name)
+    }
+}
+
+/// Replace the predefined entities and character references of XML text.
+fn unescape(text: &str) -> String {
+    if !text.contains('&') {
+        return text.to_string();
+    }
+
+    let mut result = String::new();
+    let mut rest = text;
+    while let Some(start) = rest.find('&') {
+        result += &rest[..start];
+        rest = &rest[start..];
+        let end = match rest.find(';') {
+            Some(end) => end,
+            None => break,
+        };
+        let replacement = match &rest[1..end] {
+            "lt" => Some('<'),
+            "gt" => Some('>'),
+            "amp" => Some('&'),
+            "quot" => Some('"'),
+            "apos" => Some('\''),
+            entity => entity
+                .strip_prefix("#x")
+                .map(|hex| u32::from_str_radix(hex, 16))
+                .or_else(|| entity.strip_prefix('#').map(|decimal| decimal.parse()))
+                .and_then(|code| code.ok())
+                .and_then(char::from_u32),
+        };
+        match replacement {
+            Some(replacement) => {
+                result.push(replacement);
+                rest = &rest[end + 1..];
+            }
+            None => {
+                result.push('&');
+                rest = &rest[1..];
+            }
+        }
+    }
+    result + rest
+}
+
+/// Remove the namespace prefix of a name, e.g. linux-def:dpkginfo_test.
+fn local_name(name: &str) -> &str {
+    name.rsplit(':').next().unwrap_or(name)
+}
+
+/// Get the end of a tag, skipping a > in quoted attribute values.
+fn tag_end(tag: &str) -> Option<usize> {
+    let mut quote = None;
+    for (position, character) in tag.char_indices() {
+        match (quote, character) {
+            (None, '"' | '\'') => quote = Some(character),
+            (Some(open), _) if open == character => quote = None,
+            (None, '>') => return Some(position),
+            _ => {}
+        }
+    }
+    None
+}
+
+/// Create an error for invalid XML.
+fn invalid_xml(message: &str) -> Error {
+    let message = format!("Parsing OVAL XML failed! {message}");
+    error!("{}", message);
+    Error::new(&message, libapt::ErrorType::ApiUsage)
+}
+
+/// Parse an XML document into a tree of elements. The returned element contains the root element.
+///
+/// Only the subset of XML used by OVAL feeds is supported, i.e. no DTDs with internal subsets.
+fn parse_xml(content: &str) -> Result<Element> {
+    let attribute = Regex::new(r#"([\w:.-]+)\s*=\s*(?:"([^"]*)"|'([^']*)')"#).map_err(|e| invalid_xml(&e.to_string()))?;
+    let mut stack = vec![Element::default()];
+    let mut rest = content;
+
+    while let Some(start) = rest.find('<') {
+        let text = &rest[..start];
+        if !text.trim().is_empty() {
+            if let Some(top) = stack.last_mut() {
+                top.text += &unescape(text);
+            }
+        }
+        rest = &rest[start..];
+
+        if let Some(comment) = rest.strip_prefix("<!--") {
+            let end = comment.find("-->").ok_or_else(|| invalid_xml("Unterminated comment."))?;
+            rest = &comment[end + 3..];
+            continue;
+        }
+        if let Some(data) = rest.strip_prefix("<![CDATA[") {
+            let end = data.find("]]>").ok_or_else(|| invalid_xml("Unterminated CDATA section."))?;
+            if let Some(top) = stack.last_mut() {
+                top.text += &data[..end];
+            }
+            rest = &data[end + 3..];
+            continue;
+        }
+
+        let end = tag_end(rest).ok_or_else(|| invalid_xml("Unterminated tag."))?;
+        let tag = &rest[1..end];
+        rest = &rest[end + 1..];
+
+        if tag.starts_with('?') || tag.starts_with('!') {
+            continue;
+        }
+        if let Some(name) = tag.strip_prefix('/') {
+            let element = match stack.pop() {
+                Some(_) if stack.is_empty() => return Err(invalid_xml(&format!("Unexpected closing tag {name}."))),
+                Some(element) => element,
+                None => return Err(invalid_xml(&format!("Unexpected closing tag {name}."))),
+            };
+            if element.name != local_name(name.trim()) {
+                return Err(invalid_xml(&format!("Closing tag {name} doesn't match {}.", element.name)));
+            }
+            if let Some(parent) = stack.last_mut() {
+                parent.children.push(element);
+            }
+            continue;
+        }
+
+        let self_closing = tag.ends_with('/');
+        let tag = tag.trim_end_matches('/');
+        let name = tag.split_whitespace().next().unwrap_or_default();
+        let element = Element {
+            name: local_name(name).to_string(),
+            attributes: attribute
+                .captures_iter(&tag[name.len()..])
+                .map(|captures| {
+                    let value = captures.get(2).or_else(|| captures.get(3)).map(|value| value.as_str()).unwrap_or_default();
+                    (local_name(&captures[1]).to_string(), unescape(value))
+                })
+                .collect(),
+            ..Default::default()
+        };
+
+        if self_closing {
+            if let Some(parent) = stack.last_mut() {
+                parent.children.push(element);
+            }
+        } else {
+            stack.push(element);
+        }
+    }
+
+    match stack.pop() {
+        Some(document) if stack.is_empty() => Ok(document),
+        _ => Err(invalid_xml("Unclosed elements at the end of the document.")),
+    }
+}
+
+/// Package of the repository matching a dpkginfo test.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub struct AffectedPackage {
+    pub package: String,
+    // Highest version of the package in the repository.
+    pub version: String,
+    // Version fixing the definition. None if the test only requires the package.
+    pub fixed_version: Option<String>,
+}
+
+/// OVAL definition which is not patched in the repository.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct UnpatchedDefinition {
+    // ID of the definition, e.g. oval:org.debian:def:1234.
+    pub id: String,
+    pub title: String,
+    // Referenced CVEs and advisories, e.g. CVE-2024-1234 or USN-6000-1.
+    pub references: Vec<String>,
+    // URL of the first reference.
+    pub url: Option<String>,
+    // Severity of the advisory, if given.
+    pub severity: Option<String>,
+    // Packages below the fixed version.
+    pub packages: Vec<AffectedPackage>,
+}
+
+/// Parsed OVAL feed, with the definitions, tests, objects, states and variables by ID.
+struct Feed<'a> {
+    definitions: HashMap<&'a str, &'a Element>,
+    items: HashMap<&'a str, &'a Element>,
+    // Highest versions of the binary and source packages of the repository.
+    versions: &'a HashMap<String, Version>,
+}
+
+impl<'a> Feed<'a> {
+    /// Index the elements of the OVAL document.
+    fn new(document: &'a Element, versions: &'a HashMap<String, Version>) -> Result<Feed<'a>> {
+        let root = document.child("oval_definitions").ok_or_else(|| invalid_xml("Root element oval_definitions is missing."))?;
+        let mut feed = Feed { definitions: HashMap::new(), items: HashMap::new(), versions };
+
+        for definition in root.child("definitions").iter().flat_map(|definitions| definitions.children_named("definition")) {
+            if let Some(id) = definition.attribute("id") {
+                feed.definitions.insert(id, definition);
+            }
+        }
+        for section in ["tests", "objects", "states", "variables"] {
+            for item in root.child(section).iter().flat_map(|section| section.children.iter()) {
+                if let Some(id) = item.attribute("id") {
+                    feed.items.insert(id, item);
+                }
+            }
+        }
+
+        Ok(feed)
+    }
+
+    /// Get the package names of a dpkginfo object, given directly or as values of a variable.
+    fn package_names(&self, object: &Element) -> Vec<String> {
+        let name = match object.child("name") {
+            Some(name) => name,
+            None => return Vec::new(),
+        };
+        match name.attribute("var_ref").and_then(|id| self.items.get(id)) {
+            Some(variable) => variable.children_named("value").map(|value| value.text.trim().to_string()).collect(),
+            None => vec![name.text.trim().to_string()],
+        }
+    }
+
+    /// Evaluate a test. Only dpkginfo tests are evaluated, all other tests pass.
+    fn evaluate_test(&self, id: &str, affected: &mut Vec<AffectedPackage>) -> bool {
+        let test = match self.items.get(id) {
+            Some(test) if test.name == "dpkginfo_test" => test,
+            _ => return true,
+        };
+        let object = match test.child("object").and_then(|object| object.attribute("object_ref")).and_then(|id| self.items.get(id)) {
+            Some(object) => object,
+            None => return true,
+        };
+        let evr = test
+            .child("state")
+            .and_then(|state| state.attribute("state_ref"))
+            .and_then(|id| self.items.get(id))
+            .and_then(|state| state.child("evr"));
+        // Versions without epoch are compared like epoch 0.
+        let fixed_version = match evr {
+            Some(evr) if evr.attribute("operation") == Some("less than") => Some(evr.text.trim().trim_start_matches("0:").to_string()),
+            _ => None,
+        };
+        let fixed = fixed_version.as_deref().map(Version::from_str);
+
+        let mut matched = false;
+        for package in self.package_names(object) {
+            let version = match self.versions.get(&package) {
+                Some(version) => version,
+                None => continue,
+            };
+            let is_affected = match &fixed {
+                Some(Ok(fixed)) => version < fixed,
+                Some(Err(_)) => false,
+                None => true,
+            };
+            if is_affected {
+                matched = true;
+                affected.push(AffectedPackage { package, version: format_version(version), fixed_version: fixed_version.clone() });
+            }
+        }
+        matched
+    }
+
+    /// Evaluate a criteria, criterion or extend_definition element.
+    ///
+    /// The affected packages are only collected from the criteria which decide the result.
+    fn evaluate(&self, element: &Element, affected: &mut Vec<AffectedPackage>, depth: usize) -> bool {
+        if depth > MAX_DEPTH {
+            return false;
+        }
+
+        let mut matched = Vec::new();
+        let result = match element.name.as_str() {
+            "criteria" => {
+                let results: Vec<(bool, Vec<AffectedPackage>)> = element
+                    .children
+                    .iter()
+                    .map(|child| {
+                        let mut child_affected = Vec::new();
+                        (self.evaluate(child, &mut child_affected, depth + 1), child_affected)
+                    })
+                    .collect();
+                let result = match element.attribute("operator").unwrap_or("AND") {
+                    "OR" => results.iter().any(|(result, _)| *result),
+                    "ONE" | "XOR" => results.iter().filter(|(result, _)| *result).count() == 1,
+                    _ => results.iter().all(|(result, _)| *result),
+                };
+                matched.extend(results.into_iter().filter(|(result, _)| *result).flat_map(|(_, affected)| affected));
+                result
+            }
+            "criterion" => element.attribute("test_ref").is_none_or(|id| self.evaluate_test(id, &mut matched)),
+            "extend_definition" => match element.attribute("definition_ref").and_then(|id| self.definitions.get(id)).and_then(|definition| definition.child("criteria")) {
+                Some(criteria) => self.evaluate(criteria, &mut matched, depth + 1),
+                None => true,
+            },
+            _ => true,
+        };
+
+        let result = if element.attribute("negate") == Some("true") { !result } else { result };
+        if result {
+            affected.extend(matched);
+        }
+        result
+    }
+}
+
+/// Load the OVAL feed, decompressed according to its extension.
+///
+/// URLs are downloaded, else the feed is read from a local file.
+pub async fn load(transport: &Transport, feed: &str) -> Result<String> {
+    info!("Loading OVAL feed {feed}...");
+    let data = if feed.contains("://") {
+        transport.download(feed).await?
+    } else {
+        fs::read(feed).map_err(|e| Error::from_io_error(e, feed))?
+    };
+    let data = index::decompress(data, feed)?;
+    Ok(String::from_utf8_lossy(&data).to_string())
+}
+
+/// Evaluate the vulnerability and patch definitions of the feed with the package versions. (Package, Highest version)
+pub fn unpatched(content: &str, versions: &HashMap<String, Version>) -> Result<Vec<UnpatchedDefinition>> {
+    let document = parse_xml(content)?;
+    let feed = Feed::new(&document, versions)?;
+    info!("Evaluating {} OVAL definitions...", feed.definitions.len());
+
+    let mut unpatched = Vec::new();
+    for (id, definition) in &feed.definitions {
+        if !matches!(definition.attribute("class"), Some("vulnerability" | "patch")) {
+            continue;
+        }
+        let criteria = match definition.child("criteria") {
+            Some(criteria) => criteria,
+            None => continue,
+        };
+
+        let mut packages = Vec::new();
+        if !feed.evaluate(criteria, &mut packages, 0) || packages.is_empty() {
+            continue;
+        }
+        packages.sort_by(|a, b| a.package.cmp(&b.package));
+        packages.dedup();
+
+        let metadata = definition.child("metadata");
+        let references: Vec<&Element> = metadata.iter().flat_map(|metadata| metadata.children_named("reference")).collect();
+        unpatched.push(UnpatchedDefinition {
+            id: id.to_string(),
+            title: metadata.and_then(|metadata| metadata.child("title")).map(|title| title.text.trim().to_string()).unwrap_or_default(),
+            references: references.iter().filter_map(|reference| reference.attribute("ref_id")).map(|id| id.to_string()).collect(),
+            url: references.iter().find_map(|reference| reference.attribute("ref_url")).map(|url| url.to_string()),
+            severity: metadata
+                .and_then(|metadata| metadata.child("advisory"))
+                .and_then(|advisory| advisory.child("severity"))
+                .map(|severity| severity.text.trim().to_string()),
+            packages,
+        });
+        debug!("OVAL definition {id} is unpatched.");
+    }
+
+    unpatched.sort_by(|a, b| a.id.cmp(&b.id));
+    info!("Found {} unpatched OVAL definitions.", unpatched.len());
+    Ok(unpatched)
+}
@@ -0,0 +1,73 @@
+//! Selection of the checked packages by glob patterns or regular expressions.
+
+use libapt::{Error, Result};
+use log::error;
+use regex::Regex;
+
+/// PackageFilter selects packages matching any of the given patterns.
+///
+/// Patterns enclosed in slashes, e.g. /^lib.*-dev$/, are regular expressions,
+/// all others are glob patterns like libfoo* matching the whole package name.
+#[derive(Debug, Clone, Default)]
+pub struct PackageFilter {
+    // Compiled patterns. Empty selects all packages.
+    patterns: Vec<Regex>,
+}
+
+impl PackageFilter {
+    /// Compile the given patterns.
+    pub fn new(patterns: &[String]) -> Result<PackageFilter> {
+        let mut compiled = Vec::new();
+        for pattern in patterns {
+            let expression = match pattern.strip_prefix('/').and_then(|pattern| pattern.strip_suffix('/')) {
+                Some(expression) => expression.to_string(),
+                None => glob_expression(pattern),
+            };
+            match Regex::new(&expression) {
+                Ok(regex) => compiled.push(regex),
+                Err(e) => {
+                    let message = format!("Invalid package pattern {pattern}! {e}");
+                    error!("{}", message);
+                    return Err(Error::new(&message, libapt::ErrorType::ApiUsage));
+                }
+            }
+        }
+        Ok(PackageFilter { patterns: compiled })
+    }
+
+    /// Test if the package is selected. All packages are selected if no pattern is given.
+    pub fn matches(&self, package: &str) -> bool {
+        self.patterns.is_empty() || self.patterns.iter().any(|pattern| pattern.is_match(package))
+    }
+}
+
+/// Translate a glob pattern into an anchored regular expression.
+///
+/// Supports * for any characters, ? for a single character and [...] for character classes.
+fn glob_expression(glob: &str) -> String {
+    let mut expression = String::from("^");
+    let mut in_class = false;
+    for c in glob.chars() {
+        match c {
+            '*' if !in_class => expression.push_str(".*"),
+            '?' if !in_class => expression.push('.'),
+            '[' if !in_class => {
+                in_class = true;
+                expression.push('[');
+            }
+            ']' if in_class => {
+                in_class = false;
+                expression.push(']');
+            }
+            '!' if in_class && expression.ends_with('[') => expression.push('^'),
+            c if in_class => expression.push(c),
+            c => expression.push_str(&regex::escape(&c.to_string())),
+        }
+    }
+    if in_class {
+        // An unterminated class is matched literally.
+        return format!("^{}$", regex::escape(glob));
+    }
+    expression.push('$');
+    expression
+}
@@ -0,0 +1,239 @@
+//! Parsing and application of pdiffs, i.e. the Packages.diff directory.
+
+/// Entry of a pdiff Index section.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PdiffEntry {
+    // SHA256 hash of the file.
+    pub hash: String,
+    // Size of the file in bytes.
+    pub size: u64,
+    // Name of the patch.
+    pub name: String,
+}
+
+/// PdiffIndex groups the SHA256 sections of a Packages.diff/Index file.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct PdiffIndex {
+    // Hash and size of the current index. (Hash, Size)
+    pub current: Option<(String, u64)>,
+    // Index versions the patches apply to.
+    pub history: Vec<PdiffEntry>,
+    // Uncompressed patches.
+    pub patches: Vec<PdiffEntry>,
+    // Compressed patches as downloaded.
+    pub downloads: Vec<PdiffEntry>,
+    // Each patch applies directly to the current index, instead of forming a chain.
+    pub merged: bool,
+}
+
+impl PdiffIndex {
+    /// Parse the content of a Packages.diff/Index file.
+    pub fn parse(content: &str) -> PdiffIndex {
+        let mut index = PdiffIndex::default();
+        let mut section: Option<String> = None;
+
+        for line in content.lines() {
+            if line.starts_with(' ') || line.starts_with('\t') {
+                let parts: Vec<&str> = line.split_whitespace().collect();
+                if let [hash, size, name] = parts[..] {
+                    let entry = PdiffEntry {
+                        hash: hash.to_lowercase(),
+                        size: size.parse::<u64>().unwrap_or_default(),
+                        name: name.to_string(),
+                    };
+                    match section.as_deref() {
+                        Some("SHA256-History") => index.history.push(entry),
+                        Some("SHA256-Patches") => index.patches.push(entry),
+                        Some("SHA256-Download") => index.downloads.push(entry),
+                        _ => {}
+                    }
+                }
+                continue;
+            }
+
+            section = None;
+            if let Some((key, value)) = line.split_once(':') {
+                let value = value.trim();
+                match key.trim() {
+                    "SHA256-Current" => {
+                        if let Some((hash, size)) = value.split_once(char::is_whitespace) {
+                            index.current = Some((hash.to_lowercase(), size.trim().parse::<u64>().unwrap_or_default()));
+                        }
+                    }
+                    "X-Patch-Precedence" => index.merged = value == "merged",
+                    key => section = Some(key.to_string()),
+                }
+            }
+        }
+
+        index
+    }
+
+    /// Describe the inconsistencies between the sections.
+    ///
+    /// Each patch needs a history entry and a download.
+    pub fn issues(&self) -> Vec<String> {
+        let mut issues = Vec::new();
+
+        if self.current.is_none() {
+            issues.push("SHA256-Current is missing".to_string());
+        }
+
+        for patch in &self.patches {
+            if !self.history.iter().any(|entry| entry.name == patch.name) {
+                issues.push(format!("patch {} has no history entry", patch.name));
+            }
+            if self.download(&patch.name).is_none() {
+                issues.push(format!("patch {} has no download", patch.name));
+            }
+        }
+
+        for entry in &self.history {
+            if !self.patches.iter().any(|patch| patch.name == entry.name) {
+                issues.push(format!("history entry {} has no patch", entry.name));
+            }
+        }
+
+        issues
+    }
+
+    /// Get the download of the named patch.
+    pub fn download(&self, name: &str) -> Option<&PdiffEntry> {
+        self.downloads
+            .iter()
+            .find(|entry| entry.name.strip_suffix(".gz").unwrap_or(&entry.name) == name)
+    }
+}
+
+/// Command of an ed script.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EdCommand {
+    // First affected line, starting with 1.
+    pub start: usize,
+    // Last affected line.
+    pub end: usize,
+    // Command: a(ppend), c(hange) or d(elete).
+    pub action: char,
+    // Text lines of append and change commands.
+    pub lines: Vec<String>,
+}
+
+/// Parse a pdiff patch, i.e. an ed script.
+pub fn parse_script(patch: &str) -> Result<Vec<EdCommand>, String> {
+    let mut commands = Vec::new();
+    let mut lines = patch.lines().enumerate();
+
+    while let Some((number, line)) = lines.next() {
+        if line.is_empty() {
+            continue;
+        }
+
+        let invalid = || format!("invalid command {line} in line {}", number + 1);
+        let (range, action) = match line.char_indices().last() {
+            Some((position, action)) => (&line[..position], action),
+            None => continue,
+        };
+        let (start, end) = match range.split_once(',') {
+            Some((start, end)) => (start, end),
+            None => (range, range),
+        };
+        let start = start.parse::<usize>().map_err(|_| invalid())?;
+        let end = end.parse::<usize>().map_err(|_| invalid())?;
+        if end < start {
+            return Err(invalid());
+        }
+
+        let mut text = Vec::new();
+        match action {
+            'a' | 'c' => loop {
+                match lines.next() {
+                    Some((_, ".")) => break,
+                    Some((_, line)) => text.push(line.to_string()),
+                    None => return Err(format!("text of command in line {} is not terminated", number + 1)),
+                }
+            },
+            'd' => {}
+            _ => return Err(invalid()),
+        }
+
+        commands.push(EdCommand { start, end, action, lines: text });
+    }
+
+    Ok(commands)
+}
+
+/// Apply the commands of an ed script to the content.
+///
+/// The commands must be ordered by descending line numbers, as written by diff --ed.
+pub fn apply(content: &str, commands: &[EdCommand]) -> Result<String, String> {
+    let mut lines: Vec<String> = content.lines().map(|line| line.to_string()).collect();
+
+    for command in commands {
+        let out_of_range = || format!("line {} is out of range, content has {} lines", command.end, lines.len());
+        match command.action {
+            'a' => {
+                if command.start > lines.len() {
+                    return Err(out_of_range());
+                }
+                lines.splice(command.start..command.start, command.lines.iter().cloned());
+            }
+            _ => {
+                if command.start == 0 || command.end > lines.len() {
+                    return Err(out_of_range());
+                }
+                lines.splice(command.start - 1..command.end, command.lines.iter().cloned());
+            }
+        }
+    }
+
+    let mut result = lines.join("\n");
+    if !lines.is_empty() {
+        result.push('\n');
+    }
+    Ok(result)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CONTENT: &str = "one\ntwo\nthree\nfour\n";
+
+    #[test]
+    fn append() {
+        let commands = parse_script("2a\nnew\nlines\n.\n0a\nfirst\n.\n").unwrap();
+        assert_eq!(commands[0], EdCommand { start: 2, end: 2, action: 'a', lines: vec!["new".to_string(), "lines".to_string()] });
+        assert_eq!(apply(CONTENT, &commands).unwrap(), "first\none\ntwo\nnew\nlines\nthree\nfour\n");
+    }
+
+    #[test]
+    fn change() {
+        let commands = parse_script("2,3c\nchanged\n.\n").unwrap();
+        assert_eq!(commands[0], EdCommand { start: 2, end: 3, action: 'c', lines: vec!["changed".to_string()] });
+        assert_eq!(apply(CONTENT, &commands).unwrap(), "one\nchanged\nfour\n");
+    }
+
+    #[test]
+    fn delete() {
+        let commands = parse_script("4d\n1,2d\n").unwrap();
+        assert_eq!(commands[1], EdCommand { start: 1, end: 2, action: 'd', lines: Vec::new() });
+        assert_eq!(apply(CONTENT, &commands).unwrap(), "three\n");
+        assert_eq!(apply(CONTENT, &parse_script("1,4d\n").unwrap()).unwrap(), "");
+    }
+
+    #[test]
+    fn out_of_range() {
+        assert!(apply(CONTENT, &parse_script("5a\nline\n.\n").unwrap()).is_err());
+        assert!(apply(CONTENT, &parse_script("3,5c\nline\n.\n").unwrap()).is_err());
+        assert!(apply(CONTENT, &parse_script("0d\n").unwrap()).is_err());
+        assert!(apply(CONTENT, &parse_script("5d\n").unwrap()).is_err());
+    }
+
+    #[test]
+    fn invalid_script() {
+        assert!(parse_script("3,2d\n").is_err());
+        assert!(parse_script("2x\n").is_err());
+        assert!(parse_script("a\nline\n.\n").is_err());
+        assert!(parse_script("1a\nunterminated\n").is_err());
+    }
+}
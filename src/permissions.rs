@@ -0,0 +1,88 @@
+//! Readability and permission checks of repositories on the local filesystem.
+//!
+//! A repository published by reprepro or aptly is usually copied to the web server by rsync,
+//! keeping the permissions. Files which are not readable by other users fail there, although
+//! the publishing user can read them.
+
+use std::fs;
+use std::path::Path;
+
+use crate::finding::FindingKind;
+
+/// Permission issue of a file or directory. (Path relative to the root, Kind, Issue)
+pub type PermissionIssue = (String, FindingKind, String);
+
+/// Directories of the repository which are checked.
+const DIRECTORIES: [&str; 2] = ["dists", "pool"];
+
+/// Get the permission bits other users lack, e.g. "read" for a file with mode 0640.
+#[cfg(unix)]
+fn missing_other_permissions(metadata: &fs::Metadata) -> Vec<&'static str> {
+    use std::os::unix::fs::PermissionsExt;
+
+    let mode = metadata.permissions().mode();
+    let mut missing = Vec::new();
+    if mode & 0o004 == 0 {
+        missing.push("read");
+    }
+    if metadata.is_dir() && mode & 0o001 == 0 {
+        missing.push("execute");
+    }
+    missing
+}
+
+/// Permission bits are not available on this platform.
+#[cfg(not(unix))]
+fn missing_other_permissions(_metadata: &fs::Metadata) -> Vec<&'static str> {
+    Vec::new()
+}
+
+/// Check the file or directory and the entries of the directory recursively.
+fn check_entry(root: &Path, path: &Path, issues: &mut Vec<PermissionIssue>) {
+    let name = path.strip_prefix(root).unwrap_or(path).components().map(|part| part.as_os_str().to_string_lossy().to_string()).collect::<Vec<String>>().join("/");
+
+    let metadata = match fs::metadata(path) {
+        Ok(metadata) => metadata,
+        Err(e) => {
+            issues.push((name, FindingKind::UnreadableFile, format!("can't be accessed: {e}")));
+            return;
+        }
+    };
+
+    let missing = missing_other_permissions(&metadata);
+    if !missing.is_empty() {
+        issues.push((name.clone(), FindingKind::RestrictivePermissions, format!("lacks {} permission for other users", missing.join(" and "))));
+    }
+
+    // Linked directories are not followed, so link loops don't recurse endlessly.
+    let is_link = fs::symlink_metadata(path).is_ok_and(|metadata| metadata.file_type().is_symlink());
+    if metadata.is_dir() && !is_link {
+        match fs::read_dir(path) {
+            Ok(entries) => {
+                let mut paths: Vec<_> = entries.filter_map(|entry| entry.ok()).map(|entry| entry.path()).collect();
+                paths.sort();
+                for path in paths {
+                    check_entry(root, &path, issues);
+                }
+            }
+            Err(e) => issues.push((name, FindingKind::UnreadableFile, format!("can't be listed: {e}"))),
+        }
+    } else if metadata.is_file() {
+        if let Err(e) = fs::File::open(path) {
+            issues.push((name, FindingKind::UnreadableFile, format!("can't be read: {e}")));
+        }
+    }
+}
+
+/// Check that all files and directories of the dists and pool directories are readable,
+/// also by other users.
+pub fn issues(root: &Path) -> Vec<PermissionIssue> {
+    let mut issues = Vec::new();
+    for directory in DIRECTORIES {
+        let path = root.join(directory);
+        if path.exists() {
+            check_entry(root, &path, &mut issues);
+        }
+    }
+    issues
+}
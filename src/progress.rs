@@ -0,0 +1,152 @@
+//! Progress of long-running check phases.
+//!
+//! On a terminal a progress bar is drawn on stderr,
+//! else a log line is written periodically.
+
+use std::io::{self, IsTerminal, Write};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use libapt::Architecture;
+use log::info;
+
+use crate::timing::Phase;
+use crate::transport::Transport;
+use crate::util::{format_bytes, format_duration};
+
+/// Interval between redraws of the progress bar.
+const DRAW_INTERVAL: Duration = Duration::from_millis(200);
+
+/// Interval between progress log lines, if stderr is not a terminal.
+const LOG_INTERVAL: Duration = Duration::from_secs(10);
+
+/// Width of the progress bar in characters.
+const BAR_WIDTH: u64 = 30;
+
+/// Progress of a phase, e.g. of the file probes of a component.
+///
+/// The counters are atomic, so parallel tasks can report their progress.
+pub struct Progress {
+    // Reported phase.
+    phase: Phase,
+    // Checked component.
+    component: String,
+    // Checked architecture.
+    architecture: String,
+    // Unit of the counted items, e.g. "files".
+    unit: &'static str,
+    // Number of items of the phase.
+    total: u64,
+    // Number of finished items.
+    done: AtomicU64,
+    // Transport counting the downloaded bytes. None if progress reporting is disabled.
+    transport: Option<Arc<Transport>>,
+    // Downloaded bytes at the start of the phase.
+    start_bytes: u64,
+    // Start of the phase.
+    start: Instant,
+    // Time of the last report.
+    last_report: Mutex<Instant>,
+    // Draw a progress bar instead of logging.
+    terminal: bool,
+}
+
+impl Progress {
+    /// Start reporting the progress of a phase.
+    ///
+    /// Without transport, the progress is not reported.
+    pub fn new(phase: Phase, component: &str, architecture: &Architecture, unit: &'static str, total: usize, transport: Option<Arc<Transport>>) -> Progress {
+        let start = Instant::now();
+        Progress {
+            phase,
+            component: component.to_string(),
+            architecture: architecture.to_string(),
+            unit,
+            total: total as u64,
+            done: AtomicU64::new(0),
+            start_bytes: transport.as_ref().map(|transport| transport.downloaded_bytes()).unwrap_or_default(),
+            transport,
+            start,
+            last_report: Mutex::new(start),
+            terminal: io::stderr().is_terminal(),
+        }
+    }
+
+    /// Count a finished item and report the progress, if due.
+    pub fn inc(&self) {
+        let done = self.done.fetch_add(1, Ordering::Relaxed) + 1;
+        if self.transport.is_none() {
+            return;
+        }
+
+        let interval = if self.terminal { DRAW_INTERVAL } else { LOG_INTERVAL };
+        // Concurrent reports are skipped, the next item reports again.
+        let mut last_report = match self.last_report.try_lock() {
+            Ok(last_report) => last_report,
+            Err(_) => return,
+        };
+        if last_report.elapsed() < interval {
+            return;
+        }
+        *last_report = Instant::now();
+
+        self.report(done);
+    }
+
+    /// Report the final state. The progress bar is finished with a line break.
+    pub fn finish(&self) {
+        if self.transport.is_none() || self.total == 0 || !self.terminal {
+            return;
+        }
+        self.report(self.done.load(Ordering::Relaxed));
+        eprintln!();
+    }
+
+    /// Draw the progress bar or log the progress.
+    fn report(&self, done: u64) {
+        let bytes = self
+            .transport
+            .as_ref()
+            .map(|transport| transport.downloaded_bytes().saturating_sub(self.start_bytes))
+            .unwrap_or_default();
+
+        let remaining = match done {
+            0 => "unknown".to_string(),
+            _ => {
+                let elapsed = self.start.elapsed().as_secs_f64();
+                let remaining = elapsed * (self.total.saturating_sub(done)) as f64 / done as f64;
+                format_duration(remaining.round() as i64)
+            }
+        };
+
+        if self.terminal {
+            let filled = (done * BAR_WIDTH).checked_div(self.total).unwrap_or(BAR_WIDTH).min(BAR_WIDTH) as usize;
+            let bar = format!("{}{}", "#".repeat(filled), "-".repeat(BAR_WIDTH as usize - filled));
+            let mut stderr = io::stderr().lock();
+            let _ = write!(
+                stderr,
+                "\r\x1b[K{} {}/{} [{bar}] {done}/{} {}, {} downloaded, {remaining} remaining",
+                self.phase,
+                self.component,
+                self.architecture,
+                self.total,
+                self.unit,
+                format_bytes(bytes)
+            );
+            let _ = stderr.flush();
+        } else {
+            let percent = (done * 100).checked_div(self.total).unwrap_or(100);
+            info!(
+                phase:% = self.phase, component = self.component.as_str(), arch = self.architecture.as_str();
+                "Phase {} of {}/{}: {done}/{} {} ({percent}%), {} downloaded, about {remaining} remaining.",
+                self.phase,
+                self.component,
+                self.architecture,
+                self.total,
+                self.unit,
+                format_bytes(bytes)
+            );
+        }
+    }
+}
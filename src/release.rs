@@ -0,0 +1,133 @@
+//! Loading of the InRelease file into a libapt Release.
+//!
+//! The InRelease file and the key are downloaded using the transport,
+//! so authentication and TLS settings apply like for all other requests.
+
+use chrono::DateTime;
+use libapt::{Architecture, Distro, Error, Link, LinkHash, Release, Result};
+use log::{debug, warn};
+
+use crate::signature;
+use crate::transport::Transport;
+
+/// Download, verify and parse the InRelease file of the distro.
+///
+/// Like Release::from_distro, the signature is verified if the distro has a key.
+pub async fn load(transport: &Transport, distro: &Distro) -> Result<Release> {
+    load_with_content(transport, distro).await.map(|(release, _)| release)
+}
+
+/// Download, verify and parse the InRelease file of the distro, keeping the signed file.
+///
+/// The file is kept for later checks of its signature and fields, so it is downloaded only once. (Release, InRelease)
+pub async fn load_with_content(transport: &Transport, distro: &Distro) -> Result<(Release, String)> {
+    let url = distro.in_release_url()?;
+    let data = transport.download(&url).await?;
+    let content = String::from_utf8_lossy(&data).to_string();
+
+    let payload = match signature::load_key(transport, &distro.key).await? {
+        Some(key) => signature::verify_inline(&key, &content)?,
+        None => signature::cleartext_payload(&content)?,
+    };
+
+    Ok((parse(distro, &payload)?, content))
+}
+
+/// Create a Release without any fields.
+fn empty(distro: &Distro) -> Result<Release> {
+    let distro = serde_json::to_value(distro).map_err(|e| Error::from_error(&e, libapt::ErrorType::ApiUsage, "Serializing distro failed"))?;
+    let release = serde_json::json!({
+        "architectures": [],
+        "components": [],
+        "links": {},
+        "acquire_by_hash": false,
+        "signed_by": [],
+        "distro": distro,
+        "issues": [],
+    });
+    serde_json::from_value(release).map_err(|e| Error::from_error(&e, libapt::ErrorType::ApiUsage, "Creating release failed"))
+}
+
+/// Parse a date field, which may use UTC instead of +0000.
+fn parse_date(field: &str, value: &str) -> Option<DateTime<chrono::FixedOffset>> {
+    let value = value.replace("UTC", "+0000");
+    match DateTime::parse_from_rfc2822(&value) {
+        Ok(date) => Some(date),
+        Err(e) => {
+            warn!("Parsing Release {field} \"{value}\" failed! {e}");
+            None
+        }
+    }
+}
+
+/// Parse the payload of an InRelease file like libapt does.
+///
+/// Invalid lines of the hash sections are collected as issues of the release.
+pub fn parse(distro: &Distro, content: &str) -> Result<Release> {
+    let mut release = empty(distro)?;
+    let mut section: Option<LinkHash> = None;
+
+    for line in content.lines() {
+        if line.trim().is_empty() {
+            continue;
+        }
+
+        if !line.starts_with(' ') {
+            section = None;
+        }
+
+        if let Some(hash) = &section {
+            let link = match Link::form_release(line, distro) {
+                Ok(link) => link,
+                Err(e) => {
+                    release.issues.push(e);
+                    continue;
+                }
+            };
+
+            let link = release.links.entry(link.url.clone()).or_insert(link);
+            if let Err(e) = link.add_hash(line, hash.clone()) {
+                release.issues.push(e);
+            }
+            continue;
+        }
+
+        let (keyword, value) = line.split_once(':').ok_or_else(|| Error::new(&format!("Invalid line! {line}"), libapt::ErrorType::InReleaseFormat))?;
+        let value = value.trim();
+
+        match keyword.to_lowercase().as_str() {
+            "origin" => release.origin = Some(value.to_string()),
+            "label" => release.label = Some(value.to_string()),
+            "suite" => release.suite = Some(value.to_string()),
+            "version" => release.version = Some(value.to_string()),
+            "codename" => release.codename = Some(value.to_string()),
+            "description" => release.description = Some(value.to_string()),
+            "changelogs" => release.changelogs = Some(value.to_string()),
+            "snapshots" => release.snapshots = Some(value.to_string()),
+            "date" => release.date = parse_date("date", value),
+            "valid-until" => release.valid_until = parse_date("valid until", value),
+            "architectures" => {
+                release.architectures = value
+                    .split_whitespace()
+                    .filter_map(|architecture| match Architecture::from_str(architecture) {
+                        Ok(architecture) => Some(architecture),
+                        Err(e) => {
+                            warn!("Parsing architecture {architecture} failed! {e}");
+                            None
+                        }
+                    })
+                    .collect()
+            }
+            "components" => release.components = value.split_whitespace().map(|component| component.to_string()).collect(),
+            "acquire-by-hash" => release.acquire_by_hash = value.eq_ignore_ascii_case("yes"),
+            "signed-by" => release.signed_by = value.split(',').map(|key| key.trim().to_string()).collect(),
+            "md5sum" => section = Some(LinkHash::Md5),
+            "sha1" => section = Some(LinkHash::Sha1),
+            "sha256" => section = Some(LinkHash::Sha256),
+            "sha512" => section = Some(LinkHash::Sha512),
+            keyword => debug!("Unknown keyword: {keyword} of line {line}!"),
+        }
+    }
+
+    Ok(release)
+}
@@ -0,0 +1,128 @@
+//! Minimal parser for Release files, used to compare Release variants.
+
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+
+/// Hash sections of a Release file.
+const HASH_SECTIONS: [&str; 4] = ["MD5Sum", "SHA1", "SHA256", "SHA512"];
+
+/// Maximum number of paths listed in a difference message.
+const MAX_LISTED_PATHS: usize = 5;
+
+/// ReleaseFile groups the fields of a Release file.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ReleaseFile {
+    // Single line fields. (Field, Value)
+    pub fields: HashMap<String, String>,
+    // Hash sections. (Section, (Path, (Hash, Size)))
+    pub hashes: HashMap<String, BTreeMap<String, (String, u64)>>,
+}
+
+impl ReleaseFile {
+    /// Parse the content of a Release file or the payload of an InRelease file.
+    pub fn parse(content: &str) -> ReleaseFile {
+        let mut release = ReleaseFile::default();
+        let mut section: Option<String> = None;
+
+        for line in content.lines() {
+            if line.trim().is_empty() {
+                continue;
+            }
+
+            if line.starts_with(' ') || line.starts_with('\t') {
+                // Continuation line, i.e. an entry of a hash section.
+                if let Some(section) = &section {
+                    let parts: Vec<&str> = line.split_whitespace().collect();
+                    if let [hash, size, path] = parts[..] {
+                        let size = size.parse::<u64>().unwrap_or_default();
+                        release
+                            .hashes
+                            .entry(section.clone())
+                            .or_default()
+                            .insert(path.to_string(), (hash.to_lowercase(), size));
+                    }
+                }
+                continue;
+            }
+
+            section = None;
+            if let Some((key, value)) = line.split_once(':') {
+                let key = key.trim();
+                if HASH_SECTIONS.contains(&key) {
+                    section = Some(key.to_string());
+                    release.hashes.entry(key.to_string()).or_default();
+                } else {
+                    release.fields.insert(key.to_string(), value.trim().to_string());
+                }
+            }
+        }
+
+        release
+    }
+
+    /// Get the words of a list field, e.g. Architectures.
+    pub fn list(&self, field: &str) -> BTreeSet<String> {
+        self.fields
+            .get(field)
+            .map(|value| value.split_whitespace().map(|word| word.to_string()).collect())
+            .unwrap_or_default()
+    }
+
+    /// Describe the differences to the other Release file.
+    ///
+    /// Date, Architectures, Components and the hash sections are compared.
+    pub fn differences(&self, other: &ReleaseFile) -> Vec<String> {
+        let mut differences = Vec::new();
+
+        let date = self.fields.get("Date");
+        let other_date = other.fields.get("Date");
+        if date != other_date {
+            differences.push(format!(
+                "Date differs: {} vs {}",
+                date.map(|d| d.as_str()).unwrap_or("none"),
+                other_date.map(|d| d.as_str()).unwrap_or("none")
+            ));
+        }
+
+        for field in ["Architectures", "Components"] {
+            let list = self.list(field);
+            let other_list = other.list(field);
+            if list != other_list {
+                differences.push(format!(
+                    "{field} differ: {} vs {}",
+                    list.into_iter().collect::<Vec<String>>().join(" "),
+                    other_list.into_iter().collect::<Vec<String>>().join(" ")
+                ));
+            }
+        }
+
+        let empty = BTreeMap::new();
+        for section in HASH_SECTIONS {
+            let entries = self.hashes.get(section).unwrap_or(&empty);
+            let other_entries = other.hashes.get(section).unwrap_or(&empty);
+
+            let mut changed: Vec<&str> = Vec::new();
+            for (path, entry) in entries {
+                if other_entries.get(path) != Some(entry) {
+                    changed.push(path);
+                }
+            }
+            for path in other_entries.keys() {
+                if !entries.contains_key(path) {
+                    changed.push(path);
+                }
+            }
+
+            if !changed.is_empty() {
+                let listed: Vec<&str> = changed.iter().take(MAX_LISTED_PATHS).copied().collect();
+                let more = if changed.len() > MAX_LISTED_PATHS { ", ..." } else { "" };
+                differences.push(format!(
+                    "{section} differs for {} files: {}{more}",
+                    changed.len(),
+                    listed.join(", ")
+                ));
+            }
+        }
+
+        differences
+    }
+}
@@ -0,0 +1,110 @@
+//! HTTP server of the watch mode, exposing the latest results.
+//!
+//! Serves /healthz, /metrics in the Prometheus text format and /report.json.
+//! Only simple GET requests are supported, each connection serves one request.
+
+use std::net::SocketAddr;
+use std::sync::{Arc, RwLock};
+use std::time::Duration;
+
+use libapt::{Error, Result};
+use log::{debug, error, info};
+use tokio::io::{AsyncBufReadExt, AsyncReadExt, AsyncWriteExt, BufReader};
+use tokio::net::{TcpListener, TcpStream};
+
+/// Maximum size of the request line and headers.
+const MAX_REQUEST_SIZE: u64 = 16 * 1024;
+
+/// Time a client may take to send its request.
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Latest results of the watch mode.
+#[derive(Debug, Clone, Default)]
+pub struct Latest {
+    // Report of the last round as JSON. None before the first round finished.
+    pub report: Option<String>,
+    // Metrics of the last round in the Prometheus text format.
+    pub metrics: String,
+}
+
+/// Bind the listening socket, so an unavailable address fails before the first check.
+pub async fn bind(address: SocketAddr) -> Result<TcpListener> {
+    match TcpListener::bind(address).await {
+        Ok(listener) => {
+            info!("Serving /healthz, /metrics and /report.json on http://{address}.");
+            Ok(listener)
+        }
+        Err(e) => {
+            let message = format!("Listening on {address} failed! {e}");
+            error!("{}", message);
+            Err(Error::new(&message, libapt::ErrorType::ApiUsage))
+        }
+    }
+}
+
+/// Answer the requests of the listener, until the task is dropped.
+pub async fn serve(listener: TcpListener, latest: Arc<RwLock<Latest>>) {
+    loop {
+        let (stream, peer) = match listener.accept().await {
+            Ok(connection) => connection,
+            Err(e) => {
+                debug!("Accepting connection failed: {e}");
+                continue;
+            }
+        };
+
+        let latest = latest.clone();
+        tokio::spawn(async move {
+            match tokio::time::timeout(REQUEST_TIMEOUT, handle(stream, &latest)).await {
+                Ok(Ok(())) => {}
+                Ok(Err(e)) => debug!("Serving request of {peer} failed: {e}"),
+                Err(_) => debug!("Request of {peer} timed out."),
+            }
+        });
+    }
+}
+
+/// Read a request and write the response.
+async fn handle(mut stream: TcpStream, latest: &RwLock<Latest>) -> std::io::Result<()> {
+    let (read, mut write) = stream.split();
+    let mut reader = BufReader::new(read.take(MAX_REQUEST_SIZE));
+
+    let mut request_line = String::new();
+    reader.read_line(&mut request_line).await?;
+    // The headers are not needed, but read, so the client doesn't see a reset connection.
+    loop {
+        let mut header = String::new();
+        if reader.read_line(&mut header).await? == 0 || header.trim().is_empty() {
+            break;
+        }
+    }
+
+    let mut parts = request_line.split_whitespace();
+    let method = parts.next().unwrap_or_default();
+    let path = parts.next().unwrap_or_default();
+    let path = path.split_once('?').map(|(path, _)| path).unwrap_or(path);
+
+    let (status, content_type, body) = {
+        let latest = latest.read().unwrap_or_else(|e| e.into_inner());
+        match (method, path) {
+            ("GET", "/healthz") => match latest.report {
+                Some(_) => ("200 OK", "text/plain", "ok\n".to_string()),
+                None => ("503 Service Unavailable", "text/plain", "first check is running\n".to_string()),
+            },
+            ("GET", "/metrics") => ("200 OK", "text/plain; version=0.0.4", latest.metrics.clone()),
+            ("GET", "/report.json") => match &latest.report {
+                Some(report) => ("200 OK", "application/json", report.clone()),
+                None => ("503 Service Unavailable", "text/plain", "first check is running\n".to_string()),
+            },
+            ("GET", _) => ("404 Not Found", "text/plain", "not found\n".to_string()),
+            _ => ("405 Method Not Allowed", "text/plain", "method not allowed\n".to_string()),
+        }
+    };
+
+    let response = format!(
+        "HTTP/1.1 {status}\r\nContent-Type: {content_type}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{body}",
+        body.len()
+    );
+    write.write_all(response.as_bytes()).await?;
+    write.shutdown().await
+}
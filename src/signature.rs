@@ -0,0 +1,312 @@
+//! OpenPGP verification of Release files.
+
+use std::fs;
+use std::io::Cursor;
+
+use chrono::{DateTime, Utc};
+use libapt::{Error, Key, Result};
+use log::{debug, error};
+use pgp::cleartext::CleartextSignedMessage;
+use pgp::crypto::hash::HashAlgorithm;
+use pgp::packet::{KeyFlags, Signature};
+use pgp::types::{Fingerprint, Mpi, PublicKeyTrait, PublicParams};
+use pgp::{Deserializable, SignedPublicKey, StandaloneSignature};
+
+use crate::transport::Transport;
+
+/// Marker of inline signed files.
+const CLEARTEXT_HEADER: &str = "-----BEGIN PGP SIGNED MESSAGE-----";
+
+/// Minimum size in bits of RSA and DSA keys.
+const MIN_KEY_BITS: usize = 2048;
+
+/// Marker of armored keys given inline instead of a location, e.g. by the Signed-By field of a .sources file.
+const PUBLIC_KEY_HEADER: &str = "-----BEGIN PGP PUBLIC KEY BLOCK-----";
+
+/// Get the printable location of a key. Inline keys are not printed.
+pub fn key_location(location: &str) -> &str {
+    if location.trim_start().starts_with(PUBLIC_KEY_HEADER) {
+        "embedded key"
+    } else {
+        location
+    }
+}
+
+/// Get the raw content of a key.
+///
+/// Inline keys are used as is, URLs starting with http are downloaded, else the key is read from a local file.
+async fn key_content(transport: &Transport, url: &str) -> Result<Vec<u8>> {
+    if url.trim_start().starts_with(PUBLIC_KEY_HEADER) {
+        Ok(url.as_bytes().to_vec())
+    } else if url.starts_with("http") {
+        transport.download(url).await
+    } else {
+        fs::read(url).map_err(|e| Error::from_io_error(e, url))
+    }
+}
+
+/// Get the keys of a keyring directory, like /etc/apt/trusted.gpg.d.
+///
+/// Files ending with .gpg are binary keys, files ending with .asc are armored keys.
+/// Other files are ignored, as done by apt.
+pub fn keyring_keys(path: &str) -> Result<Vec<Key>> {
+    let entries = fs::read_dir(path).map_err(|e| {
+        let message = format!("Reading keyring directory {path} failed! {e}");
+        error!("{}", message);
+        Error::new(&message, libapt::ErrorType::ApiUsage)
+    })?;
+
+    let mut files: Vec<String> = entries
+        .filter_map(|entry| entry.ok())
+        .filter(|entry| entry.path().is_file())
+        .map(|entry| entry.path().to_string_lossy().to_string())
+        .collect();
+    files.sort();
+
+    let keys = files
+        .iter()
+        .filter_map(|file| {
+            if file.ends_with(".gpg") {
+                Some(Key::key(file))
+            } else if file.ends_with(".asc") {
+                Some(Key::armored_key(file))
+            } else {
+                debug!("Ignoring file {file} of keyring directory.");
+                None
+            }
+        })
+        .collect();
+
+    Ok(keys)
+}
+
+/// Load the signing key of the distro.
+///
+/// Returns None if no signature check is configured.
+pub async fn load_key(transport: &Transport, key: &Key) -> Result<Option<SignedPublicKey>> {
+    let (url, armored) = match key {
+        Key::ArmoredKey(url) => (url, true),
+        Key::Key(url) => (url, false),
+        Key::NoSignatureCheck => return Ok(None),
+    };
+
+    let content = key_content(transport, url).await?;
+
+    let key = if armored {
+        let content = String::from_utf8_lossy(&content);
+        SignedPublicKey::from_string(&content).map(|(key, _headers)| key)
+    } else {
+        SignedPublicKey::from_bytes(Cursor::new(content))
+    };
+
+    let url = key_location(url);
+    let key = key.map_err(|e| {
+        let message = format!("Loading key {url} failed! {e}");
+        error!("{}", message);
+        Error::new(&message, libapt::ErrorType::Verification)
+    })?;
+
+    if let Err(e) = key.verify() {
+        let message = format!("Key {url} is not valid! {e}");
+        error!("{}", message);
+        return Err(Error::new(&message, libapt::ErrorType::Verification));
+    }
+
+    Ok(Some(key))
+}
+
+/// Verify a detached signature of the content.
+///
+/// The signature may be armored or binary. The primary key and all
+/// subkeys are tried, since repositories are often signed by a subkey.
+pub fn verify_detached(key: &SignedPublicKey, content: &[u8], signature: &[u8]) -> Result<()> {
+    let signature = if signature.starts_with(b"-----BEGIN") {
+        let signature = String::from_utf8_lossy(signature);
+        StandaloneSignature::from_string(&signature).map(|(signature, _headers)| signature)
+    } else {
+        StandaloneSignature::from_bytes(Cursor::new(signature))
+    };
+
+    let signature = signature.map_err(|e| {
+        Error::new(&format!("Parsing signature failed! {e}"), libapt::ErrorType::Verification)
+    })?;
+
+    if signature.verify(key, content).is_ok() {
+        debug!("Signature verified with primary key.");
+        return Ok(());
+    }
+
+    for subkey in &key.public_subkeys {
+        if signature.verify(subkey, content).is_ok() {
+            debug!("Signature verified with subkey.");
+            return Ok(());
+        }
+    }
+
+    Err(Error::new("Signature doesn't match the key!", libapt::ErrorType::Verification))
+}
+
+/// Get the payload of an inline signed file.
+///
+/// Files without inline signature are returned unchanged.
+pub fn cleartext_payload(content: &str) -> Result<String> {
+    if !content.trim_start().starts_with(CLEARTEXT_HEADER) {
+        return Ok(content.to_string());
+    }
+
+    let (message, _headers) = CleartextSignedMessage::from_string(content).map_err(|e| {
+        Error::new(&format!("Parsing inline signed file failed! {e}"), libapt::ErrorType::Verification)
+    })?;
+
+    Ok(message.text().to_string())
+}
+
+/// Verify the signature of an inline signed file and get its payload.
+///
+/// The primary key and all subkeys are tried.
+pub fn verify_inline(key: &SignedPublicKey, content: &str) -> Result<String> {
+    if !content.trim_start().starts_with(CLEARTEXT_HEADER) {
+        let message = "InRelease file is not signed!";
+        error!("{}", message);
+        return Err(Error::new(message, libapt::ErrorType::Verification));
+    }
+
+    let (message, _headers) = CleartextSignedMessage::from_string(content).map_err(|e| {
+        Error::new(&format!("Parsing inline signed file failed! {e}"), libapt::ErrorType::Verification)
+    })?;
+
+    if message.verify(&key.primary_key).is_ok() || key.public_subkeys.iter().any(|subkey| message.verify(subkey).is_ok()) {
+        debug!("InRelease signature is OK.");
+        return Ok(message.text().to_string());
+    }
+
+    let message = "InRelease signature doesn't match the key!";
+    error!("{}", message);
+    Err(Error::new(message, libapt::ErrorType::Verification))
+}
+
+/// Get the ID of the primary key as hex string.
+pub fn key_id(key: &SignedPublicKey) -> String {
+    format!("{:X}", key.key_id())
+}
+
+/// Size in bits of a multiprecision integer.
+fn mpi_bits(mpi: &Mpi) -> usize {
+    let bytes = mpi.as_bytes();
+    match bytes.first() {
+        Some(first) => bytes.len() * 8 - first.leading_zeros() as usize,
+        None => 0,
+    }
+}
+
+/// Describe the algorithm of a key, if it is too weak.
+fn weak_algorithm(params: &PublicParams) -> Option<String> {
+    let (name, bits) = match params {
+        PublicParams::RSA { n, .. } => ("RSA", mpi_bits(n)),
+        PublicParams::DSA { p, .. } => ("DSA", mpi_bits(p)),
+        _ => return None,
+    };
+
+    if bits < MIN_KEY_BITS {
+        Some(format!("{bits}-bit {name}"))
+    } else {
+        None
+    }
+}
+
+/// Test if a signature uses a hash algorithm which is broken for signatures.
+fn is_weak_hash(signature: &Signature) -> bool {
+    matches!(signature.hash_alg(), HashAlgorithm::SHA1 | HashAlgorithm::MD5)
+}
+
+/// Describe the weaknesses of a key, i.e. short RSA or DSA keys and SHA-1 or MD5 self-signatures.
+pub fn key_weaknesses(key: &SignedPublicKey) -> Vec<String> {
+    let mut weaknesses = Vec::new();
+
+    if let Some(algorithm) = weak_algorithm(key.primary_key.public_params()) {
+        weaknesses.push(format!("primary key {} is {algorithm}", key_id(key)));
+    }
+
+    for subkey in &key.public_subkeys {
+        if let Some(algorithm) = weak_algorithm(subkey.key.public_params()) {
+            weaknesses.push(format!("subkey {:X} is {algorithm}", subkey.key.key_id()));
+        }
+    }
+
+    let weak_signatures = key
+        .details
+        .users
+        .iter()
+        .flat_map(|user| &user.signatures)
+        .chain(key.public_subkeys.iter().flat_map(|subkey| &subkey.signatures))
+        .filter(|signature| is_weak_hash(signature))
+        .count();
+    if weak_signatures > 0 {
+        weaknesses.push(format!("{weak_signatures} self-signatures use SHA-1 or MD5"));
+    }
+
+    weaknesses
+}
+
+/// Test if the key flags or, if no flags are given, the algorithm allow signing.
+fn can_sign<'a>(signatures: impl Iterator<Item = &'a Signature>, key: &impl PublicKeyTrait) -> bool {
+    let flags: Vec<KeyFlags> = signatures.map(|signature| signature.key_flags()).filter(|flags| *flags != KeyFlags::default()).collect();
+    if flags.is_empty() {
+        key.is_signing_key()
+    } else {
+        flags.iter().any(|flags| flags.sign())
+    }
+}
+
+/// Test if the primary key or a subkey valid at the given time can sign.
+pub fn has_signing_key(key: &SignedPublicKey, now: DateTime<Utc>) -> bool {
+    if can_sign(key.details.users.iter().flat_map(|user| &user.signatures), &key.primary_key) {
+        return true;
+    }
+
+    key.public_subkeys.iter().any(|subkey| {
+        let expired = subkey
+            .signatures
+            .iter()
+            .filter_map(|signature| signature.key_expiration_time())
+            .max()
+            .is_some_and(|expiration| *subkey.key.created_at() + *expiration <= now);
+        !expired && can_sign(subkey.signatures.iter(), &subkey.key)
+    })
+}
+
+/// Format a fingerprint as upper case hex string.
+fn format_fingerprint(fingerprint: &Fingerprint) -> String {
+    fingerprint.as_bytes().iter().map(|byte| format!("{byte:02X}")).collect()
+}
+
+/// Normalize a user-provided fingerprint, i.e. remove spaces and a 0x prefix and use upper case.
+pub fn normalize_fingerprint(fingerprint: &str) -> String {
+    let fingerprint: String = fingerprint.chars().filter(|c| !c.is_whitespace()).collect();
+    let fingerprint = fingerprint.strip_prefix("0x").unwrap_or(&fingerprint);
+    fingerprint.to_uppercase()
+}
+
+/// Get the fingerprints of the keys which made the signature of an inline signed file.
+///
+/// The primary key and all subkeys are tried. Each result names the key which
+/// made the signature and the primary key. (Signing key, Primary key)
+pub fn signing_fingerprints(key: &SignedPublicKey, content: &str) -> Result<Vec<(String, String)>> {
+    let (message, _headers) = CleartextSignedMessage::from_string(content).map_err(|e| {
+        Error::new(&format!("Parsing inline signed file failed! {e}"), libapt::ErrorType::Verification)
+    })?;
+
+    let primary = format_fingerprint(&key.fingerprint());
+    let mut fingerprints = Vec::new();
+
+    if message.verify(&key.primary_key).is_ok() {
+        fingerprints.push((primary.clone(), primary.clone()));
+    }
+    for subkey in &key.public_subkeys {
+        if message.verify(subkey).is_ok() {
+            fingerprints.push((format_fingerprint(&subkey.fingerprint()), primary.clone()));
+        }
+    }
+
+    Ok(fingerprints)
+}
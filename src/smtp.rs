@@ -0,0 +1,168 @@
+//! Minimal SMTP client for the mail notifications.
+//!
+//! Supports STARTTLS, TLS and AUTH PLAIN, which covers mail providers as well as local relays.
+
+use std::time::Duration;
+
+use base64::engine::general_purpose::STANDARD;
+use base64::Engine;
+use chrono::Utc;
+use libapt::{Error, Result};
+use log::{debug, error};
+use tokio::io::{AsyncBufReadExt, AsyncRead, AsyncWrite, AsyncWriteExt, BufReader};
+use tokio::net::TcpStream;
+use tokio_native_tls::{TlsConnector, TlsStream};
+
+use crate::options::{EmailOptions, SmtpTls};
+
+/// Timeout of the whole SMTP session.
+const SESSION_TIMEOUT: Duration = Duration::from_secs(60);
+
+/// Report a failure of the SMTP session.
+fn smtp_error(message: &str) -> Error {
+    error!("{}", message);
+    Error::new(message, libapt::ErrorType::Download)
+}
+
+/// Read a reply, which may span several lines. Returns the code and the last line.
+async fn reply<S: AsyncRead + Unpin>(stream: &mut BufReader<S>) -> Result<(u16, String)> {
+    loop {
+        let mut line = String::new();
+        let read = stream
+            .read_line(&mut line)
+            .await
+            .map_err(|e| smtp_error(&format!("Reading SMTP reply failed! {e}")))?;
+        if read == 0 {
+            return Err(smtp_error("SMTP server closed the connection!"));
+        }
+
+        let line = line.trim_end();
+        let code = match line.get(..3).and_then(|code| code.parse::<u16>().ok()) {
+            Some(code) => code,
+            None => return Err(smtp_error(&format!("Invalid SMTP reply: {line}"))),
+        };
+        // Lines followed by further lines have a dash after the code.
+        if line.as_bytes().get(3) != Some(&b'-') {
+            return Ok((code, line.to_string()));
+        }
+    }
+}
+
+/// Read a reply and check its code. The step is named in the error.
+async fn expect<S: AsyncRead + Unpin>(stream: &mut BufReader<S>, codes: &[u16], step: &str) -> Result<()> {
+    let (code, line) = reply(stream).await?;
+    if !codes.contains(&code) {
+        return Err(smtp_error(&format!("SMTP server rejected {step}: {line}")));
+    }
+    Ok(())
+}
+
+/// Send a line and check the code of the reply. The step is named in the log and errors.
+async fn send_line<S: AsyncRead + AsyncWrite + Unpin>(stream: &mut BufReader<S>, line: &str, codes: &[u16], step: &str) -> Result<()> {
+    debug!("Sending SMTP {step}...");
+    stream
+        .get_mut()
+        .write_all(format!("{line}\r\n").as_bytes())
+        .await
+        .map_err(|e| smtp_error(&format!("Sending SMTP {step} failed! {e}")))?;
+    expect(stream, codes, step).await
+}
+
+/// Send a command and check the code of the reply.
+async fn command<S: AsyncRead + AsyncWrite + Unpin>(stream: &mut BufReader<S>, command: &str, codes: &[u16]) -> Result<()> {
+    // Only the verb is named, the arguments may contain credentials.
+    let verb = command.split_whitespace().next().unwrap_or_default();
+    send_line(stream, command, codes, verb).await
+}
+
+/// Start a TLS session on the connection.
+async fn start_tls(options: &EmailOptions, stream: TcpStream) -> Result<TlsStream<TcpStream>> {
+    let connector = native_tls::TlsConnector::new().map_err(|e| smtp_error(&format!("Creating TLS connector failed! {e}")))?;
+    TlsConnector::from(connector)
+        .connect(&options.server, stream)
+        .await
+        .map_err(|e| smtp_error(&format!("TLS handshake with SMTP server {} failed! {e}", options.server)))
+}
+
+/// Format the message with headers, CRLF line ends and dot stuffing.
+fn message(options: &EmailOptions, subject: &str, body: &str) -> String {
+    let mut lines = vec![
+        format!("Date: {}", Utc::now().to_rfc2822()),
+        format!("From: {}", options.from),
+        format!("To: {}", options.to.join(", ")),
+        format!("Subject: {subject}"),
+        "MIME-Version: 1.0".to_string(),
+        "Content-Type: text/plain; charset=utf-8".to_string(),
+        "Content-Transfer-Encoding: 8bit".to_string(),
+        String::new(),
+    ];
+    // Lines starting with a dot get a second dot, so they don't end the data.
+    lines.extend(body.lines().map(|line| if line.starts_with('.') { format!(".{line}") } else { line.to_string() }));
+    lines.join("\r\n")
+}
+
+/// Get the EHLO command, naming the domain of the sender.
+fn ehlo(options: &EmailOptions) -> String {
+    let domain = options.from.rsplit_once('@').map(|(_, domain)| domain).unwrap_or("localhost");
+    format!("EHLO {domain}")
+}
+
+/// Authenticate and send the message, after the greeting and, if requested, the TLS handshake.
+async fn transaction<S: AsyncRead + AsyncWrite + Unpin>(stream: &mut BufReader<S>, options: &EmailOptions, message: &str) -> Result<()> {
+    command(stream, &ehlo(options), &[250]).await?;
+
+    if let Some(username) = &options.username {
+        let credentials = STANDARD.encode(format!("\0{username}\0{}", options.password.as_deref().unwrap_or_default()));
+        command(stream, &format!("AUTH PLAIN {credentials}"), &[235]).await?;
+    }
+
+    command(stream, &format!("MAIL FROM:<{}>", options.from), &[250]).await?;
+    for to in &options.to {
+        command(stream, &format!("RCPT TO:<{to}>"), &[250, 251]).await?;
+    }
+    command(stream, "DATA", &[354]).await?;
+    send_line(stream, &format!("{message}\r\n."), &[250], "message").await?;
+
+    // The message is accepted, so a failing QUIT is ignored.
+    let _ = command(stream, "QUIT", &[221]).await;
+    Ok(())
+}
+
+/// Connect to the SMTP server and send the message.
+async fn deliver(options: &EmailOptions, message: &str) -> Result<()> {
+    let port = options.port();
+    let stream = TcpStream::connect((options.server.as_str(), port))
+        .await
+        .map_err(|e| smtp_error(&format!("Connecting to SMTP server {}:{port} failed! {e}", options.server)))?;
+
+    match options.tls {
+        SmtpTls::None => {
+            let mut stream = BufReader::new(stream);
+            expect(&mut stream, &[220], "connection").await?;
+            transaction(&mut stream, options, message).await
+        }
+        SmtpTls::Tls => {
+            let mut stream = BufReader::new(start_tls(options, stream).await?);
+            expect(&mut stream, &[220], "connection").await?;
+            transaction(&mut stream, options, message).await
+        }
+        SmtpTls::Starttls => {
+            let mut stream = BufReader::new(stream);
+            expect(&mut stream, &[220], "connection").await?;
+            command(&mut stream, &ehlo(options), &[250]).await?;
+            command(&mut stream, "STARTTLS", &[220]).await?;
+
+            let mut stream = BufReader::new(start_tls(options, stream.into_inner()).await?);
+            transaction(&mut stream, options, message).await
+        }
+    }
+}
+
+/// Send a plain text mail to the configured recipients.
+pub async fn send(options: &EmailOptions, subject: &str, body: &str) -> Result<()> {
+    let message = message(options, subject, body);
+    match tokio::time::timeout(SESSION_TIMEOUT, deliver(options, &message)).await {
+        Ok(result) => result,
+        Err(_) => Err(smtp_error(&format!("SMTP session with {} timed out!", options.server))),
+    }
+}
@@ -0,0 +1,353 @@
+//! Parsing of one-line sources.list files and deb822 .sources files.
+
+use libapt::{Distro, Key};
+
+/// Entry of a sources.list file.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SourceEntry {
+    // Line number of the entry, starting with 1.
+    pub line: usize,
+    // Text of the entry.
+    pub text: String,
+    // Type of the entry: deb or deb-src.
+    pub kind: String,
+    // URL of the repository.
+    pub url: String,
+    // Suite, or path of a flat repository if it ends with a slash.
+    pub suite: String,
+    // Components of the suite, empty for flat repositories.
+    pub components: Vec<String>,
+    // Architectures given by the arch option.
+    pub architectures: Vec<String>,
+    // Keyrings or fingerprints given by the signed-by option.
+    pub signed_by: Vec<String>,
+    // Armored key embedded in the Signed-By field of a deb822 stanza.
+    pub inline_key: Option<String>,
+}
+
+impl SourceEntry {
+    /// Test if the entry describes a flat repository.
+    pub fn is_flat(&self) -> bool {
+        self.suite.ends_with('/')
+    }
+
+    /// Get the signing keys of the entry. Keyrings ending with .asc are armored keys.
+    pub fn keys(&self) -> Vec<Key> {
+        self.signed_by
+            .iter()
+            .filter(|value| !is_fingerprint(value))
+            .map(|value| if value.ends_with(".asc") { Key::armored_key(value) } else { Key::key(value) })
+            .collect()
+    }
+
+    /// Get the fingerprints given by the signed-by option.
+    pub fn fingerprints(&self) -> Vec<String> {
+        self.signed_by
+            .iter()
+            .filter(|value| is_fingerprint(value))
+            .map(|value| value.trim_end_matches('!').to_string())
+            .collect()
+    }
+
+    /// Get the distro of the entry, using the given signing key.
+    pub fn distro(&self, key: Key) -> Distro {
+        let (name, path) = if self.is_flat() {
+            (None, Some(self.suite.clone()))
+        } else {
+            (Some(self.suite.clone()), None)
+        };
+
+        Distro {
+            url: self.url.clone(),
+            name,
+            path,
+            key,
+        }
+    }
+}
+
+/// Test if a signed-by value is a fingerprint, which may end with ! to select a subkey.
+fn is_fingerprint(value: &str) -> bool {
+    let value = value.trim_end_matches('!');
+    value.len() >= 16 && value.chars().all(|c| c.is_ascii_hexdigit())
+}
+
+/// Parse the options of an entry, e.g. arch=amd64,arm64 signed-by=/usr/share/keyrings/example.gpg.
+fn parse_options(options: &str, entry: &mut SourceEntry) -> Result<(), String> {
+    for option in options.split_whitespace() {
+        let (key, value) = option.split_once('=').ok_or_else(|| format!("invalid option {option}"))?;
+        let values = value.split(',').filter(|value| !value.is_empty()).map(|value| value.to_string());
+        match key {
+            "arch" | "arch+" => entry.architectures.extend(values),
+            "signed-by" => entry.signed_by.extend(values),
+            // Other options don't affect the checks.
+            _ => {}
+        }
+    }
+    Ok(())
+}
+
+/// Parse a single line, None for empty lines and comments.
+fn parse_line(number: usize, line: &str) -> Result<Option<SourceEntry>, String> {
+    let text = match line.split_once('#') {
+        Some((text, _)) => text.trim(),
+        None => line.trim(),
+    };
+    if text.is_empty() {
+        return Ok(None);
+    }
+
+    let (kind, mut rest) = text.split_once(char::is_whitespace).ok_or_else(|| format!("line {number}: incomplete entry"))?;
+    if kind != "deb" && kind != "deb-src" {
+        return Err(format!("line {number}: unknown type {kind}"));
+    }
+
+    let mut entry = SourceEntry {
+        line: number,
+        text: text.to_string(),
+        kind: kind.to_string(),
+        url: String::new(),
+        suite: String::new(),
+        components: Vec::new(),
+        architectures: Vec::new(),
+        signed_by: Vec::new(),
+        inline_key: None,
+    };
+
+    rest = rest.trim_start();
+    if let Some(options) = rest.strip_prefix('[') {
+        let (options, after) = options.split_once(']').ok_or_else(|| format!("line {number}: options are not terminated"))?;
+        parse_options(options, &mut entry).map_err(|e| format!("line {number}: {e}"))?;
+        rest = after;
+    }
+
+    let mut parts = rest.split_whitespace();
+    entry.url = parts.next().ok_or_else(|| format!("line {number}: URL is missing"))?.trim_end_matches('/').to_string();
+    entry.suite = parts.next().ok_or_else(|| format!("line {number}: suite is missing"))?.to_string();
+    entry.components = parts.map(|component| component.to_string()).collect();
+
+    if entry.is_flat() && !entry.components.is_empty() {
+        return Err(format!("line {number}: flat repository {} must not have components", entry.suite));
+    }
+    if !entry.is_flat() && entry.components.is_empty() {
+        return Err(format!("line {number}: components are missing"));
+    }
+
+    Ok(Some(entry))
+}
+
+/// Parse the deb and deb-src entries of a one-line sources.list file.
+pub fn parse(content: &str) -> Result<Vec<SourceEntry>, String> {
+    let mut entries = Vec::new();
+    for (number, line) in content.lines().enumerate() {
+        if let Some(entry) = parse_line(number + 1, line)? {
+            entries.push(entry);
+        }
+    }
+    Ok(entries)
+}
+
+/// Test if the content is in deb822 format, i.e. doesn't start with a deb or deb-src entry.
+pub fn is_deb822(content: &str) -> bool {
+    content
+        .lines()
+        .map(|line| line.trim())
+        .find(|line| !line.is_empty() && !line.starts_with('#'))
+        .is_some_and(|line| !line.starts_with("deb ") && !line.starts_with("deb-src ") && !line.starts_with("deb\t") && !line.starts_with("deb-src\t"))
+}
+
+/// Fields of a deb822 stanza. (Line number of the stanza, Fields in lower case with their values)
+type Stanza = (usize, Vec<(String, String)>);
+
+/// Split deb822 content into stanzas, joining the continuation lines of the fields.
+fn deb822_stanzas(content: &str) -> Result<Vec<Stanza>, String> {
+    let mut stanzas: Vec<Stanza> = Vec::new();
+    let mut current: Option<Stanza> = None;
+
+    for (number, line) in content.lines().enumerate() {
+        let number = number + 1;
+        if line.starts_with('#') {
+            continue;
+        }
+        if line.trim().is_empty() {
+            stanzas.extend(current.take());
+            continue;
+        }
+
+        let stanza = current.get_or_insert_with(|| (number, Vec::new()));
+        if line.starts_with(' ') || line.starts_with('\t') {
+            let (_, value) = stanza.1.last_mut().ok_or_else(|| format!("line {number}: continuation line without field"))?;
+            // A single dot marks an empty line of a multiline value.
+            let line = if line.trim() == "." { "" } else { line.trim() };
+            value.push('\n');
+            value.push_str(line);
+        } else {
+            let (name, value) = line.split_once(':').ok_or_else(|| format!("line {number}: invalid field {line}"))?;
+            stanza.1.push((name.trim().to_lowercase(), value.trim().to_string()));
+        }
+    }
+    stanzas.extend(current.take());
+
+    Ok(stanzas)
+}
+
+/// Parse the stanzas of a deb822 .sources file.
+///
+/// Each combination of type, URI and suite of a stanza becomes an entry.
+/// Stanzas with Enabled: no are skipped.
+pub fn parse_deb822(content: &str) -> Result<Vec<SourceEntry>, String> {
+    let mut entries = Vec::new();
+
+    for (number, fields) in deb822_stanzas(content)? {
+        let field = |name: &str| fields.iter().find(|(field, _)| field == name).map(|(_, value)| value.as_str());
+        let list = |name: &str| field(name).map(|value| value.split_whitespace().map(|item| item.to_string()).collect::<Vec<String>>()).unwrap_or_default();
+
+        if field("enabled").is_some_and(|enabled| enabled.eq_ignore_ascii_case("no")) {
+            continue;
+        }
+
+        let types = list("types");
+        let urls = list("uris");
+        let suites = list("suites");
+        for (name, values) in [("Types", &types), ("URIs", &urls), ("Suites", &suites)] {
+            if values.is_empty() {
+                return Err(format!("line {number}: {name} is missing"));
+            }
+        }
+        if let Some(kind) = types.iter().find(|kind| *kind != "deb" && *kind != "deb-src") {
+            return Err(format!("line {number}: unknown type {kind}"));
+        }
+
+        let (signed_by, inline_key) = match field("signed-by") {
+            Some(value) if value.contains("-----BEGIN") => (Vec::new(), Some(value.trim().to_string())),
+            Some(value) => (value.split_whitespace().map(|item| item.to_string()).collect(), None),
+            None => (Vec::new(), None),
+        };
+
+        for kind in &types {
+            for url in &urls {
+                for suite in &suites {
+                    let components = list("components");
+                    let mut text = format!("{kind} {url} {suite}");
+                    if !components.is_empty() {
+                        text = format!("{text} {}", components.join(" "));
+                    }
+
+                    let entry = SourceEntry {
+                        line: number,
+                        text,
+                        kind: kind.clone(),
+                        url: url.trim_end_matches('/').to_string(),
+                        suite: suite.clone(),
+                        components,
+                        architectures: list("architectures"),
+                        signed_by: signed_by.clone(),
+                        inline_key: inline_key.clone(),
+                    };
+
+                    if entry.is_flat() && !entry.components.is_empty() {
+                        return Err(format!("line {number}: flat repository {suite} must not have components"));
+                    }
+                    if !entry.is_flat() && entry.components.is_empty() {
+                        return Err(format!("line {number}: Components is missing"));
+                    }
+
+                    entries.push(entry);
+                }
+            }
+        }
+    }
+
+    Ok(entries)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn one_line_entries() {
+        let content = "# Debian\ndeb [arch=amd64,arm64 signed-by=/usr/share/keyrings/debian.gpg] http://deb.debian.org/debian/ bookworm main contrib\n\ndeb-src http://deb.debian.org/debian bookworm main # sources\ndeb http://example.org/repo ./\n";
+        let entries = parse(content).unwrap();
+        assert_eq!(entries.len(), 3);
+
+        assert_eq!(entries[0].line, 2);
+        assert_eq!(entries[0].kind, "deb");
+        assert_eq!(entries[0].url, "http://deb.debian.org/debian");
+        assert_eq!(entries[0].suite, "bookworm");
+        assert_eq!(entries[0].components, ["main", "contrib"]);
+        assert_eq!(entries[0].architectures, ["amd64", "arm64"]);
+        assert_eq!(entries[0].keys(), [Key::key("/usr/share/keyrings/debian.gpg")]);
+
+        assert_eq!(entries[1].kind, "deb-src");
+        assert_eq!(entries[1].text, "deb-src http://deb.debian.org/debian bookworm main");
+
+        assert!(entries[2].is_flat());
+        assert!(entries[2].components.is_empty());
+        let distro = entries[2].distro(Key::NoSignatureCheck);
+        assert_eq!(distro.name, None);
+        assert_eq!(distro.path.as_deref(), Some("./"));
+    }
+
+    #[test]
+    fn one_line_errors() {
+        assert_eq!(parse("\ndeb http://example.org/repo stable\n"), Err("line 2: components are missing".to_string()));
+        assert_eq!(parse("rpm http://example.org/repo stable main"), Err("line 1: unknown type rpm".to_string()));
+        assert_eq!(parse("deb [arch=amd64 http://example.org/repo stable main"), Err("line 1: options are not terminated".to_string()));
+        assert_eq!(
+            parse("deb http://example.org/repo ./ main"),
+            Err("line 1: flat repository ./ must not have components".to_string())
+        );
+    }
+
+    #[test]
+    fn signed_by_values() {
+        let entries = parse("deb [signed-by=/etc/apt/keyrings/repo.asc,0123456789ABCDEF0123!] http://example.org/repo stable main").unwrap();
+        assert_eq!(entries[0].keys(), [Key::armored_key("/etc/apt/keyrings/repo.asc")]);
+        assert_eq!(entries[0].fingerprints(), ["0123456789ABCDEF0123"]);
+    }
+
+    #[test]
+    fn detect_deb822() {
+        assert!(is_deb822("# comment\nTypes: deb\n"));
+        assert!(!is_deb822("# comment\n\ndeb http://example.org/repo stable main\n"));
+        assert!(!is_deb822("deb-src\thttp://example.org/repo stable main\n"));
+    }
+
+    #[test]
+    fn deb822_entries() {
+        let content = "Types: deb deb-src\nURIs: http://example.org/repo/\nSuites: stable testing\nComponents: main\nArchitectures: amd64\nSigned-By: /etc/apt/keyrings/repo.gpg\n\nTypes: deb\nURIs: http://example.org/other\nSuites: stable\nComponents: main\nEnabled: no\n";
+        let entries = parse_deb822(content).unwrap();
+        assert_eq!(entries.len(), 4);
+        assert_eq!(entries[0].text, "deb http://example.org/repo/ stable main");
+        assert_eq!(entries[0].url, "http://example.org/repo");
+        assert_eq!(entries[1].suite, "testing");
+        assert_eq!(entries[2].kind, "deb-src");
+        assert!(entries.iter().all(|entry| entry.line == 1 && entry.architectures == ["amd64"]));
+        assert_eq!(entries[3].keys(), [Key::key("/etc/apt/keyrings/repo.gpg")]);
+    }
+
+    #[test]
+    fn deb822_inline_key() {
+        let content = "Types: deb\nURIs: http://example.org/repo\nSuites: stable\nComponents: main\nSigned-By:\n -----BEGIN PGP PUBLIC KEY BLOCK-----\n .\n mQINBGQ\n -----END PGP PUBLIC KEY BLOCK-----\n";
+        let entries = parse_deb822(content).unwrap();
+        assert!(entries[0].signed_by.is_empty());
+        assert_eq!(
+            entries[0].inline_key.as_deref(),
+            Some("-----BEGIN PGP PUBLIC KEY BLOCK-----\n\nmQINBGQ\n-----END PGP PUBLIC KEY BLOCK-----")
+        );
+    }
+
+    #[test]
+    fn deb822_errors() {
+        assert_eq!(
+            parse_deb822("Types: deb\nURIs: http://example.org/repo\nSuites: stable\n"),
+            Err("line 1: Components is missing".to_string())
+        );
+        assert_eq!(
+            parse_deb822("\nTypes: deb\nSuites: stable\nComponents: main\n"),
+            Err("line 2: URIs is missing".to_string())
+        );
+        assert_eq!(parse_deb822(" main\n"), Err("line 1: continuation line without field".to_string()));
+    }
+}
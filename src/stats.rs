@@ -0,0 +1,288 @@
+//! Statistics of a repository: package counts, pool size, sources and maintainers.
+//!
+//! The statistics are computed from the Packages and Sources indices only,
+//! so the pool size is the sum of the Size fields and no pool file is downloaded.
+//! The size is also broken down by section and source, with the largest packages listed.
+//! Optionally, the architecture coverage of the sources shows which architectures lack binaries.
+
+use std::cmp::Reverse;
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
+
+use libapt::{Architecture, Release, Result};
+use log::{info, warn};
+use serde::{Deserialize, Serialize};
+
+use crate::index;
+use crate::options::StatsOptions;
+use crate::transport::Transport;
+use crate::util::format_bytes;
+
+/// Statistics of a Packages or Sources index.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct IndexStats {
+    pub component: String,
+    // Architecture of the index, source for the Sources index.
+    pub architecture: String,
+    // Number of package or source stanzas.
+    pub packages: usize,
+    // Sum of the sizes of the referenced files in bytes, including files shared with other indices.
+    pub size: u64,
+}
+
+/// Size of a binary package.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct PackageSize {
+    pub package: String,
+    pub version: String,
+    pub component: String,
+    pub architecture: String,
+    // Size of the deb file in bytes.
+    pub size: u64,
+}
+
+/// Size of the pool files of a section or source.
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+pub struct SizeGroup {
+    // Name of the section or source.
+    pub name: String,
+    // Number of distinct pool files.
+    pub files: usize,
+    // Sum of the sizes of the pool files in bytes.
+    pub size: u64,
+}
+
+/// Architectures a source package has binaries for.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct SourceCoverage {
+    pub source: String,
+    pub component: String,
+    // Architectures with an architecture dependent binary of the source.
+    pub built: Vec<String>,
+    // Architectures the source should be built for according to its Architecture field, but without binary.
+    pub missing: Vec<String>,
+}
+
+/// Matrix of the source packages versus the architectures.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct ArchCoverage {
+    // Architectures of the columns.
+    pub architectures: Vec<String>,
+    // Sources with architecture dependent binaries or with missing binaries, by name.
+    pub sources: Vec<SourceCoverage>,
+}
+
+impl ArchCoverage {
+    /// Get the sources which miss binaries for some architectures.
+    pub fn incomplete(&self) -> impl Iterator<Item = &SourceCoverage> {
+        self.sources.iter().filter(|source| !source.missing.is_empty())
+    }
+}
+
+/// RepoStats groups the statistics of a repository.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct RepoStats {
+    // InRelease URL of the repository.
+    pub repo: String,
+    // Statistics per component and architecture.
+    pub indices: Vec<IndexStats>,
+    // Sum of the sizes of the distinct pool files in bytes. Architecture all packages count once.
+    pub pool_size: u64,
+    // Number of distinct source packages of all components.
+    pub sources: usize,
+    // Number of distinct Maintainer fields of all binary and source packages.
+    pub maintainers: usize,
+    // Largest binary packages, largest first.
+    pub largest_packages: Vec<PackageSize>,
+    // Pool size per section, largest first.
+    pub sections: Vec<SizeGroup>,
+    // Pool size per source package, including its binaries, largest first.
+    pub source_sizes: Vec<SizeGroup>,
+    // Architecture coverage of the sources, if requested.
+    pub coverage: Option<ArchCoverage>,
+    // Indices which could not be loaded. (Index, Error)
+    pub failures: Vec<(String, String)>,
+}
+
+/// Sort the groups by size, largest first.
+fn sorted_groups(groups: HashMap<String, SizeGroup>) -> Vec<SizeGroup> {
+    let mut groups: Vec<SizeGroup> = groups
+        .into_iter()
+        .map(|(name, group)| SizeGroup { name, ..group })
+        .collect();
+    groups.sort_by(|a, b| b.size.cmp(&a.size).then_with(|| a.name.cmp(&b.name)));
+    groups
+}
+
+/// Test if a source with the given Architecture field is built for the architecture.
+///
+/// Wildcards like linux-any are treated like any, as the matched architectures are not known.
+fn is_built_for(source_architectures: &str, architecture: &str) -> bool {
+    source_architectures
+        .split_whitespace()
+        .any(|entry| entry == architecture || entry == "any" || entry.starts_with("any-") || entry.ends_with("-any"))
+}
+
+/// Build the coverage matrix of the sources. (Source, (Component, Architecture field)) (Source, Architectures)
+fn coverage(architectures: Vec<String>, sources: &BTreeMap<String, (String, String)>, built: &HashMap<String, BTreeSet<String>>) -> ArchCoverage {
+    let mut names: BTreeSet<&String> = sources.keys().collect();
+    names.extend(built.keys());
+
+    let sources = names
+        .into_iter()
+        .map(|source| {
+            let (component, source_architectures) = sources.get(source).cloned().unwrap_or_default();
+            let built = built.get(source).cloned().unwrap_or_default();
+            SourceCoverage {
+                source: source.clone(),
+                component,
+                missing: architectures
+                    .iter()
+                    .filter(|architecture| !built.contains(*architecture) && is_built_for(&source_architectures, architecture))
+                    .cloned()
+                    .collect(),
+                built: built.into_iter().collect(),
+            }
+        })
+        .filter(|source| !source.built.is_empty() || !source.missing.is_empty())
+        .collect();
+
+    ArchCoverage { architectures, sources }
+}
+
+/// Compute the statistics of the selected components and architectures of a repository.
+///
+/// Indices which can't be loaded are skipped and listed as failures.
+pub async fn run(transport: &Transport, release: &Release, options: &StatsOptions) -> Result<RepoStats> {
+    let mut stats = RepoStats {
+        repo: release.distro.in_release_url()?,
+        indices: Vec::new(),
+        pool_size: 0,
+        sources: 0,
+        maintainers: 0,
+        largest_packages: Vec::new(),
+        sections: Vec::new(),
+        source_sizes: Vec::new(),
+        coverage: None,
+        failures: Vec::new(),
+    };
+
+    let components: Vec<&String> = release
+        .components
+        .iter()
+        .filter(|component| options.components.is_empty() || options.components.contains(component))
+        .collect();
+    let mut architectures = release.architectures.clone();
+    architectures.push(Architecture::Source);
+    architectures.retain(|architecture| options.architectures.is_empty() || options.architectures.contains(&architecture.to_string()));
+
+    let mut pool_files: HashSet<String> = HashSet::new();
+    let mut sources: HashSet<String> = HashSet::new();
+    let mut maintainers: HashSet<String> = HashSet::new();
+    let mut sections: HashMap<String, SizeGroup> = HashMap::new();
+    let mut source_sizes: HashMap<String, SizeGroup> = HashMap::new();
+    // Architecture fields of the sources and the architectures of their binaries, for the coverage.
+    let mut source_architectures: BTreeMap<String, (String, String)> = BTreeMap::new();
+    let mut built: HashMap<String, BTreeSet<String>> = HashMap::new();
+
+    for component in components {
+        for architecture in &architectures {
+            let name = format!("{component} ({architecture})");
+            let link = match index::package_index_link(transport, release, component, architecture).await {
+                Ok(link) => link,
+                Err(e) => {
+                    warn!("Skipping index {name}: {e}");
+                    stats.failures.push((name, e.to_string()));
+                    continue;
+                }
+            };
+            let content = match index::download_index(transport, &link).await {
+                Ok(content) => content,
+                Err(e) => {
+                    warn!("Loading index {name} failed: {e}");
+                    stats.failures.push((name, e.to_string()));
+                    continue;
+                }
+            };
+
+            let is_source = architecture == &Architecture::Source;
+            let mut index_stats = IndexStats { component: component.clone(), architecture: architecture.to_string(), packages: 0, size: 0 };
+            for stanza in index::stanzas(&content) {
+                index_stats.packages += 1;
+                if let Some(maintainer) = index::field(stanza, "Maintainer") {
+                    maintainers.insert(maintainer.to_string());
+                }
+
+                let package = index::field(stanza, "Package").unwrap_or_default();
+                // The Source field of a binary may carry a version, e.g. "hello (1.0-1)".
+                let source = match index::field(stanza, "Source") {
+                    Some(source) if !is_source => source.split_whitespace().next().unwrap_or(package),
+                    _ => package,
+                };
+                let section = index::field(stanza, "Section").unwrap_or("unknown");
+
+                let binary_architecture = index::field(stanza, "Architecture").unwrap_or_default();
+                if is_source {
+                    source_architectures.insert(package.to_string(), (component.clone(), binary_architecture.to_string()));
+                } else if binary_architecture != "all" {
+                    built.entry(source.to_string()).or_default().insert(binary_architecture.to_string());
+                }
+
+                let files = if is_source {
+                    sources.insert(package.to_string());
+                    index::source_files(stanza)
+                } else {
+                    index::package_files(stanza)
+                };
+                for (path, size) in files {
+                    index_stats.size += size;
+                    if !pool_files.insert(path) {
+                        continue;
+                    }
+                    stats.pool_size += size;
+                    for group in [sections.entry(section.to_string()).or_default(), source_sizes.entry(source.to_string()).or_default()] {
+                        group.files += 1;
+                        group.size += size;
+                    }
+                    if !is_source {
+                        stats.largest_packages.push(PackageSize {
+                            package: package.to_string(),
+                            version: index::field(stanza, "Version").unwrap_or_default().to_string(),
+                            component: component.clone(),
+                            architecture: binary_architecture.to_string(),
+                            size,
+                        });
+                    }
+                }
+            }
+
+            info!("Index {name} has {} packages of {}.", index_stats.packages, format_bytes(index_stats.size));
+            stats.indices.push(index_stats);
+        }
+    }
+
+    stats.sources = sources.len();
+    stats.maintainers = maintainers.len();
+    stats.largest_packages.sort_by_key(|package| Reverse(package.size));
+    stats.largest_packages.truncate(options.top);
+    stats.sections = sorted_groups(sections);
+    stats.source_sizes = sorted_groups(source_sizes);
+
+    if options.coverage {
+        let binary_architectures = architectures
+            .iter()
+            .filter(|architecture| **architecture != Architecture::Source)
+            .map(|architecture| architecture.to_string())
+            .collect();
+        let coverage = coverage(binary_architectures, &source_architectures, &built);
+        info!("Found {} sources with incomplete architecture coverage.", coverage.incomplete().count());
+        stats.coverage = Some(coverage);
+    }
+    info!(
+        "Found {} distinct sources, {} maintainers and {} of pool files.",
+        stats.sources,
+        stats.maintainers,
+        format_bytes(stats.pool_size)
+    );
+
+    Ok(stats)
+}
@@ -0,0 +1,105 @@
+//! Timing of the check phases.
+
+use std::fmt;
+use std::time::Duration;
+
+use libapt::Architecture;
+use serde::{Deserialize, Serialize};
+
+/// Phases of a repo check.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Deserialize, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Phase {
+    // Download and verification of the InRelease file.
+    Release,
+    // Checks of the detached Release and Release.gpg files.
+    DetachedRelease,
+    // Verification of all variants of an index against the Release file.
+    IndexHashes,
+    // Download and parsing of an index.
+    Index,
+    // Existence checks of the referenced files.
+    Files,
+    // Comparison of the .dsc files with the Sources index.
+    Dsc,
+    // Comparison of the control files of the debs with the binary indices.
+    Control,
+    // Resolution of the package dependencies.
+    Dependencies,
+    // Verification of the pdiffs of an index.
+    Pdiffs,
+    // Parsing of the debian-installer index.
+    Udeb,
+    // Analysis of Breaks, Conflicts and Replaces.
+    Conflicts,
+    // Cross-reference of the Contents index.
+    Contents,
+    // Cross-reference of the command-not-found metadata.
+    Commands,
+    // Verification of the Translation indices.
+    Translations,
+    // Verification of the DEP-11 icon tarballs.
+    Dep11,
+    // Resolution of the build dependencies of the sources.
+    BuildDependencies,
+    // Lookup of the binaries listed by the sources.
+    SourceBinaries,
+    // Checks involving multiple components.
+    CrossCheck,
+}
+
+impl fmt::Display for Phase {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            Phase::Release => "release",
+            Phase::DetachedRelease => "detached-release",
+            Phase::IndexHashes => "index-hashes",
+            Phase::Index => "index",
+            Phase::Files => "files",
+            Phase::Dsc => "dsc",
+            Phase::Control => "control",
+            Phase::Dependencies => "dependencies",
+            Phase::Pdiffs => "pdiffs",
+            Phase::Udeb => "udeb",
+            Phase::Conflicts => "conflicts",
+            Phase::Contents => "contents",
+            Phase::Commands => "commands",
+            Phase::Translations => "translations",
+            Phase::Dep11 => "dep11",
+            Phase::BuildDependencies => "build-dependencies",
+            Phase::SourceBinaries => "source-binaries",
+            Phase::CrossCheck => "cross-check",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+/// Duration of a single phase of the check.
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+pub struct Timing {
+    pub phase: Phase,
+    // Checked component, if the phase is component specific.
+    pub component: Option<String>,
+    // Checked architecture, if the phase is architecture specific.
+    pub architecture: Option<Architecture>,
+    pub duration_ms: f64,
+}
+
+impl Timing {
+    /// Create a new timing for a phase not specific to a component.
+    pub fn new(phase: Phase, duration: Duration) -> Timing {
+        Timing {
+            phase,
+            component: None,
+            architecture: None,
+            duration_ms: duration.as_secs_f64() * 1000.0,
+        }
+    }
+
+    /// Set the checked component and architecture.
+    pub fn with_unit(mut self, component: &str, architecture: &Architecture) -> Timing {
+        self.component = Some(component.to_string());
+        self.architecture = Some(architecture.clone());
+        self
+    }
+}
@@ -0,0 +1,58 @@
+//! Parsing of i18n Translation indices and of the i18n/Index file listing them.
+
+use std::collections::BTreeMap;
+
+use libapt::{Link, LinkHash};
+
+use crate::index;
+use crate::release_file::ReleaseFile;
+
+/// Describe the stanzas of a Translation index which can't be parsed.
+///
+/// Each stanza must name the package, the MD5 sum of the untranslated
+/// description and the description in the language of the index.
+pub fn stanza_issues(content: &str, language: &str) -> Vec<String> {
+    let description = format!("Description-{language}");
+    let mut issues = Vec::new();
+
+    for (number, stanza) in index::stanzas(content).enumerate() {
+        let fields: Vec<&str> = stanza
+            .lines()
+            .filter(|line| !line.starts_with(' ') && !line.starts_with('\t'))
+            .filter_map(|line| line.split_once(':').map(|(key, _)| key.trim()))
+            .collect();
+
+        let missing: Vec<&str> = ["Package", "Description-md5", description.as_str()]
+            .into_iter()
+            .filter(|field| !fields.contains(field))
+            .collect();
+
+        if !missing.is_empty() {
+            issues.push(format!("stanza {} lacks {}", number + 1, missing.join(", ")));
+        }
+    }
+
+    issues
+}
+
+/// Get the Translation files listed by an i18n/Index file, with the hashes of all its sections.
+///
+/// The i18n/Index file uses the hash sections of a Release file, e.g. SHA256, with paths relative
+/// to the i18n directory, whose URL is given as base.
+pub fn index_links(content: &str, base: &str) -> Vec<Link> {
+    let mut links: BTreeMap<String, Link> = BTreeMap::new();
+    for (section, files) in ReleaseFile::parse(content).hashes {
+        let hash = match section.as_str() {
+            "MD5Sum" => LinkHash::Md5,
+            "SHA1" => LinkHash::Sha1,
+            "SHA256" => LinkHash::Sha256,
+            "SHA512" => LinkHash::Sha512,
+            _ => continue,
+        };
+        for (path, (value, size)) in files {
+            let link = links.entry(path.clone()).or_insert_with(|| Link { url: format!("{base}{path}"), size: size as usize, hashes: Default::default() });
+            link.hashes.insert(hash.clone(), value);
+        }
+    }
+    links.into_values().collect()
+}
@@ -0,0 +1,511 @@
+//! Download helpers for apt repository files.
+
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::io::{self, BufReader, Read};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use chrono::Utc;
+use libapt::{Error, Result};
+use log::{debug, error, info, warn};
+use reqwest::header::{HeaderName, CONTENT_LENGTH, CONTENT_RANGE, ETAG, LAST_MODIFIED, RANGE};
+use reqwest::StatusCode;
+use sha2::{Digest, Sha256};
+use reqwest::{Certificate, Client, Identity, Method, NoProxy, Proxy, RequestBuilder, Response, Url};
+use tokio::sync::Semaphore;
+
+use crate::options::{Auth, Http2Mode, HttpOptions, ProbeMethod};
+use crate::s3::S3Config;
+
+/// Delay before the first retry, growing linearly with each attempt.
+const RETRY_DELAY: Duration = Duration::from_millis(500);
+
+/// Transport wraps a shared HTTP client.
+///
+/// All requests use the same client, so connections are reused,
+/// and the number of parallel requests per host is limited.
+/// Files of file:// URLs are read from the local filesystem instead,
+/// and s3:// URLs are requested from the S3 endpoint of the bucket.
+#[derive(Debug)]
+pub struct Transport {
+    client: Client,
+    options: HttpOptions,
+    // Connection limits. (Host, Semaphore)
+    hosts: Mutex<HashMap<String, Arc<Semaphore>>>,
+    // Hosts which don't answer HEAD requests properly.
+    broken_head: Mutex<HashSet<String>>,
+    // Settings of the s3:// URLs.
+    s3: S3Config,
+    // Number of downloaded bytes, for the progress reports.
+    downloaded: AtomicU64,
+}
+
+/// Get the host name of the given URL.
+fn host_of(url: &str) -> String {
+    match Url::parse(url) {
+        Ok(url) => url.host_str().unwrap_or_default().to_string(),
+        Err(_) => String::new(),
+    }
+}
+
+/// Get the local path of a file:// URL.
+pub fn local_path(url: &str) -> Option<PathBuf> {
+    if !url.starts_with("file:") {
+        return None;
+    }
+    Url::parse(url).ok()?.to_file_path().ok()
+}
+
+/// Read a local file, or its beginning if a length is given.
+fn read_local(path: &Path, url: &str, length: Option<usize>) -> Result<Vec<u8>> {
+    let file = fs::File::open(path).map_err(|e| Error::from_io_error(e, url))?;
+    let mut data = Vec::new();
+    match length {
+        Some(length) => file.take(length as u64).read_to_end(&mut data),
+        None => BufReader::new(file).read_to_end(&mut data),
+    }
+    .map_err(|e| Error::from_io_error(e, url))?;
+    Ok(data)
+}
+
+/// Get the value of a numeric header.
+fn header_number(response: &Response, name: reqwest::header::HeaderName) -> Option<u64> {
+    response.headers().get(name)?.to_str().ok()?.trim().parse::<u64>().ok()
+}
+
+/// Turn a non-success response into an error.
+fn check_status(response: Response, url: &str) -> Result<Response> {
+    if response.status().is_success() {
+        Ok(response)
+    } else {
+        Err(Error::new(
+            &format!("Request of {url} failed with status {}!", response.status()),
+            libapt::ErrorType::Download,
+        ))
+    }
+}
+
+/// Read a TLS certificate or key file.
+fn read_tls_file(path: &str) -> Result<Vec<u8>> {
+    fs::read(path).map_err(|e| {
+        let message = format!("Reading TLS file {path} failed! {e}");
+        error!("{}", message);
+        Error::new(&message, libapt::ErrorType::ApiUsage)
+    })
+}
+
+/// Load the CA certificates of a PEM bundle.
+fn ca_certificates(path: &str) -> Result<Vec<Certificate>> {
+    Certificate::from_pem_bundle(&read_tls_file(path)?).map_err(|e| {
+        let message = format!("Loading CA certificates {path} failed! {e}");
+        error!("{}", message);
+        Error::new(&message, libapt::ErrorType::ApiUsage)
+    })
+}
+
+/// Load the client certificate and its PKCS#8 key from PEM files.
+fn client_identity(cert: &str, key: &str) -> Result<Identity> {
+    Identity::from_pkcs8_pem(&read_tls_file(cert)?, &read_tls_file(key)?).map_err(|e| {
+        let message = format!("Loading client certificate {cert} failed! {e}");
+        error!("{}", message);
+        Error::new(&message, libapt::ErrorType::ApiUsage)
+    })
+}
+
+impl Default for Transport {
+    fn default() -> Self {
+        Transport::new(&HttpOptions::default()).expect("Default HTTP client is valid.")
+    }
+}
+
+impl Transport {
+    /// Create a new transport using the given HTTP settings.
+    pub fn new(options: &HttpOptions) -> Result<Transport> {
+        let mut builder = Client::builder().pool_max_idle_per_host(options.max_connections_per_host);
+
+        builder = match options.http2 {
+            Http2Mode::Auto => builder,
+            Http2Mode::On => builder.http2_prior_knowledge(),
+            Http2Mode::Off => builder.http1_only(),
+        };
+
+        if let Some(keep_alive) = options.keep_alive {
+            let keep_alive = Duration::from_secs(keep_alive);
+            builder = builder.pool_idle_timeout(keep_alive).tcp_keepalive(keep_alive);
+        }
+
+        // Without explicit proxy, the client uses the http_proxy, https_proxy and no_proxy env vars.
+        if let Some(proxy) = &options.proxy {
+            let proxy = Proxy::all(proxy).map_err(|e| {
+                let message = format!("Invalid proxy! {e}");
+                error!("{}", message);
+                Error::new(&message, libapt::ErrorType::ApiUsage)
+            })?;
+            builder = builder.proxy(proxy.no_proxy(NoProxy::from_env()));
+        }
+
+        if let Some(ca_cert) = &options.ca_cert {
+            for certificate in ca_certificates(ca_cert)? {
+                builder = builder.add_root_certificate(certificate);
+            }
+        }
+
+        if options.insecure {
+            warn!("TLS certificate verification is DISABLED! Results of HTTPS repos can't be trusted.");
+            builder = builder.danger_accept_invalid_certs(true);
+        }
+
+        builder = match (&options.tls_cert, &options.tls_key) {
+            (Some(cert), Some(key)) => builder.identity(client_identity(cert, key)?),
+            (None, None) => builder,
+            _ => {
+                let message = "Client TLS certificate and key must be given together!";
+                error!("{}", message);
+                return Err(Error::new(message, libapt::ErrorType::ApiUsage));
+            }
+        };
+
+        let client = builder
+            .build()
+            .map_err(|e| Error::from_error(&e, libapt::ErrorType::ApiUsage, "Creating HTTP client failed"))?;
+
+        Ok(Transport {
+            client,
+            options: options.clone(),
+            hosts: Mutex::new(HashMap::new()),
+            broken_head: Mutex::new(HashSet::new()),
+            s3: S3Config::from_env(),
+            downloaded: AtomicU64::new(0),
+        })
+    }
+
+    /// Get the number of bytes downloaded so far.
+    pub fn downloaded_bytes(&self) -> u64 {
+        self.downloaded.load(Ordering::Relaxed)
+    }
+
+    /// Count downloaded bytes.
+    fn count(&self, bytes: usize) {
+        self.downloaded.fetch_add(bytes as u64, Ordering::Relaxed);
+    }
+
+    /// Get the connection limit for the host of the given URL.
+    fn host_limit(&self, url: &str) -> Arc<Semaphore> {
+        let host = host_of(url);
+
+        let mut hosts = self.hosts.lock().unwrap();
+        hosts
+            .entry(host)
+            .or_insert_with_key(|host| Arc::new(Semaphore::new(self.options.max_connections_for(host).max(1))))
+            .clone()
+    }
+
+    /// Send a request using the timeout and retries configured for the host.
+    ///
+    /// The request is built for the URL to request, i.e. the HTTPS URL of s3:// URLs.
+    /// Connection errors and server errors are retried.
+    async fn send(&self, url: &str, build: impl Fn(&Client, &str) -> RequestBuilder) -> Result<Response> {
+        let retries = self.options.retries_for(&host_of(url));
+        self.send_with_retries(url, retries, build).await
+    }
+
+    /// Send a request using the timeout configured for the host and the given number of retries.
+    async fn send_with_retries(&self, url: &str, retries: u32, build: impl Fn(&Client, &str) -> RequestBuilder) -> Result<Response> {
+        let host = host_of(url);
+        let timeout = self.options.timeout_for(&host);
+        let s3_url = self.s3.http_url(url);
+
+        let mut attempt = 0;
+        loop {
+            let mut request = build(&self.client, s3_url.as_deref().unwrap_or(url));
+            if s3_url.is_none() {
+                request = match self.options.auth_for(url) {
+                    Some(Auth::Basic(username, password)) => request.basic_auth(username, password),
+                    Some(Auth::Bearer(token)) => request.bearer_auth(token),
+                    Some(Auth::Query(parameter, token)) => request.query(&[(parameter, token)]),
+                    None => request,
+                };
+            }
+            if let Some(timeout) = timeout {
+                request = request.timeout(Duration::from_secs(timeout));
+            }
+
+            let result = match request.build() {
+                Ok(mut request) => {
+                    if s3_url.is_some() {
+                        self.s3.sign(&mut request, Utc::now());
+                    }
+                    self.client.execute(request).await
+                }
+                Err(e) => Err(e),
+            };
+            let failed = match &result {
+                Ok(response) => response.status().is_server_error(),
+                Err(_) => true,
+            };
+
+            if !failed || attempt >= retries {
+                // The URL of the request may contain a token parameter, so only the given URL is reported.
+                return result.map_err(|e| Error::from_reqwest(e.without_url(), url));
+            }
+
+            attempt += 1;
+            debug!("Request of {url} failed, retry {attempt} of {retries}...");
+            tokio::time::sleep(RETRY_DELAY * attempt).await;
+        }
+    }
+
+    /// Download the content of the given URL as bytes.
+    pub async fn download(&self, url: &str) -> Result<Vec<u8>> {
+        if let Some(path) = local_path(url) {
+            return read_local(&path, url, None);
+        }
+
+        let limit = self.host_limit(url);
+        let _permit = limit.acquire().await.expect("Semaphore is never closed.");
+
+        let response = self.send(url, |client, url| client.get(url)).await?;
+
+        let data = check_status(response, url)?
+            .bytes()
+            .await
+            .map_err(|e| Error::from_reqwest(e, url))?;
+        self.count(data.len());
+
+        Ok(data.to_vec())
+    }
+
+    /// Download the beginning of the given URL, e.g. the control part of a deb file.
+    ///
+    /// The download is stopped after the given number of bytes, so large files are not kept in memory.
+    pub async fn download_prefix(&self, url: &str, length: usize) -> Result<Vec<u8>> {
+        if let Some(path) = local_path(url) {
+            return read_local(&path, url, Some(length));
+        }
+
+        let limit = self.host_limit(url);
+        let _permit = limit.acquire().await.expect("Semaphore is never closed.");
+
+        let response = self.send(url, |client, url| client.get(url)).await?;
+
+        let mut response = check_status(response, url)?;
+        let mut data = Vec::new();
+        while let Some(chunk) = response.chunk().await.map_err(|e| Error::from_reqwest(e, url))? {
+            self.count(chunk.len());
+            data.extend_from_slice(&chunk);
+            if data.len() >= length {
+                data.truncate(length);
+                break;
+            }
+        }
+
+        Ok(data)
+    }
+
+    /// Download the given URL and measure the timing.
+    ///
+    /// Returns the time to the response headers, the total time and the size.
+    pub async fn timed_download(&self, url: &str) -> Result<(Duration, Duration, u64)> {
+        if let Some(path) = local_path(url) {
+            let start = Instant::now();
+            let size = read_local(&path, url, None)?.len() as u64;
+            let duration = start.elapsed();
+            return Ok((duration, duration, size));
+        }
+
+        let limit = self.host_limit(url);
+        let _permit = limit.acquire().await.expect("Semaphore is never closed.");
+
+        let start = Instant::now();
+        let response = self.send(url, |client, url| client.get(url)).await?;
+        let latency = start.elapsed();
+
+        let mut response = check_status(response, url)?;
+        let mut size: u64 = 0;
+        while let Some(chunk) = response.chunk().await.map_err(|e| Error::from_reqwest(e, url))? {
+            self.count(chunk.len());
+            size += chunk.len() as u64;
+        }
+
+        Ok((latency, start.elapsed(), size))
+    }
+
+    /// Download the given URL and compute its SHA256 hash.
+    ///
+    /// The content is hashed while downloading, so large files are not kept in memory.
+    /// Returns the size and the hex encoded hash.
+    pub async fn sha256(&self, url: &str) -> Result<(u64, String)> {
+        let limit = self.host_limit(url);
+        let _permit = limit.acquire().await.expect("Semaphore is never closed.");
+
+        debug!("Verifying hash of {url}...");
+        if let Some(path) = local_path(url) {
+            let mut file = BufReader::new(fs::File::open(&path).map_err(|e| Error::from_io_error(e, url))?);
+            let mut hasher = Sha256::new();
+            let size = io::copy(&mut file, &mut hasher).map_err(|e| Error::from_io_error(e, url))?;
+            return Ok((size, format!("{:x}", hasher.finalize())));
+        }
+
+        let response = self.send(url, |client, url| client.get(url)).await?;
+
+        let mut response = check_status(response, url)?;
+        let mut hasher = Sha256::new();
+        let mut size: u64 = 0;
+        while let Some(chunk) = response.chunk().await.map_err(|e| Error::from_reqwest(e, url))? {
+            self.count(chunk.len());
+            size += chunk.len() as u64;
+            hasher.update(&chunk);
+        }
+
+        Ok((size, format!("{:x}", hasher.finalize())))
+    }
+
+    /// Get the ETag or, if missing, the Last-Modified header of the given URL using a HEAD request.
+    ///
+    /// Local files use their size and modification time. None if the server provides neither header.
+    pub async fn validator(&self, url: &str) -> Result<Option<String>> {
+        if let Some(path) = local_path(url) {
+            let metadata = fs::metadata(&path).map_err(|e| Error::from_io_error(e, url))?;
+            return Ok(metadata.modified().ok().map(|modified| format!("{} {modified:?}", metadata.len())));
+        }
+
+        let limit = self.host_limit(url);
+        let _permit = limit.acquire().await.expect("Semaphore is never closed.");
+
+        let response = self.send(url, |client, url| client.head(url)).await?;
+        let response = check_status(response, url)?;
+
+        let validator = response
+            .headers()
+            .get(ETAG)
+            .or_else(|| response.headers().get(LAST_MODIFIED))
+            .and_then(|value| value.to_str().ok())
+            .map(|value| value.to_string());
+        Ok(validator)
+    }
+
+    /// Upload the content to the given URL using PUT, e.g. metrics to a Pushgateway.
+    pub async fn put(&self, url: &str, content: &str, headers: &[(HeaderName, String)]) -> Result<()> {
+        self.upload(Method::PUT, url, content, headers).await
+    }
+
+    /// Send the content to the given URL using POST, e.g. a notification to a webhook.
+    pub async fn post(&self, url: &str, content: &str, headers: &[(HeaderName, String)]) -> Result<()> {
+        self.upload(Method::POST, url, content, headers).await
+    }
+
+    /// Send the content with the given headers to the URL.
+    ///
+    /// POST requests aren't retried, since a retry may deliver a notification twice.
+    /// The notifications are retried by the notifier instead.
+    async fn upload(&self, method: Method, url: &str, content: &str, headers: &[(HeaderName, String)]) -> Result<()> {
+        debug!("Sending {method} request to {url}...");
+        let retries = if method == Method::POST { 0 } else { self.options.retries_for(&host_of(url)) };
+        let response = self
+            .send_with_retries(url, retries, |client, url| {
+                headers
+                    .iter()
+                    .fold(client.request(method.clone(), url), |request, (name, value)| request.header(name, value))
+                    .body(content.to_string())
+            })
+            .await?;
+
+        check_status(response, url)?;
+        Ok(())
+    }
+
+    /// Check the existence of the file at the given URL.
+    ///
+    /// Returns the size of the file, if the server reports it.
+    ///
+    /// If HEAD requests aren't supported for a host, i.e. fail with 405, 501 or a server error
+    /// or don't report the size, but a ranged GET works, all further checks for this host use ranged GET requests.
+    /// Other failures, e.g. 404 or 410, are the final answer.
+    pub async fn probe(&self, url: &str) -> Result<Option<u64>> {
+        if let Some(path) = local_path(url) {
+            debug!("Probing local file {}...", path.display());
+            let metadata = fs::metadata(&path).map_err(|e| Error::from_io_error(e, url))?;
+            return Ok(Some(metadata.len()));
+        }
+
+        let limit = self.host_limit(url);
+        let _permit = limit.acquire().await.expect("Semaphore is never closed.");
+
+        match self.options.probe_method {
+            ProbeMethod::Head => {
+                let host = host_of(url);
+                if self.broken_head.lock().unwrap().contains(&host) {
+                    return self.probe_range(url).await;
+                }
+
+                let response = self.probe_head(url).await?;
+                let status = response.status();
+                let size = header_number(&response, CONTENT_LENGTH);
+                let unsupported = status == StatusCode::METHOD_NOT_ALLOWED
+                    || status == StatusCode::NOT_IMPLEMENTED
+                    || status.is_server_error()
+                    || (status.is_success() && size.is_none());
+                let head_result = check_status(response, url).map(|_| size);
+                if !unsupported {
+                    return head_result;
+                }
+
+                match self.probe_range(url).await {
+                    Ok(size) => {
+                        info!("HEAD request of {url} is not supported, but ranged GET works. Using ranged GET for host {host}.");
+                        self.broken_head.lock().unwrap().insert(host);
+                        Ok(size)
+                    }
+                    Err(_) => head_result,
+                }
+            }
+            ProbeMethod::Range => self.probe_range(url).await,
+            ProbeMethod::Get => self.probe_get(url).await,
+        }
+    }
+
+    /// Check the file using a HEAD request. The status is checked by the caller.
+    async fn probe_head(&self, url: &str) -> Result<Response> {
+        debug!("Probing {url} using HEAD...");
+        self.send(url, |client, url| client.head(url)).await
+    }
+
+    /// Check the file using a GET request for the first byte.
+    async fn probe_range(&self, url: &str) -> Result<Option<u64>> {
+        debug!("Probing {url} using ranged GET...");
+        let response = self.send(url, |client, url| client.get(url).header(RANGE, "bytes=0-0")).await?;
+
+        let response = check_status(response, url)?;
+
+        if response.status() == StatusCode::PARTIAL_CONTENT {
+            // The total size is given as "bytes 0-0/<size>".
+            let size = response
+                .headers()
+                .get(CONTENT_RANGE)
+                .and_then(|range| range.to_str().ok())
+                .and_then(|range| range.rsplit_once('/'))
+                .and_then(|(_, size)| size.trim().parse::<u64>().ok());
+            Ok(size)
+        } else {
+            // Servers ignoring the range answer with 200 and the full content,
+            // which is dropped without reading.
+            Ok(header_number(&response, CONTENT_LENGTH))
+        }
+    }
+
+    /// Check the file by downloading it.
+    async fn probe_get(&self, url: &str) -> Result<Option<u64>> {
+        debug!("Probing {url} using GET...");
+        let response = self.send(url, |client, url| client.get(url)).await?;
+
+        let data = check_status(response, url)?
+            .bytes()
+            .await
+            .map_err(|e| Error::from_reqwest(e, url))?;
+        self.count(data.len());
+        Ok(Some(data.len() as u64))
+    }
+}
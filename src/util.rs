@@ -0,0 +1,132 @@
+//! Helper functions.
+
+use std::time::Duration;
+
+use libapt::{PackageVersion, Version, VersionRelation};
+
+/// Get the canonical pool directory of a source package, e.g. pool/main/libf/libfoo.
+pub fn pool_directory(component: &str, source: &str) -> String {
+    let length = if source.starts_with("lib") && source.len() > 3 { 4 } else { 1 };
+    let prefix: String = source.chars().take(length).collect();
+    format!("pool/{component}/{prefix}/{source}")
+}
+
+/// Format a Debian package version as in the index files.
+pub fn format_version(version: &Version) -> String {
+    let mut result = String::new();
+
+    if let Some(epoch) = version.epoch {
+        result += &format!("{epoch}:");
+    }
+
+    result += &version.version;
+
+    if let Some(revision) = &version.revision {
+        result += &format!("-{revision}");
+    }
+
+    result
+}
+
+/// Format a package relation as in the index files, e.g. "libc6 (>= 2.34)".
+pub fn format_dependency(dependency: &PackageVersion) -> String {
+    let mut result = dependency.name.clone();
+
+    if let Some(architecture) = &dependency.architecture {
+        result += &format!(":{architecture}");
+    }
+
+    if let Some(version) = &dependency.version {
+        let relation = match &dependency.relation {
+            Some(VersionRelation::StrictSmaller) => "<<",
+            Some(VersionRelation::Smaller) => "<=",
+            Some(VersionRelation::Exact) | None => "=",
+            Some(VersionRelation::Larger) => ">=",
+            Some(VersionRelation::StrictLarger) => ">>",
+        };
+        result += &format!(" ({relation} {})", format_version(version));
+    }
+
+    result
+}
+
+/// Parse a duration like "90s", "30m", "12h" or "7d".
+///
+/// A plain number is interpreted as seconds.
+pub fn parse_duration(duration: &str) -> Option<Duration> {
+    let duration = duration.trim();
+    let (value, factor) = if let Some(value) = duration.strip_suffix('s') {
+        (value, 1)
+    } else if let Some(value) = duration.strip_suffix('m') {
+        (value, 60)
+    } else if let Some(value) = duration.strip_suffix('h') {
+        (value, 60 * 60)
+    } else if let Some(value) = duration.strip_suffix('d') {
+        (value, 24 * 60 * 60)
+    } else {
+        (duration, 1)
+    };
+
+    let value = value.trim().parse::<u64>().ok()?;
+    Some(Duration::from_secs(value * factor))
+}
+
+/// Format a duration in seconds as a short text like "2d 5h" or "12m 30s".
+pub fn format_duration(seconds: i64) -> String {
+    let days = seconds / (24 * 60 * 60);
+    let hours = seconds / (60 * 60) % 24;
+    let minutes = seconds / 60 % 60;
+    let seconds = seconds % 60;
+
+    if days > 0 {
+        format!("{days}d {hours}h")
+    } else if hours > 0 {
+        format!("{hours}h {minutes}m")
+    } else if minutes > 0 {
+        format!("{minutes}m {seconds}s")
+    } else {
+        format!("{seconds}s")
+    }
+}
+
+/// Format a number of bytes as a short text like "12.3 MiB".
+pub fn format_bytes(bytes: u64) -> String {
+    let units = ["B", "KiB", "MiB", "GiB", "TiB"];
+    let mut value = bytes as f64;
+    let mut unit = 0;
+    while value >= 1024.0 && unit < units.len() - 1 {
+        value /= 1024.0;
+        unit += 1;
+    }
+
+    if unit == 0 {
+        format!("{bytes} B")
+    } else {
+        format!("{value:.1} {}", units[unit])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_durations() {
+        assert_eq!(parse_duration("90s"), Some(Duration::from_secs(90)));
+        assert_eq!(parse_duration("30m"), Some(Duration::from_secs(30 * 60)));
+        assert_eq!(parse_duration("12h"), Some(Duration::from_secs(12 * 60 * 60)));
+        assert_eq!(parse_duration("7d"), Some(Duration::from_secs(7 * 24 * 60 * 60)));
+        assert_eq!(parse_duration(" 45 "), Some(Duration::from_secs(45)));
+        assert_eq!(parse_duration("h"), None);
+        assert_eq!(parse_duration("-5m"), None);
+        assert_eq!(parse_duration("1w"), None);
+    }
+
+    #[test]
+    fn format_durations() {
+        assert_eq!(format_duration(2 * 24 * 60 * 60 + 5 * 60 * 60 + 7), "2d 5h");
+        assert_eq!(format_duration(3 * 60 * 60 + 20 * 60), "3h 20m");
+        assert_eq!(format_duration(12 * 60 + 30), "12m 30s");
+        assert_eq!(format_duration(9), "9s");
+    }
+}
@@ -0,0 +1,76 @@
+//! Watch mode, checking repositories again on a schedule.
+
+use libapt::Distro;
+use log::info;
+
+use crate::baseline::BaselineEntry;
+use crate::metrics::RepoMetrics;
+use crate::options::CheckOptions;
+use crate::RepoResult;
+
+/// Repository checked by the watch mode.
+#[derive(Debug, Clone)]
+pub struct WatchedRepo {
+    // Name of the repository in the report.
+    pub name: String,
+    pub distro: Distro,
+    // Check options of the repository.
+    pub options: CheckOptions,
+}
+
+/// State of a watched repository since its last check.
+pub struct RepoState {
+    pub repo: WatchedRepo,
+    // ETag or Last-Modified of the InRelease file at the last check. None if the server provides neither.
+    pub validator: Option<String>,
+    // Result of the last check. None before the first check.
+    pub result: Option<RepoResult>,
+    // Metrics of the last check.
+    pub metrics: Option<RepoMetrics>,
+    // Baseline entries of the findings of the last check.
+    pub known: Vec<BaselineEntry>,
+}
+
+impl RepoState {
+    /// Create the state of a repository which is not yet checked.
+    pub fn new(repo: WatchedRepo) -> RepoState {
+        RepoState {
+            repo,
+            validator: None,
+            result: None,
+            metrics: None,
+            known: Vec::new(),
+        }
+    }
+
+    /// Test if the InRelease file is unchanged since the last check.
+    ///
+    /// Without validator, the file is considered changed.
+    pub fn is_unchanged(&self, validator: &Option<String>) -> bool {
+        self.result.is_some() && validator.is_some() && *validator == self.validator
+    }
+
+    /// Store the result of a check.
+    ///
+    /// Returns true if the status differs from the last check, or if it is the first check.
+    pub fn update(&mut self, validator: Option<String>, result: RepoResult, metrics: RepoMetrics, known: Vec<BaselineEntry>) -> bool {
+        let changed = match &self.result {
+            Some(last) if last.status == result.status => {
+                info!("Repo {} is still {:?}.", self.repo.name, result.status);
+                false
+            }
+            Some(last) => {
+                info!("Repo {} changed from {:?} to {:?}.", self.repo.name, last.status, result.status);
+                true
+            }
+            None => true,
+        };
+
+        self.validator = validator;
+        self.result = Some(result);
+        self.metrics = Some(metrics);
+        self.known = known;
+
+        changed
+    }
+}